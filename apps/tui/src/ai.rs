@@ -1,6 +1,7 @@
-use chrono::NaiveDate;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
 
-use crate::model::Priority;
+use crate::model::{Priority, Progress};
+use crate::storage::AiSettings;
 
 #[derive(Debug, Clone)]
 pub struct NewTaskHints {
@@ -11,7 +12,12 @@ pub struct NewTaskHints {
     pub title: String,
 }
 
-pub fn infer_new_task(input: &str, bucket_names: &[String]) -> Option<NewTaskHints> {
+pub fn infer_new_task(
+    input: &str,
+    bucket_names: &[String],
+    today: NaiveDate,
+    settings: &AiSettings,
+) -> Option<NewTaskHints> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
         return None;
@@ -19,13 +25,13 @@ pub fn infer_new_task(input: &str, bucket_names: &[String]) -> Option<NewTaskHin
 
     // Allow manual overrides:
     //   bucketname: ...
-    // And inline hints:
-    //   due:YYYY-MM-DD
-    //   p:low|medium|high|critical
+    // And inline hints (see parse_due_date_hint / parse_priority_hint):
+    //   due:YYYY-MM-DD, by friday, tomorrow, in 3 days
+    //   p:high, low priority, urgent, asap
     let (bucket_override, rest) = parse_bucket_prefix(trimmed, bucket_names);
     let bucket_locked = bucket_override.is_some();
 
-    let (due_date, rest) = parse_due_date_hint(rest);
+    let (due_date, rest) = parse_due_date_hint(rest, today, settings);
     let (priority, title) = parse_priority_hint(&rest);
 
     let title = title.trim();
@@ -55,50 +61,265 @@ fn parse_bucket_prefix<'a>(input: &'a str, bucket_names: &[String]) -> (Option<S
     (None, input)
 }
 
-fn parse_due_date_hint(input: &str) -> (Option<NaiveDate>, String) {
-    // Naive parse: look for a token like due:2026-02-15
-    // If multiple, use the first.
+/// Finds a due date in the text and removes the words that named it:
+/// `due:2026-02-15`, `due`/`by`/`before` followed by a date phrase, or a bare
+/// `today`, `tomorrow` or `in N days`. Only the first date counts.
+fn parse_due_date_hint(
+    input: &str,
+    today: NaiveDate,
+    settings: &AiSettings,
+) -> (Option<NaiveDate>, String) {
+    let words: Vec<&str> = input.split_whitespace().collect();
     let mut due = None;
-    let mut out_tokens: Vec<&str> = Vec::new();
-
-    for token in input.split_whitespace() {
+    let mut out_words: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
         if due.is_none() {
-            if let Some(value) = token.strip_prefix("due:") {
-                if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            let word = bare_word(words[i]);
+            if let Some(value) = word.strip_prefix("due:") {
+                if let Some(date) = parse_date_phrase(&[value], today, settings).map(|(d, _)| d) {
+                    due = Some(date);
+                    i += 1;
+                    continue;
+                }
+            }
+            // "due by friday", "due on 2026-03-01"
+            let mut phrase_at = i + 1;
+            if matches!(word.as_str(), "due" | "by" | "before") {
+                if word == "due"
+                    && words
+                        .get(phrase_at)
+                        .is_some_and(|w| matches!(bare_word(w).as_str(), "by" | "on"))
+                {
+                    phrase_at += 1;
+                }
+                if let Some((date, len)) =
+                    parse_date_phrase(&words[phrase_at.min(words.len())..], today, settings)
+                {
                     due = Some(date);
+                    i = phrase_at + len;
+                    continue;
+                }
+            }
+            if matches!(word.as_str(), "today" | "tonight" | "tomorrow" | "in") {
+                if let Some((date, len)) = parse_date_phrase(&words[i..], today, settings) {
+                    due = Some(date);
+                    i += len;
                     continue;
                 }
             }
         }
-        out_tokens.push(token);
+        out_words.push(words[i]);
+        i += 1;
     }
+    (due, out_words.join(" "))
+}
 
-    (due, out_tokens.join(" "))
+/// A date at the start of `words`, with how many words it used: an ISO date,
+/// `today`/`tonight`/`tomorrow`, a weekday (optionally after `next`; always
+/// the next one after today), `in N days|weeks` or `in N workdays|business
+/// days`. `tomorrow` and `in N days|weeks` move to the next working day when
+/// working days are configured.
+fn parse_date_phrase(
+    words: &[&str],
+    today: NaiveDate,
+    settings: &AiSettings,
+) -> Option<(NaiveDate, usize)> {
+    let first = bare_word(words.first()?);
+    if let Ok(date) = NaiveDate::parse_from_str(&first, "%Y-%m-%d") {
+        return Some((date, 1));
+    }
+    match first.as_str() {
+        "today" | "tonight" => return Some((today, 1)),
+        "tomorrow" => {
+            let date = settings.roll_to_work_day(today.checked_add_days(Days::new(1))?);
+            return Some((date, 1));
+        }
+        "in" => {
+            let n: u64 = bare_word(words.get(1)?).parse().ok()?;
+            let unit = bare_word(words.get(2)?);
+            let days = match unit.as_str() {
+                "day" | "days" => n,
+                "week" | "weeks" => n * 7,
+                "workday" | "workdays" => return Some((settings.add_work_days(today, n)?, 3)),
+                "business" | "work"
+                    if matches!(bare_word(words.get(3)?).as_str(), "day" | "days") =>
+                {
+                    return Some((settings.add_work_days(today, n)?, 4));
+                }
+                _ => return None,
+            };
+            let date = settings.roll_to_work_day(today.checked_add_days(Days::new(days))?);
+            return Some((date, 3));
+        }
+        _ => {}
+    }
+    let (name, len) = if first == "next" {
+        (bare_word(words.get(1)?), 2)
+    } else {
+        (first, 1)
+    };
+    let weekday = parse_weekday(&name)?;
+    let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 { 7 } else { ahead };
+    Some((today.checked_add_days(Days::new(u64::from(ahead)))?, len))
 }
 
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    match name {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Lowercased, without the punctuation that ends a clause ("Friday." or "asap!").
+fn bare_word(word: &str) -> String {
+    word.trim_end_matches([',', '.', '!', '?', ';', ':'])
+        .to_ascii_lowercase()
+}
+
+/// Finds a priority in the text and removes the words that named it:
+/// `p:high`, `high priority`, `urgent` (Critical) or `asap` (High). Only the
+/// first one counts.
 fn parse_priority_hint(input: &str) -> (Option<Priority>, String) {
+    let words: Vec<&str> = input.split_whitespace().collect();
     let mut prio = None;
-    let mut out_tokens: Vec<&str> = Vec::new();
-
-    for token in input.split_whitespace() {
+    let mut out_words: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
         if prio.is_none() {
-            if let Some(value) = token.strip_prefix("p:") {
-                prio = match value.to_ascii_lowercase().as_str() {
-                    "low" => Some(Priority::Low),
-                    "med" | "medium" => Some(Priority::Medium),
-                    "high" => Some(Priority::High),
-                    "crit" | "critical" => Some(Priority::Critical),
-                    _ => None,
-                };
-                if prio.is_some() {
-                    continue;
-                }
+            let word = bare_word(words[i]);
+            let next_is_priority = words
+                .get(i + 1)
+                .is_some_and(|w| matches!(bare_word(w).as_str(), "priority" | "prio"));
+            let found = match word.as_str() {
+                "urgent" | "urgently" => Some((Priority::Critical, 1)),
+                "asap" => Some((Priority::High, 1)),
+                _ if next_is_priority => parse_priority_word(&word).map(|p| (p, 2)),
+                _ => word
+                    .strip_prefix("p:")
+                    .and_then(parse_priority_word)
+                    .map(|p| (p, 1)),
+            };
+            if let Some((priority, len)) = found {
+                prio = Some(priority);
+                i += len;
+                continue;
+            }
+        }
+        out_words.push(words[i]);
+        i += 1;
+    }
+    (prio, out_words.join(" "))
+}
+
+/// Field changes parsed from an `@id` instruction without the AI.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuickEdit {
+    pub progress: Option<Progress>,
+    pub priority: Option<Priority>,
+    pub bucket: Option<String>,
+    pub due_date: Option<Option<NaiveDate>>,
+}
+
+/// Parse the offline `@id` grammar: progress words (`backlog`, `todo`,
+/// `doing`/`in progress`, `done`), priorities (`low` … `critical`, or
+/// `p:high`), `bucket <name>` and `due <date|today|tomorrow|none>`, in any
+/// order. Returns `None` unless every word is understood, so free-form
+/// instructions are never half-applied.
+pub fn parse_quick_edit(instruction: &str, settings: &AiSettings) -> Option<QuickEdit> {
+    let mut words: Vec<&str> = Vec::new();
+    for word in instruction.split_whitespace() {
+        match word.split_once(':') {
+            Some((key, value))
+                if !value.is_empty()
+                    && (key.eq_ignore_ascii_case("bucket") || key.eq_ignore_ascii_case("due")) =>
+            {
+                words.push(key);
+                words.push(value);
+            }
+            _ => words.push(word),
+        }
+    }
+
+    let mut edit = QuickEdit::default();
+    let mut i = 0;
+    while i < words.len() {
+        let lower = words[i].to_ascii_lowercase();
+        i += 1;
+        match lower.as_str() {
+            "bucket" => {
+                // Longest match first, so "Team Ops" wins over "Team".
+                let (bucket, len) = (1..=words.len() - i).rev().find_map(|len| {
+                    let name = words[i..i + len].join(" ");
+                    settings
+                        .buckets
+                        .iter()
+                        .find(|b| b.name.eq_ignore_ascii_case(&name))
+                        .map(|b| (b.name.clone(), len))
+                })?;
+                edit.bucket = Some(bucket);
+                i += len;
+            }
+            "due" => {
+                let value = words.get(i)?;
+                edit.due_date = Some(parse_due_input(value, settings)?);
+                i += 1;
+            }
+            "in" if words
+                .get(i)
+                .is_some_and(|w| w.eq_ignore_ascii_case("progress")) =>
+            {
+                edit.progress = Some(Progress::InProgress);
+                i += 1;
+            }
+            "backlog" => edit.progress = Some(Progress::Backlog),
+            "todo" => edit.progress = Some(Progress::Todo),
+            "doing" | "start" | "started" | "wip" | "in-progress" => {
+                edit.progress = Some(Progress::InProgress)
+            }
+            "done" | "finish" | "finished" | "complete" | "completed" => {
+                edit.progress = Some(Progress::Done)
             }
+            other => edit.priority = Some(parse_priority_word(other.trim_start_matches("p:"))?),
         }
-        out_tokens.push(token);
     }
+    (edit != QuickEdit::default()).then_some(edit)
+}
 
-    (prio, out_tokens.join(" "))
+/// A typed due date: the configured format, ISO, or a phrase like `tomorrow`
+/// or `next fri`. `Some(None)` clears the date; `None` means unreadable.
+pub fn parse_due_input(value: &str, settings: &AiSettings) -> Option<Option<NaiveDate>> {
+    let value = value.trim();
+    let today = settings.today();
+    match value.to_ascii_lowercase().as_str() {
+        "" | "none" | "null" | "clear" => Some(None),
+        _ => settings
+            .parse_date(value)
+            .or_else(|| {
+                let words: Vec<&str> = value.split_whitespace().collect();
+                parse_date_phrase(&words, today, settings)
+                    .filter(|&(_, used)| used == words.len())
+                    .map(|(date, _)| date)
+            })
+            .map(Some),
+    }
+}
+
+fn parse_priority_word(value: &str) -> Option<Priority> {
+    match value {
+        "low" => Some(Priority::Low),
+        "med" | "medium" => Some(Priority::Medium),
+        "high" => Some(Priority::High),
+        "crit" | "critical" => Some(Priority::Critical),
+        _ => None,
+    }
 }
 
 /// Return the first bucket name as the default.
@@ -1,10 +1,33 @@
-use std::io;
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
 
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use uuid::Uuid;
 
-use crate::model::{children_of, compute_parent_progress, BucketDef, Priority, Progress, Task};
-use crate::storage::{AiSettings, Storage};
+use crate::model::{
+    children_of, delete_with_children, dependents_of, format_hours, format_task_snapshot,
+    nesting_depth, parse_hours, parse_task_color, prune_dangling_dependencies,
+    sync_progress_from_children, would_create_ancestor_cycle, would_exceed_nesting, BucketDef,
+    Priority, Progress, Task, WeekStart, MAX_NESTING_DEPTH, TASK_COLORS,
+};
+use crate::secrets;
+use crate::storage::{
+    is_valid_date_format, parse_holidays, parse_stage_labels, parse_utc_offset, parse_work_days,
+    parse_workday_end, AiSettings, Storage, DEFAULT_DATE_FORMAT, MAX_AI_CONCURRENCY,
+    MAX_SNAPSHOT_RETENTION, MAX_TIMEOUT_SECS, MIN_TIMEOUT_SECS, SYSTEM_PROMPT_MAX_CHARS,
+};
+
+// ---------------------------------------------------------------------------
+// Exit codes
+// ---------------------------------------------------------------------------
+
+/// Bad usage, an unknown id, or missing configuration such as an API key.
+pub const EXIT_CONFIG: i32 = 1;
+/// One-shot AI mode ran but the AI changed nothing.
+pub const EXIT_NO_CHANGES: i32 = 2;
+pub const EXIT_AI_TIMEOUT: i32 = 3;
+/// Tasks or settings could not be saved.
+pub const EXIT_STORAGE: i32 = 4;
 
 // ---------------------------------------------------------------------------
 // Dispatch
@@ -20,6 +43,14 @@ pub fn run_subcommand(args: &[String]) -> Option<io::Result<()>> {
         "bucket" => Some(run_bucket_cmd(&rest)),
         "settings" => Some(run_settings_cmd(&rest)),
         "suggestions" => Some(run_suggestions_cmd(&rest)),
+        "undo" | "history" if wants_help(&rest) => {
+            print_usage(HISTORY_USAGE, "");
+            Some(Ok(()))
+        }
+        "stats" => Some(run_stats_cmd(&rest)),
+        "export" => Some(run_export_cmd(&rest)),
+        "status" => Some(run_status_cmd(&rest)),
+        "path" | "paths" => Some(run_path_cmd(&rest)),
         "undo" => Some(cmd_undo()),
         "history" => Some(cmd_history()),
         _ => None,
@@ -27,28 +58,224 @@ pub fn run_subcommand(args: &[String]) -> Option<io::Result<()>> {
 }
 
 // ---------------------------------------------------------------------------
-// Helpers
+// Usage
 // ---------------------------------------------------------------------------
 
-fn find_flag(args: &[String], flag: &str) -> Option<String> {
+pub const TASK_USAGE: &str = "\
+Task commands (output JSON):
+  aipm task list [--bucket \"Y\"] [--status backlog|todo|...] [--priority ...]
+      [--assignee name] [--all] [--json]
+  aipm task show <id> [--json]      Show a task with its sub-tasks and dependents
+  aipm task add --title \"X\" [--bucket \"Y\"] [--priority low|medium|high|critical]
+      [--progress backlog|todo|in-progress|done|archived] [--due YYYY-MM-DD]
+      [--description \"...\"] [--estimate 1h30m] [--actual 2h] [--assignee name]
+      [--color red|orange|...] [--parent <id>] [--created YYYY-MM-DD] [--id-only]
+  aipm task edit <id> [--title \"X\"] [--bucket \"Y\"] [--priority ...]
+      [--progress ...] [--due YYYY-MM-DD|none] [--description \"...\"]
+      [--estimate <hours>|none] [--actual <hours>|none] [--assignee name|none]
+      [--color red|orange|...|none] [--parent <id>|none] [--created YYYY-MM-DD]
+  aipm task done <id>               Mark a task done
+  aipm task delete <id>             Delete task and its sub-tasks
+  <id> is any unique prefix of the task ID. --bucket must name an existing
+  bucket. --created backdates a task, for logging past work; it can't be in
+  the future. --id-only prints just the new task's ID. list skips archived tasks
+  and stages hidden in settings unless --all is given. list and show print
+  text on a terminal, JSON otherwise or with --json. Aliases: ls, show|get,
+  add|create, edit|update, delete|rm.
+";
+
+pub const BUCKET_USAGE: &str = "\
+Bucket commands (output JSON):
+  aipm bucket list                  List all buckets
+  aipm bucket add <name> [--description \"...\"]
+  aipm bucket rename <old> <new>
+  aipm bucket delete <name>         Moves tasks to first remaining bucket
+";
+
+pub const SETTINGS_USAGE: &str = "\
+Settings commands (output JSON):
+  aipm settings show                Print current settings
+  aipm settings update [--owner-name \"X\"] [--ai-enabled true|false]
+      [--openai-api-key KEY] [--anthropic-api-key KEY] [--model NAME]
+      [--use-keychain true|false] [--custom-models \"a,b\"]
+      [--timeout SECS] [--ai-concurrency N]
+      [--show-backlog true|false] [--show-todo true|false]
+      [--show-in-progress true|false] [--show-done true|false]
+      [--card-max-subissues N]
+      [--email-suggestions true|false] [--email-body-max-chars N]
+      [--auto-escalate true|false]
+      [--ai-lock-bucket true|false] [--ai-lock-priority true|false]
+      [--ai-lock-due-date true|false] [--ai-context-visible-only true|false]
+      [--system-prompt \"TEXT\"]
+      [--date-format \"%d/%m/%Y\"] [--week-start monday|sunday]
+      [--utc-offset +05:30|local] [--snapshot-retention N]
+      [--stage-labels \"Backlog,Todo,Review,Done\"]
+      [--work-days mon-fri] [--holidays \"2026-12-25,2027-01-01\"]
+      [--workday-end 17:30|none]
+";
+
+pub const SUGGESTIONS_USAGE: &str = "\
+Suggestions commands (Gmail + AI):
+  aipm suggestions list             List unread emails and show AI filtering
+  aipm suggestions sync [--limit N] Create tasks from actionable emails
+";
+
+pub const STATS_USAGE: &str = "\
+Stats:
+  aipm stats [--cycle-time] [--throughput] [--estimates] [--weeks N]
+      --cycle-time: average and median cycle time (first In progress to
+      Done) per bucket. --throughput: tasks completed per week over the last
+      N weeks (default 8, at most 520). --estimates: actual vs estimated
+      hours per bucket. Each flag picks a report; with none, all three are
+      shown.
+";
+
+pub const EXPORT_USAGE: &str = "\
+Export:
+  aipm export --format github-issues|linear|ics [--bucket \"Y\"] [--all]
+      github-issues: JSON array of {title, body, labels}, one per task, ready
+      for the GitHub create-issue API. linear: CSV for Linear's importer.
+      ics: iCalendar with an all-day event on each due date. Parents list
+      their sub-issues as a checklist. Archived and hidden stages are left
+      out unless --all is given.
+";
+
+pub const STATUS_USAGE: &str = "\
+Status:
+  aipm status [--oneline] [--format \"TEMPLATE\"]
+      Counts of overdue, due-today and in-progress tasks as JSON, or one line
+      for shell prompts with --oneline (nothing when all are zero). --format
+      fills {overdue}, {due_today}, {in_progress}, {todo} and {backlog} in
+      TEMPLATE. Never calls the AI or writes anything.
+";
+
+pub const PATH_USAGE: &str = "\
+Paths:
+  aipm path [NAME] [--json]
+      Where aipm keeps its data: data, tasks, settings, suggestions,
+      templates, snapshots and google_token. With NAME, prints only that
+      path, e.g. cd \"$(aipm path tasks)\". Set AIPM_DATA_DIR to move it all.
+";
+
+pub const HISTORY_USAGE: &str = "\
+Undo / History:
+  aipm undo                         Restore state before last CLI/AI change
+  aipm history                      List available undo snapshots (JSON)
+";
+
+const TASK_EXAMPLES: &str = "\
+Examples:
+  aipm task add --title \"Set up CI\" --bucket Team --priority high --due 2026-03-01
+  aipm task add --title \"Write tests\" --parent 4b01
+  id=$(aipm task add --title \"Ship it\" --id-only) && aipm task done \"$id\"
+  aipm task edit 4b01 --progress in-progress --due none
+  aipm task list --bucket Team --status todo --priority high --json
+  aipm task delete 4b01
+";
+
+const BUCKET_EXAMPLES: &str = "\
+Examples:
+  aipm bucket add Research --description \"Spikes and reading\"
+  aipm bucket rename Admin Chores
+  aipm bucket delete Chores
+";
+
+fn wants_help(args: &[String]) -> bool {
+    args.first().map(|a| a.as_str()) == Some("help")
+        || has_flag(args, "--help")
+        || has_flag(args, "-h")
+}
+
+fn print_usage(usage: &str, examples: &str) {
+    print!("{usage}");
+    if !examples.is_empty() {
+        println!();
+        print!("{examples}");
+    }
+}
+
+fn usage_error(usage: &str, msg: &str) -> ! {
+    eprintln!("Error: {msg}");
+    eprintln!();
+    eprint!("{usage}");
+    std::process::exit(EXIT_CONFIG);
+}
+
+/// Reject `--flags` that the command does not understand, so typos like
+/// `--prority` fail loudly instead of being ignored, and flags left without
+/// their value at the end of the line.
+fn check_flags(args: &[String], known: &[&str], usage: &str) {
+    for (name, value) in parse_flags(args) {
+        if name != "-h" && !known.contains(&name) {
+            usage_error(usage, &format!("Unknown flag: {name}"));
+        }
+        if value.is_none() && !SWITCHES.contains(&name) {
+            usage_error(usage, &format!("Missing value for {name}"));
+        }
+    }
+}
+
+/// Flags that take no value. Any other flag takes the next argument as its
+/// value, unless written `--flag=value`.
+const SWITCHES: &[&str] = &[
+    "--all",
+    "--json",
+    "--id-only",
+    "--oneline",
+    "--cycle-time",
+    "--throughput",
+    "--estimates",
+    "--help",
+    "-h",
+];
+
+/// The flags in `args` with their values. Values are skipped over, so
+/// `--title --draft` is a title rather than an unknown flag.
+fn parse_flags(args: &[String]) -> Vec<(&str, Option<&str>)> {
+    let mut flags = Vec::new();
     let mut iter = args.iter();
     while let Some(arg) = iter.next() {
-        if arg == flag {
-            return iter.next().cloned();
+        if !arg.starts_with("--") && arg != "-h" {
+            continue;
         }
-        if let Some(val) = arg.strip_prefix(&format!("{flag}=")) {
-            return Some(val.to_string());
+        match arg.split_once('=') {
+            Some((name, value)) => flags.push((name, Some(value))),
+            None if SWITCHES.contains(&arg.as_str()) => flags.push((arg.as_str(), None)),
+            None => flags.push((arg.as_str(), iter.next().map(|v| v.as_str()))),
         }
     }
-    None
+    flags
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    parse_flags(args).iter().any(|(name, _)| *name == flag)
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn find_flag(args: &[String], flag: &str) -> Option<String> {
+    parse_flags(args)
+        .into_iter()
+        .find(|(name, _)| *name == flag)
+        .and_then(|(_, value)| value.map(str::to_string))
 }
 
 fn load() -> (Option<Storage>, Vec<Task>, AiSettings) {
     let storage = Storage::new();
-    let tasks = match &storage {
+    let mut tasks = match &storage {
         Some(s) => s.load_tasks().unwrap_or_default(),
         None => Vec::new(),
     };
+    let pruned = prune_dangling_dependencies(&mut tasks);
+    if pruned > 0 {
+        eprintln!(
+            "Removed {pruned} dependenc{} on deleted tasks",
+            if pruned == 1 { "y" } else { "ies" }
+        );
+        save_tasks(&storage, &tasks);
+    }
     let settings = match &storage {
         Some(s) => s.load_settings().unwrap_or_default(),
         None => AiSettings::default(),
@@ -60,34 +287,67 @@ fn save_tasks(storage: &Option<Storage>, tasks: &[Task]) {
     if let Some(s) = storage {
         if let Err(err) = s.save_tasks(tasks) {
             eprintln!("Save failed: {err}");
-            std::process::exit(1);
+            std::process::exit(EXIT_STORAGE);
         }
     }
 }
 
-fn save_settings(storage: &Option<Storage>, settings: &AiSettings) {
+fn save_settings(storage: &Option<Storage>, settings: &mut AiSettings) {
     if let Some(s) = storage {
-        if let Err(err) = s.save_settings(settings) {
-            eprintln!("Settings save failed: {err}");
-            std::process::exit(1);
+        match s.save_settings(settings) {
+            Ok(refused) => {
+                for account in refused {
+                    eprintln!(
+                        "Warning: keychain refused the {account} key, it stays in the settings file"
+                    );
+                }
+            }
+            Err(err) => {
+                eprintln!("Settings save failed: {err}");
+                std::process::exit(EXIT_STORAGE);
+            }
         }
     }
 }
 
 fn die(msg: &str) -> ! {
     eprintln!("Error: {msg}");
-    std::process::exit(1);
+    std::process::exit(EXIT_CONFIG);
+}
+
+/// The configured bucket called `name`, ignoring case.
+fn resolve_bucket(settings: &AiSettings, name: &str) -> String {
+    match settings
+        .buckets
+        .iter()
+        .find(|b| b.name.eq_ignore_ascii_case(name.trim()))
+    {
+        Some(b) => b.name.clone(),
+        None => die(&format!(
+            "Unknown bucket: {name} (buckets: {})",
+            settings
+                .buckets
+                .iter()
+                .map(|b| b.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
 }
 
 fn resolve_task<'a>(tasks: &'a [Task], prefix: &str) -> &'a Task {
     let lower = prefix.to_ascii_lowercase();
-    tasks
+    let mut matches = tasks
         .iter()
-        .find(|t| {
-            let short: String = t.id.to_string().chars().take(lower.len().max(4)).collect();
-            short.to_ascii_lowercase().starts_with(&lower)
-        })
-        .unwrap_or_else(|| die(&format!("No task matching '{prefix}'")))
+        .filter(|t| t.id.to_string().starts_with(&lower));
+    match (matches.next(), matches.count()) {
+        (Some(task), 0) => task,
+        (None, _) => die(&format!("No task matching '{prefix}'")),
+        (Some(_), more) => die(&format!(
+            "ambiguous id '{prefix}' ({} tasks), use more characters",
+            more + 1
+        )),
+    }
 }
 
 fn parse_priority(s: &str) -> Priority {
@@ -111,6 +371,60 @@ fn parse_progress(s: &str) -> Progress {
     }
 }
 
+fn parse_hours_flag(flag: &str, val: &str) -> Option<f32> {
+    if val.is_empty() || val == "none" {
+        return None;
+    }
+    match parse_hours(val) {
+        Some(h) => Some(h),
+        None => die(&format!(
+            "Invalid {flag}: {val} (expected e.g. 1h30m, 90m or 1.5)"
+        )),
+    }
+}
+
+fn invalid_date_message(settings: &AiSettings, val: &str) -> String {
+    if settings.date_format() == DEFAULT_DATE_FORMAT {
+        format!("Invalid date format: {val} (expected YYYY-MM-DD)")
+    } else {
+        format!(
+            "Invalid date format: {val} (expected {} or YYYY-MM-DD)",
+            settings.date_format()
+        )
+    }
+}
+
+fn parse_created_flag(settings: &AiSettings, val: &str) -> chrono::DateTime<Utc> {
+    let Some(date) = settings.parse_date(val) else {
+        die(&invalid_date_message(settings, val));
+    };
+    settings
+        .backdate(date)
+        .unwrap_or_else(|| die(&format!("--created can't be in the future: {val}")))
+}
+
+fn parse_color_flag(val: &str) -> Option<String> {
+    if val.trim().is_empty() || val.trim() == "none" {
+        return None;
+    }
+    let color = parse_task_color(val).unwrap_or_else(|| {
+        die(&format!(
+            "Invalid color: {val} (expected {} or none)",
+            TASK_COLORS.join(", ")
+        ))
+    });
+    Some(color.to_string())
+}
+
+fn parse_assignee_flag(val: &str) -> Option<String> {
+    let name = val.trim().trim_start_matches('@');
+    if name.is_empty() || name == "none" {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
 fn print_json<T: serde::Serialize>(val: &T) {
     println!(
         "{}",
@@ -123,40 +437,130 @@ fn print_json<T: serde::Serialize>(val: &T) {
 // ---------------------------------------------------------------------------
 
 fn run_task_cmd(args: &[String]) -> io::Result<()> {
-    let sub = args.first().map(|s| s.as_str()).unwrap_or("list");
-    match sub {
-        "list" | "ls" => cmd_task_list(),
+    if wants_help(args) {
+        print_usage(TASK_USAGE, TASK_EXAMPLES);
+        return Ok(());
+    }
+    let Some(sub) = args.first() else {
+        usage_error(TASK_USAGE, "Missing task command");
+    };
+    match sub.as_str() {
+        "list" | "ls" => cmd_task_list(&args[1..]),
         "add" | "create" => cmd_task_add(&args[1..]),
         "edit" | "update" => cmd_task_edit(&args[1..]),
+        "done" => cmd_task_done(&args[1..]),
         "delete" | "rm" => cmd_task_delete(&args[1..]),
         "show" | "get" => cmd_task_show(&args[1..]),
-        other => die(&format!("Unknown task command: {other}")),
+        other => usage_error(TASK_USAGE, &format!("Unknown task command: {other}")),
     }
 }
 
-fn cmd_task_list() -> io::Result<()> {
-    let (_, tasks, _) = load();
-    print_json(&tasks);
+fn cmd_task_list(args: &[String]) -> io::Result<()> {
+    check_flags(
+        args,
+        &[
+            "--bucket",
+            "--status",
+            "--priority",
+            "--assignee",
+            "--all",
+            "--json",
+        ],
+        TASK_USAGE,
+    );
+    let (_, tasks, settings) = load();
+    let all = has_flag(args, "--all");
+    let bucket = find_flag(args, "--bucket").map(|b| resolve_bucket(&settings, &b));
+    let progress = find_flag(args, "--status").map(|p| parse_progress(&p));
+    let priority = find_flag(args, "--priority").map(|p| parse_priority(&p));
+    let assignee = find_flag(args, "--assignee").and_then(|a| parse_assignee_flag(&a));
+
+    let matching: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| all || settings.is_progress_visible(t.progress))
+        .filter(|t| bucket.as_ref().is_none_or(|b| &t.bucket == b))
+        .filter(|t| progress.is_none_or(|p| t.progress == p))
+        .filter(|t| priority.is_none_or(|p| t.priority == p))
+        .filter(|t| {
+            assignee.as_ref().is_none_or(|a| {
+                t.assignee
+                    .as_ref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(a))
+            })
+        })
+        .collect();
+
+    if has_flag(args, "--json") || !io::stdout().is_terminal() {
+        print_json(&matching);
+        return Ok(());
+    }
+    println!(
+        "{:<8}  {:<11}  {:<8}  {:<12}  {:<10}  Title",
+        "ID", "Progress", "Priority", "Bucket", "Due"
+    );
+    for task in matching {
+        let due = task
+            .due_date
+            .map(|d| settings.format_date(d))
+            .unwrap_or_default();
+        println!(
+            "{:<8}  {:<11}  {:<8}  {:<12}  {:<10}  {}",
+            &task.id.to_string()[..8],
+            settings.stage_label(task.progress),
+            task.priority.title(),
+            task.bucket.chars().take(12).collect::<String>(),
+            due,
+            task.title
+        );
+    }
     Ok(())
 }
 
+const TASK_FIELD_FLAGS: &[&str] = &[
+    "--title",
+    "--bucket",
+    "--description",
+    "--priority",
+    "--progress",
+    "--due",
+    "--estimate",
+    "--actual",
+    "--assignee",
+    "--parent",
+    "--created",
+    "--color",
+];
+
 fn cmd_task_add(args: &[String]) -> io::Result<()> {
+    let known: Vec<&str> = TASK_FIELD_FLAGS
+        .iter()
+        .copied()
+        .chain(["--id-only"])
+        .collect();
+    check_flags(args, &known, TASK_USAGE);
+    let id_only = has_flag(args, "--id-only");
     let (storage, mut tasks, settings) = load();
-    if let Some(s) = &storage {
-        s.snapshot("task add");
-    }
 
-    let title = find_flag(args, "--title").unwrap_or_else(|| die("--title is required"));
-    let bucket = find_flag(args, "--bucket").unwrap_or_else(|| {
-        settings
+    let title = find_flag(args, "--title")
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| usage_error(TASK_USAGE, "--title is required"));
+    let bucket = match find_flag(args, "--bucket") {
+        Some(name) => resolve_bucket(&settings, &name),
+        None => settings
             .buckets
             .first()
             .map(|b| b.name.clone())
-            .unwrap_or_else(|| "Unassigned".to_string())
-    });
+            .unwrap_or_else(|| "Unassigned".to_string()),
+    };
 
     let now = Utc::now();
-    let mut task = Task::new(bucket, title, now);
+    // A backdated task gets its initial stage at the same time, so the
+    // Timeline and `aipm stats` place the logged work on that day.
+    let created = match find_flag(args, "--created") {
+        Some(d) => parse_created_flag(&settings, &d),
+        None => now,
+    };
+    let mut task = Task::new(bucket, title, created);
 
     if let Some(desc) = find_flag(args, "--description") {
         task.description = desc;
@@ -165,46 +569,84 @@ fn cmd_task_add(args: &[String]) -> io::Result<()> {
         task.priority = parse_priority(&p);
     }
     if let Some(p) = find_flag(args, "--progress") {
-        task.set_progress(parse_progress(&p), now);
+        task.set_progress(parse_progress(&p), created);
     }
     if let Some(d) = find_flag(args, "--due") {
-        if let Ok(date) = chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d") {
+        if let Some(date) = settings.parse_date(&d) {
             task.due_date = Some(date);
         } else {
-            die(&format!("Invalid date format: {d} (expected YYYY-MM-DD)"));
+            die(&invalid_date_message(&settings, &d));
         }
     }
+    if let Some(h) = find_flag(args, "--estimate") {
+        task.estimate_hours = parse_hours_flag("--estimate", &h);
+    }
+    if let Some(h) = find_flag(args, "--actual") {
+        task.actual_hours = parse_hours_flag("--actual", &h);
+    }
+    if let Some(a) = find_flag(args, "--assignee") {
+        task.assignee = parse_assignee_flag(&a);
+    }
+    if let Some(c) = find_flag(args, "--color") {
+        task.color = parse_color_flag(&c);
+    }
     if let Some(parent_prefix) = find_flag(args, "--parent") {
         let parent = resolve_task(&tasks, &parent_prefix);
+        if nesting_depth(&tasks, parent.id) >= MAX_NESTING_DEPTH {
+            die("Sub-issues can't have sub-issues of their own");
+        }
         task.parent_id = Some(parent.id);
     }
+    task.updated_at = now;
 
-    print_json(&task);
-    tasks.push(task);
+    // Only once every flag is valid, so a rejected command leaves no snapshot.
+    if let Some(s) = &storage {
+        s.snapshot("task add");
+    }
+    tasks.push(task.clone());
     save_tasks(&storage, &tasks);
+    if id_only {
+        println!("{}", task.id);
+    } else {
+        print_json(&task);
+    }
     Ok(())
 }
 
 fn cmd_task_edit(args: &[String]) -> io::Result<()> {
     let prefix = args
         .first()
+        .filter(|a| !a.starts_with("--"))
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("task id required"));
-    let (storage, mut tasks, _) = load();
-    if let Some(s) = &storage {
-        s.snapshot(&format!("task edit {prefix}"));
-    }
+        .unwrap_or_else(|| usage_error(TASK_USAGE, "task id required"));
+    check_flags(args, TASK_FIELD_FLAGS, TASK_USAGE);
+    let (storage, mut tasks, settings) = load();
     let task_id = resolve_task(&tasks, prefix).id;
     let now = Utc::now();
+    let new_parent = find_flag(args, "--parent").map(|p| {
+        if p.is_empty() || p == "none" {
+            return None;
+        }
+        let parent = resolve_task(&tasks, &p).id;
+        if would_create_ancestor_cycle(&tasks, task_id, parent) {
+            die("Can't move a task under itself or its sub-tasks");
+        }
+        if would_exceed_nesting(&tasks, task_id, parent) {
+            die("Sub-issues can't have sub-issues of their own");
+        }
+        Some(parent)
+    });
 
-    let task = tasks.iter_mut().find(|t| t.id == task_id).unwrap();
+    // Edited as a copy, so a flag rejected halfway leaves nothing changed.
+    let pos = tasks.iter().position(|t| t.id == task_id).unwrap();
+    let mut task = tasks[pos].clone();
 
     if let Some(t) = find_flag(args, "--title") {
         task.title = t;
         task.updated_at = now;
     }
     if let Some(b) = find_flag(args, "--bucket") {
-        task.bucket = b;
+        task.bucket = resolve_bucket(&settings, &b);
         task.updated_at = now;
     }
     if let Some(d) = find_flag(args, "--description") {
@@ -222,16 +664,65 @@ fn cmd_task_edit(args: &[String]) -> io::Result<()> {
     }
     if let Some(d) = find_flag(args, "--due") {
         if d.is_empty() || d == "none" {
-            task.due_date = None;
-        } else if let Ok(date) = chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d") {
-            task.due_date = Some(date);
+            task.set_due_date(None);
+        } else if let Some(date) = settings.parse_date(&d) {
+            task.set_due_date(Some(date));
         } else {
-            die(&format!("Invalid date format: {d} (expected YYYY-MM-DD)"));
+            die(&invalid_date_message(&settings, &d));
         }
         task.updated_at = now;
     }
+    if let Some(h) = find_flag(args, "--estimate") {
+        task.estimate_hours = parse_hours_flag("--estimate", &h);
+        task.updated_at = now;
+    }
+    if let Some(h) = find_flag(args, "--actual") {
+        task.actual_hours = parse_hours_flag("--actual", &h);
+        task.updated_at = now;
+    }
+    if let Some(a) = find_flag(args, "--assignee") {
+        task.assignee = parse_assignee_flag(&a);
+        task.updated_at = now;
+    }
+    if let Some(c) = find_flag(args, "--color") {
+        task.color = parse_color_flag(&c);
+        task.updated_at = now;
+    }
+    if let Some(d) = find_flag(args, "--created") {
+        let mut created = parse_created_flag(&settings, &d);
+        // Created after its first progress change, cycle and throughput
+        // times would come out negative. On the same day it moves back to it.
+        if let Some(first) = task.progress_history.iter().map(|(_, at)| *at).min() {
+            if settings.to_local(created).date_naive() > settings.to_local(first).date_naive() {
+                usage_error(
+                    TASK_USAGE,
+                    &format!(
+                        "--created can't be after the task's first progress change ({})",
+                        settings.format_date(settings.to_local(first).date_naive())
+                    ),
+                );
+            }
+            created = created.min(first);
+        }
+        task.created_at = created;
+        task.updated_at = now;
+    }
+    let mut old_parent = None;
+    if let Some(parent) = new_parent.filter(|p| *p != task.parent_id) {
+        old_parent = std::mem::replace(&mut task.parent_id, parent);
+        task.updated_at = now;
+        progress_changed = true;
+    }
 
+    // Only once every flag is valid, so a rejected command leaves no snapshot.
+    if let Some(s) = &storage {
+        s.snapshot(&format!("task edit {prefix}"));
+    }
     let task_clone = task.clone();
+    tasks[pos] = task;
+    if let Some(old) = old_parent {
+        sync_progress_from_children(&mut tasks, old, now);
+    }
     if progress_changed {
         sync_parent_progress(&mut tasks, task_id, now);
     }
@@ -249,25 +740,34 @@ fn sync_parent_progress(tasks: &mut [Task], child_id: Uuid, now: chrono::DateTim
         Some(pid) => pid,
         None => return,
     };
-    let child_progresses: Vec<Progress> = tasks
-        .iter()
-        .filter(|t| t.parent_id == Some(parent_id))
-        .map(|t| t.progress)
-        .collect();
-    if let Some(new_progress) = compute_parent_progress(&child_progresses) {
-        if let Some(parent) = tasks.iter_mut().find(|t| t.id == parent_id) {
-            if parent.progress != new_progress {
-                parent.set_progress(new_progress, now);
-            }
-        }
+    sync_progress_from_children(tasks, parent_id, now);
+}
+
+fn cmd_task_done(args: &[String]) -> io::Result<()> {
+    let prefix = args
+        .first()
+        .map(|s| s.as_str())
+        .unwrap_or_else(|| usage_error(TASK_USAGE, "task id required"));
+    let (storage, mut tasks, _) = load();
+    let task_id = resolve_task(&tasks, prefix).id;
+    if let Some(s) = &storage {
+        s.snapshot(&format!("task done {prefix}"));
     }
+    let now = Utc::now();
+    let task = tasks.iter_mut().find(|t| t.id == task_id).unwrap();
+    task.set_progress(Progress::Done, now);
+    let task_clone = task.clone();
+    sync_parent_progress(&mut tasks, task_id, now);
+    save_tasks(&storage, &tasks);
+    print_json(&task_clone);
+    Ok(())
 }
 
 fn cmd_task_delete(args: &[String]) -> io::Result<()> {
     let prefix = args
         .first()
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("task id required"));
+        .unwrap_or_else(|| usage_error(TASK_USAGE, "task id required"));
     let (storage, mut tasks, _) = load();
     if let Some(s) = &storage {
         s.snapshot(&format!("task delete {prefix}"));
@@ -276,19 +776,7 @@ fn cmd_task_delete(args: &[String]) -> io::Result<()> {
     let target_id = target.id;
     let title = target.title.clone();
 
-    // Cascade: collect all children.
-    let child_ids: Vec<Uuid> = children_of(&tasks, target_id)
-        .iter()
-        .map(|&i| tasks[i].id)
-        .collect();
-    let all_deleted: Vec<Uuid> = std::iter::once(target_id).chain(child_ids).collect();
-    let deleted_count = all_deleted.len();
-
-    tasks.retain(|t| !all_deleted.contains(&t.id));
-    // Clean up dependency references.
-    for task in &mut tasks {
-        task.dependencies.retain(|dep| !all_deleted.contains(dep));
-    }
+    let deleted_count = delete_with_children(&mut tasks, target_id);
     sync_parent_progress(&mut tasks, target_id, Utc::now());
 
     save_tasks(&storage, &tasks);
@@ -303,11 +791,46 @@ fn cmd_task_delete(args: &[String]) -> io::Result<()> {
 fn cmd_task_show(args: &[String]) -> io::Result<()> {
     let prefix = args
         .first()
+        .filter(|a| !a.starts_with("--"))
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("task id required"));
-    let (_, tasks, _) = load();
+        .unwrap_or_else(|| usage_error(TASK_USAGE, "task id required"));
+    check_flags(args, &["--json"], TASK_USAGE);
+    let (_, tasks, settings) = load();
     let task = resolve_task(&tasks, prefix);
-    print_json(task);
+    if has_flag(args, "--json") || !io::stdout().is_terminal() {
+        print_json(task);
+        return Ok(());
+    }
+
+    println!("ID: {}", task.id);
+    println!("{}", format_task_snapshot(task));
+    if let Some(assignee) = &task.assignee {
+        println!("Assignee: @{assignee}");
+    }
+    if let Some(parent) = task
+        .parent_id
+        .and_then(|pid| tasks.iter().find(|t| t.id == pid))
+    {
+        println!("Parent: {}  {}", &parent.id.to_string()[..8], parent.title);
+    }
+    for (heading, indices) in [
+        ("Sub-tasks", children_of(&tasks, task.id)),
+        ("Dependents", dependents_of(&tasks, task.id)),
+    ] {
+        if indices.is_empty() {
+            continue;
+        }
+        println!("\n{heading} ({}):", indices.len());
+        for i in indices {
+            let t = &tasks[i];
+            println!(
+                "  {}  {:<11}  {}",
+                &t.id.to_string()[..8],
+                settings.stage_label(t.progress),
+                t.title
+            );
+        }
+    }
     Ok(())
 }
 
@@ -316,13 +839,19 @@ fn cmd_task_show(args: &[String]) -> io::Result<()> {
 // ---------------------------------------------------------------------------
 
 fn run_bucket_cmd(args: &[String]) -> io::Result<()> {
-    let sub = args.first().map(|s| s.as_str()).unwrap_or("list");
-    match sub {
+    if wants_help(args) {
+        print_usage(BUCKET_USAGE, BUCKET_EXAMPLES);
+        return Ok(());
+    }
+    let Some(sub) = args.first() else {
+        usage_error(BUCKET_USAGE, "Missing bucket command");
+    };
+    match sub.as_str() {
         "list" | "ls" => cmd_bucket_list(),
         "add" | "create" => cmd_bucket_add(&args[1..]),
         "rename" => cmd_bucket_rename(&args[1..]),
         "delete" | "rm" => cmd_bucket_delete(&args[1..]),
-        other => die(&format!("Unknown bucket command: {other}")),
+        other => usage_error(BUCKET_USAGE, &format!("Unknown bucket command: {other}")),
     }
 }
 
@@ -333,10 +862,12 @@ fn cmd_bucket_list() -> io::Result<()> {
 }
 
 fn cmd_bucket_add(args: &[String]) -> io::Result<()> {
+    check_flags(args, &["--description"], BUCKET_USAGE);
     let name = args
         .first()
+        .filter(|a| !a.starts_with("--"))
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("bucket name required"));
+        .unwrap_or_else(|| usage_error(BUCKET_USAGE, "bucket name required"));
     let (storage, _, mut settings) = load();
     if let Some(s) = &storage {
         s.snapshot(&format!("bucket add {name}"));
@@ -357,7 +888,7 @@ fn cmd_bucket_add(args: &[String]) -> io::Result<()> {
     };
     print_json(&bucket);
     settings.buckets.push(bucket);
-    save_settings(&storage, &settings);
+    save_settings(&storage, &mut settings);
     Ok(())
 }
 
@@ -365,11 +896,11 @@ fn cmd_bucket_rename(args: &[String]) -> io::Result<()> {
     let old = args
         .first()
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("old name required"));
+        .unwrap_or_else(|| usage_error(BUCKET_USAGE, "old name required"));
     let new = args
         .get(1)
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("new name required"));
+        .unwrap_or_else(|| usage_error(BUCKET_USAGE, "new name required"));
     let (storage, mut tasks, mut settings) = load();
     if let Some(s) = &storage {
         s.snapshot(&format!("bucket rename {old} {new}"));
@@ -392,7 +923,7 @@ fn cmd_bucket_rename(args: &[String]) -> io::Result<()> {
         }
     }
 
-    save_settings(&storage, &settings);
+    save_settings(&storage, &mut settings);
     if moved > 0 {
         save_tasks(&storage, &tasks);
     }
@@ -409,7 +940,7 @@ fn cmd_bucket_delete(args: &[String]) -> io::Result<()> {
     let name = args
         .first()
         .map(|s| s.as_str())
-        .unwrap_or_else(|| die("bucket name required"));
+        .unwrap_or_else(|| usage_error(BUCKET_USAGE, "bucket name required"));
     let (storage, mut tasks, mut settings) = load();
     if let Some(s) = &storage {
         s.snapshot(&format!("bucket delete {name}"));
@@ -440,7 +971,7 @@ fn cmd_bucket_delete(args: &[String]) -> io::Result<()> {
         }
     }
 
-    save_settings(&storage, &settings);
+    save_settings(&storage, &mut settings);
     if moved > 0 {
         save_tasks(&storage, &tasks);
     }
@@ -458,11 +989,18 @@ fn cmd_bucket_delete(args: &[String]) -> io::Result<()> {
 // ---------------------------------------------------------------------------
 
 fn run_settings_cmd(args: &[String]) -> io::Result<()> {
+    if wants_help(args) {
+        print_usage(SETTINGS_USAGE, "");
+        return Ok(());
+    }
     let sub = args.first().map(|s| s.as_str()).unwrap_or("show");
     match sub {
         "show" | "get" => cmd_settings_show(),
         "update" | "set" => cmd_settings_update(&args[1..]),
-        other => die(&format!("Unknown settings command: {other}")),
+        other => usage_error(
+            SETTINGS_USAGE,
+            &format!("Unknown settings command: {other}"),
+        ),
     }
 }
 
@@ -483,6 +1021,42 @@ fn parse_bool_flag(val: &str) -> bool {
 }
 
 fn cmd_settings_update(args: &[String]) -> io::Result<()> {
+    check_flags(
+        args,
+        &[
+            "--owner-name",
+            "--ai-enabled",
+            "--openai-api-key",
+            "--anthropic-api-key",
+            "--use-keychain",
+            "--custom-models",
+            "--model",
+            "--timeout",
+            "--ai-concurrency",
+            "--show-backlog",
+            "--show-todo",
+            "--show-in-progress",
+            "--show-done",
+            "--card-max-subissues",
+            "--email-suggestions",
+            "--email-body-max-chars",
+            "--auto-escalate",
+            "--ai-lock-bucket",
+            "--ai-lock-priority",
+            "--ai-lock-due-date",
+            "--ai-context-visible-only",
+            "--system-prompt",
+            "--date-format",
+            "--week-start",
+            "--utc-offset",
+            "--snapshot-retention",
+            "--stage-labels",
+            "--work-days",
+            "--holidays",
+            "--workday-end",
+        ],
+        SETTINGS_USAGE,
+    );
     let (storage, _, mut settings) = load();
     if let Some(s) = &storage {
         s.snapshot("settings update");
@@ -500,13 +1074,56 @@ fn cmd_settings_update(args: &[String]) -> io::Result<()> {
     if let Some(v) = find_flag(args, "--anthropic-api-key") {
         settings.anthropic_api_key = v;
     }
+    if let Some(v) = find_flag(args, "--use-keychain") {
+        settings.use_keychain = parse_bool_flag(&v);
+        if settings.use_keychain && !secrets::available() {
+            eprintln!("Warning: no keychain available, keys stay in the settings file");
+            settings.use_keychain = false;
+        }
+    }
     if let Some(v) = find_flag(args, "--model") {
         settings.model = v;
     }
+    if let Some(v) = find_flag(args, "--custom-models") {
+        settings.custom_models.clear();
+        for name in v.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if !settings.custom_models.iter().any(|m| m == name) {
+                settings.custom_models.push(name.to_string());
+            }
+        }
+    }
     if let Some(v) = find_flag(args, "--timeout") {
         settings.timeout_secs = v
             .parse::<u64>()
-            .unwrap_or_else(|_| die(&format!("Invalid timeout: {v}")));
+            .ok()
+            .filter(|n| (MIN_TIMEOUT_SECS..=MAX_TIMEOUT_SECS).contains(n))
+            .unwrap_or_else(|| {
+                die(&format!(
+                    "Invalid timeout: {v} (expected {MIN_TIMEOUT_SECS}-{MAX_TIMEOUT_SECS} seconds)"
+                ))
+            });
+    }
+    if let Some(v) = find_flag(args, "--ai-concurrency") {
+        settings.ai_concurrency = v
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=MAX_AI_CONCURRENCY).contains(n))
+            .unwrap_or_else(|| {
+                die(&format!(
+                    "Invalid AI concurrency: {v} (expected 1-{MAX_AI_CONCURRENCY})"
+                ))
+            });
+    }
+    if let Some(v) = find_flag(args, "--snapshot-retention") {
+        settings.snapshot_retention = v
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=MAX_SNAPSHOT_RETENTION).contains(n))
+            .unwrap_or_else(|| {
+                die(&format!(
+                    "Invalid snapshot retention: {v} (expected 1-{MAX_SNAPSHOT_RETENTION})"
+                ))
+            });
     }
     if let Some(v) = find_flag(args, "--show-backlog") {
         settings.show_backlog = parse_bool_flag(&v);
@@ -520,11 +1137,97 @@ fn cmd_settings_update(args: &[String]) -> io::Result<()> {
     if let Some(v) = find_flag(args, "--show-done") {
         settings.show_done = parse_bool_flag(&v);
     }
+    if let Some(v) = find_flag(args, "--card-max-subissues") {
+        settings.card_max_subissues = v
+            .parse::<usize>()
+            .unwrap_or_else(|_| die(&format!("Invalid sub-issue count: {v}")));
+    }
     if let Some(v) = find_flag(args, "--email-suggestions") {
         settings.email_suggestions_enabled = parse_bool_flag(&v);
     }
+    if let Some(v) = find_flag(args, "--email-body-max-chars") {
+        settings.email_body_max_chars = v
+            .parse::<usize>()
+            .unwrap_or_else(|_| die(&format!("Invalid character count: {v}")));
+    }
+    if let Some(v) = find_flag(args, "--auto-escalate") {
+        settings.auto_escalate = parse_bool_flag(&v);
+    }
+    if let Some(v) = find_flag(args, "--ai-lock-bucket") {
+        settings.ai_lock_bucket = parse_bool_flag(&v);
+    }
+    if let Some(v) = find_flag(args, "--ai-lock-priority") {
+        settings.ai_lock_priority = parse_bool_flag(&v);
+    }
+    if let Some(v) = find_flag(args, "--ai-lock-due-date") {
+        settings.ai_lock_due_date = parse_bool_flag(&v);
+    }
+    if let Some(v) = find_flag(args, "--ai-context-visible-only") {
+        settings.ai_context_visible_only = parse_bool_flag(&v);
+    }
+    if let Some(v) = find_flag(args, "--system-prompt") {
+        if v.trim().chars().count() > SYSTEM_PROMPT_MAX_CHARS {
+            die(&format!(
+                "System prompt is too long (max {} characters)",
+                SYSTEM_PROMPT_MAX_CHARS
+            ));
+        }
+        settings.system_prompt = v.trim().to_string();
+    }
+    if let Some(v) = find_flag(args, "--date-format") {
+        if !is_valid_date_format(&v) {
+            die(&format!(
+                "Invalid date format: {v} (expected e.g. %d/%m/%Y)"
+            ));
+        }
+        settings.date_format = v;
+    }
+    if let Some(v) = find_flag(args, "--stage-labels") {
+        settings.stage_labels = parse_stage_labels(&v).unwrap_or_else(|| {
+            die(&format!(
+                "Invalid stage labels: {v} (expected at most four names: Backlog,Todo,In progress,Done)"
+            ))
+        });
+    }
+    if let Some(v) = find_flag(args, "--work-days") {
+        settings.work_days = parse_work_days(&v).unwrap_or_else(|| {
+            die(&format!(
+                "Invalid work days: {v} (expected e.g. mon-fri or mon,wed,fri)"
+            ))
+        });
+    }
+    if let Some(v) = find_flag(args, "--holidays") {
+        settings.holidays = parse_holidays(&v).unwrap_or_else(|| {
+            die(&format!(
+                "Invalid holidays: {v} (expected YYYY-MM-DD dates separated by commas)"
+            ))
+        });
+    }
+    if let Some(v) = find_flag(args, "--workday-end") {
+        settings.workday_end = parse_workday_end(&v).unwrap_or_else(|| {
+            die(&format!(
+                "Invalid workday end: {v} (expected e.g. 17:30 or none)"
+            ))
+        });
+    }
+    if let Some(v) = find_flag(args, "--week-start") {
+        settings.week_start = match v.to_ascii_lowercase().as_str() {
+            "monday" | "mon" => WeekStart::Monday,
+            "sunday" | "sun" => WeekStart::Sunday,
+            _ => die(&format!(
+                "Invalid week start: {v} (expected monday or sunday)"
+            )),
+        };
+    }
+    if let Some(v) = find_flag(args, "--utc-offset") {
+        settings.utc_offset_minutes = parse_utc_offset(&v).unwrap_or_else(|| {
+            die(&format!(
+                "Invalid UTC offset: {v} (expected e.g. +05:30, -8 or local)"
+            ))
+        });
+    }
 
-    save_settings(&storage, &settings);
+    save_settings(&storage, &mut settings);
     print_json(&settings);
     Ok(())
 }
@@ -551,16 +1254,564 @@ fn cmd_history() -> io::Result<()> {
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Export subcommands
+// ---------------------------------------------------------------------------
+
+fn run_export_cmd(args: &[String]) -> io::Result<()> {
+    if wants_help(args) {
+        print_usage(EXPORT_USAGE, "");
+        return Ok(());
+    }
+    check_flags(args, &["--format", "--bucket", "--all"], EXPORT_USAGE);
+    let format = find_flag(args, "--format")
+        .unwrap_or_else(|| usage_error(EXPORT_USAGE, "Missing --format"));
+    let format = match format.to_ascii_lowercase().as_str() {
+        "github-issues" | "github" => "github-issues",
+        "linear" => "linear",
+        "ics" | "ical" => "ics",
+        other => usage_error(EXPORT_USAGE, &format!("Unknown export format: {other}")),
+    };
+    let (_, tasks, settings) = load();
+    let all = has_flag(args, "--all");
+    let bucket = find_flag(args, "--bucket").map(|b| resolve_bucket(&settings, &b));
+    let included: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| all || settings.is_progress_visible(t.progress))
+        .filter(|t| bucket.as_ref().is_none_or(|b| &t.bucket == b))
+        .collect();
+
+    // Parents first, each followed by its sub-issues, so importers that
+    // resolve parents by row see them before their children.
+    let mut ordered: Vec<&Task> = Vec::new();
+    for task in &included {
+        let parent_included = task
+            .parent_id
+            .is_some_and(|pid| included.iter().any(|t| t.id == pid));
+        if parent_included {
+            continue;
+        }
+        ordered.push(task);
+        ordered.extend(included.iter().filter(|t| t.parent_id == Some(task.id)));
+    }
+
+    if format == "ics" {
+        print!("{}", ics_calendar(&ordered, &tasks, &settings));
+    } else if format == "github-issues" {
+        let issues: Vec<serde_json::Value> = ordered
+            .iter()
+            .map(|task| github_issue(task, &tasks, &settings))
+            .collect();
+        print_json(&issues);
+    } else {
+        println!(
+            "ID,Title,Description,Status,Priority,Labels,Assignee,Due Date,Created,Parent issue"
+        );
+        for task in ordered {
+            let parent = task
+                .parent_id
+                .map(|pid| pid.to_string()[..8].to_string())
+                .unwrap_or_default();
+            let fields = [
+                task.id.to_string()[..8].to_string(),
+                task.title.clone(),
+                export_body(task, &tasks),
+                linear_status(task.progress).to_string(),
+                linear_priority(task.priority).to_string(),
+                task.bucket.clone(),
+                task.assignee.clone().unwrap_or_default(),
+                task.due_date.map(|d| d.to_string()).unwrap_or_default(),
+                task.created_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                parent,
+            ];
+            let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            println!("{}", row.join(","));
+        }
+    }
+    Ok(())
+}
+
+/// Description followed by a checklist of the sub-issues, ticked when done.
+fn export_body(task: &Task, tasks: &[Task]) -> String {
+    let mut body = task.description.trim().to_string();
+    let children = children_of(tasks, task.id);
+    if !children.is_empty() {
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str("### Sub-issues\n");
+        for i in children {
+            let child = &tasks[i];
+            let done = matches!(child.progress, Progress::Done | Progress::Archived);
+            body.push_str(&format!(
+                "- [{}] {}\n",
+                if done { "x" } else { " " },
+                child.title
+            ));
+        }
+    }
+    body.trim_end().to_string()
+}
+
+fn github_issue(task: &Task, tasks: &[Task], settings: &AiSettings) -> serde_json::Value {
+    let mut body = export_body(task, tasks);
+    // GitHub issues have no fields for these, so they go under the body.
+    let mut meta = vec![format!(
+        "**Status:** {}",
+        settings.stage_label(task.progress)
+    )];
+    if let Some(due) = task.due_date {
+        meta.push(format!("**Due:** {due}"));
+    }
+    if let Some(assignee) = &task.assignee {
+        meta.push(format!("**Assignee:** {assignee}"));
+    }
+    if let Some(parent) = task
+        .parent_id
+        .and_then(|pid| tasks.iter().find(|t| t.id == pid))
+    {
+        meta.push(format!("**Sub-issue of:** {}", parent.title));
+    }
+    if !body.is_empty() {
+        body.push_str("\n\n");
+    }
+    body.push_str(&meta.join("\n"));
+    serde_json::json!({
+        "title": task.title,
+        "body": body,
+        "labels": [
+            task.bucket.clone(),
+            format!("priority: {}", task.priority.title().to_ascii_lowercase()),
+        ],
+    })
+}
+
+/// An all-day VEVENT on the due date of each task that has one. Events rather
+/// than VTODOs, because most calendar apps ignore to-dos in subscriptions.
+fn ics_calendar(ordered: &[&Task], tasks: &[Task], settings: &AiSettings) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//aipm//aipm//EN",
+        "CALSCALE:GREGORIAN",
+        "X-WR-CALNAME:aipm",
+    ] {
+        ics_line(&mut out, line);
+    }
+    for task in ordered {
+        let Some(due) = task.due_date else {
+            continue;
+        };
+        let summary = if matches!(task.progress, Progress::Done | Progress::Archived) {
+            format!("\u{2713} {}", task.title)
+        } else {
+            task.title.clone()
+        };
+        let mut description = export_body(task, tasks);
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&format!(
+            "Status: {} \u{00b7} Priority: {}",
+            settings.stage_label(task.progress),
+            task.priority.title()
+        ));
+        // VEVENT has no done state; that shows as the check mark instead.
+        let status = if task.progress == Progress::Backlog {
+            "TENTATIVE"
+        } else {
+            "CONFIRMED"
+        };
+        let end = due.succ_opt().unwrap_or(due);
+        for line in [
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@aipm", task.id),
+            format!("DTSTAMP:{stamp}"),
+            format!("LAST-MODIFIED:{}", task.updated_at.format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART;VALUE=DATE:{}", due.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+            format!("SUMMARY:{}", ics_escape(&summary)),
+            format!("DESCRIPTION:{}", ics_escape(&description)),
+            format!("CATEGORIES:{}", ics_escape(&task.bucket)),
+            format!("STATUS:{status}"),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ] {
+            ics_line(&mut out, &line);
+        }
+    }
+    ics_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Append `line` with CRLF, folded so no line exceeds 75 bytes (RFC 5545).
+fn ics_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn linear_status(progress: Progress) -> &'static str {
+    match progress {
+        Progress::Backlog => "Backlog",
+        Progress::Todo => "Todo",
+        Progress::InProgress => "In Progress",
+        Progress::Done | Progress::Archived => "Done",
+    }
+}
+
+fn linear_priority(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Low => "Low",
+        Priority::Medium => "Medium",
+        Priority::High => "High",
+        Priority::Critical => "Urgent",
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Status / Path subcommands
+// ---------------------------------------------------------------------------
+
+/// Runs from shell prompts on every command, so unlike `load()` it doesn't
+/// look up keychain keys or save anything.
+fn run_status_cmd(args: &[String]) -> io::Result<()> {
+    if wants_help(args) {
+        print_usage(STATUS_USAGE, "");
+        return Ok(());
+    }
+    check_flags(args, &["--oneline", "--format"], STATUS_USAGE);
+    let Some(storage) = Storage::new() else {
+        return Ok(());
+    };
+    let tasks = storage.load_tasks().unwrap_or_default();
+    let settings = storage.read_settings_file().unwrap_or_default();
+    let today = settings.today();
+    let count = |pred: &dyn Fn(&Task) -> bool| {
+        tasks
+            .iter()
+            .filter(|t| !matches!(t.progress, Progress::Done | Progress::Archived) && pred(t))
+            .count()
+    };
+    let overdue = count(&|t| t.due_date.is_some_and(|d| settings.is_overdue(d)));
+    let due_today = count(&|t| t.due_date == Some(today) && !settings.is_overdue(today));
+    let in_progress = count(&|t| t.progress == Progress::InProgress);
+    let todo = count(&|t| t.progress == Progress::Todo);
+    let backlog = count(&|t| t.progress == Progress::Backlog);
+
+    if let Some(template) = find_flag(args, "--format") {
+        println!(
+            "{}",
+            template
+                .replace("{overdue}", &overdue.to_string())
+                .replace("{due_today}", &due_today.to_string())
+                .replace("{in_progress}", &in_progress.to_string())
+                .replace("{todo}", &todo.to_string())
+                .replace("{backlog}", &backlog.to_string())
+        );
+    } else if has_flag(args, "--oneline") {
+        let parts: Vec<String> = [
+            (overdue, "overdue"),
+            (due_today, "due today"),
+            (in_progress, "in progress"),
+        ]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, label)| format!("{n} {label}"))
+        .collect();
+        if !parts.is_empty() {
+            println!("aipm: {}", parts.join(" · "));
+        }
+    } else {
+        print_json(&serde_json::json!({
+            "overdue": overdue,
+            "due_today": due_today,
+            "in_progress": in_progress,
+            "todo": todo,
+            "backlog": backlog,
+        }));
+    }
+    Ok(())
+}
+
+fn run_path_cmd(args: &[String]) -> io::Result<()> {
+    if wants_help(args) {
+        print_usage(PATH_USAGE, "");
+        return Ok(());
+    }
+    check_flags(args, &["--json"], PATH_USAGE);
+    let Some(storage) = Storage::new() else {
+        die("Could not find a data directory; set HOME or AIPM_DATA_DIR");
+    };
+    let paths = storage.paths();
+    let names: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    match names.as_slice() {
+        [] if has_flag(args, "--json") => {
+            let map: serde_json::Map<String, serde_json::Value> = paths
+                .iter()
+                .map(|(name, path)| (name.to_string(), path.display().to_string().into()))
+                .collect();
+            print_json(&map);
+        }
+        [] => {
+            for (name, path) in &paths {
+                println!("{name:<13} {}", path.display());
+            }
+        }
+        [name] => match paths.iter().find(|(n, _)| n == name) {
+            Some((_, path)) => println!("{}", path.display()),
+            None => usage_error(PATH_USAGE, &format!("Unknown path: {name}")),
+        },
+        _ => usage_error(PATH_USAGE, "Expected at most one path name"),
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Stats subcommands
+// ---------------------------------------------------------------------------
+
+fn run_stats_cmd(args: &[String]) -> io::Result<()> {
+    if wants_help(args) {
+        print_usage(STATS_USAGE, "");
+        return Ok(());
+    }
+    check_flags(
+        args,
+        &["--cycle-time", "--throughput", "--estimates", "--weeks"],
+        STATS_USAGE,
+    );
+    let mut cycle = has_flag(args, "--cycle-time");
+    let mut throughput = has_flag(args, "--throughput");
+    let mut estimates = has_flag(args, "--estimates");
+    if !cycle && !throughput && !estimates {
+        cycle = true;
+        throughput = true;
+        estimates = true;
+    }
+    let weeks = match find_flag(args, "--weeks") {
+        Some(v) => v
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=MAX_STATS_WEEKS).contains(n))
+            .unwrap_or_else(|| {
+                usage_error(
+                    STATS_USAGE,
+                    &format!("Invalid --weeks: {v} (expected 1-{MAX_STATS_WEEKS})"),
+                )
+            }),
+        None => 8,
+    };
+
+    let (_, tasks, settings) = load();
+    let buckets = stats_buckets(&tasks, &settings);
+    if cycle {
+        print_cycle_time(&tasks, &buckets);
+    }
+    if throughput {
+        if cycle {
+            println!();
+        }
+        print_throughput(&tasks, weeks, &settings);
+    }
+    if estimates {
+        if cycle || throughput {
+            println!();
+        }
+        print_estimates(&tasks, &buckets);
+    }
+    Ok(())
+}
+
+/// Configured buckets in order, then any bucket names only found on tasks.
+fn stats_buckets(tasks: &[Task], settings: &AiSettings) -> Vec<String> {
+    let mut buckets: Vec<String> = settings.buckets.iter().map(|b| b.name.clone()).collect();
+    for task in tasks {
+        if !buckets.contains(&task.bucket) {
+            buckets.push(task.bucket.clone());
+        }
+    }
+    buckets
+}
+
+fn print_cycle_time(tasks: &[Task], buckets: &[String]) {
+    println!("Cycle time (first In progress -> Done)");
+    println!(
+        "{:<20} {:>6} {:>10} {:>10}",
+        "Bucket", "Tasks", "Average", "Median"
+    );
+    let mut all: Vec<i64> = Vec::new();
+    for bucket in buckets {
+        let mut secs: Vec<i64> = tasks
+            .iter()
+            .filter(|t| &t.bucket == bucket)
+            .filter_map(|t| t.cycle_time())
+            .map(|d| d.num_seconds())
+            .collect();
+        if secs.is_empty() {
+            continue;
+        }
+        print_cycle_row(bucket, &mut secs);
+        all.extend(secs);
+    }
+    if all.is_empty() {
+        println!("(no completed tasks with a recorded start)");
+    } else {
+        print_cycle_row("All", &mut all);
+    }
+}
+
+fn print_cycle_row(label: &str, secs: &mut [i64]) {
+    secs.sort_unstable();
+    let avg = secs.iter().sum::<i64>() / secs.len() as i64;
+    let mid = secs.len() / 2;
+    let median = if secs.len() % 2 == 0 {
+        (secs[mid - 1] + secs[mid]) / 2
+    } else {
+        secs[mid]
+    };
+    println!(
+        "{:<20} {:>6} {:>10} {:>10}",
+        label,
+        secs.len(),
+        format_span(avg),
+        format_span(median)
+    );
+}
+
+fn format_span(secs: i64) -> String {
+    let hours = secs as f64 / 3600.0;
+    if hours < 24.0 {
+        format!("{:.1}h", hours)
+    } else {
+        format!("{:.1}d", hours / 24.0)
+    }
+}
+
+/// Ten years of weeks; more is neither readable nor always a valid date.
+const MAX_STATS_WEEKS: usize = 520;
+
+fn print_throughput(tasks: &[Task], weeks: usize, settings: &AiSettings) {
+    let this_week = settings.week_start.week_of(settings.today());
+    let week_starts: Vec<NaiveDate> = (0..weeks)
+        .rev()
+        .filter_map(|i| this_week.checked_sub_signed(chrono::Duration::weeks(i as i64)))
+        .collect();
+    let weeks = week_starts.len();
+
+    let mut counts = vec![0usize; weeks];
+    for task in tasks {
+        let Some(done) = task.completed_at() else {
+            continue;
+        };
+        let day = settings.to_local(done).date_naive();
+        if let Some(i) = week_starts
+            .iter()
+            .rposition(|start| day >= *start && day < *start + chrono::Duration::weeks(1))
+        {
+            counts[i] += 1;
+        }
+    }
+
+    println!("Throughput (tasks completed per week)");
+    println!("{:<20} {:>6}", "Week of", "Done");
+    for (start, count) in week_starts.iter().zip(&counts) {
+        println!("{:<20} {:>6}", start.format("%Y-%m-%d"), count);
+    }
+    let total: usize = counts.iter().sum();
+    println!(
+        "{:<20} {:>6}",
+        format!("Avg/week ({weeks}w)"),
+        format!("{:.1}", total as f64 / weeks as f64)
+    );
+}
+
+fn print_estimates(tasks: &[Task], buckets: &[String]) {
+    println!("Estimates (tasks with both estimate and actual)");
+    println!(
+        "{:<20} {:>6} {:>10} {:>10} {:>8}",
+        "Bucket", "Tasks", "Estimated", "Actual", "Ratio"
+    );
+    let row = |label: &str, pairs: &[(f32, f32)]| {
+        let est: f32 = pairs.iter().map(|(e, _)| e).sum();
+        let act: f32 = pairs.iter().map(|(_, a)| a).sum();
+        let ratio = if est > 0.0 {
+            format!("{:.2}x", act / est)
+        } else {
+            "—".to_string()
+        };
+        println!(
+            "{:<20} {:>6} {:>10} {:>10} {:>8}",
+            label,
+            pairs.len(),
+            format_hours(est),
+            format_hours(act),
+            ratio
+        );
+    };
+
+    let mut all: Vec<(f32, f32)> = Vec::new();
+    for bucket in buckets {
+        let pairs: Vec<(f32, f32)> = tasks
+            .iter()
+            .filter(|t| &t.bucket == bucket)
+            .filter_map(|t| Some((t.estimate_hours?, t.actual_hours?)))
+            .collect();
+        if pairs.is_empty() {
+            continue;
+        }
+        row(bucket, &pairs);
+        all.extend(pairs);
+    }
+    if all.is_empty() {
+        println!("(no tasks with both an estimate and an actual)");
+    } else {
+        row("All", &all);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Suggestions subcommands
 // ---------------------------------------------------------------------------
 
 fn run_suggestions_cmd(args: &[String]) -> io::Result<()> {
+    if wants_help(args) {
+        print_usage(SUGGESTIONS_USAGE, "");
+        return Ok(());
+    }
     let sub = args.first().map(|s| s.as_str()).unwrap_or("list");
     match sub {
         "list" | "ls" => cmd_suggestions_list(),
         "sync" => cmd_suggestions_sync(&args[1..]),
-        other => die(&format!("Unknown suggestions command: {other}")),
+        other => usage_error(
+            SUGGESTIONS_USAGE,
+            &format!("Unknown suggestions command: {other}"),
+        ),
     }
 }
 
@@ -583,20 +1834,31 @@ fn cmd_suggestions_list() -> io::Result<()> {
         println!("Date: {}", email.date);
 
         // Try AI filtering
-        if let Ok(Some(suggestion)) = crate::llm::filter_email_for_suggestions(
+        match crate::llm::filter_email_for_suggestions(
             &settings,
             &email.subject,
             &email.sender,
             email.content.as_deref().unwrap_or(""),
         ) {
-            println!("✓ Actionable:");
-            println!("  Title: {}", suggestion.title);
-            println!("  Priority: {}", suggestion.priority);
-            if !suggestion.description.is_empty() {
-                println!("  Description: {}", suggestion.description);
+            Ok(Some(suggestion)) => {
+                println!("✓ Actionable:");
+                println!("  Title: {}", suggestion.title);
+                println!("  Priority: {}", suggestion.priority);
+                if !suggestion.description.is_empty() {
+                    println!("  Description: {}", suggestion.description);
+                }
+                for sub in &suggestion.sub_tasks {
+                    println!("  - {sub}");
+                }
+            }
+            Ok(None) => println!("✗ Not actionable (filtered out)"),
+            Err(err) => {
+                println!("! Not filtered: {err}");
+                if crate::llm::is_timeout(&err) {
+                    eprintln!("Stopping after a timeout; run again to filter the rest.");
+                    break;
+                }
             }
-        } else {
-            println!("✗ Not actionable (filtered out)");
         }
     }
 
@@ -604,6 +1866,7 @@ fn cmd_suggestions_list() -> io::Result<()> {
 }
 
 fn cmd_suggestions_sync(args: &[String]) -> io::Result<()> {
+    check_flags(args, &["--limit"], SUGGESTIONS_USAGE);
     let (storage, mut tasks, settings) = load();
     if let Some(s) = &storage {
         s.snapshot("suggestions sync");
@@ -622,14 +1885,33 @@ fn cmd_suggestions_sync(args: &[String]) -> io::Result<()> {
         10
     };
 
+    // Emails that already became a task, or were dismissed in the TUI, are
+    // not turned into tasks again.
+    let mut skipped: HashSet<String> = tasks.iter().filter_map(|t| t.email_id.clone()).collect();
+    if let Some(s) = &storage {
+        skipped.extend(s.load_suggestions().1);
+    }
+
     let mut created = 0;
-    for email in emails.iter().filter(|e| !e.is_read).take(limit) {
-        if let Ok(Some(suggestion)) = crate::llm::filter_email_for_suggestions(
+    for email in emails
+        .iter()
+        .filter(|e| !e.is_read && !skipped.contains(&e.id))
+        .take(limit)
+    {
+        let filtered = crate::llm::filter_email_for_suggestions(
             &settings,
             &email.subject,
             &email.sender,
             email.content.as_deref().unwrap_or(""),
-        ) {
+        );
+        if let Err(err) = &filtered {
+            eprintln!("Warning: could not filter \"{}\": {err}", email.subject);
+            if crate::llm::is_timeout(err) {
+                eprintln!("Stopping after a timeout; run again to sync the rest.");
+                break;
+            }
+        }
+        if let Ok(Some(suggestion)) = filtered {
             let priority = match suggestion.priority.to_ascii_lowercase().as_str() {
                 "low" => Priority::Low,
                 "medium" => Priority::Medium,
@@ -652,8 +1934,20 @@ fn cmd_suggestions_sync(args: &[String]) -> io::Result<()> {
             );
             task.priority = priority;
             task.progress = Progress::Backlog;
+            task.email_id = Some(email.id.clone());
+            task.email_subject = Some(email.subject.clone());
 
             println!("Created task from email: {}", task.title);
+            for (pos, title) in suggestion.sub_tasks.iter().enumerate() {
+                let mut child = Task::new(task.bucket.clone(), title.clone(), now);
+                child.parent_id = Some(task.id);
+                child.priority = priority;
+                child.sort_order = Some(pos as u32);
+                child.email_id = task.email_id.clone();
+                child.email_subject = task.email_subject.clone();
+                println!("  Sub-issue: {title}");
+                tasks.push(child);
+            }
             tasks.push(task);
             created += 1;
         }
@@ -665,3 +1959,39 @@ fn cmd_suggestions_sync(args: &[String]) -> io::Result<()> {
     }));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("Plain title"), "Plain title");
+        assert_eq!(csv_field("Milk, eggs"), "\"Milk, eggs\"");
+        assert_eq!(csv_field("Say \"hi\""), "\"Say \"\"hi\"\"\"");
+        assert_eq!(csv_field("Two\nlines"), "\"Two\nlines\"");
+    }
+
+    #[test]
+    fn ics_text_is_escaped_and_folded_at_75_octets() {
+        assert_eq!(ics_escape("a,b;c\\d\r\ne\nf"), r"a\,b\;c\\d\ne\nf");
+
+        let line = format!("SUMMARY:{}", "x".repeat(100));
+        let mut out = String::new();
+        ics_line(&mut out, &line);
+        let lines: Vec<&str> = out.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 75);
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(out.replace("\r\n ", ""), format!("{line}\r\n"));
+
+        // A multi-byte character is never split across lines.
+        let mut out = String::new();
+        ics_line(&mut out, &format!("SUMMARY:{}", "é".repeat(40)));
+        for line in out.split_terminator("\r\n") {
+            assert!(line.len() <= 75);
+        }
+        let unfolded = out.replace("\r\n ", "");
+        assert_eq!(unfolded, format!("SUMMARY:{}\r\n", "é".repeat(40)));
+    }
+}
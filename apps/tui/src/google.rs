@@ -1,15 +1,20 @@
 use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::net::TcpListener;
 use std::path::{Path, PathBuf};
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use sha2::{Digest, Sha256};
 
+use crate::log;
+
 const AUTH_ENDPOINT: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 const TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
 const SCOPES: &str = "https://www.googleapis.com/auth/gmail.readonly https://www.googleapis.com/auth/calendar.readonly";
 
+/// Part of every error that only reconnecting the Google account can fix.
+pub const RECONNECT_HINT: &str = "reconnect Google in Settings";
+
 const PLACEHOLDER_CLIENT_ID: &str = "PLACEHOLDER.apps.googleusercontent.com";
 const BUNDLED_CLIENT_ID: Option<&str> = option_env!("GOOGLE_CLIENT_ID");
 const BUNDLED_CLIENT_SECRET: Option<&str> = option_env!("GOOGLE_CLIENT_SECRET");
@@ -254,7 +259,7 @@ fn exchange_code(code: &str, verifier: &str, redirect_uri: &str) -> Result<Googl
             ("grant_type", "authorization_code"),
             ("code_verifier", verifier),
         ])
-        .map_err(|e| format!("Token exchange failed: {e}"))?;
+        .map_err(|e| format!("Token exchange failed: {}", log::redact(&e.to_string())))?;
 
     let json: serde_json::Value = resp.into_json().map_err(|e| e.to_string())?;
     Ok(GoogleToken {
@@ -280,7 +285,20 @@ fn refresh_access_token(refresh_token: &str) -> Result<GoogleToken, String> {
             ("client_secret", &csec),
             ("grant_type", "refresh_token"),
         ])
-        .map_err(|e| format!("Token refresh failed: {e}"))?;
+        .map_err(|e| match e {
+            ureq::Error::Status(code @ (400 | 401), r) => {
+                let body = r.into_string().unwrap_or_default();
+                if body.contains("invalid_grant") {
+                    format!("Google sign-in expired or was revoked: {RECONNECT_HINT}")
+                } else {
+                    format!(
+                        "Token refresh failed: HTTP {code}: {}",
+                        log::redact(body.trim())
+                    )
+                }
+            }
+            e => format!("Token refresh failed: {}", log::redact(&e.to_string())),
+        })?;
 
     let json: serde_json::Value = resp.into_json().map_err(|e| e.to_string())?;
     Ok(GoogleToken {
@@ -305,7 +323,7 @@ pub fn get_upcoming_events(token: &str, days: u32) -> Result<Vec<CalendarEvent>,
         .query("orderBy", "startTime")
         .query("maxResults", "100")
         .call()
-        .map_err(|e| format!("Calendar API error: {e}"))?;
+        .map_err(|e| format!("Calendar API error: {}", log::redact(&e.to_string())))?;
 
     let json: serde_json::Value = resp.into_json().map_err(|e| e.to_string())?;
     let empty = Vec::new();
@@ -348,13 +366,40 @@ pub fn get_upcoming_events(token: &str, days: u32) -> Result<Vec<CalendarEvent>,
     Ok(events)
 }
 
+/// True for errors the user has to act on, as opposed to outages and
+/// network trouble that the next poll may not hit.
+pub fn needs_reconnect(err: &str) -> bool {
+    err.contains(RECONNECT_HINT)
+}
+
+fn gmail_error(err: ureq::Error) -> String {
+    match err {
+        ureq::Error::Status(401, _) => format!("Gmail sign-in expired: {RECONNECT_HINT}"),
+        ureq::Error::Status(code, r) => {
+            let body = r.into_string().unwrap_or_default();
+            // Gmail reports quota trouble as 403 too.
+            if code == 429 || code >= 500 || body.contains("rateLimitExceeded") {
+                format!("Gmail unavailable (HTTP {code}), retrying")
+            } else if code == 403 {
+                format!("Gmail read access not granted: {RECONNECT_HINT} and allow reading email")
+            } else {
+                format!(
+                    "Gmail list error: HTTP {code}: {}",
+                    log::redact(body.trim())
+                )
+            }
+        }
+        ureq::Error::Transport(t) => format!("Can't reach Gmail: {}", log::redact(&t.to_string())),
+    }
+}
+
 pub fn get_recent_emails(token: &str, limit: u32) -> Result<Vec<Email>, String> {
     let resp = ureq::get("https://gmail.googleapis.com/gmail/v1/users/me/messages")
         .set("Authorization", &format!("Bearer {token}"))
         .query("q", "is:unread in:inbox")
         .query("maxResults", &limit.to_string())
         .call()
-        .map_err(|e| format!("Gmail list error: {e}"))?;
+        .map_err(gmail_error)?;
 
     let json: serde_json::Value = resp.into_json().map_err(|e| e.to_string())?;
     let msg_refs = match json["messages"].as_array() {
@@ -414,6 +459,39 @@ pub fn get_recent_emails(token: &str, limit: u32) -> Result<Vec<Email>, String>
     Ok(emails)
 }
 
+/// Whether a message still has the INBOX label. Reading an email leaves it
+/// there; archiving or deleting it doesn't.
+pub fn is_in_inbox(token: &str, email_id: &str) -> Result<bool, String> {
+    let resp = ureq::get(&format!(
+        "https://gmail.googleapis.com/gmail/v1/users/me/messages/{email_id}"
+    ))
+    .set("Authorization", &format!("Bearer {token}"))
+    .query("format", "minimal")
+    .call();
+    let json: serde_json::Value = match resp {
+        Ok(r) => r.into_json().map_err(|e| e.to_string())?,
+        Err(ureq::Error::Status(404, _)) => return Ok(false),
+        Err(err) => return Err(gmail_error(err)),
+    };
+    Ok(json["labelIds"]
+        .as_array()
+        .is_some_and(|labels| labels.iter().any(|l| l.as_str() == Some("INBOX"))))
+}
+
+/// Open a message in Gmail in the default browser.
+pub fn open_message(email_id: &str) -> io::Result<()> {
+    let url = format!("https://mail.google.com/mail/u/0/#all/{email_id}");
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(opener)
+        .arg(url)
+        .spawn()
+        .map(|_| ())
+}
+
 fn extract_body(payload: &serde_json::Value) -> Option<String> {
     if let Some(data) = payload["body"]["data"].as_str() {
         if !data.is_empty() {
@@ -1,17 +1,20 @@
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::env;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use chrono::{Local, NaiveDate};
+use chrono::{NaiveDate, Utc};
 use serde::Deserialize;
 use serde_json::json;
 use uuid::Uuid;
 
-use crate::model::{Priority, Progress};
-use crate::storage::AiSettings;
+use crate::log;
+use crate::model::{BucketDef, Priority, Progress, Task};
+use crate::storage::{self, AiSettings};
 
 #[derive(Debug, Clone)]
 pub struct ContextTask {
@@ -32,8 +35,9 @@ pub struct AiJob {
     pub task_id: Uuid,
     pub title: String,
     pub suggested_bucket: String,
-    pub context: Vec<ContextTask>,
-    pub bucket_names: Vec<String>,
+    /// Shared between the jobs of a bulk update, which all see the same list.
+    pub context: Arc<[ContextTask]>,
+    pub buckets: Vec<BucketDef>,
     pub lock_bucket: bool,
     pub lock_priority: bool,
     pub lock_due_date: bool,
@@ -51,6 +55,8 @@ pub struct AiJob {
     pub user_profile: String,
     /// Auto-remembered facts about the user.
     pub memory_facts: Vec<String>,
+    /// Assignee from an `@@name` token, applied to whatever the job creates or updates.
+    pub assignee: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -64,6 +70,7 @@ pub struct TaskUpdate {
     pub due_date: Option<Option<NaiveDate>>,
     pub dependencies: Vec<String>,
     pub parent_id: Option<String>,
+    pub assignee: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +84,7 @@ pub enum TriageAction {
     /// AI decided to update multiple tasks at once.
     BulkUpdate {
         targets: Vec<String>,
+        filter: BulkFilter,
         instruction: String,
     },
     /// AI decided to decompose a complex task into sub-issues.
@@ -90,6 +98,26 @@ pub enum TriageAction {
     Chat(String),
 }
 
+/// Field predicates narrowing a bulk update. Empty fields match any task.
+#[derive(Debug, Clone, Default)]
+pub struct BulkFilter {
+    pub bucket: Option<String>,
+    pub progress: Option<Progress>,
+    pub priority: Option<Priority>,
+}
+
+impl BulkFilter {
+    pub fn is_empty(&self) -> bool {
+        self.bucket.is_none() && self.progress.is_none() && self.priority.is_none()
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.bucket.as_ref().is_none_or(|b| task.bucket == *b)
+            && self.progress.is_none_or(|p| task.progress == p)
+            && self.priority.is_none_or(|p| task.priority == p)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SubTaskSpec {
     pub title: String,
@@ -115,15 +143,67 @@ pub struct AiResult {
 pub struct AiRuntime {
     job_tx: Sender<AiJob>,
     result_rx: Receiver<AiResult>,
+    /// Jobs enqueued whose result has not been drained yet.
+    pending: Cell<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Provider {
+pub enum Provider {
     OpenAi,
     Anthropic,
 }
 
-fn detect_provider(model: &str) -> Provider {
+/// Where an API key was found, in precedence order: an environment
+/// variable beats the keychain, which beats the settings file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    Env,
+    Keychain,
+    Settings,
+}
+
+impl KeySource {
+    pub fn label(self) -> &'static str {
+        match self {
+            KeySource::Env => "from env",
+            KeySource::Keychain => "from keychain",
+            KeySource::Settings => "from settings",
+        }
+    }
+}
+
+/// The API key `provider` requests use, and where it came from.
+pub fn api_key(settings: &AiSettings, provider: Provider) -> Option<(String, KeySource)> {
+    let (var, stored) = match provider {
+        Provider::Anthropic => ("ANTHROPIC_API_KEY", &settings.anthropic_api_key),
+        Provider::OpenAi => ("OPENAI_API_KEY", &settings.openai_api_key),
+    };
+    if let Some(key) = env::var(var).ok().filter(|k| !k.trim().is_empty()) {
+        return Some((key, KeySource::Env));
+    }
+    if stored.trim().is_empty() {
+        return None;
+    }
+    // Keychain keys are merged into the settings when they load; one the
+    // keychain refused is still in the file.
+    let source = if settings.key_in_keychain(provider.name()) {
+        KeySource::Keychain
+    } else {
+        KeySource::Settings
+    };
+    Some((stored.clone(), source))
+}
+
+impl Provider {
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::OpenAi => "openai",
+            Provider::Anthropic => "anthropic",
+        }
+    }
+}
+
+pub fn detect_provider(model: &str) -> Provider {
     if model.starts_with("claude-") {
         Provider::Anthropic
     } else {
@@ -138,14 +218,26 @@ struct LlmConfig {
     model: String,
     api_key: String,
     timeout: Duration,
-}
-
-fn build_config(settings: &AiSettings) -> Option<LlmConfig> {
-    if !settings.enabled {
-        return None;
+    /// Settings timezone, so "today" in prompts matches the board.
+    utc_offset_minutes: Option<i32>,
+    system_prompt: String,
+    concurrency: usize,
+    /// Shared by all workers: after a 429 nobody sends until this passes, so
+    /// parallel jobs back off together instead of each tripping the limit.
+    rate_limited_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl LlmConfig {
+    fn today(&self) -> String {
+        storage::to_offset(Utc::now(), self.utc_offset_minutes)
+            .format("%Y-%m-%d")
+            .to_string()
     }
+}
 
-    let model = if !settings.model.trim().is_empty() {
+/// The model requests will use: the configured one, else `AIPM_MODEL`, else the default.
+pub fn resolve_model(settings: &AiSettings) -> String {
+    if !settings.model.trim().is_empty() {
         settings.model.clone()
     } else {
         env::var("AIPM_MODEL")
@@ -157,22 +249,35 @@ fn build_config(settings: &AiSettings) -> Option<LlmConfig> {
                     .filter(|s| !s.trim().is_empty())
             })
             .unwrap_or_else(|| "claude-sonnet-4-5".to_string())
-    };
+    }
+}
+
+/// Input context window in tokens, used to show how full the triage prompt is.
+/// Unknown models fall back to a conservative 128k.
+pub fn model_context_window(model: &str) -> usize {
+    if model.starts_with("claude-")
+        || model.starts_with("o3")
+        || model.starts_with("o4")
+        || model.starts_with("codex-")
+    {
+        200_000
+    } else if model.starts_with("gpt-4.1") {
+        1_047_576
+    } else {
+        128_000
+    }
+}
+
+fn build_config(settings: &AiSettings) -> Option<LlmConfig> {
+    if !settings.enabled {
+        return None;
+    }
+
+    let model = resolve_model(settings);
 
     let provider = detect_provider(&model);
 
-    let settings_key = match provider {
-        Provider::Anthropic => &settings.anthropic_api_key,
-        Provider::OpenAi => &settings.openai_api_key,
-    };
-    let key = if !settings_key.trim().is_empty() {
-        settings_key.clone()
-    } else {
-        match provider {
-            Provider::Anthropic => env::var("ANTHROPIC_API_KEY").ok()?,
-            Provider::OpenAi => env::var("OPENAI_API_KEY").ok()?,
-        }
-    };
+    let (key, _) = api_key(settings, provider)?;
 
     let default_url = match provider {
         Provider::Anthropic => "https://api.anthropic.com/v1/messages",
@@ -190,7 +295,9 @@ fn build_config(settings: &AiSettings) -> Option<LlmConfig> {
         .unwrap_or_else(|| default_url.to_string());
 
     let timeout = Duration::from_secs(if settings.timeout_secs > 0 {
-        settings.timeout_secs
+        settings
+            .timeout_secs
+            .clamp(storage::MIN_TIMEOUT_SECS, storage::MAX_TIMEOUT_SECS)
     } else {
         60
     });
@@ -201,9 +308,66 @@ fn build_config(settings: &AiSettings) -> Option<LlmConfig> {
         model,
         api_key: key,
         timeout,
+        utc_offset_minutes: settings.utc_offset_minutes,
+        system_prompt: bounded_system_prompt(&settings.system_prompt).to_string(),
+        concurrency: settings
+            .ai_concurrency
+            .clamp(1, storage::MAX_AI_CONCURRENCY),
+        rate_limited_until: Arc::new(Mutex::new(None)),
     })
 }
 
+/// The user's instruction preamble, trimmed and cut to
+/// `SYSTEM_PROMPT_MAX_CHARS` (the settings file may be edited by hand).
+pub fn bounded_system_prompt(prompt: &str) -> &str {
+    let prompt = prompt.trim();
+    match prompt.char_indices().nth(storage::SYSTEM_PROMPT_MAX_CHARS) {
+        Some((end, _)) => prompt[..end].trim_end(),
+        None => prompt,
+    }
+}
+
+fn with_system_prompt<'a>(cfg: &LlmConfig, system: &'a str) -> Cow<'a, str> {
+    if cfg.system_prompt.is_empty() {
+        return Cow::Borrowed(system);
+    }
+    Cow::Owned(format!(
+        "Standing instructions from the user (follow them unless they conflict with the rules below):\n{}\n\n{}",
+        cfg.system_prompt, system
+    ))
+}
+
+/// Send a one-line request with `settings`, for the Settings tab's connection
+/// check. The reply says how long the model took, or exactly why it failed.
+pub fn test_connection(settings: &AiSettings) -> Receiver<Result<String, String>> {
+    let (tx, rx) = mpsc::channel();
+    let model = resolve_model(settings);
+    let cfg = build_config(settings).ok_or_else(|| {
+        if !settings.enabled {
+            "AI is disabled".to_string()
+        } else {
+            let var = match detect_provider(&model) {
+                Provider::Anthropic => "ANTHROPIC_API_KEY",
+                Provider::OpenAi => "OPENAI_API_KEY",
+            };
+            format!("No API key for {model} (set {var} or add the key in Settings)")
+        }
+    });
+    thread::spawn(move || {
+        let result = cfg.and_then(|cfg| {
+            let started = Instant::now();
+            call_llm(&cfg, "Reply with the single word: ok", "ping")?;
+            Ok(format!(
+                "{model} at {} replied in {:.1}s",
+                cfg.api_url,
+                started.elapsed().as_secs_f32()
+            ))
+        });
+        let _ = tx.send(result);
+    });
+    rx
+}
+
 impl AiRuntime {
     pub fn from_settings(settings: &AiSettings) -> Option<AiRuntime> {
         let cfg = build_config(settings)?;
@@ -213,11 +377,17 @@ impl AiRuntime {
 
         thread::spawn(move || worker_loop(cfg, job_rx, result_tx));
 
-        Some(AiRuntime { job_tx, result_rx })
+        Some(AiRuntime {
+            job_tx,
+            result_rx,
+            pending: Cell::new(0),
+        })
     }
 
     pub fn enqueue(&self, job: AiJob) {
-        let _ = self.job_tx.send(job);
+        if self.job_tx.send(job).is_ok() {
+            self.pending.set(self.pending.get() + 1);
+        }
     }
 
     pub fn drain(&self) -> Vec<AiResult> {
@@ -225,17 +395,23 @@ impl AiRuntime {
         while let Ok(result) = self.result_rx.try_recv() {
             out.push(result);
         }
+        self.pending
+            .set(self.pending.get().saturating_sub(out.len()));
         out
     }
 
+    pub fn pending(&self) -> usize {
+        self.pending.get()
+    }
+
     /// Blocking receive for CLI mode. Returns None on timeout.
     pub fn recv_blocking(&self, timeout: Duration) -> Option<AiResult> {
-        self.result_rx.recv_timeout(timeout).ok()
+        let result = self.result_rx.recv_timeout(timeout).ok()?;
+        self.pending.set(self.pending.get().saturating_sub(1));
+        Some(result)
     }
 }
 
-const MAX_PARALLEL_JOBS: usize = 8;
-
 fn worker_loop(cfg: LlmConfig, job_rx: Receiver<AiJob>, result_tx: Sender<AiResult>) {
     let cfg = Arc::new(cfg);
     let active = Arc::new((Mutex::new(0usize), Condvar::new()));
@@ -248,7 +424,7 @@ fn worker_loop(cfg: LlmConfig, job_rx: Receiver<AiJob>, result_tx: Sender<AiResu
         {
             let (lock, cvar) = &*active;
             let mut count = lock.lock().unwrap();
-            while *count >= MAX_PARALLEL_JOBS {
+            while *count >= cfg.concurrency {
                 count = cvar.wait(count).unwrap();
             }
             *count += 1;
@@ -269,7 +445,7 @@ fn worker_loop(cfg: LlmConfig, job_rx: Receiver<AiJob>, result_tx: Sender<AiResu
 /// Whether an LLM error is transient and worth retrying.
 fn is_retryable(err: &str) -> bool {
     // Transport / timeout errors.
-    if err.contains("transport error") || err.contains("response read failed") {
+    if err.contains("transport error") || err.contains("response read failed") || is_timeout(err) {
         return true;
     }
     // Rate-limit or server errors.
@@ -487,17 +663,39 @@ fn strip_html_tags(html: &str) -> String {
 
 /// Compute a timeout that scales with prompt size so large requests
 /// (triage with many tasks, decompose, bulk updates) get more time.
-/// Adds ~10 s per 4 KiB of prompt, capped at 3× base (minimum 120 s).
+/// Adds ~10 s per 4 KiB of prompt, capped at 3× base (minimum 120 s), and
+/// never more than `MAX_TIMEOUT_SECS` for one attempt.
 fn scaled_timeout(base: Duration, body: &serde_json::Value, attempt: u32) -> Duration {
     let body_len = body.to_string().len();
     let extra_secs = (body_len / 4096) as u64 * 10;
     let total = base.as_secs() + extra_secs;
     let cap = base.as_secs().saturating_mul(3).max(120);
-    let scaled = Duration::from_secs(total.min(cap));
+    let scaled = total.min(cap);
     // Bump 50 % per retry so a timed-out request gets a longer second chance.
-    scaled + Duration::from_secs(scaled.as_secs() * attempt as u64 / 2)
+    let bumped = scaled + scaled * attempt as u64 / 2;
+    Duration::from_secs(bumped.min(storage::MAX_TIMEOUT_SECS))
 }
 
+const TIMEOUT_ERROR: &str = "AI request timed out";
+
+/// Whether an LLM error is a timeout, so callers working through a queue
+/// (the email poller) can stop instead of waiting out every item.
+pub fn is_timeout(err: &str) -> bool {
+    err.starts_with(TIMEOUT_ERROR)
+}
+
+fn is_io_timeout(err: &(dyn std::error::Error + 'static)) -> bool {
+    err.downcast_ref::<std::io::Error>().is_some_and(|e| {
+        matches!(
+            e.kind(),
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+        )
+    })
+}
+
+const RATE_LIMIT_PAUSE_SECS: u64 = 2;
+const MAX_RATE_LIMIT_PAUSE_SECS: u64 = 30;
+
 /// Send a raw HTTP request to the LLM and return the response body.
 fn send_llm_request(
     cfg: &LlmConfig,
@@ -515,22 +713,75 @@ fn send_llm_request(
             .set("anthropic-version", "2023-06-01"),
     };
 
-    let resp = req.send_string(&body.to_string());
+    let wait = cfg
+        .rate_limited_until
+        .lock()
+        .unwrap()
+        .map(|until| until.saturating_duration_since(Instant::now()));
+    if let Some(wait) = wait.filter(|w| !w.is_zero()) {
+        thread::sleep(wait);
+    }
 
-    match resp {
-        Ok(r) => r
-            .into_string()
-            .map_err(|err| format!("AI response read failed: {err}")),
+    let request = body.to_string();
+    log::info(
+        "ai.request",
+        json!({
+            "provider": cfg.provider.name(),
+            "model": cfg.model,
+            "bytes": request.len(),
+            "timeout_secs": timeout.as_secs(),
+        }),
+    );
+    let started = Instant::now();
+    let resp = req.send_string(&request);
+
+    let timed_out = || format!("{TIMEOUT_ERROR} after {}s", timeout.as_secs());
+    let result = match resp {
+        Ok(r) => r.into_string().map_err(|err| {
+            if is_io_timeout(&err) {
+                timed_out()
+            } else {
+                format!("AI response read failed: {err}")
+            }
+        }),
         Err(ureq::Error::Status(code, r)) => {
+            if code == 429 {
+                let secs = r
+                    .header("retry-after")
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .unwrap_or(RATE_LIMIT_PAUSE_SECS)
+                    .min(MAX_RATE_LIMIT_PAUSE_SECS);
+                let until = Instant::now() + Duration::from_secs(secs);
+                let mut shared = cfg.rate_limited_until.lock().unwrap();
+                if shared.is_none_or(|t| t < until) {
+                    *shared = Some(until);
+                }
+            }
             let body = r.into_string().unwrap_or_default();
-            Err(format!("AI HTTP {}: {}", code, truncate(&body, 200)))
+            Err(format!("AI HTTP {}: {}", code, log::redact(&body)))
+        }
+        Err(ureq::Error::Transport(t)) => {
+            if std::error::Error::source(&t).is_some_and(is_io_timeout) {
+                Err(timed_out())
+            } else {
+                Err(format!(
+                    "AI transport error: {}",
+                    log::redact(&t.to_string())
+                ))
+            }
         }
-        Err(ureq::Error::Transport(t)) => Err(format!("AI transport error: {t}")),
+    };
+    let ms = started.elapsed().as_millis() as u64;
+    match &result {
+        Ok(text) => log::info("ai.response", json!({ "bytes": text.len(), "ms": ms })),
+        Err(err) => log::error("ai.error", json!({ "error": err, "ms": ms })),
     }
+    result
 }
 
 /// Send a system+user prompt to the configured LLM and return the text content.
 fn call_llm(cfg: &LlmConfig, system: &str, user: &str) -> Result<String, String> {
+    let system = with_system_prompt(cfg, system);
     let body = match cfg.provider {
         Provider::OpenAi => json!({
             "model": cfg.model,
@@ -598,6 +849,7 @@ fn call_llm_with_tools(
     user: &str,
     tools: &serde_json::Value,
 ) -> Result<ToolCallResult, String> {
+    let system = with_system_prompt(cfg, system);
     let body = match cfg.provider {
         Provider::OpenAi => json!({
             "model": cfg.model,
@@ -674,7 +926,7 @@ fn enrich_task(cfg: &LlmConfig, job: &AiJob) -> AiResult {
         return edit_task(cfg, job, instruction);
     }
 
-    let today = Local::now().format("%Y-%m-%d").to_string();
+    let today = cfg.today();
     let system = format!(
         "Today is {today}. You are an expert AI project manager. Output ONLY valid JSON. No markdown."
     );
@@ -695,17 +947,18 @@ fn enrich_task(cfg: &LlmConfig, job: &AiJob) -> AiResult {
     );
 
     let bucket_enum = job
-        .bucket_names
+        .buckets
         .iter()
-        .map(|n| format!("\"{}\"", n))
+        .map(|b| format!("\"{}\"", b.name))
         .collect::<Vec<_>>()
         .join("|");
 
     let user = format!(
-        "New task title: {}\nSuggested bucket: {}\n{}\n\nExisting tasks you may depend on (id_prefix [bucket] title):\n{}\nReturn JSON with keys:\n{{\n  \"bucket\": {bucket_enum},\n  \"description\": string,\n  \"priority\": \"Low\"|\"Medium\"|\"High\"|\"Critical\",\n  \"due_date\": \"YYYY-MM-DD\" | null,\n  \"dependencies\": [\"id_prefix\", ...]\n}}\nRules:\n- If a field is locked, keep it aligned with the suggested value (bucket) or output null/Medium (due/priority) as appropriate.\n- If unsure, keep bucket as suggested.\n- Dependencies must use the provided id_prefix values.\n",
+        "New task title: {}\nSuggested bucket: {}\n{}\n\n{}\nExisting tasks you may depend on (id_prefix [bucket] title):\n{}\nReturn JSON with keys:\n{{\n  \"bucket\": {bucket_enum},\n  \"description\": string,\n  \"priority\": \"Low\"|\"Medium\"|\"High\"|\"Critical\",\n  \"due_date\": \"YYYY-MM-DD\" | null,\n  \"dependencies\": [\"id_prefix\", ...]\n}}\nRules:\n- If a field is locked, keep it aligned with the suggested value (bucket) or output null/Medium (due/priority) as appropriate.\n- Pick the bucket whose description fits the task best. If unsure, keep bucket as suggested.\n- Dependencies must use the provided id_prefix values.\n",
         job.title.trim(),
         &job.suggested_bucket,
         lock_line,
+        bucket_guide(&job.buckets),
         context_lines
     );
 
@@ -752,7 +1005,7 @@ fn enrich_task(cfg: &LlmConfig, job: &AiJob) -> AiResult {
         if let Some(bucket) = enriched
             .bucket
             .as_deref()
-            .and_then(|b| parse_bucket(b, &job.bucket_names))
+            .and_then(|b| suggested_bucket(b, &job.buckets))
         {
             update.bucket = Some(bucket);
         }
@@ -867,12 +1120,40 @@ fn extract_json_object(text: &str) -> Option<String> {
     Some(text[start..=end].to_string())
 }
 
-fn parse_bucket(input: &str, valid_names: &[String]) -> Option<String> {
+fn parse_bucket(input: &str, buckets: &[BucketDef]) -> Option<String> {
     let lower = input.to_ascii_lowercase();
-    valid_names
+    buckets
         .iter()
-        .find(|n| n.to_ascii_lowercase() == lower)
-        .cloned()
+        .find(|b| b.name.to_ascii_lowercase() == lower)
+        .map(|b| b.name.clone())
+}
+
+/// The configured buckets with their descriptions, so the model files tasks
+/// by the user's definitions instead of guessing from names alone.
+fn bucket_guide(buckets: &[BucketDef]) -> String {
+    let mut out = String::from("Buckets:\n");
+    for bucket in buckets {
+        match bucket
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+        {
+            Some(desc) => out.push_str(&format!("- {}: {}\n", bucket.name, desc)),
+            None => out.push_str(&format!("- {}\n", bucket.name)),
+        }
+    }
+    out
+}
+
+/// Like `parse_bucket`, but keeps a name the model made up so the caller
+/// can tell the user where the task went instead.
+fn suggested_bucket(input: &str, buckets: &[BucketDef]) -> Option<String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    parse_bucket(trimmed, buckets).or_else(|| Some(truncate(trimmed, 60).to_string()))
 }
 
 fn parse_priority(input: &str) -> Option<Priority> {
@@ -912,7 +1193,7 @@ fn short_id(id: Uuid) -> String {
 }
 
 fn edit_task(cfg: &LlmConfig, job: &AiJob, instruction: &str) -> AiResult {
-    let today = Local::now().format("%Y-%m-%d").to_string();
+    let today = cfg.today();
     let system = format!(
         "Today is {today}. You are an expert AI project manager. Modify the given task based on the user instruction. Output ONLY valid JSON. No markdown."
     );
@@ -930,18 +1211,33 @@ fn edit_task(cfg: &LlmConfig, job: &AiJob, instruction: &str) -> AiResult {
     }
 
     let bucket_enum = job
-        .bucket_names
+        .buckets
         .iter()
-        .map(|n| format!("\"{}\"", n))
+        .map(|b| format!("\"{}\"", b.name))
         .collect::<Vec<_>>()
         .join("|");
 
-    let user = format!(
-        "Current task:\n{}\n\nInstruction: {}\n\nExisting tasks (id_prefix [bucket] title):\n{}\nReturn JSON with ONLY fields that should change (omit unchanged fields):\n{{\n  \"title\": string | null,\n  \"bucket\": {bucket_enum} | null,\n  \"description\": string | null,\n  \"progress\": \"Backlog\"|\"Todo\"|\"In progress\"|\"Done\" | null,\n  \"priority\": \"Low\"|\"Medium\"|\"High\"|\"Critical\" | null,\n  \"due_date\": \"YYYY-MM-DD\" | null,\n  \"dependencies\": [\"id_prefix\", ...] | null,\n  \"parent_id\": \"id_prefix\" | \"none\" | null,\n  \"subtasks\": [{{\"title\": string, \"description\": string, \"bucket\": {bucket_enum}, \"priority\": \"Low\"|\"Medium\"|\"High\"|\"Critical\", \"progress\": \"Backlog\"|\"Todo\"|\"In progress\"|\"Done\", \"due_date\": \"YYYY-MM-DD\" | null, \"depends_on\": [0-based index, ...]}}] | null\n}}\nRules:\n- If the instruction asks to create sub-issues, sub-tasks, break down, or decompose the task, return them as entries in the \"subtasks\" array. NEVER write sub-task lists, numbered breakdowns, or step-by-step plans into the \"description\" field.\n- depends_on is an array of 0-based indices into the subtasks array representing execution order. Use it to express sequential dependencies between subtasks.\n- Subtasks inherit the parent task's bucket and priority unless the instruction specifies otherwise.\n- To move this task under another task as a sub-task, set \"parent_id\" to the target task's id_prefix. To promote to a root task, set \"parent_id\" to \"none\".\n- Omit unchanged fields.\n- To remove an existing due date, set \"due_date\" to null.\n",
+    let mut user = format!(
+        "Current task:\n{}\n\nInstruction: {}\n\n{}\nExisting tasks (id_prefix [bucket] title):\n{}\nReturn JSON with ONLY fields that should change (omit unchanged fields):\n{{\n  \"title\": string | null,\n  \"bucket\": {bucket_enum} | null,\n  \"description\": string | null,\n  \"progress\": \"Backlog\"|\"Todo\"|\"In progress\"|\"Done\" | null,\n  \"priority\": \"Low\"|\"Medium\"|\"High\"|\"Critical\" | null,\n  \"due_date\": \"YYYY-MM-DD\" | null,\n  \"dependencies\": [\"id_prefix\", ...] | null,\n  \"parent_id\": \"id_prefix\" | \"none\" | null,\n  \"subtasks\": [{{\"title\": string, \"description\": string, \"bucket\": {bucket_enum}, \"priority\": \"Low\"|\"Medium\"|\"High\"|\"Critical\", \"progress\": \"Backlog\"|\"Todo\"|\"In progress\"|\"Done\", \"due_date\": \"YYYY-MM-DD\" | null, \"depends_on\": [0-based index, ...]}}] | null\n}}\nRules:\n- If the instruction asks to create sub-issues, sub-tasks, break down, or decompose the task, return them as entries in the \"subtasks\" array. NEVER write sub-task lists, numbered breakdowns, or step-by-step plans into the \"description\" field.\n- depends_on is an array of 0-based indices into the subtasks array representing execution order. Use it to express sequential dependencies between subtasks.\n- Subtasks inherit the parent task's bucket and priority unless the instruction specifies otherwise.\n- To move this task under another task as a sub-task, set \"parent_id\" to the target task's id_prefix. To promote to a root task, set \"parent_id\" to \"none\".\n- Omit unchanged fields.\n- To remove an existing due date, set \"due_date\" to null.\n",
         snapshot,
         instruction,
+        bucket_guide(&job.buckets),
         context_lines
     );
+    let locked: Vec<&str> = [
+        (job.lock_bucket, "bucket"),
+        (job.lock_priority, "priority"),
+        (job.lock_due_date, "due_date"),
+    ]
+    .into_iter()
+    .filter_map(|(locked, name)| locked.then_some(name))
+    .collect();
+    if !locked.is_empty() {
+        user.push_str(&format!(
+            "- The user locked these fields; never change them: {}.\n",
+            locked.join(", ")
+        ));
+    }
 
     let content = match call_llm(cfg, &system, &user) {
         Ok(text) => text,
@@ -995,7 +1291,7 @@ fn edit_task(cfg: &LlmConfig, job: &AiJob, instruction: &str) -> AiResult {
     if let Some(bucket) = enriched
         .bucket
         .as_deref()
-        .and_then(|b| parse_bucket(b, &job.bucket_names))
+        .and_then(|b| suggested_bucket(b, &job.buckets))
     {
         update.bucket = Some(bucket);
     }
@@ -1067,7 +1363,7 @@ fn edit_task(cfg: &LlmConfig, job: &AiJob, instruction: &str) -> AiResult {
                 bucket: st
                     .bucket
                     .as_deref()
-                    .and_then(|b| parse_bucket(b, &job.bucket_names)),
+                    .and_then(|b| parse_bucket(b, &job.buckets)),
                 priority: st
                     .priority
                     .as_deref()
@@ -1149,7 +1445,11 @@ struct DecomposeTaskArgs {
 
 #[derive(Debug, Deserialize)]
 struct BulkUpdateTasksArgs {
+    #[serde(default)]
     target_ids: Vec<String>,
+    bucket: Option<String>,
+    progress: Option<String>,
+    priority: Option<String>,
     instruction: String,
 }
 
@@ -1173,8 +1473,8 @@ struct SubTaskEnriched {
 // Tool schema definitions for triage
 // ---------------------------------------------------------------------------
 
-fn subtask_schema(bucket_names: &[String]) -> serde_json::Value {
-    let bucket_values: Vec<serde_json::Value> = bucket_names.iter().map(|n| json!(n)).collect();
+fn subtask_schema(buckets: &[BucketDef]) -> serde_json::Value {
+    let bucket_values: Vec<serde_json::Value> = buckets.iter().map(|b| json!(b.name)).collect();
     json!({
         "type": "object",
         "properties": {
@@ -1217,9 +1517,9 @@ fn make_tool_def(
     }
 }
 
-fn triage_tool_defs(provider: Provider, bucket_names: &[String]) -> serde_json::Value {
-    let st = subtask_schema(bucket_names);
-    let bucket_values: Vec<serde_json::Value> = bucket_names.iter().map(|n| json!(n)).collect();
+fn triage_tool_defs(provider: Provider, buckets: &[BucketDef]) -> serde_json::Value {
+    let st = subtask_schema(buckets);
+    let bucket_values: Vec<serde_json::Value> = buckets.iter().map(|b| json!(b.name)).collect();
     json!([
         make_tool_def(
             provider,
@@ -1260,7 +1560,7 @@ fn triage_tool_defs(provider: Provider, bucket_names: &[String]) -> serde_json::
                 "properties": {
                     "target_id": {"type": "string", "description": "id_prefix of the task to update"},
                     "title": {"type": "string", "description": "New title"},
-                    "bucket": {"type": "string", "enum": bucket_values},
+                    "bucket": {"type": "string", "enum": bucket_values.clone()},
                     "description": {"type": "string"},
                     "priority": {"type": "string", "enum": ["Low", "Medium", "High", "Critical"]},
                     "progress": {"type": "string", "enum": ["Backlog", "Todo", "In progress", "Done"]},
@@ -1315,18 +1615,21 @@ fn triage_tool_defs(provider: Provider, bucket_names: &[String]) -> serde_json::
         make_tool_def(
             provider,
             "bulk_update_tasks",
-            "Update multiple tasks at once. Use when the instruction affects many tasks.",
+            "Update multiple tasks at once. Use when the instruction affects many tasks. Prefer the bucket/progress/priority filters over listing ids when the user describes a group of tasks.",
             json!({
                 "type": "object",
                 "properties": {
                     "target_ids": {
                         "type": "array",
                         "items": {"type": "string"},
-                        "description": "id_prefix values, or [\"all\"] for every task"
+                        "description": "id_prefix values, or [\"all\"] for every task. Omit to select every task matching the filters"
                     },
+                    "bucket": {"type": "string", "enum": bucket_values, "description": "Only tasks in this bucket"},
+                    "progress": {"type": "string", "enum": ["backlog", "todo", "in-progress", "done"], "description": "Only tasks at this stage"},
+                    "priority": {"type": "string", "enum": ["low", "medium", "high", "critical"], "description": "Only tasks with this priority"},
                     "instruction": {"type": "string", "description": "What to change"}
                 },
-                "required": ["target_ids", "instruction"]
+                "required": ["instruction"]
             })
         ),
         make_tool_def(
@@ -1347,7 +1650,7 @@ fn triage_tool_defs(provider: Provider, bucket_names: &[String]) -> serde_json::
     ])
 }
 
-fn parse_subtask_args(args: Option<Vec<SubTaskArg>>, bucket_names: &[String]) -> Vec<SubTaskSpec> {
+fn parse_subtask_args(args: Option<Vec<SubTaskArg>>, buckets: &[BucketDef]) -> Vec<SubTaskSpec> {
     args.unwrap_or_default()
         .into_iter()
         .filter_map(|st| {
@@ -1362,10 +1665,7 @@ fn parse_subtask_args(args: Option<Vec<SubTaskArg>>, bucket_names: &[String]) ->
                     .as_deref()
                     .map(|s| truncate(s.trim(), 400).to_string())
                     .unwrap_or_default(),
-                bucket: st
-                    .bucket
-                    .as_deref()
-                    .and_then(|b| parse_bucket(b, bucket_names)),
+                bucket: st.bucket.as_deref().and_then(|b| parse_bucket(b, buckets)),
                 priority: st
                     .priority
                     .as_deref()
@@ -1417,7 +1717,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
         fetch_url_contexts(&urls, cfg.timeout)
     };
 
-    let today = Local::now().format("%Y-%m-%d").to_string();
+    let today = cfg.today();
     let mut system = format!(
         "Today is {today}. You are an expert AI project manager.\n\n\
         WORKFLOW — Follow these steps for EVERY request:\n\
@@ -1439,6 +1739,10 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
         with parent_id set to Y's id_prefix. Do NOT create new tasks.\n\
         - When adding sub-tasks, check the parent's existing sub-tasks first. Do NOT create \
         sub-tasks that overlap with ones already listed.\n\
+        - 'deps:' lists the id_prefixes a task already depends on (+N means more). Don't add \
+        dependencies that are already there, and don't make a task depend on one of its dependents.\n\
+        - Put new tasks in the bucket whose description best fits the work, using the \
+        Buckets list. Only fall back to the first bucket when none fits.\n\
         - Generate clean, actionable titles (do NOT copy the user's raw words verbatim).\n\
         - Infer progress from context (e.g. 'already working on X' → 'In progress').\n\
         - If the user asks to break down, decompose, split, or create sub-tasks, use decompose_task.\n\
@@ -1461,8 +1765,10 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
     let triage_ctx = job.triage_context.as_deref().unwrap_or("");
 
     let mut user_prompt = format!(
-        "User message: \"{}\"\n\nExisting tasks:\n{}",
-        raw_input, triage_ctx
+        "User message: \"{}\"\n\n{}\nExisting tasks:\n{}",
+        raw_input,
+        bucket_guide(&job.buckets),
+        triage_ctx
     );
 
     if !url_contexts.is_empty() {
@@ -1482,7 +1788,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
         }
     }
 
-    let tools = triage_tool_defs(cfg.provider, &job.bucket_names);
+    let tools = triage_tool_defs(cfg.provider, &job.buckets);
 
     let tool_result = match call_llm_with_tools(cfg, &system, &user_prompt, &tools) {
         Ok(result) => result,
@@ -1515,13 +1821,13 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                 Ok(v) => v,
                 Err(e) => return err_result(format!("Failed to parse create_task args: {e}")),
             };
-            let sub_task_specs = parse_subtask_args(parsed.subtasks, &job.bucket_names);
+            let sub_task_specs = parse_subtask_args(parsed.subtasks, &job.buckets);
             AiResult {
                 task_id: job.task_id,
                 update: TaskUpdate {
                     is_edit: false,
                     title: Some(truncate(parsed.title.trim(), 200).to_string()),
-                    bucket: parse_bucket(&parsed.bucket, &job.bucket_names),
+                    bucket: suggested_bucket(&parsed.bucket, &job.buckets),
                     description: parsed
                         .description
                         .as_deref()
@@ -1539,6 +1845,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                     due_date: parsed.due_date.as_deref().and_then(parse_due_date_text),
                     dependencies: resolve_deps(parsed.dependencies, &allowed),
                     parent_id: None,
+                    assignee: job.assignee.clone(),
                 },
                 error: None,
                 triage_action: Some(TriageAction::Create),
@@ -1551,7 +1858,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                 Err(e) => return err_result(format!("Failed to parse update_task args: {e}")),
             };
             let target = parsed.target_id.trim().to_string();
-            let sub_task_specs = parse_subtask_args(parsed.subtasks, &job.bucket_names);
+            let sub_task_specs = parse_subtask_args(parsed.subtasks, &job.buckets);
             if target.is_empty() {
                 return err_result(
                     "update_task called without target_id — specify which task to update"
@@ -1576,7 +1883,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                     bucket: parsed
                         .bucket
                         .as_deref()
-                        .and_then(|b| parse_bucket(b, &job.bucket_names)),
+                        .and_then(|b| suggested_bucket(b, &job.buckets)),
                     description: parsed
                         .description
                         .as_deref()
@@ -1594,6 +1901,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                     due_date: parse_due_date_update(parsed.due_date.as_ref()),
                     dependencies: resolve_deps(parsed.dependencies, &allowed),
                     parent_id,
+                    assignee: job.assignee.clone(),
                 },
                 error: None,
                 triage_action: Some(TriageAction::Update(target)),
@@ -1622,7 +1930,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                 Ok(v) => v,
                 Err(e) => return err_result(format!("Failed to parse decompose_task args: {e}")),
             };
-            let specs = parse_subtask_args(Some(parsed.subtasks), &job.bucket_names);
+            let specs = parse_subtask_args(Some(parsed.subtasks), &job.buckets);
             if specs.is_empty() {
                 return err_result("decompose_task: no subtasks provided".to_string());
             }
@@ -1655,8 +1963,31 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                 .map(|s| s.trim().to_string())
                 .filter(|s| !s.is_empty())
                 .collect();
-            if targets.is_empty() {
-                return err_result("bulk_update_tasks: empty target_ids".to_string());
+            let mut filter = BulkFilter::default();
+            if let Some(raw) = parsed.bucket.as_deref() {
+                match parse_bucket(raw, &job.buckets) {
+                    Some(b) => filter.bucket = Some(b),
+                    None => return err_result(format!("bulk_update_tasks: unknown bucket {raw}")),
+                }
+            }
+            if let Some(raw) = parsed.progress.as_deref() {
+                match parse_progress(raw) {
+                    Some(p) => filter.progress = Some(p),
+                    None => {
+                        return err_result(format!("bulk_update_tasks: unknown progress {raw}"))
+                    }
+                }
+            }
+            if let Some(raw) = parsed.priority.as_deref() {
+                match parse_priority(raw) {
+                    Some(p) => filter.priority = Some(p),
+                    None => {
+                        return err_result(format!("bulk_update_tasks: unknown priority {raw}"))
+                    }
+                }
+            }
+            if targets.is_empty() && filter.is_empty() {
+                return err_result("bulk_update_tasks: no target_ids or filters".to_string());
             }
             AiResult {
                 task_id: job.task_id,
@@ -1664,6 +1995,7 @@ fn triage_task(cfg: &LlmConfig, job: &AiJob, raw_input: &str) -> AiResult {
                 error: None,
                 triage_action: Some(TriageAction::BulkUpdate {
                     targets,
+                    filter,
                     instruction: parsed.instruction,
                 }),
                 sub_task_specs: Vec::new(),
@@ -1719,6 +2051,7 @@ fn call_llm_with_image(
     image_base64: &str,
     media_type: &str,
 ) -> Result<String, String> {
+    let system = with_system_prompt(cfg, system);
     let body = match cfg.provider {
         Provider::OpenAi => json!({
             "model": cfg.model,
@@ -1820,6 +2153,110 @@ pub struct SuggestedTask {
     pub title: String,
     pub description: String,
     pub priority: String,
+    /// Titles of separate action items when the email asks for several.
+    pub sub_tasks: Vec<String>,
+}
+
+/// Rough check for an HTML-only email body (no text/plain part was sent).
+fn looks_like_html(content: &str) -> bool {
+    let lower = content.to_ascii_lowercase();
+    ["<html", "<body", "<div", "<p>", "<p ", "<br", "<table"]
+        .iter()
+        .any(|tag| lower.contains(tag))
+}
+
+/// Plain text from an HTML email. Block tags become line breaks so the quote
+/// and signature checks in `email_excerpt` still see lines; `<blockquote>`
+/// (quoted replies) and head/style/script contents are dropped.
+fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len() / 2);
+    let mut hidden_depth = 0usize;
+    let mut chars = html.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '<' {
+            if hidden_depth == 0 {
+                out.push(ch);
+            }
+            continue;
+        }
+        let tag: String = chars.by_ref().take_while(|&c| c != '>').collect();
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "blockquote" | "head" | "style" | "script" => {
+                if closing {
+                    hidden_depth = hidden_depth.saturating_sub(1);
+                } else if !tag.ends_with('/') {
+                    hidden_depth += 1;
+                }
+            }
+            "br" | "p" | "div" | "li" | "tr" | "hr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+                if hidden_depth == 0 =>
+            {
+                out.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    let decoded = out
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let mut text = String::new();
+    let mut blank_run = 0;
+    for line in decoded.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+/// The part of an email body worth filtering: HTML is turned into text, then
+/// quoted lines, everything from an "On … wrote:" or "Original Message"
+/// header and the signature after a `-- ` line are dropped, and the rest is
+/// cut to `max_chars` characters.
+fn email_excerpt(content: &str, max_chars: usize) -> String {
+    let content = if looks_like_html(content) {
+        Cow::Owned(html_to_text(content))
+    } else {
+        Cow::Borrowed(content)
+    };
+    let mut kept = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end();
+        let reply_header = (line.starts_with("On ") && line.ends_with("wrote:"))
+            || line.contains("-----Original Message-----");
+        if line == "--" || reply_header {
+            break;
+        }
+        if !line.trim_start().starts_with('>') {
+            kept.push(line);
+        }
+    }
+    let text = kept.join("\n");
+    let text = text.trim();
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
 }
 
 pub fn filter_email_for_suggestions(
@@ -1840,12 +2277,23 @@ pub fn filter_email_for_suggestions(
         "Analyze this email and determine if it requires action.\n\n\
         From: {}\nSubject: {}\n\n{}\n\n\
         If this email requires action, respond with JSON: {{\"actionable\": true, \"title\": \"short task title\", \"description\": \"brief summary\", \"priority\": \"Low|Medium|High|Critical\"}}\n\
+        If it asks for several separate things, also add \"sub_tasks\": [\"short title\", ...] with one entry per action item, and make the title cover them all.\n\
         If NOT actionable (newsletter, spam, marketing, etc.), respond with: {{\"actionable\": false}}",
-        sender, subject, truncate(content, 800)
+        sender,
+        subject,
+        email_excerpt(content, settings.email_body_max_chars)
     );
 
     let response = call_llm(&cfg, system, &user)?;
-    let json_text = extract_json_object(&response).unwrap_or_else(|| response.trim().to_string());
+    parse_filter_response(&response, subject, sender)
+}
+
+fn parse_filter_response(
+    response: &str,
+    subject: &str,
+    sender: &str,
+) -> Result<Option<SuggestedTask>, String> {
+    let json_text = extract_json_object(response).unwrap_or_else(|| response.trim().to_string());
 
     #[derive(serde::Deserialize)]
     struct FilterResponse {
@@ -1856,6 +2304,8 @@ pub fn filter_email_for_suggestions(
         description: Option<String>,
         #[serde(default)]
         priority: Option<String>,
+        #[serde(default)]
+        sub_tasks: Vec<String>,
     }
 
     let parsed: FilterResponse = serde_json::from_str(&json_text)
@@ -1871,6 +2321,12 @@ pub fn filter_email_for_suggestions(
             .description
             .unwrap_or_else(|| format!("From: {}", sender)),
         priority: parsed.priority.unwrap_or_else(|| "Medium".to_string()),
+        sub_tasks: parsed
+            .sub_tasks
+            .into_iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
     }))
 }
 
@@ -1904,4 +2360,94 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn email_excerpt_drops_quotes_and_signature() {
+        let body = "Can you send the deck by Friday?\n\n> earlier thread\nThanks,\nSam\n-- \nSam Lee | Acme\n";
+        assert_eq!(
+            email_excerpt(body, 800),
+            "Can you send the deck by Friday?\n\nThanks,\nSam"
+        );
+        let reply = "Sounds good.\nOn Mon, 2 Mar 2026, Jo <jo@x.com> wrote:\nold text";
+        assert_eq!(email_excerpt(reply, 800), "Sounds good.");
+        assert_eq!(email_excerpt("héllo wörld", 4), "héll");
+        assert_eq!(email_excerpt("anything", 0), "");
+    }
+
+    #[test]
+    fn filter_response_keeps_action_items() {
+        let reply = r#"Sure: {"actionable": true, "title": "Prep offsite", "priority": "High",
+            "sub_tasks": ["Book venue", " ", "Send agenda"]}"#;
+        let task = parse_filter_response(reply, "Offsite", "jo@x.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.title, "Prep offsite");
+        assert_eq!(task.description, "From: jo@x.com");
+        assert_eq!(task.sub_tasks, ["Book venue", "Send agenda"]);
+        assert!(parse_filter_response(r#"{"actionable": false}"#, "s", "f")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn email_excerpt_reads_html_bodies() {
+        let html = "<html><head><style>p { color: red; }</style></head><body>\
+            <div dir=\"ltr\">Please review the Q3 budget&nbsp;by Thursday.<br>Thanks &amp; regards</div>\
+            <div class=\"gmail_quote\"><div>On Tue, 3 Mar 2026 at 10:00, Jo &lt;jo@x.com&gt; wrote:</div>\
+            <blockquote>Here is <b>last</b> week's draft</blockquote></div></body></html>";
+        assert_eq!(
+            email_excerpt(html, 800),
+            "Please review the Q3 budget by Thursday.\nThanks & regards"
+        );
+        assert_eq!(email_excerpt("a < b and c > d", 800), "a < b and c > d");
+    }
+
+    #[test]
+    fn scaled_timeout_never_passes_the_hard_cap() {
+        let big = json!({ "prompt": "x".repeat(200_000) });
+        let small = json!({ "prompt": "hi" });
+        assert_eq!(
+            scaled_timeout(Duration::from_secs(60), &small, 0),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            scaled_timeout(Duration::from_secs(60), &small, 2),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            scaled_timeout(Duration::from_secs(300), &big, 2),
+            Duration::from_secs(storage::MAX_TIMEOUT_SECS)
+        );
+    }
+
+    #[test]
+    fn system_prompt_is_trimmed_and_bounded() {
+        assert_eq!(
+            bounded_system_prompt("  Use British spelling.\n"),
+            "Use British spelling."
+        );
+        let long = "é".repeat(storage::SYSTEM_PROMPT_MAX_CHARS + 10);
+        assert_eq!(
+            bounded_system_prompt(&long).chars().count(),
+            storage::SYSTEM_PROMPT_MAX_CHARS
+        );
+    }
+
+    #[test]
+    fn bucket_guide_lists_descriptions() {
+        let buckets = vec![
+            BucketDef {
+                name: "Inbox".to_string(),
+                description: Some("unsorted".to_string()),
+            },
+            BucketDef {
+                name: "Backend".to_string(),
+                description: Some("  ".to_string()),
+            },
+        ];
+        assert_eq!(
+            bucket_guide(&buckets),
+            "Buckets:\n- Inbox: unsorted\n- Backend\n"
+        );
+    }
 }
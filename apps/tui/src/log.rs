@@ -0,0 +1,184 @@
+//! Opt-in diagnostics log for bug reports, turned on with `--verbose`/`-v` or
+//! `AIPM_LOG=1`. One JSON object per line in the data directory. Only sizes,
+//! timings and error messages are written, never prompts, replies or keys.
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+pub const LOG_FILE: &str = "aipm.log";
+
+/// Error text past this many characters is cut. Longer ones are usually a
+/// response body echoing the request, which can hold task descriptions or
+/// email text.
+const MAX_REDACTED_CHARS: usize = 300;
+
+/// Starts of the keys and tokens aipm handles: OpenAI and Anthropic API keys,
+/// Google access and refresh tokens, Google API keys.
+const SECRET_PREFIXES: [&str; 4] = ["sk-", "ya29.", "1//", "AIza"];
+
+/// Labels a secret follows in headers and URLs, for custom endpoints whose
+/// keys look like anything.
+const SECRET_LABELS: [&str; 4] = ["bearer ", "key=", "token=", "secret="];
+
+/// Past this size the log moves to `aipm.log.1`, replacing the previous one,
+/// so at most twice this is kept on disk.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+static LOG: OnceLock<Mutex<LogFile>> = OnceLock::new();
+
+/// Whether `AIPM_LOG` is set to anything but empty, `0`, `false` or `off`.
+pub fn env_enabled() -> bool {
+    std::env::var("AIPM_LOG").is_ok_and(|v| {
+        !matches!(
+            v.trim().to_ascii_lowercase().as_str(),
+            "" | "0" | "false" | "off"
+        )
+    })
+}
+
+/// Start appending to `LOG_FILE` in `dir`. If it can't be opened, logging
+/// stays off rather than getting in the way.
+pub fn init(dir: &Path) {
+    let path = dir.join(LOG_FILE);
+    let _ = fs::create_dir_all(dir);
+    let Ok(file) = open(&path) else {
+        return;
+    };
+    let len = file.metadata().map_or(0, |m| m.len());
+    if LOG.set(Mutex::new(LogFile { path, file, len })).is_err() {
+        return;
+    }
+    // A panicking background thread (the email poller, an AI job) otherwise
+    // disappears without a trace behind the TUI.
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        error("panic", json!({ "message": info.to_string() }));
+        previous(info);
+    }));
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// `••••` and the last four characters, enough to tell keys apart.
+pub fn mask_key(key: &str) -> String {
+    let tail: String = key
+        .chars()
+        .skip(key.chars().count().saturating_sub(4))
+        .collect();
+    if tail.len() == key.len() {
+        return "••••".to_string();
+    }
+    format!("••••{tail}")
+}
+
+/// Mask anything that looks like an API key or token in `text` and cut it to
+/// `MAX_REDACTED_CHARS`. Used on error messages before they are shown or
+/// logged.
+pub fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    'scan: while let Some(c) = rest.chars().next() {
+        // "risk-free" is not a key.
+        if out.chars().next_back().is_none_or(|c| !c.is_alphanumeric()) {
+            for prefix in SECRET_PREFIXES {
+                let Some(after) = rest.strip_prefix(prefix) else {
+                    continue;
+                };
+                let len = prefix.len() + secret_len(after);
+                if len >= prefix.len() + 8 {
+                    out.push_str(&mask_key(&rest[..len]));
+                    rest = &rest[len..];
+                    continue 'scan;
+                }
+            }
+        }
+        for label in SECRET_LABELS {
+            if !rest
+                .get(..label.len())
+                .is_some_and(|s| s.eq_ignore_ascii_case(label))
+            {
+                continue;
+            }
+            let after = &rest[label.len()..];
+            let len = secret_len(after);
+            if len >= 8 {
+                out.push_str(&rest[..label.len()]);
+                out.push_str(&mask_key(&after[..len]));
+                rest = &after[len..];
+                continue 'scan;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if out.chars().count() > MAX_REDACTED_CHARS {
+        out = out.chars().take(MAX_REDACTED_CHARS).collect();
+        out.push('…');
+    }
+    out
+}
+
+fn secret_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '+')))
+        .unwrap_or(s.len())
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = redact(s),
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(map) => map.values_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+pub fn info(event: &str, fields: Value) {
+    write("info", event, fields);
+}
+
+pub fn error(event: &str, fields: Value) {
+    write("error", event, fields);
+}
+
+fn write(level: &str, event: &str, mut fields: Value) {
+    let Some(log) = LOG.get() else {
+        return;
+    };
+    redact_value(&mut fields);
+    let mut entry = json!({
+        "ts": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": level,
+        "event": event,
+    });
+    if let (Some(entry), Value::Object(fields)) = (entry.as_object_mut(), fields) {
+        entry.extend(fields);
+    }
+    let line = format!("{entry}\n");
+    let Ok(mut log) = log.lock() else {
+        return;
+    };
+    if log.len + line.len() as u64 > MAX_LOG_BYTES {
+        let rotated = log.path.with_extension("log.1");
+        if fs::rename(&log.path, &rotated).is_ok() {
+            if let Ok(file) = open(&log.path) {
+                log.file = file;
+                log.len = 0;
+            }
+        }
+    }
+    if log.file.write_all(line.as_bytes()).is_ok() {
+        log.len += line.len() as u64;
+    }
+}
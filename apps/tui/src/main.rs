@@ -2,18 +2,25 @@ mod ai;
 mod cli;
 mod google;
 mod llm;
+mod log;
 mod model;
+mod secrets;
 mod storage;
 
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
 use std::io::{self, Stdout, Write};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
 
-use chrono::{Datelike, Local, Timelike, Utc};
+use chrono::{Datelike, Timelike, Utc};
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
     event::{
-        self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyModifiers,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
     },
     execute, queue,
     style::{
@@ -25,9 +32,18 @@ use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use uuid::Uuid;
 
 use crate::model::{
-    children_of, compute_parent_progress, EmailEvent, Priority, Progress, Suggestion, Task,
+    check_integrity, children_of, children_progress_mixed, clone_subtree, critical_path,
+    delete_with_children, dependents_of, effective_priority, escalation_levels, format_hours,
+    format_task_snapshot, humanize_age, nesting_depth, orphaned_bucket_tasks, parse_hours,
+    prune_dangling_dependencies, repair_integrity, sync_progress_from_children, task_start_date,
+    unmet_dependencies, would_create_ancestor_cycle, would_exceed_nesting, EmailEvent,
+    IntegrityReport, Priority, Progress, SortMode, Suggestion, Task, TaskIndex, WeekStart,
+    MAX_NESTING_DEPTH, TASK_COLORS,
+};
+use crate::storage::{
+    format_utc_offset, is_valid_date_format, parse_holidays, parse_stage_labels, parse_utc_offset,
+    parse_work_days, parse_workday_end, AiSettings, Storage,
 };
-use crate::storage::{AiSettings, Storage};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Tab {
@@ -79,6 +95,15 @@ enum Focus {
     Edit,
 }
 
+/// Severity of a status toast, which picks its border color and label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Toast {
+    Success,
+    Info,
+    Warning,
+    Error,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum InputMode {
     Chat,
@@ -99,17 +124,27 @@ enum EditField {
     Progress,
     Priority,
     DueDate,
+    Assignee,
+    Color,
+    Estimate,
+    Actual,
+    Notes,
     SubIssues,
 }
 
 impl EditField {
-    const ALL: [EditField; 7] = [
+    const ALL: [EditField; 12] = [
         EditField::Title,
         EditField::Description,
         EditField::Bucket,
         EditField::Progress,
         EditField::Priority,
         EditField::DueDate,
+        EditField::Assignee,
+        EditField::Color,
+        EditField::Estimate,
+        EditField::Actual,
+        EditField::Notes,
         EditField::SubIssues,
     ];
 
@@ -121,13 +156,18 @@ impl EditField {
             EditField::Progress => "Progress",
             EditField::Priority => "Priority",
             EditField::DueDate => "Due date",
+            EditField::Assignee => "Assignee",
+            EditField::Color => "Color",
+            EditField::Estimate => "Estimate",
+            EditField::Actual => "Actual",
+            EditField::Notes => "Notes",
             EditField::SubIssues => "Sub-issues",
         }
     }
 
     fn fields_for(is_child: bool) -> &'static [EditField] {
         if is_child {
-            &EditField::ALL[..6]
+            &EditField::ALL[..11]
         } else {
             &EditField::ALL
         }
@@ -145,31 +185,65 @@ enum SettingsField {
     GoogleAccount,
     OwnerName,
     UserProfile,
+    SystemPrompt,
     AiEnabled,
     OpenAiKey,
     AnthropicKey,
+    Keychain,
     Model,
     Timeout,
+    AiConcurrency,
+    AiLockBucket,
+    AiLockPriority,
+    AiLockDueDate,
+    AiVisibleOnly,
     ShowBacklog,
     ShowTodo,
     ShowInProgress,
     ShowDone,
+    StageLabels,
+    CardSubIssues,
+    AutoEscalate,
+    SnapshotRetention,
+    DateFormat,
+    WeekStart,
+    UtcOffset,
+    WorkDays,
+    Holidays,
+    WorkdayEnd,
 }
 
 impl SettingsField {
-    const ALL: [SettingsField; 12] = [
+    const ALL: [SettingsField; 29] = [
         SettingsField::GoogleAccount,
         SettingsField::OwnerName,
         SettingsField::UserProfile,
+        SettingsField::SystemPrompt,
         SettingsField::AiEnabled,
         SettingsField::OpenAiKey,
         SettingsField::AnthropicKey,
+        SettingsField::Keychain,
         SettingsField::Model,
         SettingsField::Timeout,
+        SettingsField::AiConcurrency,
+        SettingsField::AiLockBucket,
+        SettingsField::AiLockPriority,
+        SettingsField::AiLockDueDate,
+        SettingsField::AiVisibleOnly,
         SettingsField::ShowBacklog,
         SettingsField::ShowTodo,
         SettingsField::ShowInProgress,
         SettingsField::ShowDone,
+        SettingsField::StageLabels,
+        SettingsField::CardSubIssues,
+        SettingsField::AutoEscalate,
+        SettingsField::SnapshotRetention,
+        SettingsField::DateFormat,
+        SettingsField::WeekStart,
+        SettingsField::UtcOffset,
+        SettingsField::WorkDays,
+        SettingsField::Holidays,
+        SettingsField::WorkdayEnd,
     ];
 
     fn label(self) -> &'static str {
@@ -177,15 +251,32 @@ impl SettingsField {
             SettingsField::GoogleAccount => "Google",
             SettingsField::OwnerName => "Owner Name",
             SettingsField::UserProfile => "About You",
+            SettingsField::SystemPrompt => "Instructions",
             SettingsField::AiEnabled => "AI Enabled",
             SettingsField::OpenAiKey => "OpenAI Key",
             SettingsField::AnthropicKey => "Anthropic Key",
+            SettingsField::Keychain => "Keys in Keychain",
             SettingsField::Model => "Model",
             SettingsField::Timeout => "Timeout (sec)",
+            SettingsField::AiConcurrency => "AI Parallel",
+            SettingsField::AiLockBucket => "Lock Bucket",
+            SettingsField::AiLockPriority => "Lock Priority",
+            SettingsField::AiLockDueDate => "Lock Due Date",
+            SettingsField::AiVisibleOnly => "AI Sees Visible",
             SettingsField::ShowBacklog => "Show Backlog",
             SettingsField::ShowTodo => "Show Todo",
             SettingsField::ShowInProgress => "Show In Prog.",
             SettingsField::ShowDone => "Show Done",
+            SettingsField::StageLabels => "Stage Names",
+            SettingsField::CardSubIssues => "Card Sub-issues",
+            SettingsField::AutoEscalate => "Auto-escalate",
+            SettingsField::SnapshotRetention => "Undo History",
+            SettingsField::DateFormat => "Date Format",
+            SettingsField::WeekStart => "Week Starts",
+            SettingsField::UtcOffset => "Timezone",
+            SettingsField::WorkDays => "Work Days",
+            SettingsField::Holidays => "Holidays",
+            SettingsField::WorkdayEnd => "Workday Ends",
         }
     }
 
@@ -193,10 +284,17 @@ impl SettingsField {
         matches!(
             self,
             SettingsField::AiEnabled
+                | SettingsField::Keychain
+                | SettingsField::AiLockBucket
+                | SettingsField::AiLockPriority
+                | SettingsField::AiLockDueDate
+                | SettingsField::AiVisibleOnly
                 | SettingsField::ShowBacklog
                 | SettingsField::ShowTodo
                 | SettingsField::ShowInProgress
                 | SettingsField::ShowDone
+                | SettingsField::AutoEscalate
+                | SettingsField::WeekStart
         )
     }
 }
@@ -209,6 +307,27 @@ fn default_bucket_name(settings: &AiSettings) -> String {
         .unwrap_or_else(|| "Unassigned".to_string())
 }
 
+/// Configured bucket name matching `name`, ignoring case. Bucket names can
+/// change while an AI job is in flight, so results are re-checked here.
+fn known_bucket(settings: &AiSettings, name: &str) -> Option<String> {
+    settings
+        .buckets
+        .iter()
+        .find(|b| b.name.eq_ignore_ascii_case(name.trim()))
+        .map(|b| b.name.clone())
+}
+
+fn unknown_bucket_warning(
+    settings: &AiSettings,
+    suggested: Option<&str>,
+    placed_in: &str,
+) -> Option<String> {
+    let name = suggested?;
+    known_bucket(settings, name)
+        .is_none()
+        .then(|| format!("AI suggested unknown bucket '{name}', placed in {placed_in}"))
+}
+
 fn bucket_names(settings: &AiSettings) -> Vec<String> {
     settings.buckets.iter().map(|b| b.name.clone()).collect()
 }
@@ -216,6 +335,12 @@ fn bucket_names(settings: &AiSettings) -> Vec<String> {
 struct App {
     storage: Option<Storage>,
     tasks: Vec<Task>,
+    /// `Storage::tasks_stamp` as of the last save or load, to notice
+    /// changes made outside the TUI.
+    tasks_stamp: u64,
+    /// The tasks as last saved or loaded: the common ancestor when both the
+    /// TUI and another process changed them.
+    tasks_base: Vec<Task>,
     ai: Option<llm::AiRuntime>,
     tab: Tab,
     focus: Focus,
@@ -227,15 +352,20 @@ struct App {
 
     input: String,
     input_cursor: usize,
-    status: Option<(String, Instant, bool)>,
+    status: Option<(String, Instant, bool, Toast)>,
+    toast_queue: VecDeque<(String, bool, Toast)>,
 
     edit_task_id: Option<Uuid>,
     edit_field: EditField,
     edit_buf: String,
     edit_buf_cursor: usize,
     editing_text: bool,
+    /// `editing_text` is collecting an `@id` to move the edited task under.
+    reparent_prompt: bool,
     edit_sub_selected: usize,
     edit_parent_stack: Vec<(Uuid, EditField, usize)>,
+    /// `g` was pressed in the edit overlay; the next key picks a field to jump to.
+    edit_pending_g: bool,
 
     timeline_selected: usize,
     timeline_scroll: usize,
@@ -246,6 +376,10 @@ struct App {
     kanban_scroll: [usize; 4],
 
     confirm_delete_id: Option<Uuid>,
+    /// `/reset`: what has been typed into its confirmation so far.
+    confirm_reset: Option<String>,
+    /// Set when quitting was requested while AI jobs were in flight.
+    confirm_quit: Option<Instant>,
 
     bucket_header_selected: bool,
     bucket_edit_active: bool,
@@ -262,6 +396,17 @@ struct App {
     memory_selected: usize,
     /// Fact pending user confirmation before being stored.
     pending_memory: Option<String>,
+    /// AI bulk update (target ids, instruction) awaiting confirmation.
+    pending_bulk: Option<(Vec<Uuid>, String)>,
+    /// Confirmation for `A` (`Some(true)`, accept every suggestion) or `X`
+    /// (`Some(false)`, dismiss them all).
+    confirm_suggestions_all: Option<bool>,
+    /// Undo snapshots listed by `/snapshots`, newest first.
+    snapshot_list: Option<Vec<storage::HistoryEntry>>,
+    snapshot_selected: usize,
+    /// Snapshot (seq, label) about to be restored and what that would change,
+    /// shown for confirmation.
+    snapshot_preview: Option<(u64, String, storage::SnapshotDiff)>,
 
     input_mode: InputMode,
     checklist_selected: usize,
@@ -282,14 +427,19 @@ struct App {
     update_rx: Option<mpsc::Receiver<String>>,
 
     suggestions: Vec<Suggestion>,
+    /// Emails whose suggestion was dismissed, so the poller can't bring them back.
+    suggestions_dismissed: Vec<String>,
     suggestions_selected: usize,
     suggestions_last_polled_at: Option<chrono::DateTime<Utc>>,
     suggestions_last_poll_unread: usize,
     suggestions_last_poll_actionable: usize,
-    suggestions_last_poll_ok: bool,
+    suggestions_last_poll_error: Option<String>,
+    /// Suggestions added since the last poll summary, shown as "N new".
+    suggestions_new_since_poll: usize,
+    suggestions_last_poll_new: usize,
+    suggestions_last_poll_failed: Option<(usize, String)>,
 
     suggestions_rx: Option<mpsc::Receiver<EmailEvent>>,
-    task_email_map: std::collections::HashMap<Uuid, String>,
 
     calendar_events: Vec<google::CalendarEvent>,
     calendar_loading: bool,
@@ -298,10 +448,37 @@ struct App {
     data_dir: Option<std::path::PathBuf>,
     google_connected: bool,
     google_auth_rx: Option<mpsc::Receiver<Result<google::GoogleToken, String>>>,
+    connection_test_rx: Option<mpsc::Receiver<Result<String, String>>>,
 
     esc_count: u8,
     esc_last: Instant,
     autocomplete_last_height: usize,
+
+    help_open: bool,
+    help_scroll: usize,
+
+    too_small: bool,
+    context_overlay_open: bool,
+    /// Help-line token estimate and the `context_fingerprint` it was made for.
+    context_estimate: Option<(u64, usize)>,
+    task_index: TaskIndex,
+    /// Board filter set by `/filter @@name`.
+    assignee_filter: Option<String>,
+    /// `/pinned`: show only pinned tasks on Buckets and Kanban.
+    pinned_only: bool,
+    /// Kanban shows only this bucket's tasks, cycled with `[`/`]`; `None`
+    /// shows every bucket.
+    kanban_bucket: Option<String>,
+    /// Title quick filter typed after `/` on the board; cleared on tab switch.
+    board_query: String,
+    board_query_editing: bool,
+    /// Id prefix typed after `g` on the board; `None` while the prompt is closed.
+    goto_query: Option<String>,
+
+    /// Click targets from the last frame: tabs as (tab, x_start, x_end) on the
+    /// tab row, task cards/rows as (task_id, x, y, width, height).
+    tab_hitboxes: Vec<(Tab, u16, u16)>,
+    card_hitboxes: Vec<(Uuid, u16, u16, u16, u16)>,
 }
 
 struct TerminalGuard;
@@ -309,7 +486,13 @@ struct TerminalGuard;
 impl TerminalGuard {
     fn enter(stdout: &mut Stdout) -> io::Result<TerminalGuard> {
         terminal::enable_raw_mode()?;
-        execute!(stdout, EnterAlternateScreen, EnableBracketedPaste, Hide)?;
+        execute!(
+            stdout,
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            EnableMouseCapture,
+            Hide
+        )?;
         Ok(TerminalGuard)
     }
 }
@@ -318,7 +501,13 @@ impl Drop for TerminalGuard {
     fn drop(&mut self) {
         let mut stdout = io::stdout();
         let _ = terminal::disable_raw_mode();
-        let _ = execute!(stdout, Show, DisableBracketedPaste, LeaveAlternateScreen);
+        let _ = execute!(
+            stdout,
+            Show,
+            DisableMouseCapture,
+            DisableBracketedPaste,
+            LeaveAlternateScreen
+        );
     }
 }
 
@@ -335,48 +524,38 @@ fn spawn_calendar_fetch(
     rx
 }
 
+/// `tracked_email_ids` are emails with a suggestion, reported as archived
+/// once they leave the inbox. Only reading one keeps it tracked.
 fn spawn_email_poller(
     data_dir: std::path::PathBuf,
     settings: AiSettings,
+    mut tracked_email_ids: std::collections::HashSet<String>,
 ) -> mpsc::Receiver<EmailEvent> {
     let (tx, rx) = mpsc::channel();
     std::thread::spawn(move || {
-        let mut tracked_email_ids = std::collections::HashSet::<String>::new();
         loop {
             let checked_at = chrono::Utc::now();
-            let token = match google::get_valid_token(&data_dir) {
-                Ok(t) => t,
-                Err(_) => {
-                    if tx
-                        .send(EmailEvent::PollSummary {
-                            checked_at,
-                            unread_count: 0,
-                            actionable_count: 0,
-                            ok: false,
-                        })
-                        .is_err()
-                    {
-                        break;
-                    }
-                    std::thread::sleep(std::time::Duration::from_secs(60));
-                    continue;
-                }
-            };
-            let emails: Vec<google::Email> = match google::get_recent_emails(&token, 10) {
+            let token = google::get_valid_token(&data_dir);
+            let emails = token
+                .clone()
+                .and_then(|token| google::get_recent_emails(&token, 10));
+            let emails: Vec<google::Email> = match emails {
                 Ok(e) => e,
-                Err(_) => {
+                Err(err) => {
+                    log::error("poller.fetch", serde_json::json!({ "error": err }));
                     if tx
                         .send(EmailEvent::PollSummary {
                             checked_at,
                             unread_count: 0,
                             actionable_count: 0,
-                            ok: false,
+                            error: Some(err),
+                            failed: None,
                         })
                         .is_err()
                     {
                         break;
                     }
-                    std::thread::sleep(std::time::Duration::from_secs(60));
+                    poller_sleep();
                     continue;
                 }
             };
@@ -385,9 +564,16 @@ fn spawn_email_poller(
                 .filter(|e| !e.is_read)
                 .map(|e| e.id.clone())
                 .collect();
+            // An email that's no longer unread may only have been opened, so
+            // ask Gmail whether it left the inbox. Errors count as still there.
             let archived: Vec<String> = tracked_email_ids
                 .iter()
                 .filter(|id| !current_unread_ids.contains(*id))
+                .filter(|id| {
+                    token
+                        .as_ref()
+                        .is_ok_and(|t| google::is_in_inbox(t, id) == Ok(false))
+                })
                 .cloned()
                 .collect();
             for email_id in archived {
@@ -397,11 +583,11 @@ fn spawn_email_poller(
                 tracked_email_ids.remove(&email_id);
             }
             let mut actionable_count = 0usize;
+            let mut failed: Option<(usize, String)> = None;
             for email in emails {
                 if email.is_read {
                     continue;
                 }
-                tracked_email_ids.insert(email.id.clone());
                 let content = email.content.as_deref().unwrap_or("");
                 let filtered = match llm::filter_email_for_suggestions(
                     &settings,
@@ -410,7 +596,18 @@ fn spawn_email_poller(
                     content,
                 ) {
                     Ok(Some(task)) => task,
-                    _ => continue,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        let timed_out = llm::is_timeout(&err);
+                        let count = failed.as_ref().map_or(0, |(n, _)| *n);
+                        failed = Some((count + 1, err));
+                        // A model that timed out once would likely make every
+                        // remaining email wait just as long.
+                        if timed_out {
+                            break;
+                        }
+                        continue;
+                    }
                 };
                 let priority = match filtered.priority.to_ascii_lowercase().as_str() {
                     "low" => Priority::Low,
@@ -427,28 +624,63 @@ fn spawn_email_poller(
                     description: filtered.description,
                     priority,
                     created_at: chrono::Utc::now(),
+                    sender: email.sender.clone(),
+                    subject: email.subject.clone(),
+                    sub_tasks: filtered.sub_tasks,
                 };
+                tracked_email_ids.insert(email.id.clone());
                 if tx.send(EmailEvent::NewSuggestion(suggestion)).is_err() {
                     return;
                 }
             }
+            log::info(
+                "poller.poll",
+                serde_json::json!({
+                    "unread": current_unread_ids.len(),
+                    "actionable": actionable_count,
+                    "failed": failed.as_ref().map_or(0, |(n, _)| *n),
+                    "last_error": failed.as_ref().map(|(_, e)| e),
+                }),
+            );
             if tx
                 .send(EmailEvent::PollSummary {
                     checked_at,
                     unread_count: current_unread_ids.len(),
                     actionable_count,
-                    ok: true,
+                    error: None,
+                    failed,
                 })
                 .is_err()
             {
                 return;
             }
-            std::thread::sleep(std::time::Duration::from_secs(60));
+            poller_sleep();
         }
     });
     rx
 }
 
+const EMAIL_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Extra wait after a wake from sleep, so the poll and its AI calls don't
+/// all fail while Wi-Fi reconnects.
+const WAKE_GRACE: Duration = Duration::from_secs(15);
+
+/// Wait for the next email poll. `thread::sleep` doesn't count time spent
+/// suspended, so a wall-clock gap much longer than the interval means the
+/// machine slept: that still gives one poll, just a bit later.
+fn poller_sleep() {
+    let before = Utc::now();
+    std::thread::sleep(EMAIL_POLL_INTERVAL);
+    let slept = (Utc::now() - before).to_std().unwrap_or_default();
+    if slept > EMAIL_POLL_INTERVAL * 3 {
+        log::info(
+            "poller.wake",
+            serde_json::json!({ "asleep_secs": slept.as_secs() }),
+        );
+        std::thread::sleep(WAKE_GRACE);
+    }
+}
+
 fn spawn_update_check() -> mpsc::Receiver<String> {
     let (tx, rx) = mpsc::channel();
     std::thread::spawn(move || {
@@ -479,17 +711,24 @@ fn spawn_update_check() -> mpsc::Receiver<String> {
 }
 
 fn main() -> io::Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.iter().any(|a| a == "--help" || a == "-h") {
-        print_help();
-        return Ok(());
-    }
-    if args.iter().any(|a| a == "--version" || a == "-V") {
-        println!("aipm {}", env!("CARGO_PKG_VERSION"));
-        return Ok(());
+    let mut args: Vec<String> = std::env::args().collect();
+    // Taken out here so every subcommand accepts it.
+    let verbose = args.len() > 1 && {
+        let before = args.len();
+        args.retain(|a| a != "--verbose" && a != "-v");
+        args.len() < before
+    };
+    if verbose || log::env_enabled() {
+        if let Some(dir) = storage::data_dir() {
+            log::init(&dir);
+            log::info(
+                "start",
+                serde_json::json!({ "version": env!("CARGO_PKG_VERSION") }),
+            );
+        }
     }
 
-    // CLI subcommands: task, bucket.
+    // CLI subcommands first so `aipm task --help` shows the task usage.
     if let Some(result) = cli::run_subcommand(&args) {
         return result;
     }
@@ -499,6 +738,15 @@ fn main() -> io::Result<()> {
         return run_ingest(&args[2..]);
     }
 
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print_help();
+        return Ok(());
+    }
+    if args.iter().any(|a| a == "--version" || a == "-V") {
+        println!("aipm {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
     // CLI mode: `aipm "break down all tickets"` — headless AI, no TUI.
     let positional: Vec<&str> = args[1..]
         .iter()
@@ -511,25 +759,32 @@ fn main() -> io::Result<()> {
     }
 
     let storage = Storage::new();
-    let tasks = match &storage {
-        Some(s) => match s.load_tasks() {
-            Ok(tasks) => tasks,
+    let (tasks, skipped_files, newer_files) = match &storage {
+        Some(s) => match s.load_tasks_skipping() {
+            Ok(loaded) => loaded,
             Err(err) => {
                 eprintln!("Failed to load tasks: {err}");
-                Vec::new()
+                Default::default()
             }
         },
-        None => Vec::new(),
+        None => Default::default(),
     };
     let settings = match &storage {
         Some(s) => s.load_settings().unwrap_or_default(),
         None => AiSettings::default(),
     };
+    let (suggestions, suggestions_dismissed) = match &storage {
+        Some(s) => s.load_suggestions(),
+        None => Default::default(),
+    };
 
     let bucket_count = settings.buckets.len();
+    let tasks_stamp = storage.as_ref().map_or(0, Storage::tasks_stamp);
     let mut app = App {
         storage,
+        tasks_base: tasks.clone(),
         tasks,
+        tasks_stamp,
         ai: llm::AiRuntime::from_settings(&settings),
         tab: Tab::Checklist,
         focus: Focus::Board,
@@ -539,13 +794,16 @@ fn main() -> io::Result<()> {
         input: String::new(),
         input_cursor: 0,
         status: None,
+        toast_queue: VecDeque::new(),
         edit_task_id: None,
         edit_field: EditField::Title,
         edit_buf: String::new(),
         edit_buf_cursor: 0,
         editing_text: false,
+        reparent_prompt: false,
         edit_sub_selected: 0,
         edit_parent_stack: Vec::new(),
+        edit_pending_g: false,
         timeline_selected: 0,
         timeline_scroll: 0,
         timeline_collapsed: std::collections::HashSet::new(),
@@ -553,6 +811,8 @@ fn main() -> io::Result<()> {
         kanban_selected: None,
         kanban_scroll: [0; 4],
         confirm_delete_id: None,
+        confirm_reset: None,
+        confirm_quit: None,
         bucket_header_selected: false,
         bucket_edit_active: false,
         bucket_edit_field: BucketEditField::Name,
@@ -566,6 +826,11 @@ fn main() -> io::Result<()> {
         settings_memory_focus: false,
         memory_selected: 0,
         pending_memory: None,
+        pending_bulk: None,
+        confirm_suggestions_all: None,
+        snapshot_list: None,
+        snapshot_selected: 0,
+        snapshot_preview: None,
         input_mode: InputMode::Chat,
         checklist_selected: 0,
         checklist_section: ChecklistSection::Tasks,
@@ -579,14 +844,17 @@ fn main() -> io::Result<()> {
         at_autocomplete_selected: 0,
         slash_autocomplete_selected: 0,
         update_rx: None,
-        suggestions: Vec::new(),
+        suggestions,
+        suggestions_dismissed,
         suggestions_selected: 0,
         suggestions_last_polled_at: None,
         suggestions_last_poll_unread: 0,
         suggestions_last_poll_actionable: 0,
-        suggestions_last_poll_ok: true,
+        suggestions_last_poll_error: None,
+        suggestions_new_since_poll: 0,
+        suggestions_last_poll_new: 0,
+        suggestions_last_poll_failed: None,
         suggestions_rx: None,
-        task_email_map: std::collections::HashMap::new(),
         calendar_events: Vec::new(),
         calendar_loading: false,
         calendar_rx: None,
@@ -595,44 +863,190 @@ fn main() -> io::Result<()> {
             .and_then(|d| google::load_token(&d))
             .is_some(),
         google_auth_rx: None,
+        connection_test_rx: None,
         esc_count: 0,
         esc_last: Instant::now(),
         autocomplete_last_height: 0,
+        help_open: false,
+        help_scroll: 0,
+        too_small: false,
+        context_overlay_open: false,
+        context_estimate: None,
+        task_index: TaskIndex::default(),
+        assignee_filter: None,
+        pinned_only: false,
+        kanban_bucket: None,
+        board_query: String::new(),
+        board_query_editing: false,
+        goto_query: None,
+        tab_hitboxes: Vec::new(),
+        card_hitboxes: Vec::new(),
     };
 
     if auto_archive_tasks(&mut app.tasks) {
         persist(&mut app);
     }
+    // `aipm suggestions sync` may have turned pending suggestions into tasks.
+    let pending = app.suggestions.len();
+    app.suggestions.retain(|s| {
+        !app.tasks
+            .iter()
+            .any(|t| t.email_id.as_ref() == Some(&s.email_id))
+    });
+    if app.suggestions.len() != pending {
+        persist_suggestions(&mut app);
+    }
+    if app.settings.auto_escalate && auto_escalate_tasks(&mut app.tasks, app.settings.today()) {
+        persist(&mut app);
+    }
+    let pruned = prune_dangling_dependencies(&mut app.tasks);
+    if pruned > 0 {
+        push_toast(
+            &mut app,
+            pruned_dependencies_msg(pruned),
+            false,
+            Toast::Warning,
+        );
+        persist(&mut app);
+    }
+    if let [first, rest @ ..] = skipped_files.as_slice() {
+        let msg = match rest.len() {
+            0 => format!("1 task skipped (corrupt): tasks/{first}"),
+            n => format!(
+                "{} tasks skipped (corrupt): tasks/{first} and {n} more",
+                n + 1
+            ),
+        };
+        push_toast(&mut app, msg, false, Toast::Warning);
+    } else if let [first, rest @ ..] = newer_files.as_slice() {
+        let msg = match rest.len() {
+            0 => format!("1 task from a newer aipm is read-only: tasks/{first}"),
+            n => format!(
+                "{} tasks from a newer aipm are read-only: tasks/{first} and {n} more",
+                n + 1
+            ),
+        };
+        push_toast(&mut app, msg, false, Toast::Warning);
+    }
 
     app.update_rx = Some(spawn_update_check());
     if app.google_connected {
         if let Some(ref dir) = app.data_dir {
-            app.suggestions_rx = Some(spawn_email_poller(dir.clone(), app.settings.clone()));
+            app.suggestions_rx = Some(spawn_email_poller(
+                dir.clone(),
+                app.settings.clone(),
+                tracked_suggestion_emails(&app),
+            ));
         }
     }
 
     ensure_default_selection(&mut app);
 
+    let terminate = install_terminate_flag();
     let mut stdout = io::stdout();
     let _guard = TerminalGuard::enter(&mut stdout)?;
 
-    run_app(&mut stdout, &mut app)
+    run_app(&mut stdout, &mut app, &terminate)
+}
+
+fn pruned_dependencies_msg(pruned: usize) -> String {
+    format!(
+        "Removed {pruned} dependenc{} on deleted tasks",
+        if pruned == 1 { "y" } else { "ies" }
+    )
+}
+
+/// Set when the process gets SIGTERM or SIGHUP (e.g. the terminal window was
+/// closed), so the event loop can save and restore the terminal before exiting.
+fn install_terminate_flag() -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGHUP] {
+        let _ = signal_hook::flag::register(signal, Arc::clone(&flag));
+    }
+    flag
 }
 
 const TOAST_DURATION: Duration = Duration::from_secs(3);
+const TOAST_QUEUE_MAX: usize = 5;
+
+/// Show a toast, or queue it behind the one on screen so a burst of results
+/// (bulk AI edits) doesn't hide all but the last. Persistent toasts report
+/// progress, so newer messages show over them and they come back afterwards.
+fn push_toast(app: &mut App, mut msg: String, persistent: bool, kind: Toast) {
+    if kind == Toast::Error {
+        msg = log::redact(&msg);
+        log::error("toast", serde_json::json!({ "message": msg }));
+    }
+    // A persistent toast tracks running work such as an AI request, and the
+    // Esc×3 cancel looks for it in `status`, so it never waits in the queue.
+    // The toast it displaces is shown again afterwards.
+    if persistent {
+        app.toast_queue.retain(|(_, persistent, _)| !persistent);
+        if let Some((shown, _, false, shown_kind)) = app.status.take() {
+            app.toast_queue.push_front((shown, false, shown_kind));
+            app.toast_queue.truncate(TOAST_QUEUE_MAX);
+        }
+        app.status = Some((msg, Instant::now(), persistent, kind));
+    } else if matches!(app.status, Some((_, _, false, _))) {
+        if app.toast_queue.len() >= TOAST_QUEUE_MAX {
+            // Drop the oldest message, not the progress toast waiting first.
+            if let Some(i) = app.toast_queue.iter().position(|(_, p, _)| !p) {
+                app.toast_queue.remove(i);
+            }
+        }
+        app.toast_queue.push_back((msg, persistent, kind));
+    } else {
+        if let Some((shown, _, true, shown_kind)) = app.status.take() {
+            app.toast_queue.push_front((shown, true, shown_kind));
+        }
+        app.status = Some((msg, Instant::now(), persistent, kind));
+    }
+}
+
+/// Drop the progress toast once its work is done, shown or waiting to come
+/// back, so the results that follow don't bring it back.
+fn end_progress_toast(app: &mut App) {
+    app.toast_queue.retain(|(_, persistent, _)| !persistent);
+    if matches!(app.status, Some((_, _, true, _))) {
+        next_toast(app);
+    }
+}
+
+/// Replace the visible toast with the next queued one, if any.
+fn next_toast(app: &mut App) {
+    app.status = app
+        .toast_queue
+        .pop_front()
+        .map(|(msg, persistent, kind)| (msg, Instant::now(), persistent, kind));
+}
 
-fn run_app(stdout: &mut Stdout, app: &mut App) -> io::Result<()> {
+fn clear_toasts(app: &mut App) {
+    app.status = None;
+    app.toast_queue.clear();
+}
+
+fn run_app(stdout: &mut Stdout, app: &mut App, terminate: &AtomicBool) -> io::Result<()> {
     let mut needs_redraw = true;
     let mut needs_clear = true; // full screen clear on first draw
 
     let mut archive_check = Instant::now();
+    let mut tasks_check = Instant::now();
+    let mut orphan_count = 0;
+    check_orphaned_buckets(app, &mut orphan_count);
 
     loop {
-        if poll_ai(app) {
-            needs_redraw = true;
+        if terminate.load(Ordering::Relaxed) {
+            persist(app);
+            persist_settings(app);
+            break;
         }
 
-        if poll_suggestions(app) {
+        let anchor = view_anchor(app);
+        let mut polled = poll_ai(app);
+        polled |= poll_suggestions(app);
+        if polled {
+            restore_view_anchor(app, &anchor);
             needs_redraw = true;
         }
 
@@ -644,33 +1058,54 @@ fn run_app(stdout: &mut Stdout, app: &mut App) -> io::Result<()> {
             needs_redraw = true;
         }
 
+        if poll_connection_test(app) {
+            needs_redraw = true;
+        }
+
+        if tasks_check.elapsed() >= Duration::from_millis(500) {
+            if poll_external_tasks(app) {
+                needs_redraw = true;
+            }
+            tasks_check = Instant::now();
+        }
+
         if archive_check.elapsed() >= Duration::from_secs(60) {
             if auto_archive_tasks(&mut app.tasks) {
                 persist(app);
                 needs_redraw = true;
             }
+            if app.settings.auto_escalate
+                && auto_escalate_tasks(&mut app.tasks, app.settings.today())
+            {
+                persist(app);
+                needs_redraw = true;
+            }
+            if check_orphaned_buckets(app, &mut orphan_count) {
+                needs_redraw = true;
+            }
             archive_check = Instant::now();
         }
 
         if let Some(rx) = &app.update_rx {
             if let Ok(latest) = rx.try_recv() {
-                app.status = Some((
+                push_toast(
+                    app,
                     format!(
                         "Update available: v{latest} (current: v{})",
                         env!("CARGO_PKG_VERSION")
                     ),
-                    Instant::now(),
                     false,
-                ));
+                    Toast::Info,
+                );
                 app.update_rx = None;
                 needs_redraw = true;
             }
         }
 
         // Auto-dismiss toast after timeout (skip for persistent toasts).
-        if let Some((_, shown_at, persistent)) = &app.status {
+        if let Some((_, shown_at, persistent, _)) = &app.status {
             if !persistent && shown_at.elapsed() >= TOAST_DURATION {
-                app.status = None;
+                next_toast(app);
                 needs_redraw = true;
                 needs_clear = true; // clear toast remnants
             } else {
@@ -690,29 +1125,36 @@ fn run_app(stdout: &mut Stdout, app: &mut App) -> io::Result<()> {
             let prev_focus = app.focus;
             let prev_edit = app.edit_task_id;
             let prev_confirm = app.confirm_delete_id;
+            let prev_popup = app.help_open
+                || app.context_overlay_open
+                || app.confirm_reset.is_some()
+                || app.confirm_quit.is_some()
+                || app.confirm_suggestions_all.is_some()
+                || app.snapshot_list.is_some()
+                || app.snapshot_preview.is_some();
             let prev_bucket_edit = app.bucket_edit_active;
             let prev_header_sel = app.bucket_header_selected;
             let prev_at_ac =
                 input_has_at_prefix(&app.input, app.input_cursor) && app.focus == Focus::Input;
+            let mut handled = false;
             match event::read()? {
+                // While too small, only resize (below) and Ctrl+C are handled.
+                Event::Key(key)
+                    if app.too_small
+                        && key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    break;
+                }
+                Event::Key(_) | Event::Paste(_) | Event::Mouse(_) if app.too_small => {}
                 Event::Key(key) => {
                     if handle_key(app, key)? {
                         break;
                     }
-                    needs_redraw = true;
-                    let cur_at_ac = input_has_at_prefix(&app.input, app.input_cursor)
-                        && app.focus == Focus::Input;
-                    // Full clear when layout changes significantly.
-                    if app.tab != prev_tab
-                        || app.focus != prev_focus
-                        || app.edit_task_id != prev_edit
-                        || app.confirm_delete_id != prev_confirm
-                        || app.bucket_edit_active != prev_bucket_edit
-                        || app.bucket_header_selected != prev_header_sel
-                        || prev_at_ac != cur_at_ac
-                    {
-                        needs_clear = true;
-                    }
+                    handled = true;
+                }
+                Event::Mouse(mouse) => {
+                    handled = handle_mouse(app, mouse);
                 }
                 Event::Paste(text) => {
                     handle_paste(app, &text);
@@ -724,13 +1166,150 @@ fn run_app(stdout: &mut Stdout, app: &mut App) -> io::Result<()> {
                 }
                 _ => {}
             }
+            if handled {
+                needs_redraw = true;
+                let cur_at_ac =
+                    input_has_at_prefix(&app.input, app.input_cursor) && app.focus == Focus::Input;
+                // Full clear when layout changes significantly.
+                if app.tab != prev_tab
+                    || app.focus != prev_focus
+                    || app.edit_task_id != prev_edit
+                    || app.confirm_delete_id != prev_confirm
+                    || (app.help_open
+                        || app.context_overlay_open
+                        || app.confirm_reset.is_some()
+                        || app.confirm_quit.is_some()
+                        || app.confirm_suggestions_all.is_some()
+                        || app.snapshot_list.is_some()
+                        || app.snapshot_preview.is_some())
+                        != prev_popup
+                    || app.bucket_edit_active != prev_bucket_edit
+                    || app.bucket_header_selected != prev_header_sel
+                    || prev_at_ac != cur_at_ac
+                {
+                    needs_clear = true;
+                }
+            }
         }
     }
 
     Ok(())
 }
 
+/// Returns true when the event changed something worth redrawing.
+fn handle_mouse(app: &mut App, mouse: MouseEvent) -> bool {
+    let overlay_open = app.focus == Focus::Edit
+        || app.confirm_delete_id.is_some()
+        || app.confirm_reset.is_some()
+        || app.pending_memory.is_some()
+        || app.pending_bulk.is_some()
+        || app.confirm_suggestions_all.is_some()
+        || app.snapshot_list.is_some()
+        || app.snapshot_preview.is_some()
+        || app.bucket_edit_active
+        || app.context_overlay_open
+        || app.confirm_quit.is_some()
+        || (app.tab == Tab::Settings && app.settings_editing);
+
+    match mouse.kind {
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            let code = if mouse.kind == MouseEventKind::ScrollUp {
+                KeyCode::Up
+            } else {
+                KeyCode::Down
+            };
+            if app.help_open || (!overlay_open && app.focus == Focus::Board) {
+                let _ = handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+                return true;
+            }
+            false
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            if app.help_open || overlay_open {
+                return false;
+            }
+            if let Some(&(tab, _, _)) = app
+                .tab_hitboxes
+                .iter()
+                .find(|(_, x0, x1)| mouse.row == 1 && mouse.column >= *x0 && mouse.column < *x1)
+            {
+                switch_tab(app, tab);
+                return true;
+            }
+            let Some(&(id, ..)) = app.card_hitboxes.iter().find(|(_, x, y, w, h)| {
+                mouse.column >= *x && mouse.column < x + w && mouse.row >= *y && mouse.row < y + h
+            }) else {
+                return false;
+            };
+            select_task_at_click(app, id);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Clicking a task selects it in the current view; clicking it again opens it.
+fn select_task_at_click(app: &mut App, id: Uuid) {
+    let already_selected = app.focus == Focus::Board
+        && match app.tab {
+            Tab::Kanban => app.kanban_selected == Some(id),
+            Tab::Timeline => {
+                let indices = sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed);
+                indices
+                    .get(app.timeline_selected)
+                    .is_some_and(|&i| app.tasks[i].id == id)
+            }
+            _ => app.selected_task_id == Some(id) && !app.bucket_header_selected,
+        };
+    app.focus = Focus::Board;
+    clear_toasts(app);
+    select_in_view(app, id);
+
+    if already_selected {
+        open_edit_for(app, id);
+    }
+}
+
+/// Select `id` in the current tab: its bucket on Buckets, its stage column on
+/// Kanban, its row on Timeline.
+fn select_in_view(app: &mut App, id: Uuid) {
+    app.selected_task_id = Some(id);
+    match app.tab {
+        Tab::Default => {
+            app.bucket_header_selected = false;
+            if let Some(task) = app.task_index.get(&app.tasks, id) {
+                if let Some(bi) = app
+                    .settings
+                    .buckets
+                    .iter()
+                    .position(|b| b.name == task.bucket)
+                {
+                    app.selected_bucket = bi;
+                }
+            }
+        }
+        Tab::Kanban => {
+            if let Some(task) = app.task_index.get(&app.tasks, id) {
+                app.kanban_stage = task.progress;
+            }
+            app.kanban_selected = Some(id);
+        }
+        Tab::Timeline => {
+            let indices = sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed);
+            if let Some(pos) = indices.iter().position(|&i| app.tasks[i].id == id) {
+                app.timeline_selected = pos;
+            }
+        }
+        _ => {}
+    }
+}
+
 fn handle_paste(app: &mut App, text: &str) {
+    if app.focus == Focus::Edit && !app.editing_text && app.edit_field == EditField::SubIssues {
+        add_sub_issues_from_text(app, text);
+        return;
+    }
+
     let cleaned: String = text
         .chars()
         .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
@@ -755,7 +1334,12 @@ fn handle_paste(app: &mut App, text: &str) {
         app.bucket_edit_buf.insert_str(byte_pos, &cleaned);
         app.bucket_edit_buf_cursor += cleaned.chars().count();
     } else if app.tab == Tab::Settings && app.settings_editing {
-        app.settings_buf.push_str(&cleaned);
+        if app.settings_field == SettingsField::SystemPrompt {
+            app.settings_buf
+                .push_str(&text.replace("\r\n", "\n").replace('\r', "\n"));
+        } else {
+            app.settings_buf.push_str(&cleaned);
+        }
     } else if app.focus == Focus::Input {
         let byte_pos = app
             .input
@@ -768,14 +1352,40 @@ fn handle_paste(app: &mut App, text: &str) {
     }
 }
 
+const QUIT_FORCE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Returns true to quit now, or opens the quit confirmation if AI jobs are in flight.
+fn request_quit(app: &mut App) -> bool {
+    if app.ai.as_ref().is_some_and(|ai| ai.pending() > 0) {
+        app.confirm_quit = Some(Instant::now());
+        false
+    } else {
+        true
+    }
+}
+
 fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
-    // Ctrl-C always quits.
+    // Ctrl-C quits; while AI is working it asks first, and a second Ctrl-C forces it.
     if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
-        return Ok(true);
+        if let Some(asked) = app.confirm_quit {
+            if asked.elapsed() < QUIT_FORCE_WINDOW {
+                return Ok(true);
+            }
+        }
+        return Ok(request_quit(app));
+    }
+
+    // Quit confirmation intercepts all keys.
+    if app.confirm_quit.is_some() {
+        if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+            return Ok(true);
+        }
+        app.confirm_quit = None;
+        return Ok(false);
     }
 
-    // Esc×3: cancel AI and revert changes.
-    let ai_active = matches!(&app.status, Some((_, _, true)));
+    // Esc×3: cancel AI and offer to revert its changes.
+    let ai_active = matches!(&app.status, Some((_, _, true, _)));
     if key.code == KeyCode::Esc && ai_active {
         if app.esc_last.elapsed() > Duration::from_millis(1500) {
             app.esc_count = 0;
@@ -785,36 +1395,32 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
 
         if app.esc_count >= 3 {
             app.ai = None;
-            if let Some(storage) = &app.storage {
-                if let Ok(label) = storage.undo() {
-                    if let Ok(fresh) = storage.reload_tasks() {
-                        app.tasks = fresh;
-                    }
-                    app.status = Some((
-                        format!("AI cancelled — reverted \"{}\"", label),
-                        Instant::now(),
-                        false,
-                    ));
-                } else {
-                    app.status = Some(("AI cancelled".to_string(), Instant::now(), false));
+            clear_toasts(app);
+            let latest = app
+                .storage
+                .as_ref()
+                .and_then(|s| s.list_history().last().map(|e| e.seq));
+            match latest {
+                Some(seq) => preview_restore(app, seq),
+                None => {
+                    push_toast(app, "AI cancelled".to_string(), false, Toast::Info);
                 }
-            } else {
-                app.status = Some(("AI cancelled".to_string(), Instant::now(), false));
             }
             app.ai = llm::AiRuntime::from_settings(&app.settings);
             app.esc_count = 0;
             return Ok(false);
         }
         let remaining = 3 - app.esc_count;
-        app.status = Some((
+        push_toast(
+            app,
             format!(
                 "Press Esc {} more time{} to cancel AI and revert",
                 remaining,
                 if remaining == 1 { "" } else { "s" }
             ),
-            Instant::now(),
             true,
-        ));
+            Toast::Warning,
+        );
         return Ok(false);
     } else if key.code != KeyCode::Esc {
         app.esc_count = 0;
@@ -822,38 +1428,72 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
 
     // Ctrl+Z: undo last operation.
     if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
-        if let Some(storage) = &app.storage {
-            if let Ok(label) = storage.undo() {
-                if let Ok(fresh) = storage.reload_tasks() {
-                    app.tasks = fresh;
-                }
+        // The prompt counts tasks the undo is about to change.
+        app.confirm_reset = None;
+        match app.storage.as_ref().map(Storage::undo) {
+            Some(Ok(label)) => {
+                reload_tasks(app);
                 app.checklist_frozen_order = None;
-                app.status = Some((format!("Undid \"{}\"", label), Instant::now(), false));
-            } else {
-                app.status = Some(("Nothing to undo".to_string(), Instant::now(), false));
+                push_toast(app, format!("Undid \"{}\"", label), false, Toast::Success);
+            }
+            Some(Err(_)) => {
+                push_toast(app, "Nothing to undo".to_string(), false, Toast::Info);
             }
+            None => {}
         }
         return Ok(false);
     }
 
     // Toast dismissal intercepts all keys (skip for persistent toasts).
-    if let Some((_, _, persistent)) = &app.status {
+    if let Some((_, _, persistent, _)) = &app.status {
         if !persistent {
-            app.status = None;
+            next_toast(app);
             return Ok(false);
         }
     }
 
+    // Context breakdown closes on any key.
+    if app.context_overlay_open {
+        app.context_overlay_open = false;
+        return Ok(false);
+    }
+
+    // Help overlay intercepts all keys.
+    if app.help_open {
+        return handle_help_key(app, key);
+    }
+
     // Memory confirmation intercepts all keys.
     if app.pending_memory.is_some() {
         return handle_memory_confirm_key(app, key);
     }
 
+    // Bulk update confirmation intercepts all keys.
+    if app.pending_bulk.is_some() {
+        return handle_bulk_confirm_key(app, key);
+    }
+
+    if app.confirm_suggestions_all.is_some() {
+        return handle_suggestions_all_key(app, key);
+    }
+
+    if app.snapshot_preview.is_some() {
+        return handle_snapshot_preview_key(app, key);
+    }
+
+    if app.snapshot_list.is_some() {
+        return handle_snapshots_key(app, key);
+    }
+
     // Delete confirmation intercepts all keys.
     if app.confirm_delete_id.is_some() {
         return handle_confirm_delete_key(app, key);
     }
 
+    if app.confirm_reset.is_some() {
+        return handle_confirm_reset_key(app, key);
+    }
+
     // Bucket edit overlay intercepts all keys.
     if app.bucket_edit_active {
         return handle_bucket_edit_key(app, key);
@@ -869,6 +1509,20 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         return handle_settings_edit_key(app, key);
     }
 
+    // Board quick filter prompt intercepts all keys while typing.
+    if app.board_query_editing {
+        return handle_board_query_key(app, key);
+    }
+    if app.goto_query.is_some() {
+        return handle_goto_key(app, key);
+    }
+
+    if key.code == KeyCode::Char('?') && app.focus != Focus::Input {
+        app.help_open = true;
+        app.help_scroll = 0;
+        return Ok(false);
+    }
+
     // Tab bar navigation intercepts all keys.
     if app.focus == Focus::Tabs {
         return handle_tabs_key(app, key);
@@ -879,60 +1533,27 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         return handle_input_key(app, key);
     }
 
-    // Tab switching with 1/2/3/4/5/0 and Checklist Suggestions with 6 (no modifiers).
-    match key.code {
-        KeyCode::Char('1') => {
-            app.tab = Tab::Checklist;
-            app.checklist_section = ChecklistSection::Tasks;
-            app.focus = Focus::Board;
-            app.status = None;
-            return Ok(false);
-        }
-        KeyCode::Char('2') => {
-            app.tab = Tab::Calendar;
-            app.focus = Focus::Board;
-            app.status = None;
-            if app.google_connected && !app.calendar_loading && app.calendar_events.is_empty() {
-                app.calendar_loading = true;
-                if let Some(ref dir) = app.data_dir {
-                    app.calendar_rx = Some(spawn_calendar_fetch(dir.clone()));
-                }
-            }
-            return Ok(false);
-        }
-        KeyCode::Char('3') => {
-            app.tab = Tab::Default;
-            app.focus = Focus::Board;
-            app.status = None;
-            return Ok(false);
-        }
-        KeyCode::Char('4') => {
-            app.tab = Tab::Timeline;
-            app.focus = Focus::Board;
-            app.status = None;
-            return Ok(false);
-        }
-        KeyCode::Char('5') => {
-            app.tab = Tab::Kanban;
-            app.focus = Focus::Board;
-            app.status = None;
-            return Ok(false);
-        }
-        KeyCode::Char('6') => {
-            app.tab = Tab::Checklist;
-            app.checklist_section = ChecklistSection::Suggestions;
-            app.focus = Focus::Board;
-            app.status = None;
+    if matches!(app.tab, Tab::Default | Tab::Kanban) {
+        if key.code == KeyCode::Char('/') {
+            app.board_query_editing = true;
             return Ok(false);
         }
-        KeyCode::Char('0') => {
-            app.tab = Tab::Settings;
-            app.focus = Focus::Board;
-            app.settings_editing = false;
-            app.status = None;
+        if key.code == KeyCode::Esc && !app.board_query.is_empty() {
+            clear_board_query(app);
             return Ok(false);
         }
-        _ => {}
+    }
+    if key.code == KeyCode::Char('g')
+        && matches!(app.tab, Tab::Default | Tab::Kanban | Tab::Timeline)
+    {
+        app.goto_query = Some(String::new());
+        return Ok(false);
+    }
+
+    // Tab switching with 1/2/3/4/5/0 and Checklist Suggestions with 6 (no modifiers).
+    if let KeyCode::Char(ch @ ('0'..='6')) = key.code {
+        switch_tab_by_digit(app, ch);
+        return Ok(false);
     }
 
     if key.code == KeyCode::Char('@') {
@@ -958,73 +1579,160 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     }
 }
 
-fn handle_tabs_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
-    match key.code {
-        KeyCode::Left | KeyCode::Char('h') => {
-            app.tab = app.tab.prev();
-        }
-        KeyCode::Right | KeyCode::Char('l') => {
-            app.tab = app.tab.next();
+fn switch_tab(app: &mut App, tab: Tab) {
+    app.tab = tab;
+    app.focus = Focus::Board;
+    clear_toasts(app);
+    clear_board_query(app);
+    match tab {
+        Tab::Checklist => app.checklist_section = ChecklistSection::Tasks,
+        Tab::Calendar
+            if app.google_connected && !app.calendar_loading && app.calendar_events.is_empty() =>
+        {
+            app.calendar_loading = true;
+            if let Some(ref dir) = app.data_dir {
+                app.calendar_rx = Some(spawn_calendar_fetch(dir.clone()));
+            }
         }
-        KeyCode::Enter | KeyCode::Down | KeyCode::Char('j') => {
-            app.focus = Focus::Board;
-            app.status = None;
+        Tab::Settings => app.settings_editing = false,
+        _ => {}
+    }
+}
+
+fn handle_board_query_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    match key.code {
+        KeyCode::Esc => clear_board_query(app),
+        KeyCode::Enter => app.board_query_editing = false,
+        KeyCode::Backspace => {
+            app.board_query.pop();
         }
-        KeyCode::Char('1') => {
-            app.tab = Tab::Checklist;
-            app.checklist_section = ChecklistSection::Tasks;
-            app.focus = Focus::Board;
-            app.status = None;
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.board_query.push(c);
         }
-        KeyCode::Char('2') => {
-            app.tab = Tab::Calendar;
-            app.focus = Focus::Board;
-            app.status = None;
-            if app.google_connected && !app.calendar_loading && app.calendar_events.is_empty() {
-                app.calendar_loading = true;
-                if let Some(ref dir) = app.data_dir {
-                    app.calendar_rx = Some(spawn_calendar_fetch(dir.clone()));
+        _ => return Ok(false),
+    }
+    ensure_default_selection(app);
+    ensure_kanban_selection(app);
+    Ok(false)
+}
+
+fn handle_goto_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    let Some(query) = app.goto_query.as_mut() else {
+        return Ok(false);
+    };
+    match key.code {
+        KeyCode::Esc => app.goto_query = None,
+        KeyCode::Backspace => {
+            query.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            query.push(c);
+        }
+        KeyCode::Enter => {
+            let prefix = query.trim().trim_start_matches('@').to_ascii_lowercase();
+            match app.task_index.matching(&app.tasks, &prefix).as_slice() {
+                [] => push_toast(
+                    app,
+                    format!("No task matches @{prefix}"),
+                    false,
+                    Toast::Warning,
+                ),
+                [id] => {
+                    app.goto_query = None;
+                    goto_task(app, *id);
                 }
+                // Left open so a character or two can be added.
+                many => push_toast(
+                    app,
+                    format!(
+                        "ambiguous id @{prefix} ({} tasks), use more characters",
+                        many.len()
+                    ),
+                    false,
+                    Toast::Warning,
+                ),
             }
         }
-        KeyCode::Char('3') => {
-            app.tab = Tab::Default;
-            app.focus = Focus::Board;
-            app.status = None;
-        }
-        KeyCode::Char('4') => {
-            app.tab = Tab::Timeline;
-            app.focus = Focus::Board;
-            app.status = None;
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Select `id` on the current board, clearing the filters that would hide
+/// it. Tasks the view can't show at all (hidden stages, parents on Kanban)
+/// open in the edit overlay instead.
+fn goto_task(app: &mut App, id: Uuid) {
+    app.board_query.clear();
+    if app
+        .task_index
+        .get(&app.tasks, id)
+        .is_some_and(|t| app.kanban_bucket.as_ref().is_some_and(|b| *b != t.bucket))
+    {
+        app.kanban_bucket = None;
+    }
+    select_in_view(app, id);
+    let shown = match app.tab {
+        Tab::Kanban => {
+            ensure_kanban_selection(app);
+            scroll_kanban_to_selected(app);
+            app.kanban_selected == Some(id)
         }
-        KeyCode::Char('5') => {
-            app.tab = Tab::Kanban;
-            app.focus = Focus::Board;
-            app.status = None;
+        Tab::Timeline => sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed)
+            .get(app.timeline_selected)
+            .is_some_and(|&i| app.tasks[i].id == id),
+        _ => {
+            ensure_default_selection(app);
+            app.selected_task_id == Some(id)
         }
-        KeyCode::Char('6') => {
-            app.tab = Tab::Checklist;
+    };
+    if !shown {
+        open_edit_for(app, id);
+    }
+}
+
+fn clear_board_query(app: &mut App) {
+    app.board_query.clear();
+    app.board_query_editing = false;
+    ensure_default_selection(app);
+    ensure_kanban_selection(app);
+}
+
+fn switch_tab_by_digit(app: &mut App, digit: char) {
+    match digit {
+        '1' => switch_tab(app, Tab::Checklist),
+        '2' => switch_tab(app, Tab::Calendar),
+        '3' => switch_tab(app, Tab::Default),
+        '4' => switch_tab(app, Tab::Timeline),
+        '5' => switch_tab(app, Tab::Kanban),
+        '6' => {
+            switch_tab(app, Tab::Checklist);
             app.checklist_section = ChecklistSection::Suggestions;
-            app.focus = Focus::Board;
-            app.status = None;
         }
-        KeyCode::Char('0') => {
-            app.tab = Tab::Settings;
+        '0' => switch_tab(app, Tab::Settings),
+        _ => {}
+    }
+}
+
+fn handle_tabs_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    match key.code {
+        KeyCode::Left | KeyCode::Char('h') => {
+            app.tab = app.tab.prev();
+            clear_board_query(app);
+        }
+        KeyCode::Right | KeyCode::Char('l') => {
+            app.tab = app.tab.next();
+            clear_board_query(app);
+        }
+        KeyCode::Enter | KeyCode::Down | KeyCode::Char('j') => {
             app.focus = Focus::Board;
-            app.settings_editing = false;
-            app.status = None;
+            clear_toasts(app);
         }
+        KeyCode::Char(ch @ ('0'..='6')) => switch_tab_by_digit(app, ch),
         _ => {}
     }
     Ok(false)
 }
 
-fn task_start_date(task: &Task) -> chrono::NaiveDate {
-    task.start_date
-        .map(|dt| dt.date_naive())
-        .unwrap_or_else(|| task.created_at.date_naive())
-}
-
 fn sorted_timeline_tasks(
     tasks: &[Task],
     collapsed: &std::collections::HashSet<Uuid>,
@@ -1132,9 +1840,9 @@ fn char_byte_pos(s: &str, char_idx: usize) -> usize {
         .unwrap_or(s.len())
 }
 
-/// Return the visible slice of `input` that keeps `cursor_char` on-screen,
-/// plus the visual x-offset of the cursor within that slice.
-fn input_visible_window(input: &str, cursor_char: usize, max_width: usize) -> (String, usize) {
+/// Char index where the visible window of `input` starts so `cursor_char`
+/// stays on-screen within `max_width` columns.
+fn input_window_start(input: &str, cursor_char: usize, max_width: usize) -> usize {
     use unicode_width::UnicodeWidthChar;
 
     let chars: Vec<char> = input.chars().collect();
@@ -1145,23 +1853,9 @@ fn input_visible_window(input: &str, cursor_char: usize, max_width: usize) -> (S
         .map(|c| UnicodeWidthChar::width(*c).unwrap_or(0))
         .sum();
 
-    if input.width() <= max_width {
-        return (input.to_string(), width_before);
-    }
-
-    if width_before < max_width {
-        // Cursor visible when showing from start.
-        let mut out = String::new();
-        let mut w = 0;
-        for &ch in &chars {
-            let cw = UnicodeWidthChar::width(ch).unwrap_or(0);
-            if w + cw > max_width {
-                break;
-            }
-            out.push(ch);
-            w += cw;
-        }
-        return (out, width_before);
+    // Cursor visible when showing from start.
+    if input.width() <= max_width || width_before < max_width {
+        return 0;
     }
 
     // Scroll so cursor is near the right edge.
@@ -1175,6 +1869,17 @@ fn input_visible_window(input: &str, cursor_char: usize, max_width: usize) -> (S
         start -= 1;
         vis_w += cw;
     }
+    start
+}
+
+/// Return the visible slice of `input` that keeps `cursor_char` on-screen,
+/// plus the visual x-offset of the cursor within that slice.
+fn input_visible_window(input: &str, cursor_char: usize, max_width: usize) -> (String, usize) {
+    use unicode_width::UnicodeWidthChar;
+
+    let chars: Vec<char> = input.chars().collect();
+    let cursor_char = cursor_char.min(chars.len());
+    let start = input_window_start(input, cursor_char, max_width);
 
     let mut out = String::new();
     let mut w = 0;
@@ -1229,7 +1934,7 @@ fn replace_at_token(input: &str, cursor: usize, replacement: &str) -> (String, u
 
 /// Expand every resolved `@<hex-id>` token in `input` to `"<title>" (@<id>)` so the AI
 /// understands which tasks are being referenced when mentions appear mid-sentence.
-fn expand_at_mentions(tasks: &[Task], input: &str) -> String {
+fn expand_at_mentions(tasks: &[Task], index: &mut TaskIndex, input: &str) -> String {
     let chars: Vec<char> = input.chars().collect();
     let mut out = String::with_capacity(input.len());
     let mut i = 0;
@@ -1241,16 +1946,9 @@ fn expand_at_mentions(tasks: &[Task], input: &str) -> String {
                 token_end += 1;
             }
             let token: String = chars[token_start..token_end].iter().collect();
-            let lower = token.to_ascii_lowercase();
-            if (4..=8).contains(&lower.len()) && lower.chars().all(|c| c.is_ascii_hexdigit()) {
-                if let Some(task) = tasks.iter().find(|t| {
-                    let short =
-                        t.id.to_string()
-                            .chars()
-                            .take(lower.len())
-                            .collect::<String>();
-                    short.to_ascii_lowercase() == lower
-                }) {
+            // An ambiguous prefix is left as typed rather than pinned to a guess.
+            if let [id] = index.matching(tasks, &token).as_slice() {
+                if let Some(task) = index.get(tasks, *id) {
                     let short = task.id.to_string().chars().take(8).collect::<String>();
                     out.push_str(&format!("\"{}\" (@{})", task.title, short));
                     i = token_end;
@@ -1264,6 +1962,27 @@ fn expand_at_mentions(tasks: &[Task], input: &str) -> String {
     out
 }
 
+/// Strip an `@@name` assignee token from `input`. The last token wins.
+/// Without one, `input` comes back as it was, spacing and line breaks
+/// included.
+fn extract_assignee(input: &str) -> (String, Option<String>) {
+    let mut assignee = None;
+    let words: Vec<&str> = input
+        .split_whitespace()
+        .filter(|w| match w.strip_prefix("@@") {
+            Some(name) if !name.is_empty() => {
+                assignee = Some(name.to_string());
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    match assignee {
+        Some(name) => (words.join(" "), Some(name)),
+        None => (input.to_string(), None),
+    }
+}
+
 /// Compute @ autocomplete completions based on the `@token` at the cursor position.
 fn at_completions(tasks: &[Task], input: &str, cursor: usize) -> Vec<(String, String, String)> {
     let query = match active_at_query(input, cursor) {
@@ -1313,27 +2032,248 @@ fn active_slash_query(input: &str, cursor: usize) -> Option<String> {
     }
 }
 
+/// Slash commands as (command, arguments, description). Drives autocomplete,
+/// the `/help` overlay and `--help`.
+const SLASH_COMMANDS: &[(&str, &str, &str)] = &[
+    ("help", "", "Show commands and keybindings"),
+    ("clear", "", "Clear AI chat history"),
+    ("buckets", "", "List all buckets"),
+    ("bucket add", "<name>", "Add a new bucket"),
+    ("bucket rename", "<old> <new>", "Rename a bucket"),
+    (
+        "bucket desc",
+        "<name> [text]",
+        "Set or clear a bucket description",
+    ),
+    (
+        "bucket delete",
+        "<name>",
+        "Delete a bucket (tasks move to the first)",
+    ),
+    ("organize", "", "AI restructures all tasks"),
+    ("context", "", "Show what the AI context is made of"),
+    ("snapshots", "", "Browse undo snapshots and restore one"),
+    ("filter", "[@@name]", "Show only tasks assigned to name"),
+    ("pinned", "", "Show only pinned tasks (again to show all)"),
+    ("reset", "", "Delete all tasks, after typing DELETE"),
+    (
+        "template save",
+        "<name>",
+        "Save the selected task and its sub-issues",
+    ),
+    (
+        "template use",
+        "<name>",
+        "Create tasks from a template in the current bucket",
+    ),
+    ("template list", "", "List saved templates"),
+    (
+        "doctor",
+        "",
+        "List data problems (missing buckets, broken links)",
+    ),
+    (
+        "doctor fix",
+        "[bucket]",
+        "Repair them; orphaned tasks move to bucket or the first one",
+    ),
+    ("exit", "", "Quit the app"),
+];
+
+/// Keybindings grouped by context, shown in the `/help` overlay.
+const KEY_HELP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Input",
+        &[
+            ("<text>", "AI triage: create, update or answer"),
+            ("@<id> <text>", "AI-edit a task by ID prefix"),
+            ("Tab", "Toggle Chat / Add mode"),
+            ("↑/↓", "Input history"),
+            ("Enter", "Submit"),
+            ("Esc", "Back to the board"),
+        ],
+    ),
+    (
+        "Global",
+        &[
+            ("1-5, 0", "Switch tabs (0 = Settings)"),
+            ("6", "Checklist suggestions"),
+            ("i", "Focus the input"),
+            ("@", "Mention the selected task in the input"),
+            ("?", "Show this help"),
+            ("Ctrl+Z", "Undo the last change"),
+            ("Esc ×3", "Cancel a running AI job and offer to revert"),
+            ("Ctrl+C", "Quit (asks first while AI is working)"),
+        ],
+    ),
+    (
+        "Checklist",
+        &[
+            ("j/k", "Move selection"),
+            ("Tab", "Switch tasks / suggestions"),
+            ("Enter", "Toggle done, or accept a suggestion"),
+            ("Space", "Expand or collapse sub-issues"),
+            ("e", "Edit task"),
+            ("d", "Delete task or dismiss suggestion"),
+            ("A / X", "Accept / dismiss all suggestions"),
+        ],
+    ),
+    (
+        "Buckets",
+        &[
+            ("h/j/k/l", "Move between buckets and cards"),
+            ("Enter/e", "Edit task (or bucket header)"),
+            ("p / P", "Advance / retreat progress"),
+            ("o", "Cycle sort order"),
+            ("*", "Pin / unpin task"),
+            ("/", "Filter cards by title (Esc clears)"),
+            ("g", "Go to a task by id"),
+            ("y / Y", "Duplicate task / with sub-issues"),
+            ("d/x", "Delete task"),
+        ],
+    ),
+    (
+        "Timeline",
+        &[
+            ("j/k", "Move selection"),
+            ("Enter/e", "Edit task"),
+            ("Space", "Fold or unfold sub-issues"),
+            ("g", "Go to a task by id"),
+        ],
+    ),
+    (
+        "Kanban",
+        &[
+            ("h/l", "Move between stages"),
+            ("j/k", "Move within a stage"),
+            ("p / P", "Advance / retreat progress"),
+            ("o", "Cycle sort order"),
+            ("[ / ]", "Show one bucket / all buckets"),
+            ("*", "Pin / unpin task"),
+            ("/", "Filter cards by title (Esc clears)"),
+            ("g", "Go to a task by id"),
+            ("Enter/e", "Edit task"),
+            ("y / Y", "Duplicate task / with sub-issues"),
+            ("d/x", "Delete task"),
+        ],
+    ),
+    (
+        "Edit overlay",
+        &[
+            ("j/k", "Move between fields"),
+            ("Enter/e", "Edit field or open sub-issue"),
+            ("←/→", "Cycle bucket, progress, priority"),
+            ("n", "Add a note"),
+            ("a", "Add a sub-issue"),
+            ("u", "Promote sub-issue to a top-level task"),
+            ("Shift+↑/↓", "Reorder the selected sub-issue"),
+            ("m", "Move under another task (@id)"),
+            ("o", "Open the source email in Gmail"),
+            ("g t/d/s", "Jump to title / description / sub-issues"),
+            ("G", "Jump to the last field"),
+            ("y / Y", "Duplicate / with sub-issues"),
+            ("d", "Delete"),
+            ("Esc", "Close"),
+        ],
+    ),
+    (
+        "Settings",
+        &[
+            ("j/k", "Move between fields"),
+            ("Enter/Space", "Edit or toggle"),
+            ("←/→", "Cycle model or toggle"),
+            ("Alt+Enter", "New line in Instructions"),
+            ("t", "Test the AI connection (on Model)"),
+            ("e / d", "Type a model name / drop a custom one (on Model)"),
+        ],
+    ),
+];
+
+/// Help overlay content: (heading, [(keys, description)]).
+fn help_sections() -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+    let mut sections = vec![(
+        "Slash commands",
+        SLASH_COMMANDS
+            .iter()
+            .map(|(cmd, args, desc)| {
+                let usage = if args.is_empty() {
+                    format!("/{}", cmd)
+                } else {
+                    format!("/{} {}", cmd, args)
+                };
+                (usage, *desc)
+            })
+            .collect(),
+    )];
+    for (heading, keys) in KEY_HELP {
+        sections.push((
+            heading,
+            keys.iter().map(|(k, d)| (k.to_string(), *d)).collect(),
+        ));
+    }
+    sections
+}
+
 fn slash_completions(input: &str, cursor: usize) -> Vec<(&'static str, &'static str)> {
     let query = match active_slash_query(input, cursor) {
         Some(q) => q,
         None => return Vec::new(),
     };
-    let all_commands: &[(&str, &str)] = &[
-        ("clear", "Clear AI chat history"),
-        ("buckets", "List all buckets"),
-        ("bucket add", "Add a new bucket"),
-        ("bucket rename", "Rename a bucket"),
-        ("organize", "AI restructures all tasks"),
-        ("exit", "Quit the app"),
-    ];
     let query_lower = query.to_lowercase();
-    all_commands
+    SLASH_COMMANDS
         .iter()
-        .filter(|(cmd, _)| cmd.starts_with(&query_lower) || query_lower.is_empty())
-        .copied()
+        .filter(|(cmd, _, _)| cmd.starts_with(&query_lower) || query_lower.is_empty())
+        .map(|(cmd, _, desc)| (*cmd, *desc))
         .collect()
 }
 
+/// Colored spans of the input line as char ranges: a known slash command in
+/// cyan and, in chat mode, each `@id` in green when it names exactly one task
+/// or red when it doesn't.
+fn input_highlights(app: &mut App) -> Vec<(std::ops::Range<usize>, Color)> {
+    let chars: Vec<char> = app.input.chars().collect();
+    let mut spans = Vec::new();
+    let lead = chars.iter().take_while(|c| c.is_whitespace()).count();
+    if chars.get(lead) == Some(&'/') {
+        let rest: String = chars[lead + 1..].iter().collect::<String>().to_lowercase();
+        let known = SLASH_COMMANDS
+            .iter()
+            .map(|(cmd, _, _)| *cmd)
+            .filter(|cmd| {
+                rest.strip_prefix(cmd)
+                    .is_some_and(|after| after.is_empty() || after.starts_with(' '))
+            })
+            .max_by_key(|cmd| cmd.len());
+        if let Some(cmd) = known {
+            spans.push((lead..lead + 1 + cmd.chars().count(), Color::Cyan));
+        }
+    }
+    if app.input_mode == InputMode::Chat {
+        let mut i = 0;
+        while i < chars.len() {
+            let word_start = i == 0 || chars[i - 1] == ' ';
+            if !word_start || chars[i] != '@' || chars.get(i + 1) == Some(&'@') {
+                i += 1;
+                continue;
+            }
+            let end = chars[i..]
+                .iter()
+                .position(|&c| c == ' ')
+                .map_or(chars.len(), |p| i + p);
+            let token: String = chars[i + 1..end].iter().collect();
+            if !token.is_empty() {
+                let color = match app.task_index.matching(&app.tasks, &token).len() {
+                    1 => Color::Green,
+                    _ => Color::Red,
+                };
+                spans.push((i..end, color));
+            }
+            i = end;
+        }
+    }
+    spans
+}
+
 fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     // / command autocomplete interception.
     let slash_comps = slash_completions(&app.input, app.input_cursor);
@@ -1412,7 +2352,7 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     match key.code {
         KeyCode::Esc => {
             app.focus = Focus::Board;
-            app.status = None;
+            clear_toasts(app);
             Ok(false)
         }
         KeyCode::Tab => {
@@ -1517,13 +2457,127 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             app.input_saved.clear();
 
             if app.input.trim().eq_ignore_ascii_case("/exit") {
-                return Ok(true);
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(request_quit(app));
+            }
+
+            if app.input.trim().eq_ignore_ascii_case("/context") {
+                app.context_overlay_open = true;
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(false);
+            }
+
+            if app.input.trim().eq_ignore_ascii_case("/snapshots") {
+                open_snapshots(app);
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(false);
+            }
+
+            // /filter [@@name]: show only tasks assigned to name; bare /filter clears.
+            let filter_arg = app
+                .input
+                .trim()
+                .strip_prefix("/filter")
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+                .map(|rest| rest.trim().trim_start_matches('@').to_string());
+            if let Some(name) = filter_arg {
+                let msg = if name.is_empty() {
+                    "Filter cleared".to_string()
+                } else {
+                    format!("Showing tasks assigned to @{name}")
+                };
+                app.assignee_filter = (!name.is_empty()).then_some(name);
+                push_toast(app, msg, false, Toast::Info);
+                ensure_default_selection(app);
+                ensure_kanban_selection(app);
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(false);
+            }
+
+            // /pinned toggles the pinned-only board filter.
+            if app.input.trim() == "/pinned" {
+                app.input.clear();
+                app.input_cursor = 0;
+                if !app.pinned_only && !app.tasks.iter().any(|t| t.pinned) {
+                    push_toast(
+                        app,
+                        "No pinned tasks; press * on a card to pin it".to_string(),
+                        false,
+                        Toast::Warning,
+                    );
+                    return Ok(false);
+                }
+                app.pinned_only = !app.pinned_only;
+                let msg = if app.pinned_only {
+                    app.kanban_bucket = None;
+                    "Showing pinned tasks"
+                } else {
+                    "Showing all tasks"
+                };
+                push_toast(app, msg.to_string(), false, Toast::Info);
+                ensure_default_selection(app);
+                ensure_kanban_selection(app);
+                return Ok(false);
+            }
+
+            // /reset clears every task once DELETE is typed to confirm.
+            if app.input.trim() == "/reset" {
+                app.input.clear();
+                app.input_cursor = 0;
+                if app.tasks.is_empty() {
+                    push_toast(app, "No tasks to delete".to_string(), false, Toast::Info);
+                } else {
+                    app.confirm_reset = Some(String::new());
+                }
+                return Ok(false);
+            }
+
+            // /template save|use|list: reusable task trees.
+            let template_args = app
+                .input
+                .trim()
+                .strip_prefix("/template")
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+                .map(|rest| rest.trim().to_string());
+            if let Some(args) = template_args {
+                let (msg, kind) = run_template_command(app, &args);
+                push_toast(app, msg, false, kind);
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(false);
+            }
+
+            // /doctor [fix [bucket]]: report or repair data integrity problems.
+            let doctor_arg = app
+                .input
+                .trim()
+                .strip_prefix("/doctor")
+                .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+                .map(|rest| rest.trim().to_string());
+            if let Some(args) = doctor_arg {
+                let (msg, kind) = run_doctor_command(app, &args);
+                push_toast(app, msg, false, kind);
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(false);
+            }
+
+            if app.input.trim().eq_ignore_ascii_case("/help") {
+                app.help_open = true;
+                app.help_scroll = 0;
+                app.input.clear();
+                app.input_cursor = 0;
+                return Ok(false);
             }
 
             // /clear: reset AI conversation context.
             if app.input.trim().eq_ignore_ascii_case("/clear") {
                 app.chat_history.clear();
-                app.status = Some(("Context cleared".to_string(), Instant::now(), false));
+                push_toast(app, "Context cleared".to_string(), false, Toast::Success);
                 app.input.clear();
                 app.input_cursor = 0;
                 return Ok(false);
@@ -1543,11 +2597,12 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                         }
                     })
                     .collect();
-                app.status = Some((
+                push_toast(
+                    app,
                     format!("Buckets: {}", names.join(", ")),
-                    Instant::now(),
                     false,
-                ));
+                    Toast::Info,
+                );
                 app.input.clear();
                 app.input_cursor = 0;
                 return Ok(false);
@@ -1557,22 +2612,24 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             if let Some(rest) = app.input.trim().strip_prefix("/bucket add ") {
                 let name = rest.trim().to_string();
                 if name.is_empty() {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Usage: /bucket add <name>".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 } else if app
                     .settings
                     .buckets
                     .iter()
                     .any(|b| b.name.eq_ignore_ascii_case(&name))
                 {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         format!("Bucket \"{}\" already exists", name),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 } else {
                     app.settings.buckets.push(crate::model::BucketDef {
                         name: name.clone(),
@@ -1580,7 +2637,12 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     });
                     app.bucket_scrolls.push(0);
                     persist_settings(app);
-                    app.status = Some((format!("Added bucket: {}", name), Instant::now(), false));
+                    push_toast(
+                        app,
+                        format!("Added bucket: {}", name),
+                        false,
+                        Toast::Success,
+                    );
                 }
                 app.input.clear();
                 app.input_cursor = 0;
@@ -1591,11 +2653,12 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             if let Some(rest) = app.input.trim().strip_prefix("/bucket rename ") {
                 let parts: Vec<&str> = rest.splitn(2, ' ').collect();
                 if parts.len() < 2 || parts[0].trim().is_empty() || parts[1].trim().is_empty() {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Usage: /bucket rename <old> <new>".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 } else {
                     let old = parts[0].trim();
                     let new_name = parts[1].trim().to_string();
@@ -1615,17 +2678,19 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                         }
                         persist_settings(app);
                         persist(app);
-                        app.status = Some((
+                        push_toast(
+                            app,
                             format!("Renamed: {} → {}", old_name, new_name),
-                            Instant::now(),
                             false,
-                        ));
+                            Toast::Success,
+                        );
                     } else {
-                        app.status = Some((
+                        push_toast(
+                            app,
                             format!("Bucket \"{}\" not found", old),
-                            Instant::now(),
                             false,
-                        ));
+                            Toast::Warning,
+                        );
                     }
                 }
                 app.input.clear();
@@ -1637,11 +2702,12 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             if let Some(rest) = app.input.trim().strip_prefix("/bucket desc ") {
                 let parts: Vec<&str> = rest.splitn(2, ' ').collect();
                 if parts.is_empty() || parts[0].trim().is_empty() {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Usage: /bucket desc <name> <description>".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 } else {
                     let name = parts[0].trim();
                     let desc = parts
@@ -1662,13 +2728,14 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                             format!("Cleared description for {}", bname)
                         };
                         persist_settings(app);
-                        app.status = Some((msg, Instant::now(), false));
+                        push_toast(app, msg, false, Toast::Success);
                     } else {
-                        app.status = Some((
+                        push_toast(
+                            app,
                             format!("Bucket \"{}\" not found", name),
-                            Instant::now(),
                             false,
-                        ));
+                            Toast::Warning,
+                        );
                     }
                 }
                 app.input.clear();
@@ -1680,17 +2747,19 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             if let Some(rest) = app.input.trim().strip_prefix("/bucket delete ") {
                 let name = rest.trim();
                 if name.is_empty() {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Usage: /bucket delete <name>".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 } else if app.settings.buckets.len() <= 1 {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Cannot delete the last bucket".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 } else if let Some(pos) = app
                     .settings
                     .buckets
@@ -1730,14 +2799,15 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     } else {
                         format!("Deleted bucket \"{}\" (no tasks affected)", removed_name)
                     };
-                    app.status = Some((msg, Instant::now(), false));
+                    push_toast(app, msg, false, Toast::Success);
                     ensure_default_selection(app);
                 } else {
-                    app.status = Some((
+                    push_toast(
+                        app,
                         format!("Bucket \"{}\" not found", name),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Warning,
+                    );
                 }
                 app.input.clear();
                 app.input_cursor = 0;
@@ -1745,19 +2815,16 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             }
 
             // Reload tasks from disk before processing input to pick up external changes.
-            if let Some(storage) = &app.storage {
-                if let Ok(fresh) = storage.reload_tasks() {
-                    app.tasks = fresh;
-                }
-            }
+            reload_tasks(app);
 
             // /organize: AI restructures all tasks into optimal format.
             if app.input.trim() == "/organize" {
                 app.input.clear();
                 app.input_cursor = 0;
                 if let Some(ai) = &app.ai {
-                    let context = build_ai_context(&app.tasks);
-                    let triage_ctx = build_triage_context(&app.tasks);
+                    let context = build_ai_context(&app.tasks, ai_context_filter(&app.settings));
+                    let triage_ctx =
+                        build_triage_context(&app.tasks, ai_context_filter(&app.settings));
                     let organize_prompt = format!(
                         "Review ALL tasks and subtasks listed below. Restructure them into the best possible format:\n\
                         - Merge or consolidate obvious duplicates\n\
@@ -1774,10 +2841,10 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                         title: String::new(),
                         suggested_bucket: default_bucket_name(&app.settings),
                         context,
-                        bucket_names: bucket_names(&app.settings),
-                        lock_bucket: false,
-                        lock_priority: false,
-                        lock_due_date: false,
+                        buckets: app.settings.buckets.clone(),
+                        lock_bucket: app.settings.ai_lock_bucket,
+                        lock_priority: app.settings.ai_lock_priority,
+                        lock_due_date: app.settings.ai_lock_due_date,
                         edit_instruction: None,
                         task_snapshot: None,
                         triage_input: Some(organize_prompt),
@@ -1785,23 +2852,26 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                         chat_history: app.chat_history.clone(),
                         user_profile: app.settings.user_profile.clone(),
                         memory_facts: app.settings.memory_facts.clone(),
+                        assignee: None,
                     });
-                    app.status = Some(("AI organising tasks…".to_string(), Instant::now(), true));
+                    push_toast(app, "AI organising tasks…".to_string(), true, Toast::Info);
                 } else {
-                    app.status = Some(("AI not configured".to_string(), Instant::now(), false));
+                    push_toast(app, "AI not configured".to_string(), false, Toast::Warning);
                 }
                 return Ok(false);
             }
 
             // Add mode: create task directly, no AI.
             if app.input_mode == InputMode::Add {
-                let title = app.input.trim().to_string();
+                let (title, assignee) = extract_assignee(&app.input);
+                let title = title.trim();
                 if !title.is_empty() {
                     let now = Utc::now();
                     let bucket = default_bucket_name(&app.settings);
-                    let task = Task::new(bucket, title.clone(), now);
+                    let mut task = Task::new(bucket, title.to_string(), now);
+                    task.assignee = assignee;
                     app.tasks.push(task);
-                    app.status = Some((format!("Added: {title}"), Instant::now(), false));
+                    push_toast(app, format!("Added: {title}"), false, Toast::Success);
                     persist(app);
                 }
                 app.input.clear();
@@ -1810,7 +2880,7 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             }
 
             // @ prefix: edit a specific task (by id) or the selected task via AI.
-            if app.input.trim().starts_with('@') {
+            if app.input.trim().starts_with('@') && !app.input.trim().starts_with("@@") {
                 let after_at = app
                     .input
                     .trim()
@@ -1819,8 +2889,18 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     .trim()
                     .to_string();
                 if !after_at.is_empty() {
-                    let (target_task_id, instruction) =
-                        resolve_at_mention(&app.tasks, &after_at, app.selected_task_id);
+                    let (target_task_id, instruction) = match resolve_at_mention(
+                        &app.tasks,
+                        &mut app.task_index,
+                        &after_at,
+                        app.selected_task_id,
+                    ) {
+                        Ok(resolved) => resolved,
+                        Err(e) => {
+                            push_toast(app, e, false, Toast::Warning);
+                            return Ok(false);
+                        }
+                    };
 
                     if !instruction.is_empty() {
                         // Detect decompose-intent instructions and route through triage.
@@ -1836,8 +2916,10 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
 
                         if is_decompose {
                             if let Some(ai) = &app.ai {
-                                let context = build_ai_context(&app.tasks);
-                                let triage_ctx = build_triage_context(&app.tasks);
+                                // Decomposing may need hidden tasks too (e.g. finished
+                                // siblings), so it always sees the whole list.
+                                let context = build_ai_context(&app.tasks, |_| true);
+                                let triage_ctx = build_triage_context(&app.tasks, |_| true);
                                 let triage_input =
                                     annotate_mention(&app.tasks, target_task_id, &instruction);
                                 app.last_triage_input = triage_input.clone();
@@ -1846,10 +2928,10 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                                     title: String::new(),
                                     suggested_bucket: default_bucket_name(&app.settings),
                                     context,
-                                    bucket_names: bucket_names(&app.settings),
-                                    lock_bucket: false,
-                                    lock_priority: false,
-                                    lock_due_date: false,
+                                    buckets: app.settings.buckets.clone(),
+                                    lock_bucket: app.settings.ai_lock_bucket,
+                                    lock_priority: app.settings.ai_lock_priority,
+                                    lock_due_date: app.settings.ai_lock_due_date,
                                     edit_instruction: None,
                                     task_snapshot: None,
                                     triage_input: Some(triage_input),
@@ -1857,27 +2939,55 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                                     chat_history: app.chat_history.clone(),
                                     user_profile: app.settings.user_profile.clone(),
                                     memory_facts: app.settings.memory_facts.clone(),
+                                    assignee: None,
                                 });
-                                app.status =
-                                    Some(("AI decomposing…".to_string(), Instant::now(), true));
+                                push_toast(app, "AI decomposing…".to_string(), true, Toast::Info);
                             } else {
-                                app.status =
-                                    Some(("AI not configured".to_string(), Instant::now(), false));
+                                push_toast(
+                                    app,
+                                    "AI not configured".to_string(),
+                                    false,
+                                    Toast::Warning,
+                                );
+                            }
+                        } else if let Some(task_id) = target_task_id.filter(|_| app.ai.is_none()) {
+                            match ai::parse_quick_edit(&instruction, &app.settings) {
+                                Some(quick) => {
+                                    if let Some(storage) = &app.storage {
+                                        storage.snapshot("quick edit");
+                                    }
+                                    let summary = apply_quick_edit(
+                                        &mut app.tasks,
+                                        task_id,
+                                        &quick,
+                                        &app.settings,
+                                        Utc::now(),
+                                    );
+                                    if let Some(summary) = summary {
+                                        push_toast(app, summary, false, Toast::Success);
+                                    }
+                                    follow_selected_task(app);
+                                    persist(app);
+                                }
+                                None => {
+                                    push_toast(app, "AI not configured. Offline edits understand: done, high, bucket <name>, due <date>".to_string(), false, Toast::Warning);
+                                }
                             }
                         } else if let Some(task_id) = target_task_id {
-                            if let Some(task) = app.tasks.iter().find(|t| t.id == task_id) {
+                            if let Some(task) = app.task_index.get(&app.tasks, task_id) {
                                 let snapshot = format_task_snapshot(task);
-                                let context = build_ai_context(&app.tasks);
+                                let context =
+                                    build_ai_context(&app.tasks, ai_context_filter(&app.settings));
                                 if let Some(ai) = &app.ai {
                                     ai.enqueue(llm::AiJob {
                                         task_id,
                                         title: task.title.clone(),
                                         suggested_bucket: task.bucket.clone(),
                                         context,
-                                        bucket_names: bucket_names(&app.settings),
-                                        lock_bucket: false,
-                                        lock_priority: false,
-                                        lock_due_date: false,
+                                        buckets: app.settings.buckets.clone(),
+                                        lock_bucket: app.settings.ai_lock_bucket,
+                                        lock_priority: app.settings.ai_lock_priority,
+                                        lock_due_date: app.settings.ai_lock_due_date,
                                         edit_instruction: Some(instruction),
                                         task_snapshot: Some(snapshot),
                                         triage_input: None,
@@ -1885,23 +2995,25 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                                         chat_history: Vec::new(),
                                         user_profile: app.settings.user_profile.clone(),
                                         memory_facts: app.settings.memory_facts.clone(),
+                                        assignee: None,
                                     });
-                                    app.status = Some((
+                                    push_toast(
+                                        app,
                                         format!("AI editing: {}…", task.title),
-                                        Instant::now(),
                                         true,
-                                    ));
+                                        Toast::Info,
+                                    );
                                 } else {
-                                    app.status = Some((
+                                    push_toast(
+                                        app,
                                         "AI not configured".to_string(),
-                                        Instant::now(),
                                         false,
-                                    ));
+                                        Toast::Warning,
+                                    );
                                 }
                             }
                         } else {
-                            app.status =
-                                Some(("No task selected".to_string(), Instant::now(), false));
+                            push_toast(app, "No task selected".to_string(), false, Toast::Warning);
                         }
                     }
                 }
@@ -1910,8 +3022,8 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 return Ok(false);
             }
 
-            let raw_input = app.input.trim().to_string();
-            if raw_input.is_empty() {
+            let (raw_input, assignee) = extract_assignee(&app.input);
+            if raw_input.trim().is_empty() {
                 return Ok(false);
             }
             app.input.clear();
@@ -1919,20 +3031,20 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
 
             // AI triage: let the AI decide create vs update.
             if let Some(ai) = &app.ai {
-                let context = build_ai_context(&app.tasks);
-                let triage_ctx = build_triage_context(&app.tasks);
+                let context = build_ai_context(&app.tasks, ai_context_filter(&app.settings));
+                let triage_ctx = build_triage_context(&app.tasks, ai_context_filter(&app.settings));
                 // Expand any inline @<id> mentions so the AI knows exactly which tasks are referenced.
-                let triage_input = expand_at_mentions(&app.tasks, &raw_input);
+                let triage_input = expand_at_mentions(&app.tasks, &mut app.task_index, &raw_input);
                 app.last_triage_input = triage_input.clone();
                 ai.enqueue(llm::AiJob {
                     task_id: Uuid::nil(),
                     title: String::new(),
                     suggested_bucket: default_bucket_name(&app.settings),
                     context,
-                    bucket_names: bucket_names(&app.settings),
-                    lock_bucket: false,
-                    lock_priority: false,
-                    lock_due_date: false,
+                    buckets: app.settings.buckets.clone(),
+                    lock_bucket: app.settings.ai_lock_bucket,
+                    lock_priority: app.settings.ai_lock_priority,
+                    lock_due_date: app.settings.ai_lock_due_date,
                     edit_instruction: None,
                     task_snapshot: None,
                     triage_input: Some(triage_input),
@@ -1940,12 +3052,14 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     chat_history: app.chat_history.clone(),
                     user_profile: app.settings.user_profile.clone(),
                     memory_facts: app.settings.memory_facts.clone(),
+                    assignee,
                 });
-                app.status = Some(("AI thinking…".to_string(), Instant::now(), true));
+                push_toast(app, "AI thinking…".to_string(), true, Toast::Info);
             } else {
                 // Fallback: local inference when AI is not configured.
                 let bnames = bucket_names(&app.settings);
-                let maybe = ai::infer_new_task(&raw_input, &bnames);
+                let maybe =
+                    ai::infer_new_task(&raw_input, &bnames, app.settings.today(), &app.settings);
                 if let Some(hints) = maybe {
                     let now = Utc::now();
                     let mut task = Task::new(hints.bucket.clone(), hints.title, now);
@@ -1955,12 +3069,14 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     if let Some(d) = hints.due_date {
                         task.due_date = Some(d);
                     }
+                    task.assignee = assignee;
                     app.tasks.push(task);
-                    app.status = Some((
+                    push_toast(
+                        app,
                         format!("Created in {}", hints.bucket),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Success,
+                    );
                     ensure_default_selection(app);
                     persist(app);
                 }
@@ -2089,72 +3205,279 @@ fn handle_input_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     }
 }
 
+fn handle_help_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('?') => {
+            app.help_open = false;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.help_scroll = app.help_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.help_scroll += 1;
+        }
+        KeyCode::PageUp => {
+            app.help_scroll = app.help_scroll.saturating_sub(10);
+        }
+        KeyCode::PageDown | KeyCode::Char(' ') => {
+            app.help_scroll += 10;
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
 fn handle_memory_confirm_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     match key.code {
         KeyCode::Char('y') | KeyCode::Enter => {
             if let Some(fact) = app.pending_memory.take() {
                 app.settings.memory_facts.push(fact);
                 persist_settings(app);
-                app.status = Some(("Memory saved".to_string(), Instant::now(), false));
+                push_toast(app, "Memory saved".to_string(), false, Toast::Success);
             }
         }
         KeyCode::Char('n') | KeyCode::Esc => {
             app.pending_memory = None;
-            app.status = Some(("Memory dismissed".to_string(), Instant::now(), false));
+            push_toast(app, "Memory dismissed".to_string(), false, Toast::Info);
         }
         _ => {}
     }
     Ok(false)
 }
 
-fn handle_confirm_delete_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+fn handle_bulk_confirm_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     match key.code {
-        KeyCode::Enter => {
-            if let Some(id) = app.confirm_delete_id.take() {
-                app.selected_task_id = Some(id);
-                delete_selected(app);
-                // If still in the edit overlay, clamp sub-issue selection.
-                if app.focus == Focus::Edit {
-                    if let Some(parent_id) = app.edit_task_id {
-                        let child_count =
-                            visible_children_of(&app.tasks, parent_id, &app.settings).len();
-                        app.edit_sub_selected =
-                            app.edit_sub_selected.min(child_count.saturating_sub(1));
-                    }
-                }
+        KeyCode::Char('y') | KeyCode::Enter => {
+            if let Some((task_ids, instruction)) = app.pending_bulk.take() {
+                enqueue_bulk_edits(app, &task_ids, &instruction);
             }
         }
-        KeyCode::Esc => {
-            app.confirm_delete_id = None;
+        KeyCode::Char('n') | KeyCode::Esc => {
+            app.pending_bulk = None;
+            push_toast(app, "Bulk update cancelled".to_string(), false, Toast::Info);
         }
         _ => {}
     }
     Ok(false)
 }
 
-fn handle_board_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
-    // ── Bucket header selected ──
-    if app.bucket_header_selected {
-        match key.code {
-            KeyCode::Esc => {
-                app.bucket_header_selected = false;
-                app.focus = Focus::Tabs;
-            }
-            KeyCode::Tab | KeyCode::Char('i') => {
-                app.bucket_header_selected = false;
-                app.focus = Focus::Input;
-            }
-            KeyCode::Left | KeyCode::Char('h') => {
-                let n = app.settings.buckets.len();
-                if n > 0 {
-                    app.selected_bucket = (app.selected_bucket + n - 1) % n;
-                }
-            }
-            KeyCode::Right | KeyCode::Char('l') => {
-                let n = app.settings.buckets.len();
-                if n > 0 {
-                    app.selected_bucket = (app.selected_bucket + 1) % n;
-                }
+fn handle_suggestions_all_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    match (app.confirm_suggestions_all, key.code) {
+        (Some(true), KeyCode::Char('y') | KeyCode::Enter) => {
+            app.confirm_suggestions_all = None;
+            accept_all_suggestions(app, false);
+        }
+        (Some(true), KeyCode::Char('t')) => {
+            app.confirm_suggestions_all = None;
+            accept_all_suggestions(app, true);
+        }
+        (Some(false), KeyCode::Char('y') | KeyCode::Enter) => {
+            app.confirm_suggestions_all = None;
+            dismiss_all_suggestions(app);
+        }
+        (_, KeyCode::Char('n') | KeyCode::Esc) => {
+            app.confirm_suggestions_all = None;
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn open_snapshots(app: &mut App) {
+    let Some(storage) = &app.storage else {
+        push_toast(app, "No data directory".to_string(), false, Toast::Warning);
+        return;
+    };
+    let mut entries = storage.list_history();
+    if entries.is_empty() {
+        push_toast(app, "No snapshots yet".to_string(), false, Toast::Info);
+        return;
+    }
+    entries.reverse();
+    app.snapshot_list = Some(entries);
+    app.snapshot_selected = 0;
+}
+
+fn handle_snapshots_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    let len = app.snapshot_list.as_ref().map_or(0, Vec::len);
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.snapshot_selected = app.snapshot_selected.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.snapshot_selected + 1 < len => {
+            app.snapshot_selected += 1;
+        }
+        KeyCode::Enter => {
+            let seq = app
+                .snapshot_list
+                .as_ref()
+                .and_then(|entries| entries.get(app.snapshot_selected).map(|e| e.seq));
+            if let Some(seq) = seq {
+                preview_restore(app, seq);
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => app.snapshot_list = None,
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn preview_restore(app: &mut App, seq: u64) {
+    let Some(storage) = &app.storage else {
+        return;
+    };
+    match storage.load_snapshot(seq) {
+        Ok(snap) => {
+            let diff = storage::diff_snapshot(&app.tasks, &snap);
+            app.snapshot_preview = Some((seq, snap.label, diff));
+        }
+        Err(err) => {
+            push_toast(app, format!("Restore failed: {err}"), false, Toast::Error);
+        }
+    }
+}
+
+fn handle_snapshot_preview_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            if let Some((seq, _, _)) = app.snapshot_preview.take() {
+                app.snapshot_list = None;
+                restore_snapshot(app, seq);
+            }
+        }
+        KeyCode::Char('n') | KeyCode::Esc => app.snapshot_preview = None,
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Restore snapshot `seq` and reload what it wrote. Settings are reloaded
+/// too, or the next settings save would put the current ones back.
+fn restore_snapshot(app: &mut App, seq: u64) {
+    let Some(storage) = &app.storage else {
+        return;
+    };
+    let (msg, kind) = match storage.restore_snapshot(seq) {
+        Ok(label) => {
+            if let Ok(settings) = storage.load_settings() {
+                app.settings = settings;
+            }
+            reload_tasks(app);
+            app.bucket_scrolls.resize(app.settings.buckets.len(), 0);
+            app.selected_bucket = app
+                .selected_bucket
+                .min(app.settings.buckets.len().saturating_sub(1));
+            app.checklist_frozen_order = None;
+            rebuild_ai(app);
+            ensure_default_selection(app);
+            ensure_kanban_selection(app);
+            (format!("Restored to before \"{label}\""), Toast::Success)
+        }
+        Err(err) => (format!("Restore failed: {err}"), Toast::Error),
+    };
+    push_toast(app, msg, false, kind);
+}
+
+fn handle_confirm_delete_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    match key.code {
+        KeyCode::Enter => {
+            if let Some(id) = app.confirm_delete_id.take() {
+                app.selected_task_id = Some(id);
+                delete_selected(app);
+                // If still in the edit overlay, clamp sub-issue selection.
+                if app.focus == Focus::Edit {
+                    if let Some(parent_id) = app.edit_task_id {
+                        let child_count =
+                            visible_children_of(&app.tasks, parent_id, &app.settings).len();
+                        app.edit_sub_selected =
+                            app.edit_sub_selected.min(child_count.saturating_sub(1));
+                    }
+                }
+            }
+        }
+        KeyCode::Esc => {
+            app.confirm_delete_id = None;
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Typed rather than a single key, because `/reset` removes every task.
+const RESET_CONFIRM_WORD: &str = "DELETE";
+
+fn handle_confirm_reset_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    let Some(typed) = app.confirm_reset.as_mut() else {
+        return Ok(false);
+    };
+    match key.code {
+        KeyCode::Esc => app.confirm_reset = None,
+        KeyCode::Backspace => {
+            typed.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => typed.push(c),
+        KeyCode::Enter if typed == RESET_CONFIRM_WORD => {
+            app.confirm_reset = None;
+            reset_all_tasks(app);
+        }
+        _ => {}
+    }
+    Ok(false)
+}
+
+fn reset_all_tasks(app: &mut App) {
+    let count = app.tasks.len();
+    // Without a snapshot the reset couldn't be undone, so it doesn't happen.
+    if let Some(Err(err)) = app.storage.as_ref().map(|s| s.try_snapshot("reset")) {
+        push_toast(
+            app,
+            format!("Reset cancelled, couldn't save an undo snapshot: {err}"),
+            false,
+            Toast::Error,
+        );
+        return;
+    }
+    app.tasks.clear();
+    app.selected_task_id = None;
+    app.kanban_selected = None;
+    app.checklist_frozen_order = None;
+    ensure_default_selection(app);
+    ensure_kanban_selection(app);
+    push_toast(
+        app,
+        format!(
+            "Deleted all {count} task{} (Ctrl+Z to undo)",
+            if count == 1 { "" } else { "s" }
+        ),
+        false,
+        Toast::Success,
+    );
+    persist(app);
+}
+
+fn handle_board_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    // ── Bucket header selected ──
+    if app.bucket_header_selected {
+        match key.code {
+            KeyCode::Esc => {
+                app.bucket_header_selected = false;
+                app.focus = Focus::Tabs;
+            }
+            KeyCode::Tab | KeyCode::Char('i') => {
+                app.bucket_header_selected = false;
+                app.focus = Focus::Input;
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                let n = app.settings.buckets.len();
+                if n > 0 {
+                    app.selected_bucket = (app.selected_bucket + n - 1) % n;
+                }
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                let n = app.settings.buckets.len();
+                if n > 0 {
+                    app.selected_bucket = (app.selected_bucket + 1) % n;
+                }
             }
             KeyCode::Down | KeyCode::Char('j') => {
                 app.bucket_header_selected = false;
@@ -2213,7 +3536,14 @@ fn handle_board_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 .get(app.selected_bucket)
                 .map(|b| b.name.as_str())
                 .unwrap_or("");
-            let bucket_tasks = bucket_task_indices(&app.tasks, bname, &app.settings);
+            let bucket_tasks = bucket_task_indices(
+                &app.tasks,
+                bname,
+                &app.settings,
+                app.assignee_filter.as_deref(),
+                app.pinned_only,
+                &app.board_query,
+            );
             let at_first = app
                 .selected_task_id
                 .and_then(|id| bucket_tasks.iter().position(|&idx| app.tasks[idx].id == id))
@@ -2233,7 +3563,14 @@ fn handle_board_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 .get(app.selected_bucket)
                 .map(|b| b.name.as_str())
                 .unwrap_or("");
-            let bucket_tasks = bucket_task_indices(&app.tasks, bname, &app.settings);
+            let bucket_tasks = bucket_task_indices(
+                &app.tasks,
+                bname,
+                &app.settings,
+                app.assignee_filter.as_deref(),
+                app.pinned_only,
+                &app.board_query,
+            );
             let at_last = app
                 .selected_task_id
                 .and_then(|id| bucket_tasks.iter().position(|&idx| app.tasks[idx].id == id))
@@ -2248,39 +3585,47 @@ fn handle_board_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         KeyCode::Char('p') => {
             if let Some(id) = app.selected_task_id {
                 let now = Utc::now();
-                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
+                if let Some(task) = app.task_index.get_mut(&mut app.tasks, id) {
                     let from = task.progress;
                     task.advance_progress(now);
-                    app.status = Some((
-                        format!(
-                            "{}: {} → {}",
-                            task.title,
-                            from.title(),
-                            task.progress.title()
-                        ),
-                        Instant::now(),
-                        false,
-                    ));
+                    let msg = format!(
+                        "{}: {} → {}",
+                        task.title,
+                        app.settings.stage_label(from),
+                        app.settings.stage_label(task.progress)
+                    );
+                    push_toast(app, msg, false, Toast::Success);
                     persist(app);
                 }
             }
         }
+        KeyCode::Char('o') => cycle_sort_mode(app),
+        KeyCode::Char('*') => {
+            if let Some(id) = app.selected_task_id {
+                toggle_pin(app, id);
+            }
+        }
+        KeyCode::Char(c @ ('y' | 'Y')) => {
+            if let Some(id) = app.selected_task_id {
+                if let Some(copy_id) = duplicate_task(app, id, c == 'Y') {
+                    app.selected_task_id = Some(copy_id);
+                    ensure_default_selection(app);
+                }
+            }
+        }
         KeyCode::Char('P') => {
             if let Some(id) = app.selected_task_id {
                 let now = Utc::now();
-                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
+                if let Some(task) = app.task_index.get_mut(&mut app.tasks, id) {
                     let from = task.progress;
                     task.retreat_progress(now);
-                    app.status = Some((
-                        format!(
-                            "{}: {} → {}",
-                            task.title,
-                            from.title(),
-                            task.progress.title()
-                        ),
-                        Instant::now(),
-                        false,
-                    ));
+                    let msg = format!(
+                        "{}: {} → {}",
+                        task.title,
+                        app.settings.stage_label(from),
+                        app.settings.stage_label(task.progress)
+                    );
+                    push_toast(app, msg, false, Toast::Success);
                     persist(app);
                 }
             }
@@ -2432,14 +3777,13 @@ fn handle_bucket_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     app.bucket_edit_buf_cursor += 1;
                 }
             }
-            KeyCode::Char(ch) => {
+            KeyCode::Char(ch)
                 if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    let bp = char_byte_pos(&app.bucket_edit_buf, app.bucket_edit_buf_cursor);
-                    app.bucket_edit_buf.insert(bp, ch);
-                    app.bucket_edit_buf_cursor += 1;
-                }
+                    && !key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                let bp = char_byte_pos(&app.bucket_edit_buf, app.bucket_edit_buf_cursor);
+                app.bucket_edit_buf.insert(bp, ch);
+                app.bucket_edit_buf_cursor += 1;
             }
             _ => {}
         }
@@ -2523,7 +3867,7 @@ fn open_edit(app: &mut App) {
 }
 
 fn open_edit_for(app: &mut App, task_id: Uuid) {
-    let Some(task) = app.tasks.iter().find(|t| t.id == task_id) else {
+    let Some(task) = app.task_index.get(&app.tasks, task_id) else {
         return;
     };
     app.edit_task_id = Some(task_id);
@@ -2536,6 +3880,55 @@ fn open_edit_for(app: &mut App, task_id: Uuid) {
     app.focus = Focus::Edit;
 }
 
+/// Select `field` in the edit overlay; sub-issues don't have a Sub-issues field.
+fn jump_to_edit_field(app: &mut App, field: EditField) {
+    let is_child = app
+        .edit_task_id
+        .is_some_and(|id| app.tasks.iter().any(|t| t.id == id && t.is_child()));
+    if !EditField::fields_for(is_child).contains(&field) {
+        return;
+    }
+    app.edit_field = field;
+    app.edit_sub_selected = 0;
+    load_edit_buf(app);
+}
+
+/// Swap the selected sub-issue with its neighbour and number every visible
+/// sibling, so the hand-made order survives new children and restarts.
+fn move_sub_issue(app: &mut App, delta: isize) {
+    let Some(parent_id) = app.edit_task_id else {
+        return;
+    };
+    if app.settings.sort_mode != SortMode::Default {
+        push_toast(
+            app,
+            "Sub-issues can only be reordered in the default sort (o)".to_string(),
+            false,
+            Toast::Warning,
+        );
+        return;
+    }
+    let mut child_ids: Vec<Uuid> = visible_children_of(&app.tasks, parent_id, &app.settings)
+        .iter()
+        .map(|&i| app.tasks[i].id)
+        .collect();
+    let from = app.edit_sub_selected;
+    let Some(to) = from
+        .checked_add_signed(delta)
+        .filter(|&to| from < child_ids.len() && to < child_ids.len())
+    else {
+        return;
+    };
+    child_ids.swap(from, to);
+    for (pos, id) in child_ids.into_iter().enumerate() {
+        if let Some(child) = app.task_index.get_mut(&mut app.tasks, id) {
+            child.sort_order = Some(pos as u32);
+        }
+    }
+    app.edit_sub_selected = to;
+    persist(app);
+}
+
 fn close_edit(app: &mut App) {
     if let Some((parent_id, field, sub_sel)) = app.edit_parent_stack.pop() {
         if app.tasks.iter().any(|t| t.id == parent_id) {
@@ -2555,72 +3948,483 @@ fn close_edit(app: &mut App) {
     app.focus = Focus::Board;
 }
 
-fn delete_selected(app: &mut App) {
-    let Some(id) = app.selected_task_id else {
-        return;
-    };
-    if let Some(pos) = app.tasks.iter().position(|t| t.id == id) {
-        let title = app.tasks[pos].title.clone();
-        // Cascade: remove all children of this task.
-        let child_ids: Vec<Uuid> = children_of(&app.tasks, id)
+/// Why `child` can't be moved under `new_parent`, if it can't.
+fn reparent_error(tasks: &[Task], child: Uuid, new_parent: Uuid) -> Option<&'static str> {
+    if would_create_ancestor_cycle(tasks, child, new_parent) {
+        Some("Can't move a task under itself or its sub-issues")
+    } else if would_exceed_nesting(tasks, child, new_parent) {
+        Some(NESTING_LIMIT_MSG)
+    } else {
+        None
+    }
+}
+
+const NESTING_LIMIT_MSG: &str = "Sub-issues can't have sub-issues of their own";
+
+/// Apply an AI `parent_id` change ("none" promotes to top level) and re-sync
+/// the old parent. Returns the new parent's title, or None when promoted.
+fn apply_parent_change(
+    tasks: &mut [Task],
+    id: Uuid,
+    new_parent_prefix: &str,
+    now: chrono::DateTime<Utc>,
+) -> Result<Option<String>, String> {
+    let new_parent = if new_parent_prefix.eq_ignore_ascii_case("none") {
+        None
+    } else {
+        let parent_id = TaskIndex::default()
+            .resolve(tasks, new_parent_prefix)
+            .map_err(|err| format!("parent {err}"))?;
+        if let Some(err) = reparent_error(tasks, id, parent_id) {
+            return Err(err.to_string());
+        }
+        let title = tasks
             .iter()
-            .map(|&i| app.tasks[i].id)
-            .collect();
-        app.tasks
-            .retain(|t| !child_ids.contains(&t.id) && t.id != id);
-        // Clean up any dependency references to the deleted task(s).
-        let all_deleted: Vec<Uuid> = std::iter::once(id).chain(child_ids).collect();
-        for task in &mut app.tasks {
-            task.dependencies.retain(|dep| !all_deleted.contains(dep));
-        }
-        app.status = Some((format!("Deleted: {title}"), Instant::now(), false));
-        ensure_default_selection(app);
-        persist(app);
+            .find(|t| t.id == parent_id)
+            .map(|t| t.title.clone())
+            .unwrap_or_default();
+        Some((parent_id, title))
+    };
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "task not found".to_string())?;
+    let old_parent = std::mem::replace(&mut task.parent_id, new_parent.as_ref().map(|p| p.0));
+    if task.parent_id != old_parent {
+        task.updated_at = now;
+    }
+    if let Some(old) = old_parent {
+        sync_progress_from_children(tasks, old, now);
     }
+    Ok(new_parent.map(|p| p.1))
 }
 
-fn load_edit_buf(app: &mut App) {
+/// Move the edited task under the task named by the `@id` in `edit_buf`.
+fn reparent_from_prompt(app: &mut App) {
     let Some(id) = app.edit_task_id else {
         return;
     };
-    let Some(task) = app.tasks.iter().find(|t| t.id == id) else {
-        return;
+    let prefix = app.edit_buf.trim().trim_start_matches('@').to_string();
+    let new_parent = match app.task_index.resolve(&app.tasks, &prefix) {
+        Ok(id) => id,
+        Err(err) => {
+            push_toast(app, format!("Can't move: {err}"), false, Toast::Warning);
+            return;
+        }
     };
-    app.edit_buf = match app.edit_field {
-        EditField::Title => task.title.clone(),
-        EditField::Description => task.description.clone(),
-        EditField::Bucket => task.bucket.clone(),
-        EditField::Progress => task.progress.title().to_string(),
-        EditField::Priority => task.priority.title().to_string(),
-        EditField::DueDate => task
-            .due_date
-            .map(|d| d.format("%Y-%m-%d").to_string())
-            .unwrap_or_default(),
-        EditField::SubIssues => String::new(),
+    if let Some(err) = reparent_error(&app.tasks, id, new_parent) {
+        push_toast(app, err.to_string(), false, Toast::Warning);
+        return;
+    }
+
+    let now = Utc::now();
+    let Some(task) = app.task_index.get_mut(&mut app.tasks, id) else {
+        return;
     };
-    app.edit_buf_cursor = app.edit_buf.chars().count();
+    let old_parent = task.parent_id.replace(new_parent);
+    task.updated_at = now;
+    let title = task.title.clone();
+    if let Some(old) = old_parent {
+        sync_progress_from_children(&mut app.tasks, old, now);
+    }
+    sync_parent_progress(&mut app.tasks, id, now);
+    persist(app);
+
+    // Esc now leads back to the new parent's sub-issue list.
+    let sub_idx = visible_children_of(&app.tasks, new_parent, &app.settings)
+        .iter()
+        .position(|&i| app.tasks[i].id == id)
+        .unwrap_or(0);
+    app.edit_parent_stack = vec![(new_parent, EditField::SubIssues, sub_idx)];
+    if !EditField::fields_for(true).contains(&app.edit_field) {
+        app.edit_field = EditField::Title;
+    }
+    let parent_title = app
+        .tasks
+        .iter()
+        .find(|t| t.id == new_parent)
+        .map(|t| t.title.clone())
+        .unwrap_or_default();
+    push_toast(
+        app,
+        format!("Moved {title} under {parent_title}"),
+        false,
+        Toast::Success,
+    );
 }
 
-fn commit_edit_buf(app: &mut App) {
-    let Some(id) = app.edit_task_id else {
+/// A new Backlog sub-issue under `parent_id`, in the parent's bucket.
+fn push_sub_issue(
+    app: &mut App,
+    parent_id: Uuid,
+    title: String,
+    now: chrono::DateTime<Utc>,
+) -> Uuid {
+    let bucket = app
+        .tasks
+        .iter()
+        .find(|t| t.id == parent_id)
+        .map(|t| t.bucket.clone())
+        .unwrap_or_else(|| default_bucket_name(&app.settings));
+    let mut child = Task::new(bucket, title, now);
+    child.parent_id = Some(parent_id);
+    let id = child.id;
+    app.tasks.push(child);
+    id
+}
+
+/// The text of a pasted checklist line without its bullet, box or number.
+fn checklist_item(line: &str) -> Option<&str> {
+    let mut item = line.trim();
+    for marker in ["- [ ]", "- [x]", "- [X]", "* [ ]", "* [x]", "-", "*", "•"] {
+        if let Some(rest) = item.strip_prefix(marker) {
+            item = rest;
+            break;
+        }
+    }
+    // A number is a list marker only when a space follows, so "2.5h review"
+    // keeps its hours.
+    let digits = item.len() - item.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0 && item[digits..].starts_with(['.', ')']) {
+        let rest = &item[digits + 1..];
+        if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+            item = rest;
+        }
+    }
+    let item = item.trim();
+    (!item.is_empty()).then_some(item)
+}
+
+/// One sub-issue per non-empty pasted line, kept in the pasted order below
+/// the existing sub-issues.
+fn add_sub_issues_from_text(app: &mut App, text: &str) {
+    let Some(parent_id) = app.edit_task_id else {
         return;
     };
-    let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) else {
+    let items: Vec<&str> = text.lines().filter_map(checklist_item).collect();
+    if items.is_empty() {
         return;
-    };
+    }
+    if let Some(storage) = &app.storage {
+        storage.snapshot("paste sub-issues");
+    }
     let now = Utc::now();
-
-    match app.edit_field {
-        EditField::Title => {
-            let trimmed = app.edit_buf.trim().to_string();
-            if !trimmed.is_empty() {
-                task.title = trimmed;
-                task.updated_at = now;
-            }
-        }
-        EditField::Description => {
-            task.description = app.edit_buf.trim().to_string();
-            task.updated_at = now;
+    let mut ordered: Vec<Uuid> = visible_children_of(&app.tasks, parent_id, &app.settings)
+        .iter()
+        .map(|&i| app.tasks[i].id)
+        .collect();
+    let first_new = ordered.len();
+    for item in &items {
+        ordered.push(push_sub_issue(app, parent_id, item.to_string(), now));
+    }
+    for (pos, id) in ordered.into_iter().enumerate() {
+        if let Some(child) = app.task_index.get_mut(&mut app.tasks, id) {
+            child.sort_order = Some(pos as u32);
+        }
+    }
+    sync_progress_from_children(&mut app.tasks, parent_id, now);
+    persist(app);
+    app.edit_sub_selected = first_new;
+    push_toast(
+        app,
+        format!(
+            "Added {} sub-issue{}",
+            items.len(),
+            if items.len() == 1 { "" } else { "s" }
+        ),
+        false,
+        Toast::Success,
+    );
+}
+
+/// Copy a task as a new Backlog task titled "… (copy)" under the same parent,
+/// with its sub-issue subtree when `with_subtree`. Returns the copy's id.
+fn duplicate_task(app: &mut App, id: Uuid, with_subtree: bool) -> Option<Uuid> {
+    let original = app.task_index.get(&app.tasks, id)?;
+    let parent_id = original.parent_id;
+    let now = Utc::now();
+    let mut copies = clone_subtree(&app.tasks, id, now);
+    if !with_subtree {
+        copies.truncate(1);
+    }
+    let root = &mut copies[0];
+    root.title.push_str(" (copy)");
+    root.parent_id = parent_id;
+    root.dependencies.clear();
+    let copy_id = root.id;
+    let title = root.title.clone();
+    let msg = match copies.len() - 1 {
+        0 => format!("Duplicated: {title}"),
+        n => format!(
+            "Duplicated: {title} (+{n} sub-issue{})",
+            if n == 1 { "" } else { "s" }
+        ),
+    };
+
+    if let Some(storage) = &app.storage {
+        storage.snapshot("duplicate task");
+    }
+    app.tasks.extend(copies);
+    sync_parent_progress(&mut app.tasks, copy_id, now);
+    push_toast(app, msg, false, Toast::Success);
+    persist(app);
+    Some(copy_id)
+}
+
+fn run_template_command(app: &mut App, args: &str) -> (String, Toast) {
+    let (cmd, name) = args
+        .split_once(' ')
+        .map(|(cmd, name)| (cmd, name.trim()))
+        .unwrap_or((args, ""));
+    let Some(storage) = app.storage.clone() else {
+        return (
+            "Templates need a data directory".to_string(),
+            Toast::Warning,
+        );
+    };
+    match cmd {
+        "list" => {
+            let names = storage.list_templates();
+            let msg = if names.is_empty() {
+                "No templates saved".to_string()
+            } else {
+                format!("Templates: {}", names.join(", "))
+            };
+            (msg, Toast::Info)
+        }
+        "save" if !name.is_empty() => {
+            let Some(id) = app.selected_task_id else {
+                return (
+                    "Select a task to save as a template".to_string(),
+                    Toast::Warning,
+                );
+            };
+            let mut tree = clone_subtree(&app.tasks, id, Utc::now());
+            for task in &mut tree {
+                task.due_date = None;
+            }
+            match storage.save_template(name, &tree) {
+                Ok(saved) => (
+                    format!(
+                        "Saved template \"{saved}\" ({} task{})",
+                        tree.len(),
+                        if tree.len() == 1 { "" } else { "s" }
+                    ),
+                    Toast::Success,
+                ),
+                Err(err) => (format!("Template save failed: {err}"), Toast::Error),
+            }
+        }
+        "use" if !name.is_empty() => {
+            let template = match storage.load_template(name) {
+                Ok(template) => template,
+                Err(err) => return (err.to_string(), Toast::Error),
+            };
+            let Some(root) = template.first().map(|t| t.id) else {
+                return ("Template is empty".to_string(), Toast::Warning);
+            };
+            let bucket = app
+                .settings
+                .buckets
+                .get(app.selected_bucket)
+                .map(|b| b.name.clone())
+                .unwrap_or_else(|| default_bucket_name(&app.settings));
+            let mut copies = clone_subtree(&template, root, Utc::now());
+            for task in &mut copies {
+                task.bucket = bucket.clone();
+                task.due_date = None;
+            }
+            let count = copies.len();
+            let new_root = copies[0].id;
+            storage.snapshot("template use");
+            app.tasks.extend(copies);
+            app.selected_task_id = Some(new_root);
+            ensure_default_selection(app);
+            persist(app);
+            (
+                format!(
+                    "Created {count} task{} in {bucket}",
+                    if count == 1 { "" } else { "s" }
+                ),
+                Toast::Success,
+            )
+        }
+        _ => (
+            "Usage: /template save <name> | use <name> | list".to_string(),
+            Toast::Warning,
+        ),
+    }
+}
+
+fn run_doctor_command(app: &mut App, args: &str) -> (String, Toast) {
+    let (cmd, bucket) = args
+        .split_once(' ')
+        .map(|(cmd, bucket)| (cmd, bucket.trim()))
+        .unwrap_or((args, ""));
+    match cmd {
+        "" => {
+            let report = check_integrity(&app.tasks, &app.settings.buckets);
+            if report.is_empty() {
+                ("No problems found".to_string(), Toast::Success)
+            } else {
+                (
+                    format!(
+                        "{}. Run /doctor fix to repair",
+                        describe_integrity(&app.tasks, &report)
+                    ),
+                    Toast::Warning,
+                )
+            }
+        }
+        "fix" => {
+            let fallback = if bucket.is_empty() {
+                default_bucket_name(&app.settings)
+            } else {
+                match known_bucket(&app.settings, bucket) {
+                    Some(name) => name,
+                    None => return (format!("No bucket named \"{bucket}\""), Toast::Warning),
+                }
+            };
+            if check_integrity(&app.tasks, &app.settings.buckets).is_empty() {
+                return ("No problems found".to_string(), Toast::Success);
+            }
+            if let Some(storage) = &app.storage {
+                storage.snapshot("doctor");
+            }
+            let report =
+                repair_integrity(&mut app.tasks, &app.settings.buckets, &fallback, Utc::now());
+            ensure_default_selection(app);
+            persist(app);
+            (
+                format!("Fixed {}", describe_integrity(&app.tasks, &report)),
+                Toast::Success,
+            )
+        }
+        _ => ("Usage: /doctor [fix [bucket]]".to_string(), Toast::Warning),
+    }
+}
+
+fn describe_integrity(tasks: &[Task], report: &IntegrityReport) -> String {
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let mut parts = Vec::new();
+    if !report.unknown_bucket.is_empty() {
+        let mut missing: Vec<&str> = report
+            .unknown_bucket
+            .iter()
+            .map(|&idx| tasks[idx].bucket.as_str())
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        let n = report.unknown_bucket.len();
+        parts.push(format!(
+            "{n} task{} in missing buckets ({})",
+            plural(n),
+            missing.join(", ")
+        ));
+    }
+    let n = report.dangling_dependencies.len();
+    if n > 0 {
+        parts.push(format!("{n} task{} with deleted dependencies", plural(n)));
+    }
+    let n = report.missing_parent.len();
+    if n > 0 {
+        parts.push(format!("{n} sub-task{} with a deleted parent", plural(n)));
+    }
+    let n = report.stale_parent_progress.len();
+    if n > 0 {
+        parts.push(format!(
+            "{n} parent{} out of sync with sub-issues",
+            plural(n)
+        ));
+    }
+    parts.join(", ")
+}
+
+/// Shows the orphaned-task notice when the count changes, so it isn't
+/// repeated every check while the user decides what to do.
+fn check_orphaned_buckets(app: &mut App, last_count: &mut usize) -> bool {
+    let orphans = orphaned_bucket_tasks(&app.tasks, &app.settings.buckets);
+    let changed = orphans.len() != *last_count;
+    *last_count = orphans.len();
+    if changed && !orphans.is_empty() {
+        let report = IntegrityReport {
+            unknown_bucket: orphans,
+            ..IntegrityReport::default()
+        };
+        push_toast(
+            app,
+            format!(
+                "{} won't show on the board. Run /doctor fix to move them",
+                describe_integrity(&app.tasks, &report)
+            ),
+            false,
+            Toast::Warning,
+        );
+        return true;
+    }
+    false
+}
+
+fn delete_selected(app: &mut App) {
+    let Some(id) = app.selected_task_id else {
+        return;
+    };
+    if let Some(pos) = app.task_index.position(&app.tasks, id) {
+        let title = app.tasks[pos].title.clone();
+        delete_with_children(&mut app.tasks, id);
+        push_toast(app, format!("Deleted: {title}"), false, Toast::Success);
+        ensure_default_selection(app);
+        persist(app);
+    }
+}
+
+fn load_edit_buf(app: &mut App) {
+    let Some(id) = app.edit_task_id else {
+        return;
+    };
+    let Some(task) = app.task_index.get(&app.tasks, id) else {
+        return;
+    };
+    app.edit_buf = match app.edit_field {
+        EditField::Title => task.title.clone(),
+        EditField::Description => task.description.clone(),
+        EditField::Bucket => task.bucket.clone(),
+        EditField::Progress => app.settings.stage_label(task.progress).to_string(),
+        EditField::Priority => task.priority.title().to_string(),
+        EditField::DueDate => task
+            .due_date
+            .map(|d| app.settings.format_date(d))
+            .unwrap_or_default(),
+        EditField::Assignee => task.assignee.clone().unwrap_or_default(),
+        EditField::Color => task.color.clone().unwrap_or_default(),
+        EditField::Estimate => task.estimate_hours.map(format_hours).unwrap_or_default(),
+        EditField::Actual => task.actual_hours.map(format_hours).unwrap_or_default(),
+        EditField::Notes | EditField::SubIssues => String::new(),
+    };
+    app.edit_buf_cursor = app.edit_buf.chars().count();
+}
+
+fn commit_edit_buf(app: &mut App) {
+    let Some(id) = app.edit_task_id else {
+        return;
+    };
+    let is_parent = app.tasks.iter().any(|t| t.parent_id == Some(id));
+    let Some(task) = app.task_index.get_mut(&mut app.tasks, id) else {
+        return;
+    };
+    let now = Utc::now();
+
+    match app.edit_field {
+        EditField::Title => {
+            let trimmed = app.edit_buf.trim().to_string();
+            if !trimmed.is_empty() {
+                task.title = trimmed;
+                task.updated_at = now;
+            }
+        }
+        EditField::Description => {
+            task.description = app.edit_buf.trim().to_string();
+            task.updated_at = now;
         }
         EditField::Bucket => {
             let input = app.edit_buf.trim();
@@ -2635,15 +4439,23 @@ fn commit_edit_buf(app: &mut App) {
             }
         }
         EditField::Progress => {
-            if let Some(p) = match app.edit_buf.trim().to_ascii_lowercase().as_str() {
-                "backlog" => Some(Progress::Backlog),
-                "todo" => Some(Progress::Todo),
-                "in progress" | "inprogress" | "in-progress" => Some(Progress::InProgress),
-                "done" => Some(Progress::Done),
-                "archived" => Some(Progress::Archived),
-                _ => None,
-            } {
+            let input = app.edit_buf.trim().to_ascii_lowercase();
+            if let Some(p) = app
+                .settings
+                .stage_from_label(&input)
+                .or(match input.as_str() {
+                    "backlog" => Some(Progress::Backlog),
+                    "todo" => Some(Progress::Todo),
+                    "in progress" | "inprogress" | "in-progress" => Some(Progress::InProgress),
+                    "done" => Some(Progress::Done),
+                    "archived" => Some(Progress::Archived),
+                    _ => None,
+                })
+            {
                 task.set_progress(p, now);
+                if is_parent {
+                    task.progress_locked_at = Some(now);
+                }
             }
         }
         EditField::Priority => {
@@ -2659,20 +4471,43 @@ fn commit_edit_buf(app: &mut App) {
             }
         }
         EditField::DueDate => {
-            let s = app.edit_buf.trim();
-            if s.is_empty()
-                || s.eq_ignore_ascii_case("none")
-                || s.eq_ignore_ascii_case("null")
-                || s.eq_ignore_ascii_case("clear")
-            {
-                task.due_date = None;
+            if let Some(due) = ai::parse_due_input(&app.edit_buf, &app.settings) {
+                task.set_due_date(due);
                 task.updated_at = now;
-            } else if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                task.due_date = Some(date);
+            }
+        }
+        EditField::Assignee => {
+            let s = app.edit_buf.trim().trim_start_matches('@');
+            task.assignee = if s.is_empty() || s.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(s.to_string())
+            };
+            task.updated_at = now;
+        }
+        EditField::Estimate | EditField::Actual => {
+            let s = app.edit_buf.trim();
+            let value = if s.is_empty() || s.eq_ignore_ascii_case("none") {
+                Some(None)
+            } else {
+                parse_hours(s).map(Some)
+            };
+            if let Some(hours) = value {
+                if app.edit_field == EditField::Estimate {
+                    task.estimate_hours = hours;
+                } else {
+                    task.actual_hours = hours;
+                }
                 task.updated_at = now;
             }
         }
-        EditField::SubIssues => {}
+        EditField::Notes => {
+            let text = app.edit_buf.trim().to_string();
+            if !text.is_empty() {
+                task.add_note(text, now);
+            }
+        }
+        EditField::Color | EditField::SubIssues => {}
     }
 
     persist(app);
@@ -2682,7 +4517,8 @@ fn cycle_edit_field_value(app: &mut App, forward: bool) {
     let Some(id) = app.edit_task_id else {
         return;
     };
-    let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) else {
+    let is_parent = app.tasks.iter().any(|t| t.parent_id == Some(id));
+    let Some(task) = app.task_index.get_mut(&mut app.tasks, id) else {
         return;
     };
     let now = Utc::now();
@@ -2708,6 +4544,9 @@ fn cycle_edit_field_value(app: &mut App, forward: bool) {
                 task.progress.retreat()
             };
             task.set_progress(next, now);
+            if is_parent {
+                task.progress_locked_at = Some(now);
+            }
         }
         EditField::Priority => {
             task.priority = if forward {
@@ -2727,6 +4566,22 @@ fn cycle_edit_field_value(app: &mut App, forward: bool) {
             };
             task.updated_at = now;
         }
+        EditField::Color => {
+            // No color sits between the last and the first.
+            let cur = task
+                .color
+                .as_deref()
+                .and_then(|c| TASK_COLORS.iter().position(|t| t.eq_ignore_ascii_case(c)))
+                .map_or(0, |i| i + 1);
+            let len = TASK_COLORS.len() + 1;
+            let next = if forward {
+                (cur + 1) % len
+            } else {
+                (cur + len - 1) % len
+            };
+            task.color = next.checked_sub(1).map(|i| TASK_COLORS[i].to_string());
+            task.updated_at = now;
+        }
         _ => {}
     }
 
@@ -2736,12 +4591,12 @@ fn cycle_edit_field_value(app: &mut App, forward: bool) {
 
 fn shift_due_date(app: &mut App, days: i64) {
     let Some(id) = app.edit_task_id else { return };
-    let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) else {
+    let Some(task) = app.task_index.get_mut(&mut app.tasks, id) else {
         return;
     };
     let now = Utc::now();
-    let base = task.due_date.unwrap_or_else(|| now.date_naive());
-    task.due_date = Some(base + chrono::Duration::days(days));
+    let base = task.due_date.unwrap_or_else(|| app.settings.today());
+    task.set_due_date(Some(base + chrono::Duration::days(days)));
     task.updated_at = now;
     persist(app);
     load_edit_buf(app);
@@ -2752,11 +4607,17 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         match key.code {
             KeyCode::Esc => {
                 app.editing_text = false;
+                app.reparent_prompt = false;
                 load_edit_buf(app);
             }
             KeyCode::Enter => {
-                commit_edit_buf(app);
+                if app.reparent_prompt {
+                    reparent_from_prompt(app);
+                } else {
+                    commit_edit_buf(app);
+                }
                 app.editing_text = false;
+                app.reparent_prompt = false;
                 load_edit_buf(app);
             }
             KeyCode::Backspace => {
@@ -2875,25 +4736,52 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     app.edit_buf_cursor += 1;
                 }
             }
-            KeyCode::Char(ch) => {
+            KeyCode::Char(ch)
                 if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    let bp = char_byte_pos(&app.edit_buf, app.edit_buf_cursor);
-                    app.edit_buf.insert(bp, ch);
-                    app.edit_buf_cursor += 1;
-                }
+                    && !key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                let bp = char_byte_pos(&app.edit_buf, app.edit_buf_cursor);
+                app.edit_buf.insert(bp, ch);
+                app.edit_buf_cursor += 1;
             }
             _ => {}
         }
         return Ok(false);
     }
 
+    if std::mem::take(&mut app.edit_pending_g) {
+        let target = match key.code {
+            KeyCode::Char('g' | 't') => Some(EditField::Title),
+            KeyCode::Char('d') => Some(EditField::Description),
+            KeyCode::Char('s') => Some(EditField::SubIssues),
+            _ => None,
+        };
+        if let Some(field) = target {
+            jump_to_edit_field(app, field);
+        }
+        return Ok(false);
+    }
+
     match key.code {
+        KeyCode::Char('g') => app.edit_pending_g = true,
+        KeyCode::Char('G') => {
+            let is_child = app
+                .edit_task_id
+                .is_some_and(|id| app.tasks.iter().any(|t| t.id == id && t.is_child()));
+            if let Some(&last) = EditField::fields_for(is_child).last() {
+                jump_to_edit_field(app, last);
+            }
+        }
         KeyCode::Esc => {
             close_edit(app);
             ensure_default_selection(app);
         }
+        KeyCode::Up | KeyCode::Down
+            if key.modifiers.contains(KeyModifiers::SHIFT)
+                && app.edit_field == EditField::SubIssues =>
+        {
+            move_sub_issue(app, if key.code == KeyCode::Up { -1 } else { 1 });
+        }
         KeyCode::Up | KeyCode::Char('k') => {
             if app.edit_field == EditField::SubIssues {
                 if let Some(task_id) = app.edit_task_id {
@@ -2969,11 +4857,20 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 }
             } else {
                 match app.edit_field {
-                    EditField::Title | EditField::Description | EditField::DueDate => {
+                    EditField::Title
+                    | EditField::Description
+                    | EditField::DueDate
+                    | EditField::Assignee
+                    | EditField::Estimate
+                    | EditField::Actual
+                    | EditField::Notes => {
                         load_edit_buf(app);
                         app.editing_text = true;
                     }
-                    EditField::Bucket | EditField::Progress | EditField::Priority => {
+                    EditField::Bucket
+                    | EditField::Progress
+                    | EditField::Priority
+                    | EditField::Color => {
                         cycle_edit_field_value(app, true);
                     }
                     EditField::SubIssues => {}
@@ -2981,7 +4878,7 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             }
         }
         KeyCode::Left | KeyCode::Char('h') => match app.edit_field {
-            EditField::Bucket | EditField::Progress | EditField::Priority => {
+            EditField::Bucket | EditField::Progress | EditField::Priority | EditField::Color => {
                 cycle_edit_field_value(app, false);
             }
             EditField::DueDate => {
@@ -2996,7 +4893,7 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                             .collect();
                     if let Some(&child_id) = child_ids.get(app.edit_sub_selected) {
                         let now = Utc::now();
-                        if let Some(task) = app.tasks.iter_mut().find(|t| t.id == child_id) {
+                        if let Some(task) = app.task_index.get_mut(&mut app.tasks, child_id) {
                             task.retreat_progress(now);
                         }
                         sync_parent_progress(&mut app.tasks, child_id, now);
@@ -3007,7 +4904,7 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             _ => {}
         },
         KeyCode::Right | KeyCode::Char('l') => match app.edit_field {
-            EditField::Bucket | EditField::Progress | EditField::Priority => {
+            EditField::Bucket | EditField::Progress | EditField::Priority | EditField::Color => {
                 cycle_edit_field_value(app, true);
             }
             EditField::DueDate => {
@@ -3022,7 +4919,7 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                             .collect();
                     if let Some(&child_id) = child_ids.get(app.edit_sub_selected) {
                         let now = Utc::now();
-                        if let Some(task) = app.tasks.iter_mut().find(|t| t.id == child_id) {
+                        if let Some(task) = app.task_index.get_mut(&mut app.tasks, child_id) {
                             task.advance_progress(now);
                         }
                         sync_parent_progress(&mut app.tasks, child_id, now);
@@ -3032,35 +4929,94 @@ fn handle_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             }
             _ => {}
         },
-        KeyCode::Char('a') => {
+        KeyCode::Char('n') => {
+            app.edit_field = EditField::Notes;
+            load_edit_buf(app);
+            app.editing_text = true;
+        }
+        KeyCode::Char(c @ ('y' | 'Y')) => {
+            let with_subtree = c == 'Y';
             if app.edit_field == EditField::SubIssues {
                 if let Some(parent_id) = app.edit_task_id {
-                    let parent_bucket = app
-                        .tasks
+                    let child_ids: Vec<Uuid> =
+                        visible_children_of(&app.tasks, parent_id, &app.settings)
+                            .iter()
+                            .map(|&i| app.tasks[i].id)
+                            .collect();
+                    if let Some(&child_id) = child_ids.get(app.edit_sub_selected) {
+                        duplicate_task(app, child_id, with_subtree);
+                    }
+                }
+            } else if let Some(id) = app.edit_task_id {
+                if let Some(copy_id) = duplicate_task(app, id, with_subtree) {
+                    let stack = std::mem::take(&mut app.edit_parent_stack);
+                    open_edit_for(app, copy_id);
+                    app.edit_parent_stack = stack;
+                    app.selected_task_id = Some(copy_id);
+                }
+            }
+        }
+        KeyCode::Char('u') if app.edit_field == EditField::SubIssues => {
+            if let Some(parent_id) = app.edit_task_id {
+                let child_ids: Vec<Uuid> =
+                    visible_children_of(&app.tasks, parent_id, &app.settings)
                         .iter()
-                        .find(|t| t.id == parent_id)
-                        .map(|t| t.bucket.clone())
-                        .unwrap_or_else(|| default_bucket_name(&app.settings));
+                        .map(|&i| app.tasks[i].id)
+                        .collect();
+                if let Some(&child_id) = child_ids.get(app.edit_sub_selected) {
                     let now = Utc::now();
-                    let mut child = Task::new(parent_bucket, "New sub-issue".to_string(), now);
-                    child.parent_id = Some(parent_id);
-                    let child_id = child.id;
-                    app.tasks.push(child);
+                    if let Some(child) = app.task_index.get_mut(&mut app.tasks, child_id) {
+                        child.parent_id = None;
+                        child.updated_at = now;
+                        let msg = format!("Promoted: {}", child.title);
+                        push_toast(app, msg, false, Toast::Success);
+                    }
+                    sync_progress_from_children(&mut app.tasks, parent_id, now);
+                    app.edit_sub_selected =
+                        app.edit_sub_selected.min(child_ids.len().saturating_sub(2));
                     persist(app);
-                    let child_count =
-                        visible_children_of(&app.tasks, parent_id, &app.settings).len();
-                    let new_sub_idx = child_count.saturating_sub(1);
-                    app.edit_parent_stack
-                        .push((parent_id, EditField::SubIssues, new_sub_idx));
-                    app.edit_task_id = Some(child_id);
-                    app.edit_field = EditField::Title;
-                    app.edit_buf = "New sub-issue".to_string();
-                    app.edit_buf_cursor = app.edit_buf.chars().count();
-                    app.editing_text = true;
-                    app.edit_sub_selected = 0;
                 }
             }
         }
+        KeyCode::Char('o') => {
+            let email_id = app
+                .edit_task_id
+                .and_then(|id| app.task_index.get(&app.tasks, id))
+                .and_then(|t| t.email_id.clone());
+            if let Some(email_id) = email_id {
+                let (msg, kind) = match google::open_message(&email_id) {
+                    Ok(()) => (
+                        "Opened the source email in Gmail".to_string(),
+                        Toast::Success,
+                    ),
+                    Err(err) => (format!("Couldn't open the email: {err}"), Toast::Error),
+                };
+                push_toast(app, msg, false, kind);
+            }
+        }
+        KeyCode::Char('m') if app.edit_field != EditField::SubIssues => {
+            app.edit_buf = "@".to_string();
+            app.edit_buf_cursor = 1;
+            app.editing_text = true;
+            app.reparent_prompt = true;
+        }
+        KeyCode::Char('a') if app.edit_field == EditField::SubIssues => {
+            if let Some(parent_id) = app.edit_task_id {
+                let child_id =
+                    push_sub_issue(app, parent_id, "New sub-issue".to_string(), Utc::now());
+                persist(app);
+                let child_count = visible_children_of(&app.tasks, parent_id, &app.settings).len();
+                let new_sub_idx = child_count.saturating_sub(1);
+                app.edit_parent_stack
+                    .push((parent_id, EditField::SubIssues, new_sub_idx));
+                app.edit_task_id = Some(child_id);
+                app.edit_field = EditField::Title;
+                app.edit_buf = "New sub-issue".to_string();
+                app.edit_buf_cursor = app.edit_buf.chars().count();
+                app.editing_text = true;
+                app.edit_sub_selected = 0;
+            }
+        }
         KeyCode::Char('d') | KeyCode::Char('x') | KeyCode::Backspace | KeyCode::Delete => {
             if app.edit_field == EditField::SubIssues {
                 // Delete the selected sub-issue (via confirmation dialog).
@@ -3125,10 +5081,21 @@ fn handle_kanban_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         }
         KeyCode::Up | KeyCode::Char('k') => move_kanban_selection(app, -1),
         KeyCode::Down | KeyCode::Char('j') => move_kanban_selection(app, 1),
+        KeyCode::Char('o') => {
+            cycle_sort_mode(app);
+            scroll_kanban_to_selected(app);
+        }
+        KeyCode::Char('*') => {
+            if let Some(id) = app.kanban_selected {
+                toggle_pin(app, id);
+            }
+        }
+        KeyCode::Char('[') => cycle_kanban_bucket(app, false),
+        KeyCode::Char(']') => cycle_kanban_bucket(app, true),
         KeyCode::Char('p') => {
             if let Some(id) = app.kanban_selected {
                 let now = Utc::now();
-                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
+                if let Some(task) = app.task_index.get_mut(&mut app.tasks, id) {
                     task.advance_progress(now);
                     persist(app);
                     ensure_kanban_selection(app);
@@ -3138,7 +5105,7 @@ fn handle_kanban_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         KeyCode::Char('P') => {
             if let Some(id) = app.kanban_selected {
                 let now = Utc::now();
-                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
+                if let Some(task) = app.task_index.get_mut(&mut app.tasks, id) {
                     task.retreat_progress(now);
                     persist(app);
                     ensure_kanban_selection(app);
@@ -3151,6 +5118,16 @@ fn handle_kanban_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 open_edit_for(app, id);
             }
         }
+        KeyCode::Char(c @ ('y' | 'Y')) => {
+            if let Some(id) = app.kanban_selected {
+                if let Some(copy_id) = duplicate_task(app, id, c == 'Y') {
+                    app.kanban_stage = Progress::Backlog;
+                    app.kanban_selected = Some(copy_id);
+                    ensure_kanban_selection(app);
+                    scroll_kanban_to_selected(app);
+                }
+            }
+        }
         KeyCode::Char('d') | KeyCode::Char('x') | KeyCode::Backspace | KeyCode::Delete => {
             if let Some(id) = app.kanban_selected {
                 app.confirm_delete_id = Some(id);
@@ -3162,21 +5139,78 @@ fn handle_kanban_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     Ok(false)
 }
 
-fn kanban_task_ids(tasks: &[Task], stage: Progress) -> Vec<Uuid> {
+fn kanban_task_ids(
+    tasks: &[Task],
+    stage: Progress,
+    sort: SortMode,
+    bucket: Option<&str>,
+    assignee: Option<&str>,
+    pinned_only: bool,
+    query: &str,
+) -> Vec<Uuid> {
     let has_children: std::collections::HashSet<Uuid> =
         tasks.iter().filter_map(|t| t.parent_id).collect();
     let mut ids: Vec<(usize, Uuid)> = tasks
         .iter()
         .enumerate()
-        .filter(|(_, t)| t.progress == stage && !has_children.contains(&t.id))
+        .filter(|(_, t)| {
+            t.progress == stage
+                && !has_children.contains(&t.id)
+                && bucket.is_none_or(|b| t.bucket == b)
+                && passes_board_filter(t, assignee, pinned_only, query)
+        })
         .map(|(i, t)| (i, t.id))
         .collect();
-    ids.sort_by(|a, b| tasks[b.0].created_at.cmp(&tasks[a.0].created_at));
+    ids.sort_by(|a, b| {
+        let (ta, tb) = (&tasks[a.0], &tasks[b.0]);
+        sort_mode_cmp(sort, ta, tb).unwrap_or_else(|| tb.created_at.cmp(&ta.created_at))
+    });
     ids.into_iter().map(|(_, id)| id).collect()
 }
 
+/// Step the Kanban bucket filter through all buckets, then back to showing
+/// every bucket.
+fn cycle_kanban_bucket(app: &mut App, forward: bool) {
+    let names: Vec<&str> = app
+        .settings
+        .buckets
+        .iter()
+        .map(|b| b.name.as_str())
+        .collect();
+    // Position 0 is "all buckets", then one per bucket.
+    let current = app
+        .kanban_bucket
+        .as_deref()
+        .and_then(|b| names.iter().position(|n| *n == b))
+        .map_or(0, |i| i + 1);
+    let len = names.len() + 1;
+    let next = if forward {
+        (current + 1) % len
+    } else {
+        (current + len - 1) % len
+    };
+    app.kanban_bucket = next.checked_sub(1).map(|i| names[i].to_string());
+    app.kanban_scroll = [0; 4];
+    ensure_kanban_selection(app);
+    scroll_kanban_to_selected(app);
+}
+
 fn ensure_kanban_selection(app: &mut App) {
-    let ids = kanban_task_ids(&app.tasks, app.kanban_stage);
+    // A renamed or deleted bucket falls back to showing all of them.
+    if let Some(bucket) = &app.kanban_bucket {
+        if !app.settings.buckets.iter().any(|b| &b.name == bucket) {
+            app.kanban_bucket = None;
+        }
+    }
+    let ids = kanban_task_ids(
+        &app.tasks,
+        app.kanban_stage,
+        app.settings.sort_mode,
+        app.kanban_bucket.as_deref(),
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
     if ids.is_empty() {
         app.kanban_selected = None;
         return;
@@ -3189,9 +5223,79 @@ fn ensure_kanban_selection(app: &mut App) {
     app.kanban_selected = Some(ids[0]);
 }
 
+/// Scroll positions and the Timeline selection are indices, so tasks added
+/// or removed above them by a background poll would shift the view. These
+/// ids let `restore_view_anchor` put it back on the same rows.
+struct ViewAnchor {
+    timeline_selected: Option<Uuid>,
+    timeline_top: Option<Uuid>,
+    kanban_top: Option<Uuid>,
+}
+
+fn view_anchor(app: &App) -> ViewAnchor {
+    let timeline = sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed);
+    let kanban = kanban_task_ids(
+        &app.tasks,
+        app.kanban_stage,
+        app.settings.sort_mode,
+        app.kanban_bucket.as_deref(),
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
+    ViewAnchor {
+        timeline_selected: timeline
+            .get(app.timeline_selected)
+            .map(|&i| app.tasks[i].id),
+        timeline_top: timeline.get(app.timeline_scroll).map(|&i| app.tasks[i].id),
+        kanban_top: app
+            .kanban_scroll
+            .get(app.kanban_stage.stage_index())
+            .and_then(|&scroll| kanban.get(scroll).copied()),
+    }
+}
+
+fn restore_view_anchor(app: &mut App, anchor: &ViewAnchor) {
+    let timeline = sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed);
+    let timeline_pos =
+        |id: Option<Uuid>| id.and_then(|id| timeline.iter().position(|&i| app.tasks[i].id == id));
+    if let Some(pos) = timeline_pos(anchor.timeline_selected) {
+        app.timeline_selected = pos;
+    }
+    if let Some(pos) = timeline_pos(anchor.timeline_top) {
+        app.timeline_scroll = pos;
+    }
+    let kanban = kanban_task_ids(
+        &app.tasks,
+        app.kanban_stage,
+        app.settings.sort_mode,
+        app.kanban_bucket.as_deref(),
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
+    let stage_idx = app.kanban_stage.stage_index();
+    if let (Some(pos), Some(scroll)) = (
+        anchor
+            .kanban_top
+            .and_then(|id| kanban.iter().position(|&k| k == id)),
+        app.kanban_scroll.get_mut(stage_idx),
+    ) {
+        *scroll = pos;
+    }
+}
+
 fn scroll_kanban_to_selected(app: &mut App) {
     let stage_idx = app.kanban_stage.stage_index();
-    let ids = kanban_task_ids(&app.tasks, app.kanban_stage);
+    let ids = kanban_task_ids(
+        &app.tasks,
+        app.kanban_stage,
+        app.settings.sort_mode,
+        app.kanban_bucket.as_deref(),
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
     let sel_pos = app
         .kanban_selected
         .and_then(|id| ids.iter().position(|i| *i == id))
@@ -3206,7 +5310,15 @@ fn scroll_kanban_to_selected(app: &mut App) {
 }
 
 fn move_kanban_selection(app: &mut App, delta: i32) {
-    let ids = kanban_task_ids(&app.tasks, app.kanban_stage);
+    let ids = kanban_task_ids(
+        &app.tasks,
+        app.kanban_stage,
+        app.settings.sort_mode,
+        app.kanban_bucket.as_deref(),
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
     if ids.is_empty() {
         app.kanban_selected = None;
         return;
@@ -3226,28 +5338,154 @@ fn move_kanban_selection(app: &mut App, delta: i32) {
     scroll_kanban_to_selected(app);
 }
 
+/// Save the tasks. If another process changed task files since they were
+/// last loaded here, its changes are merged in first instead of overwritten.
 fn persist(app: &mut App) {
-    let Some(storage) = &app.storage else {
+    let Some(storage) = app.storage.clone() else {
         return;
     };
-    if let Err(err) = storage.save_tasks(&app.tasks) {
-        app.status = Some((format!("Save failed: {err}"), Instant::now(), false));
+    match storage.save_tasks_merging(&app.tasks_base, &mut app.tasks, &mut app.tasks_stamp) {
+        Ok(merged) => {
+            app.tasks_base = app.tasks.clone();
+            let (msg, kind) = match merged {
+                None => return,
+                Some(0) => ("Merged changes made outside aipm".to_string(), Toast::Info),
+                Some(1) => (
+                    "1 task was also changed outside aipm; kept your version".to_string(),
+                    Toast::Warning,
+                ),
+                Some(n) => (
+                    format!("{n} tasks were also changed outside aipm; kept your versions"),
+                    Toast::Warning,
+                ),
+            };
+            push_toast(app, msg, false, kind);
+        }
+        Err(err) => push_toast(app, format!("Save failed: {err}"), false, Toast::Error),
     }
 }
 
-fn persist_settings(app: &mut App) {
+/// Replace the tasks with what is on disk, e.g. after an undo rewrote them.
+fn reload_tasks(app: &mut App) {
     let Some(storage) = &app.storage else {
         return;
     };
-    if let Err(err) = storage.save_settings(&app.settings) {
-        app.status = Some((
-            format!("Settings save failed: {err}"),
-            Instant::now(),
-            false,
-        ));
+    let stamp = storage.tasks_stamp();
+    if let Ok(fresh) = storage.reload_tasks() {
+        app.tasks_stamp = stamp;
+        app.tasks_base = fresh.clone();
+        app.tasks = fresh;
     }
 }
 
+/// Pick up task files changed by something else, such as `aipm task` in
+/// another terminal, before a save here could overwrite them. The task open
+/// in the edit overlay is kept if it was deleted, so the edit isn't lost.
+fn poll_external_tasks(app: &mut App) -> bool {
+    let Some(storage) = &app.storage else {
+        return false;
+    };
+    let stamp = storage.tasks_stamp();
+    if stamp == app.tasks_stamp {
+        return false;
+    }
+    app.tasks_stamp = stamp;
+    let Ok(mut fresh) = storage.reload_tasks() else {
+        return false;
+    };
+    app.tasks_base = fresh.clone();
+    // Our own saves and reloads change the stamp too.
+    if storage::same_tasks(&fresh, &app.tasks) {
+        return false;
+    }
+    if let Some(edited) = app
+        .edit_task_id
+        .filter(|id| !fresh.iter().any(|t| t.id == *id))
+        .and_then(|id| app.tasks.iter().find(|t| t.id == id))
+    {
+        fresh.push(edited.clone());
+    }
+    let anchor = view_anchor(app);
+    app.tasks = fresh;
+    app.checklist_frozen_order = None;
+    restore_view_anchor(app, &anchor);
+    ensure_default_selection(app);
+    ensure_kanban_selection(app);
+    if app.status.is_none() {
+        push_toast(
+            app,
+            "Reloaded (external change)".to_string(),
+            false,
+            Toast::Info,
+        );
+    }
+    true
+}
+
+fn persist_suggestions(app: &mut App) {
+    let Some(storage) = &app.storage else {
+        return;
+    };
+    if let Err(err) = storage.save_suggestions(&app.suggestions, &app.suggestions_dismissed) {
+        push_toast(
+            app,
+            format!("Suggestions save failed: {err}"),
+            false,
+            Toast::Error,
+        );
+    }
+}
+
+/// Saves the settings, returning false after showing a warning or error.
+fn persist_settings(app: &mut App) -> bool {
+    let Some(storage) = &app.storage else {
+        return true;
+    };
+    match storage.save_settings(&mut app.settings) {
+        Ok(refused) if !refused.is_empty() => push_toast(
+            app,
+            format!(
+                "Keychain refused the {} key, it stays in the settings file",
+                refused.join(" and ")
+            ),
+            false,
+            Toast::Warning,
+        ),
+        Ok(_) => return true,
+        Err(err) => push_toast(
+            app,
+            format!("Settings save failed: {err}"),
+            false,
+            Toast::Error,
+        ),
+    }
+    false
+}
+
+/// Move the API keys into or out of the OS keychain. Saving does the move,
+/// so without a keychain the setting stays off and the keys stay put.
+fn toggle_keychain(app: &mut App) {
+    if !app.settings.use_keychain && !secrets::available() {
+        push_toast(
+            app,
+            "No keychain available, keys stay in the settings file".to_string(),
+            false,
+            Toast::Warning,
+        );
+        return;
+    }
+    app.settings.use_keychain = !app.settings.use_keychain;
+    if !persist_settings(app) {
+        return;
+    }
+    let msg = if app.settings.use_keychain {
+        "API keys moved to the keychain"
+    } else {
+        "API keys moved to the settings file"
+    };
+    push_toast(app, msg.to_string(), false, Toast::Success);
+}
+
 fn rebuild_ai(app: &mut App) {
     app.ai = llm::AiRuntime::from_settings(&app.settings);
 }
@@ -3302,19 +5540,44 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 }
             }
         }
-        KeyCode::Char('d') | KeyCode::Delete if app.settings_memory_focus => {
-            if !app.settings.memory_facts.is_empty()
-                && app.memory_selected < app.settings.memory_facts.len()
-            {
-                app.settings.memory_facts.remove(app.memory_selected);
-                let remaining = app.settings.memory_facts.len();
-                app.memory_selected = app.memory_selected.min(remaining.saturating_sub(1));
-                if remaining == 0 {
-                    app.settings_memory_focus = false;
-                }
+        KeyCode::Char('d') | KeyCode::Delete
+            if app.settings_memory_focus
+                && app.memory_selected < app.settings.memory_facts.len() =>
+        {
+            app.settings.memory_facts.remove(app.memory_selected);
+            let remaining = app.settings.memory_facts.len();
+            app.memory_selected = app.memory_selected.min(remaining.saturating_sub(1));
+            if remaining == 0 {
+                app.settings_memory_focus = false;
+            }
+            persist_settings(app);
+        }
+        KeyCode::Char('e') if app.settings_field == SettingsField::Model => {
+            app.settings_buf = app.settings.model.clone();
+            app.settings_editing = true;
+        }
+        KeyCode::Char('d') | KeyCode::Delete
+            if app.settings_field == SettingsField::Model && !app.settings_memory_focus =>
+        {
+            let current = app.settings.model.clone();
+            let before = app.settings.custom_models.len();
+            app.settings.custom_models.retain(|m| m.trim() != current);
+            if app.settings.custom_models.len() < before {
+                push_toast(
+                    app,
+                    format!("Removed {current} from the model list"),
+                    false,
+                    Toast::Success,
+                );
                 persist_settings(app);
             }
         }
+        KeyCode::Char('t')
+            if app.settings_field == SettingsField::Model && app.connection_test_rx.is_none() =>
+        {
+            app.connection_test_rx = Some(llm::test_connection(&app.settings));
+            push_toast(app, "Testing connection…".to_string(), true, Toast::Info);
+        }
         KeyCode::Enter | KeyCode::Char(' ') => match app.settings_field {
             SettingsField::OwnerName => {
                 app.settings_buf = app.settings.owner_name.clone();
@@ -3324,6 +5587,10 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 app.settings_buf = app.settings.user_profile.clone();
                 app.settings_editing = true;
             }
+            SettingsField::SystemPrompt => {
+                app.settings_buf = app.settings.system_prompt.clone();
+                app.settings_editing = true;
+            }
             SettingsField::AiEnabled => {
                 app.settings.enabled = !app.settings.enabled;
                 persist_settings(app);
@@ -3344,6 +5611,23 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 app.settings_buf = app.settings.timeout_secs.to_string();
                 app.settings_editing = true;
             }
+            SettingsField::Keychain => toggle_keychain(app),
+            SettingsField::AiLockBucket => {
+                app.settings.ai_lock_bucket = !app.settings.ai_lock_bucket;
+                persist_settings(app);
+            }
+            SettingsField::AiLockPriority => {
+                app.settings.ai_lock_priority = !app.settings.ai_lock_priority;
+                persist_settings(app);
+            }
+            SettingsField::AiLockDueDate => {
+                app.settings.ai_lock_due_date = !app.settings.ai_lock_due_date;
+                persist_settings(app);
+            }
+            SettingsField::AiVisibleOnly => {
+                app.settings.ai_context_visible_only = !app.settings.ai_context_visible_only;
+                persist_settings(app);
+            }
             SettingsField::ShowBacklog => {
                 app.settings.show_backlog = !app.settings.show_backlog;
                 persist_settings(app);
@@ -3360,6 +5644,47 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 app.settings.show_done = !app.settings.show_done;
                 persist_settings(app);
             }
+            SettingsField::CardSubIssues => {
+                app.settings_buf = app.settings.card_max_subissues.to_string();
+                app.settings_editing = true;
+            }
+            SettingsField::AiConcurrency => {
+                app.settings_buf = app.settings.ai_concurrency.to_string();
+                app.settings_editing = true;
+            }
+            SettingsField::SnapshotRetention => {
+                app.settings_buf = app.settings.snapshot_retention.to_string();
+                app.settings_editing = true;
+            }
+            SettingsField::AutoEscalate => toggle_auto_escalate(app),
+            SettingsField::DateFormat => {
+                app.settings_buf = app.settings.date_format.clone();
+                app.settings_editing = true;
+            }
+            SettingsField::StageLabels => {
+                app.settings_buf = app.settings.stage_labels_text();
+                app.settings_editing = true;
+            }
+            SettingsField::WeekStart => toggle_week_start(app),
+            SettingsField::WorkDays => {
+                app.settings_buf = app.settings.work_days_text();
+                app.settings_editing = true;
+            }
+            SettingsField::Holidays => {
+                app.settings_buf = app.settings.holidays_text();
+                app.settings_editing = true;
+            }
+            SettingsField::WorkdayEnd => {
+                app.settings_buf = app.settings.workday_end_text();
+                app.settings_editing = true;
+            }
+            SettingsField::UtcOffset => {
+                app.settings_buf = match app.settings.utc_offset_minutes {
+                    Some(m) => format_utc_offset(Some(m)),
+                    None => String::new(),
+                };
+                app.settings_editing = true;
+            }
             SettingsField::GoogleAccount => {
                 if app.google_connected {
                     if let Some(ref dir) = app.data_dir {
@@ -3368,11 +5693,12 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                     app.google_connected = false;
                     app.calendar_events.clear();
                     app.calendar_loading = false;
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Google account disconnected".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Success,
+                    );
                 } else if let Some(ref dir) = app.data_dir {
                     let dir_clone = dir.clone();
                     let (tx, rx) = mpsc::channel();
@@ -3380,11 +5706,12 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                         let _ = tx.send(google::authorize(&dir_clone));
                     });
                     app.google_auth_rx = Some(rx);
-                    app.status = Some((
+                    push_toast(
+                        app,
                         "Opening browser for Google sign-in...".to_string(),
-                        Instant::now(),
                         false,
-                    ));
+                        Toast::Info,
+                    );
                 }
             }
         },
@@ -3397,6 +5724,23 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
             SettingsField::Model => {
                 cycle_model(app, key.code == KeyCode::Right);
             }
+            SettingsField::Keychain => toggle_keychain(app),
+            SettingsField::AiLockBucket => {
+                app.settings.ai_lock_bucket = !app.settings.ai_lock_bucket;
+                persist_settings(app);
+            }
+            SettingsField::AiLockPriority => {
+                app.settings.ai_lock_priority = !app.settings.ai_lock_priority;
+                persist_settings(app);
+            }
+            SettingsField::AiLockDueDate => {
+                app.settings.ai_lock_due_date = !app.settings.ai_lock_due_date;
+                persist_settings(app);
+            }
+            SettingsField::AiVisibleOnly => {
+                app.settings.ai_context_visible_only = !app.settings.ai_context_visible_only;
+                persist_settings(app);
+            }
             SettingsField::ShowBacklog => {
                 app.settings.show_backlog = !app.settings.show_backlog;
                 persist_settings(app);
@@ -3413,6 +5757,39 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 app.settings.show_done = !app.settings.show_done;
                 persist_settings(app);
             }
+            SettingsField::CardSubIssues => {
+                let n = app.settings.card_max_subissues;
+                app.settings.card_max_subissues = if key.code == KeyCode::Right {
+                    n + 1
+                } else {
+                    n.saturating_sub(1)
+                };
+                persist_settings(app);
+                ensure_default_selection(app);
+            }
+            SettingsField::AiConcurrency => {
+                let n = app.settings.ai_concurrency;
+                let next = if key.code == KeyCode::Right {
+                    n + 1
+                } else {
+                    n.saturating_sub(1)
+                };
+                app.settings.ai_concurrency = next.clamp(1, storage::MAX_AI_CONCURRENCY);
+                persist_settings(app);
+                rebuild_ai(app);
+            }
+            SettingsField::SnapshotRetention => {
+                let n = app.settings.snapshot_retention;
+                let next = if key.code == KeyCode::Right {
+                    n + 10
+                } else {
+                    n.saturating_sub(10)
+                };
+                app.settings.snapshot_retention = next.clamp(1, storage::MAX_SNAPSHOT_RETENTION);
+                persist_settings(app);
+            }
+            SettingsField::AutoEscalate => toggle_auto_escalate(app),
+            SettingsField::WeekStart => toggle_week_start(app),
             _ => {}
         },
         _ => {}
@@ -3420,24 +5797,74 @@ fn handle_settings_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     Ok(false)
 }
 
+fn toggle_week_start(app: &mut App) {
+    app.settings.week_start = app.settings.week_start.toggled();
+    persist_settings(app);
+}
+
+fn toggle_auto_escalate(app: &mut App) {
+    app.settings.auto_escalate = !app.settings.auto_escalate;
+    persist_settings(app);
+    if app.settings.auto_escalate && auto_escalate_tasks(&mut app.tasks, app.settings.today()) {
+        persist(app);
+    }
+}
+
+/// Built-in models followed by the user's own, without repeats.
+fn model_options(settings: &AiSettings) -> Vec<&str> {
+    let mut options: Vec<&str> = MODEL_OPTIONS.to_vec();
+    for name in &settings.custom_models {
+        let name = name.trim();
+        if !name.is_empty() && !options.contains(&name) {
+            options.push(name);
+        }
+    }
+    options
+}
+
 fn cycle_model(app: &mut App, forward: bool) {
-    let current_idx = MODEL_OPTIONS
+    let options = model_options(&app.settings);
+    let current_idx = options
         .iter()
         .position(|&m| m == app.settings.model)
         .unwrap_or(0);
     let next = if forward {
-        (current_idx + 1) % MODEL_OPTIONS.len()
+        (current_idx + 1) % options.len()
     } else if current_idx == 0 {
-        MODEL_OPTIONS.len() - 1
+        options.len() - 1
     } else {
         current_idx - 1
     };
-    app.settings.model = MODEL_OPTIONS[next].to_string();
+    app.settings.model = options[next].to_string();
     persist_settings(app);
     rebuild_ai(app);
 }
 
+/// Set a typed model name, keeping it in the cycle if it isn't built in.
+fn set_typed_model(app: &mut App) {
+    let name = app.settings_buf.trim().to_string();
+    if !name.is_empty() && !model_options(&app.settings).contains(&name.as_str()) {
+        app.settings.custom_models.push(name.clone());
+        push_toast(
+            app,
+            format!("Added {name} to the model list"),
+            false,
+            Toast::Success,
+        );
+    }
+    app.settings.model = name;
+}
+
 fn handle_settings_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
+    let newline = match key.code {
+        KeyCode::Enter => key.modifiers.contains(KeyModifiers::ALT),
+        KeyCode::Char('j') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    };
+    if newline && app.settings_field == SettingsField::SystemPrompt {
+        app.settings_buf.push('\n');
+        return Ok(false);
+    }
     match key.code {
         KeyCode::Esc => {
             app.settings_editing = false;
@@ -3450,18 +5877,153 @@ fn handle_settings_edit_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 SettingsField::UserProfile => {
                     app.settings.user_profile = app.settings_buf.trim().to_string();
                 }
-                SettingsField::OpenAiKey => {
-                    app.settings.openai_api_key = app.settings_buf.clone();
+                SettingsField::SystemPrompt => {
+                    let bounded = llm::bounded_system_prompt(&app.settings_buf).to_string();
+                    if bounded.len() < app.settings_buf.trim().len() {
+                        push_toast(
+                            app,
+                            format!(
+                                "Instructions cut to {} characters",
+                                storage::SYSTEM_PROMPT_MAX_CHARS
+                            ),
+                            false,
+                            Toast::Warning,
+                        );
+                    }
+                    app.settings.system_prompt = bounded;
+                }
+                SettingsField::OpenAiKey | SettingsField::AnthropicKey => {
+                    let (provider, var) = if app.settings_field == SettingsField::OpenAiKey {
+                        app.settings.openai_api_key = app.settings_buf.clone();
+                        (llm::Provider::OpenAi, "OPENAI_API_KEY")
+                    } else {
+                        app.settings.anthropic_api_key = app.settings_buf.clone();
+                        (llm::Provider::Anthropic, "ANTHROPIC_API_KEY")
+                    };
+                    if llm::api_key(&app.settings, provider)
+                        .is_some_and(|(_, source)| source == llm::KeySource::Env)
+                    {
+                        push_toast(
+                            app,
+                            format!("Saved, but {var} from the environment takes precedence"),
+                            false,
+                            Toast::Warning,
+                        );
+                    }
+                }
+                SettingsField::Model => set_typed_model(app),
+                SettingsField::Timeout => match app.settings_buf.trim().parse::<u64>() {
+                    Ok(secs) if secs > 0 => {
+                        let clamped =
+                            secs.clamp(storage::MIN_TIMEOUT_SECS, storage::MAX_TIMEOUT_SECS);
+                        if clamped != secs {
+                            push_toast(
+                                app,
+                                format!(
+                                    "Timeout kept within {}-{}s",
+                                    storage::MIN_TIMEOUT_SECS,
+                                    storage::MAX_TIMEOUT_SECS
+                                ),
+                                false,
+                                Toast::Warning,
+                            );
+                        }
+                        app.settings.timeout_secs = clamped;
+                    }
+                    _ => {
+                        push_toast(
+                            app,
+                            "Timeout must be a positive number".to_string(),
+                            false,
+                            Toast::Error,
+                        );
+                    }
+                },
+                SettingsField::CardSubIssues => {
+                    if let Ok(n) = app.settings_buf.trim().parse::<usize>() {
+                        app.settings.card_max_subissues = n;
+                    }
+                }
+                SettingsField::AiConcurrency => {
+                    if let Ok(n) = app.settings_buf.trim().parse::<usize>() {
+                        app.settings.ai_concurrency = n.clamp(1, storage::MAX_AI_CONCURRENCY);
+                    }
                 }
-                SettingsField::AnthropicKey => {
-                    app.settings.anthropic_api_key = app.settings_buf.clone();
+                SettingsField::SnapshotRetention => {
+                    if let Ok(n) = app.settings_buf.trim().parse::<usize>() {
+                        app.settings.snapshot_retention =
+                            n.clamp(1, storage::MAX_SNAPSHOT_RETENTION);
+                    }
                 }
-                SettingsField::Model => app.settings.model = app.settings_buf.clone(),
-                SettingsField::Timeout => {
-                    if let Ok(secs) = app.settings_buf.parse::<u64>() {
-                        app.settings.timeout_secs = secs;
+                SettingsField::UtcOffset => match parse_utc_offset(&app.settings_buf) {
+                    Some(offset) => app.settings.utc_offset_minutes = offset,
+                    None => {
+                        push_toast(
+                            app,
+                            format!(
+                                "Invalid timezone: {} (try +05:30, -8 or empty for local)",
+                                app.settings_buf.trim()
+                            ),
+                            false,
+                            Toast::Error,
+                        );
+                    }
+                },
+                SettingsField::DateFormat => {
+                    let fmt = app.settings_buf.trim();
+                    if is_valid_date_format(fmt) {
+                        app.settings.date_format = fmt.to_string();
+                    } else {
+                        push_toast(
+                            app,
+                            format!("Invalid date format: {fmt} (try %d/%m/%Y)"),
+                            false,
+                            Toast::Error,
+                        );
                     }
                 }
+                SettingsField::StageLabels => match parse_stage_labels(&app.settings_buf) {
+                    Some(labels) => app.settings.stage_labels = labels,
+                    None => push_toast(
+                        app,
+                        "At most four names: Backlog, Todo, In progress, Done".to_string(),
+                        false,
+                        Toast::Error,
+                    ),
+                },
+                SettingsField::WorkDays => match parse_work_days(&app.settings_buf) {
+                    Some(days) => app.settings.work_days = days,
+                    None => push_toast(
+                        app,
+                        format!(
+                            "Invalid work days: {} (try Mon-Fri or Sun-Thu)",
+                            app.settings_buf.trim()
+                        ),
+                        false,
+                        Toast::Error,
+                    ),
+                },
+                SettingsField::Holidays => match parse_holidays(&app.settings_buf) {
+                    Some(dates) => app.settings.holidays = dates,
+                    None => push_toast(
+                        app,
+                        "Holidays are YYYY-MM-DD dates separated by commas".to_string(),
+                        false,
+                        Toast::Error,
+                    ),
+                },
+                SettingsField::WorkdayEnd => match parse_workday_end(&app.settings_buf) {
+                    Some(end) => app.settings.workday_end = end,
+                    None => push_toast(
+                        app,
+                        format!(
+                            "Invalid time: {} (try 17:30, or empty for midnight)",
+                            app.settings_buf.trim()
+                        ),
+                        false,
+                        Toast::Error,
+                    ),
+                },
                 _ => {}
             }
             persist_settings(app);
@@ -3519,29 +6081,130 @@ fn create_task_from_selected_suggestion(app: &mut App) {
         return;
     }
     clamp_suggestions_selection(app);
-    if let Some(suggestion) = app.suggestions.get(app.suggestions_selected).cloned() {
-        let now = Utc::now();
-        let mut task = Task::new(
-            default_bucket_name(&app.settings),
-            suggestion.title.clone(),
-            now,
+    let suggestion = app.suggestions.remove(app.suggestions_selected);
+    push_suggestion_task(app, &suggestion, Utc::now());
+    clamp_suggestions_selection(app);
+    persist(app);
+    persist_suggestions(app);
+    let msg = match suggestion.sub_tasks.len() {
+        0 => "Task created from suggestion".to_string(),
+        n => format!("Task with {n} sub-issues created from suggestion"),
+    };
+    push_toast(app, msg, false, Toast::Success);
+}
+
+/// Create the Backlog task for `suggestion`, with one sub-issue per action item.
+fn push_suggestion_task(app: &mut App, suggestion: &Suggestion, now: chrono::DateTime<Utc>) {
+    let mut task = Task::new(
+        default_bucket_name(&app.settings),
+        suggestion.title.clone(),
+        now,
+    );
+    task.description = suggestion.description.clone();
+    task.priority = suggestion.priority;
+    task.progress = Progress::Backlog;
+    task.email_id = Some(suggestion.email_id.clone());
+    task.email_subject = Some(suggestion.subject.clone());
+    let task_id = task.id;
+    app.tasks.push(task);
+    // Linked too, so archiving the email removes the whole structure.
+    for (pos, title) in suggestion.sub_tasks.iter().enumerate() {
+        let child_id = push_sub_issue(app, task_id, title.clone(), now);
+        if let Some(child) = app.task_index.get_mut(&mut app.tasks, child_id) {
+            child.priority = suggestion.priority;
+            child.sort_order = Some(pos as u32);
+            child.email_id = Some(suggestion.email_id.clone());
+            child.email_subject = Some(suggestion.subject.clone());
+        }
+    }
+}
+
+/// Accept every pending suggestion. With `triage` each one goes to the AI as
+/// triage input instead, which may update an existing task rather than
+/// create one.
+fn accept_all_suggestions(app: &mut App, triage: bool) {
+    let count = app.suggestions.len();
+    if triage {
+        let Some(ai) = &app.ai else {
+            push_toast(app, "AI not configured".to_string(), false, Toast::Warning);
+            return;
+        };
+        let suggestions = std::mem::take(&mut app.suggestions);
+        app.suggestions_selected = 0;
+        let context = build_ai_context(&app.tasks, ai_context_filter(&app.settings));
+        let triage_ctx = build_triage_context(&app.tasks, ai_context_filter(&app.settings));
+        for suggestion in &suggestions {
+            let mut input = format!("{}\n\n{}", suggestion.title, suggestion.description);
+            for item in &suggestion.sub_tasks {
+                input.push_str(&format!("\n- {item}"));
+            }
+            input.push_str(&format!(
+                "\n\nFrom email: {}: {}",
+                suggestion.sender, suggestion.subject
+            ));
+            ai.enqueue(llm::AiJob {
+                task_id: Uuid::nil(),
+                title: String::new(),
+                suggested_bucket: default_bucket_name(&app.settings),
+                context: Arc::clone(&context),
+                buckets: app.settings.buckets.clone(),
+                lock_bucket: app.settings.ai_lock_bucket,
+                lock_priority: app.settings.ai_lock_priority,
+                lock_due_date: app.settings.ai_lock_due_date,
+                edit_instruction: None,
+                task_snapshot: None,
+                triage_input: Some(input),
+                triage_context: Some(triage_ctx.clone()),
+                chat_history: Vec::new(),
+                user_profile: app.settings.user_profile.clone(),
+                memory_facts: app.settings.memory_facts.clone(),
+                assignee: None,
+            });
+        }
+        // Triaged tasks aren't linked to their email, so without this the
+        // poller would suggest the same emails again.
+        app.suggestions_dismissed
+            .extend(suggestions.into_iter().map(|s| s.email_id));
+        persist_suggestions(app);
+        push_toast(
+            app,
+            format!("AI triaging {count} suggestions…"),
+            true,
+            Toast::Info,
         );
-        task.description = suggestion.description;
-        task.priority = suggestion.priority;
-        task.progress = Progress::Backlog;
-        let task_id = task.id;
-        app.tasks.push(task);
-        app.task_email_map
-            .insert(task_id, suggestion.email_id.clone());
-        app.suggestions.remove(app.suggestions_selected);
-        clamp_suggestions_selection(app);
-        persist(app);
-        app.status = Some((
-            "Task created from suggestion".to_string(),
-            Instant::now(),
-            false,
-        ));
+        return;
+    }
+    if let Some(storage) = &app.storage {
+        storage.snapshot("accept suggestions");
+    }
+    let suggestions = std::mem::take(&mut app.suggestions);
+    app.suggestions_selected = 0;
+    let now = Utc::now();
+    for suggestion in &suggestions {
+        push_suggestion_task(app, suggestion, now);
     }
+    persist(app);
+    persist_suggestions(app);
+    push_toast(
+        app,
+        format!("Created {count} tasks from suggestions"),
+        false,
+        Toast::Success,
+    );
+}
+
+fn dismiss_all_suggestions(app: &mut App) {
+    let count = app.suggestions.len();
+    let ids = app.suggestions.drain(..).map(|s| s.email_id);
+    app.suggestions_dismissed.extend(ids);
+    app.suggestions_selected = 0;
+    persist_suggestions(app);
+    push_toast(
+        app,
+        format!("Dismissed {count} suggestions"),
+        false,
+        Toast::Success,
+    );
 }
 
 fn dismiss_selected_suggestion(app: &mut App) {
@@ -3549,8 +6212,21 @@ fn dismiss_selected_suggestion(app: &mut App) {
         return;
     }
     clamp_suggestions_selection(app);
-    app.suggestions.remove(app.suggestions_selected);
+    let suggestion = app.suggestions.remove(app.suggestions_selected);
+    app.suggestions_dismissed.push(suggestion.email_id);
     clamp_suggestions_selection(app);
+    persist_suggestions(app);
+}
+
+/// Emails behind pending and dismissed suggestions. Tasks are left out: their
+/// emails are usually read long before the task is done, and being read
+/// counts as archived.
+fn tracked_suggestion_emails(app: &App) -> std::collections::HashSet<String> {
+    app.suggestions
+        .iter()
+        .map(|s| s.email_id.clone())
+        .chain(app.suggestions_dismissed.iter().cloned())
+        .collect()
 }
 
 fn poll_ai(app: &mut App) -> bool {
@@ -3562,6 +6238,7 @@ fn poll_ai(app: &mut App) -> bool {
     if results.is_empty() {
         return false;
     }
+    end_progress_toast(app);
 
     if let Some(storage) = &app.storage {
         storage.snapshot("ai triage");
@@ -3570,9 +6247,10 @@ fn poll_ai(app: &mut App) -> bool {
     let mut changed = false;
     for result in results {
         if let Some(err) = result.error {
-            app.status = Some((format!("AI error: {}", err), Instant::now(), false));
+            push_toast(app, format!("AI error: {}", err), false, Toast::Error);
             continue;
         }
+        let mut bucket_warning = None;
 
         // Handle triage results: create new task or find & update existing.
         if let Some(triage_action) = &result.triage_action {
@@ -3588,8 +6266,14 @@ fn poll_ai(app: &mut App) -> bool {
                     let bucket = result
                         .update
                         .bucket
-                        .clone()
+                        .as_deref()
+                        .and_then(|b| known_bucket(&app.settings, b))
                         .unwrap_or_else(|| default_bucket_name(&app.settings));
+                    bucket_warning = unknown_bucket_warning(
+                        &app.settings,
+                        result.update.bucket.as_deref(),
+                        &bucket,
+                    );
                     let mut task = Task::new(bucket.clone(), title, now);
                     if let Some(desc) = &result.update.description {
                         task.description = desc.clone();
@@ -3601,8 +6285,9 @@ fn poll_ai(app: &mut App) -> bool {
                         task.priority = priority;
                     }
                     if let Some(due_date) = result.update.due_date {
-                        task.due_date = due_date;
+                        task.set_due_date(due_date);
                     }
+                    task.assignee = result.update.assignee.clone();
                     if !result.update.dependencies.is_empty() {
                         task.dependencies = resolve_dependency_prefixes(
                             &app.tasks,
@@ -3644,7 +6329,7 @@ fn poll_ai(app: &mut App) -> bool {
                                 .filter_map(|&idx| new_ids.get(idx).copied())
                                 .filter(|&dep_id| dep_id != task_id)
                                 .collect();
-                            if let Some(t) = app.tasks.iter_mut().find(|t| t.id == task_id) {
+                            if let Some(t) = app.task_index.get_mut(&mut app.tasks, task_id) {
                                 t.dependencies = dep_ids;
                             }
                         }
@@ -3652,186 +6337,184 @@ fn poll_ai(app: &mut App) -> bool {
                         if let Some(first_id) = new_ids.first().copied() {
                             sync_parent_progress(&mut app.tasks, first_id, now);
                         }
-                        app.status = Some((
+                        push_toast(
+                            app,
                             format!(
                                 "AI created: {} (+{} sub-task{})",
                                 status_title,
                                 count,
                                 if count == 1 { "" } else { "s" }
                             ),
-                            Instant::now(),
                             false,
-                        ));
+                            Toast::Success,
+                        );
                     } else {
-                        app.status = Some((
+                        push_toast(
+                            app,
                             format!("AI created: {}", status_title),
-                            Instant::now(),
                             false,
-                        ));
+                            Toast::Success,
+                        );
                     }
                     changed = true;
                 }
                 llm::TriageAction::Update(prefix) => {
-                    let target_id = app.tasks.iter().find_map(|t| {
-                        let short = t.id.to_string().chars().take(8).collect::<String>();
-                        if short.eq_ignore_ascii_case(prefix) {
-                            Some(t.id)
-                        } else {
-                            None
-                        }
-                    });
-                    if let Some(id) = target_id {
-                        let deps = if !result.update.dependencies.is_empty() {
-                            resolve_dependency_prefixes(&app.tasks, id, &result.update.dependencies)
-                        } else {
-                            Vec::new()
-                        };
-                        if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
-                            let now = Utc::now();
-                            apply_update(task, &result.update, &deps, now);
-                            changed = true;
-                        }
-                        if let Some(ref new_parent_prefix) = result.update.parent_id {
-                            let old_parent = app
-                                .tasks
-                                .iter()
-                                .find(|t| t.id == id)
-                                .and_then(|t| t.parent_id);
-                            if new_parent_prefix.eq_ignore_ascii_case("none") {
-                                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
-                                    task.parent_id = None;
-                                    changed = true;
-                                }
+                    match app.task_index.resolve(&app.tasks, prefix) {
+                        Ok(id) => {
+                            let deps = if !result.update.dependencies.is_empty() {
+                                resolve_dependency_prefixes(
+                                    &app.tasks,
+                                    id,
+                                    &result.update.dependencies,
+                                )
                             } else {
-                                let new_parent_id = app.tasks.iter().find_map(|t| {
-                                    let short =
-                                        t.id.to_string().chars().take(8).collect::<String>();
-                                    if short.eq_ignore_ascii_case(new_parent_prefix) {
-                                        Some(t.id)
-                                    } else {
-                                        None
-                                    }
-                                });
-                                if let Some(pid) = new_parent_id {
-                                    if let Some(task) = app.tasks.iter_mut().find(|t| t.id == id) {
-                                        task.parent_id = Some(pid);
-                                        changed = true;
+                                Vec::new()
+                            };
+                            if let Some(task) = app.task_index.get_mut(&mut app.tasks, id) {
+                                let now = Utc::now();
+                                apply_update(task, &result.update, &deps, &app.settings, now);
+                                bucket_warning = unknown_bucket_warning(
+                                    &app.settings,
+                                    result
+                                        .update
+                                        .bucket
+                                        .as_deref()
+                                        .filter(|_| !app.settings.ai_lock_bucket),
+                                    &task.bucket,
+                                );
+                                changed = true;
+                            }
+                            if let Some(ref new_parent_prefix) = result.update.parent_id {
+                                match apply_parent_change(
+                                    &mut app.tasks,
+                                    id,
+                                    new_parent_prefix,
+                                    Utc::now(),
+                                ) {
+                                    Ok(_) => changed = true,
+                                    Err(err) => {
+                                        push_toast(app, format!("AI: {err}"), false, Toast::Error);
                                     }
                                 }
                             }
-                            if let Some(old_pid) = old_parent {
-                                sync_parent_progress(&mut app.tasks, old_pid, Utc::now());
-                            }
-                        }
-                        sync_parent_progress(&mut app.tasks, id, Utc::now());
-                        // Create sub-tasks if the update response includes them.
-                        if !result.sub_task_specs.is_empty() {
-                            let now = Utc::now();
-                            let parent_bucket = app
-                                .tasks
-                                .iter()
-                                .find(|t| t.id == id)
-                                .map(|t| t.bucket.clone())
-                                .unwrap_or_else(|| default_bucket_name(&app.settings));
-                            let count = result.sub_task_specs.len();
-                            let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
-                            for spec in result.sub_task_specs.iter() {
-                                let bucket =
-                                    spec.bucket.clone().unwrap_or_else(|| parent_bucket.clone());
-                                let mut task = Task::new(bucket, spec.title.clone(), now);
-                                task.parent_id = Some(id);
-                                task.description = spec.description.clone();
-                                if let Some(p) = spec.priority {
-                                    task.priority = p;
-                                }
-                                if let Some(prog) = spec.progress {
-                                    task.set_progress(prog, now);
+                            sync_parent_progress(&mut app.tasks, id, Utc::now());
+                            // Create sub-tasks if the update response includes them.
+                            if !result.sub_task_specs.is_empty()
+                                && nesting_depth(&app.tasks, id) >= MAX_NESTING_DEPTH
+                            {
+                                push_toast(
+                                    app,
+                                    format!("AI: {NESTING_LIMIT_MSG}"),
+                                    false,
+                                    Toast::Warning,
+                                );
+                            } else if !result.sub_task_specs.is_empty() {
+                                let now = Utc::now();
+                                let parent_bucket = app
+                                    .tasks
+                                    .iter()
+                                    .find(|t| t.id == id)
+                                    .map(|t| t.bucket.clone())
+                                    .unwrap_or_else(|| default_bucket_name(&app.settings));
+                                let count = result.sub_task_specs.len();
+                                let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
+                                for spec in result.sub_task_specs.iter() {
+                                    let bucket = spec
+                                        .bucket
+                                        .clone()
+                                        .unwrap_or_else(|| parent_bucket.clone());
+                                    let mut task = Task::new(bucket, spec.title.clone(), now);
+                                    task.parent_id = Some(id);
+                                    task.description = spec.description.clone();
+                                    if let Some(p) = spec.priority {
+                                        task.priority = p;
+                                    }
+                                    if let Some(prog) = spec.progress {
+                                        task.set_progress(prog, now);
+                                    }
+                                    if let Some(due) = spec.due_date {
+                                        task.due_date = Some(due);
+                                    }
+                                    new_ids.push(task.id);
+                                    app.tasks.push(task);
                                 }
-                                if let Some(due) = spec.due_date {
-                                    task.due_date = Some(due);
+                                for (i, spec) in result.sub_task_specs.iter().enumerate() {
+                                    if spec.depends_on.is_empty() {
+                                        continue;
+                                    }
+                                    let task_id = new_ids[i];
+                                    let dep_ids: Vec<Uuid> = spec
+                                        .depends_on
+                                        .iter()
+                                        .filter_map(|&idx| new_ids.get(idx).copied())
+                                        .filter(|&dep_id| dep_id != task_id)
+                                        .collect();
+                                    if let Some(task) =
+                                        app.task_index.get_mut(&mut app.tasks, task_id)
+                                    {
+                                        task.dependencies = dep_ids;
+                                    }
                                 }
-                                new_ids.push(task.id);
-                                app.tasks.push(task);
-                            }
-                            for (i, spec) in result.sub_task_specs.iter().enumerate() {
-                                if spec.depends_on.is_empty() {
-                                    continue;
+                                // Sync parent progress after subtask creation.
+                                if let Some(first_id) = new_ids.first().copied() {
+                                    sync_parent_progress(&mut app.tasks, first_id, now);
                                 }
-                                let task_id = new_ids[i];
-                                let dep_ids: Vec<Uuid> = spec
-                                    .depends_on
+                                let title = app
+                                    .tasks
                                     .iter()
-                                    .filter_map(|&idx| new_ids.get(idx).copied())
-                                    .filter(|&dep_id| dep_id != task_id)
-                                    .collect();
-                                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == task_id) {
-                                    task.dependencies = dep_ids;
-                                }
-                            }
-                            // Sync parent progress after subtask creation.
-                            if let Some(first_id) = new_ids.first().copied() {
-                                sync_parent_progress(&mut app.tasks, first_id, now);
+                                    .find(|t| t.id == id)
+                                    .map(|t| t.title.clone())
+                                    .unwrap_or_default();
+                                push_toast(
+                                    app,
+                                    format!(
+                                        "AI updated: {} (+{} sub-task{})",
+                                        title,
+                                        count,
+                                        if count == 1 { "" } else { "s" }
+                                    ),
+                                    false,
+                                    Toast::Success,
+                                );
+                            } else {
+                                let title = app
+                                    .tasks
+                                    .iter()
+                                    .find(|t| t.id == id)
+                                    .map(|t| t.title.clone())
+                                    .unwrap_or_default();
+                                push_toast(
+                                    app,
+                                    format!("AI updated: {}", title),
+                                    false,
+                                    Toast::Success,
+                                );
                             }
-                            let title = app
-                                .tasks
-                                .iter()
-                                .find(|t| t.id == id)
-                                .map(|t| t.title.clone())
-                                .unwrap_or_default();
-                            app.status = Some((
-                                format!(
-                                    "AI updated: {} (+{} sub-task{})",
-                                    title,
-                                    count,
-                                    if count == 1 { "" } else { "s" }
-                                ),
-                                Instant::now(),
-                                false,
-                            ));
-                        } else {
-                            let title = app
-                                .tasks
-                                .iter()
-                                .find(|t| t.id == id)
-                                .map(|t| t.title.clone())
-                                .unwrap_or_default();
-                            app.status =
-                                Some((format!("AI updated: {}", title), Instant::now(), false));
                         }
-                    } else {
-                        app.status = Some((
-                            format!("AI: task {} not found", prefix),
-                            Instant::now(),
-                            false,
-                        ));
+                        Err(e) => {
+                            push_toast(app, format!("AI: {e}"), false, Toast::Error);
+                        }
                     }
                 }
                 llm::TriageAction::Delete(prefix) => {
-                    let target = app.tasks.iter().position(|t| {
-                        let short = t.id.to_string().chars().take(8).collect::<String>();
-                        short.eq_ignore_ascii_case(prefix)
-                    });
-                    if let Some(pos) = target {
-                        let id = app.tasks[pos].id;
-                        let title = app.tasks[pos].title.clone();
-                        let child_ids: Vec<Uuid> = children_of(&app.tasks, id)
-                            .iter()
-                            .map(|&i| app.tasks[i].id)
-                            .collect();
-                        let all_deleted: Vec<Uuid> = std::iter::once(id).chain(child_ids).collect();
-                        app.tasks.retain(|t| !all_deleted.contains(&t.id));
-                        for task in &mut app.tasks {
-                            task.dependencies.retain(|dep| !all_deleted.contains(dep));
+                    match app.task_index.resolve(&app.tasks, prefix) {
+                        Ok(id) => {
+                            let title = app
+                                .task_index
+                                .get(&app.tasks, id)
+                                .map_or_else(String::new, |t| t.title.clone());
+                            delete_with_children(&mut app.tasks, id);
+                            push_toast(
+                                app,
+                                format!("AI deleted: {}", title),
+                                false,
+                                Toast::Success,
+                            );
+                            changed = true;
+                        }
+                        Err(e) => {
+                            push_toast(app, format!("AI: {e}"), false, Toast::Error);
                         }
-                        app.status =
-                            Some((format!("AI deleted: {}", title), Instant::now(), false));
-                        changed = true;
-                    } else {
-                        app.status = Some((
-                            format!("AI: task {} not found", prefix),
-                            Instant::now(),
-                            false,
-                        ));
                     }
                 }
                 llm::TriageAction::Decompose { target_id, specs } => {
@@ -3840,154 +6523,125 @@ fn poll_ai(app: &mut App) -> bool {
                     // Resolve parent: prefer AI-specified target_id, fall back to selected.
                     let parent_id = target_id
                         .as_ref()
-                        .and_then(|prefix| {
-                            app.tasks.iter().find_map(|t| {
-                                let short = t.id.to_string().chars().take(8).collect::<String>();
-                                if short.eq_ignore_ascii_case(prefix) {
-                                    Some(t.id)
-                                } else {
-                                    None
-                                }
-                            })
-                        })
+                        .and_then(|prefix| app.task_index.resolve(&app.tasks, prefix).ok())
                         .or(app.selected_task_id);
-                    // First pass: create all tasks and collect their Uuids.
-                    let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
-                    let default_bucket = specs
-                        .first()
-                        .and_then(|s| s.bucket.clone())
-                        .unwrap_or_else(|| default_bucket_name(&app.settings));
-                    for spec in specs.iter() {
-                        let bucket = spec
-                            .bucket
-                            .clone()
-                            .unwrap_or_else(|| default_bucket.clone());
-                        let mut task = Task::new(bucket, spec.title.clone(), now);
-                        task.parent_id = parent_id;
-                        task.description = spec.description.clone();
-                        if let Some(p) = spec.priority {
-                            task.priority = p;
-                        }
-                        if let Some(prog) = spec.progress {
-                            task.set_progress(prog, now);
-                        }
-                        if let Some(due) = spec.due_date {
-                            task.due_date = Some(due);
+                    if parent_id
+                        .is_some_and(|pid| nesting_depth(&app.tasks, pid) >= MAX_NESTING_DEPTH)
+                    {
+                        push_toast(
+                            app,
+                            format!("AI: {NESTING_LIMIT_MSG}"),
+                            false,
+                            Toast::Warning,
+                        );
+                    } else {
+                        // First pass: create all tasks and collect their Uuids.
+                        let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
+                        let default_bucket = specs
+                            .first()
+                            .and_then(|s| s.bucket.clone())
+                            .unwrap_or_else(|| default_bucket_name(&app.settings));
+                        for spec in specs.iter() {
+                            let bucket = spec
+                                .bucket
+                                .clone()
+                                .unwrap_or_else(|| default_bucket.clone());
+                            let mut task = Task::new(bucket, spec.title.clone(), now);
+                            task.parent_id = parent_id;
+                            task.description = spec.description.clone();
+                            if let Some(p) = spec.priority {
+                                task.priority = p;
+                            }
+                            if let Some(prog) = spec.progress {
+                                task.set_progress(prog, now);
+                            }
+                            if let Some(due) = spec.due_date {
+                                task.due_date = Some(due);
+                            }
+                            new_ids.push(task.id);
+                            app.tasks.push(task);
                         }
-                        new_ids.push(task.id);
-                        app.tasks.push(task);
-                    }
-                    // Second pass: resolve depends_on indices to Uuid dependencies.
-                    for (i, spec) in specs.iter().enumerate() {
-                        if spec.depends_on.is_empty() {
-                            continue;
+                        // Second pass: resolve depends_on indices to Uuid dependencies.
+                        for (i, spec) in specs.iter().enumerate() {
+                            if spec.depends_on.is_empty() {
+                                continue;
+                            }
+                            let task_id = new_ids[i];
+                            let deps: Vec<Uuid> = spec
+                                .depends_on
+                                .iter()
+                                .filter_map(|&idx| new_ids.get(idx).copied())
+                                .filter(|&dep_id| dep_id != task_id)
+                                .collect();
+                            if let Some(task) = app.task_index.get_mut(&mut app.tasks, task_id) {
+                                task.dependencies = deps;
+                            }
                         }
-                        let task_id = new_ids[i];
-                        let deps: Vec<Uuid> = spec
-                            .depends_on
-                            .iter()
-                            .filter_map(|&idx| new_ids.get(idx).copied())
-                            .filter(|&dep_id| dep_id != task_id)
-                            .collect();
-                        if let Some(task) = app.tasks.iter_mut().find(|t| t.id == task_id) {
-                            task.dependencies = deps;
+                        // Sync parent progress after decomposition.
+                        if let Some(first_id) = new_ids.first().copied() {
+                            sync_parent_progress(&mut app.tasks, first_id, now);
                         }
+                        push_toast(
+                            app,
+                            format!(
+                                "AI created {} sub-task{}",
+                                count,
+                                if count == 1 { "" } else { "s" }
+                            ),
+                            false,
+                            Toast::Success,
+                        );
+                        changed = true;
                     }
-                    // Sync parent progress after decomposition.
-                    if let Some(first_id) = new_ids.first().copied() {
-                        sync_parent_progress(&mut app.tasks, first_id, now);
-                    }
-                    app.status = Some((
-                        format!(
-                            "AI created {} sub-task{}",
-                            count,
-                            if count == 1 { "" } else { "s" }
-                        ),
-                        Instant::now(),
-                        false,
-                    ));
-                    changed = true;
                 }
                 llm::TriageAction::BulkUpdate {
                     targets,
+                    filter,
                     instruction,
                 } => {
-                    // Resolve target IDs: "all" means every task.
-                    let task_ids: Vec<Uuid> =
-                        if targets.len() == 1 && targets[0].eq_ignore_ascii_case("all") {
-                            app.tasks.iter().map(|t| t.id).collect()
-                        } else {
-                            targets
-                                .iter()
-                                .filter_map(|prefix| {
-                                    app.tasks.iter().find_map(|t| {
-                                        let short =
-                                            t.id.to_string().chars().take(8).collect::<String>();
-                                        if short.eq_ignore_ascii_case(prefix) {
-                                            Some(t.id)
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                })
-                                .collect()
-                        };
+                    let task_ids = resolve_bulk_targets(&app.tasks, targets, filter, true);
 
                     if task_ids.is_empty() {
-                        app.status = Some((
+                        push_toast(
+                            app,
                             "AI: no matching tasks found".to_string(),
-                            Instant::now(),
                             false,
-                        ));
-                    } else if let Some(ai) = &app.ai {
-                        let context = build_ai_context(&app.tasks);
-                        for &tid in &task_ids {
-                            if let Some(task) = app.tasks.iter().find(|t| t.id == tid) {
-                                let snapshot = format_task_snapshot(task);
-                                ai.enqueue(llm::AiJob {
-                                    task_id: tid,
-                                    title: task.title.clone(),
-                                    suggested_bucket: task.bucket.clone(),
-                                    context: context.clone(),
-                                    bucket_names: bucket_names(&app.settings),
-                                    lock_bucket: false,
-                                    lock_priority: false,
-                                    lock_due_date: false,
-                                    edit_instruction: Some(instruction.clone()),
-                                    task_snapshot: Some(snapshot),
-                                    triage_input: None,
-                                    triage_context: None,
-                                    chat_history: Vec::new(),
-                                    user_profile: app.settings.user_profile.clone(),
-                                    memory_facts: app.settings.memory_facts.clone(),
-                                });
-                            }
-                        }
-                        app.status = Some((
-                            format!(
-                                "AI updating {} task{}…",
-                                task_ids.len(),
-                                if task_ids.len() == 1 { "" } else { "s" }
-                            ),
-                            Instant::now(),
-                            true,
-                        ));
+                            Toast::Warning,
+                        );
+                    } else if task_ids.len() < BULK_CONFIRM_THRESHOLD {
+                        enqueue_bulk_edits(app, &task_ids, instruction);
+                    } else {
+                        push_toast(
+                            app,
+                            format!("AI wants to update {} tasks", task_ids.len()),
+                            false,
+                            Toast::Info,
+                        );
+                        app.pending_bulk = Some((task_ids, instruction.clone()));
                     }
                 }
                 llm::TriageAction::RememberFact(fact) => {
                     app.pending_memory = Some(fact.clone());
                 }
                 llm::TriageAction::Chat(text) => {
-                    app.status = Some((text.clone(), Instant::now(), true));
+                    push_toast(app, text.clone(), true, Toast::Info);
                 }
             }
             // Update chat history after triage.
             if !app.last_triage_input.is_empty() {
-                let summary = app
-                    .status
-                    .as_ref()
-                    .map(|(s, _, _)| s.clone())
-                    .unwrap_or_default();
+                // The newest toast is the outcome; earlier ones may still be
+                // queued. A persistent toast shows at once, so when one is
+                // showing nothing came after it.
+                let summary = match &app.status {
+                    Some((s, _, true, _)) => Some(s),
+                    status => app
+                        .toast_queue
+                        .back()
+                        .map(|(s, _, _)| s)
+                        .or(status.as_ref().map(|(s, _, _, _)| s)),
+                }
+                .cloned()
+                .unwrap_or_default();
                 app.chat_history.push(llm::ChatEntry {
                     user_input: std::mem::take(&mut app.last_triage_input),
                     ai_summary: summary,
@@ -3996,8 +6650,11 @@ fn poll_ai(app: &mut App) -> bool {
                     app.chat_history.drain(..app.chat_history.len() - 20);
                 }
             }
+            if let Some(warning) = bucket_warning {
+                push_toast(app, warning, false, Toast::Warning);
+            }
             if changed {
-                ensure_default_selection(app);
+                follow_selected_task(app);
                 persist(app);
             }
             continue;
@@ -4012,56 +6669,47 @@ fn poll_ai(app: &mut App) -> bool {
 
         let parent_id = result.task_id;
 
-        if let Some(task) = app.tasks.iter_mut().find(|t| t.id == parent_id) {
+        let mut updated_title = None;
+        if let Some(task) = app.task_index.get_mut(&mut app.tasks, parent_id) {
             let now = Utc::now();
-            if apply_update(task, &result.update, &deps, now) {
-                app.status = Some((format!("AI updated: {}", task.title), Instant::now(), false));
+            if apply_update(task, &result.update, &deps, &app.settings, now) {
+                updated_title = Some(task.title.clone());
                 changed = true;
             }
+            bucket_warning = unknown_bucket_warning(
+                &app.settings,
+                result
+                    .update
+                    .bucket
+                    .as_deref()
+                    .filter(|_| !app.settings.ai_lock_bucket),
+                &task.bucket,
+            );
+        }
+        if let Some(title) = updated_title {
+            push_toast(app, format!("AI updated: {title}"), false, Toast::Success);
         }
         if let Some(ref new_parent_prefix) = result.update.parent_id {
-            let task_id = result.task_id;
-            let old_parent = app
-                .tasks
-                .iter()
-                .find(|t| t.id == task_id)
-                .and_then(|t| t.parent_id);
-            if new_parent_prefix.eq_ignore_ascii_case("none") {
-                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == task_id) {
-                    task.parent_id = None;
-                    changed = true;
-                }
-            } else {
-                let new_parent_id = app.tasks.iter().find_map(|t| {
-                    let short = t.id.to_string().chars().take(8).collect::<String>();
-                    if short.eq_ignore_ascii_case(new_parent_prefix) {
-                        Some(t.id)
-                    } else {
-                        None
-                    }
-                });
-                if let Some(pid) = new_parent_id {
-                    if let Some(task) = app.tasks.iter_mut().find(|t| t.id == task_id) {
-                        task.parent_id = Some(pid);
-                        changed = true;
-                    }
+            match apply_parent_change(&mut app.tasks, parent_id, new_parent_prefix, Utc::now()) {
+                Ok(_) => changed = true,
+                Err(err) => {
+                    push_toast(app, format!("AI: {err}"), false, Toast::Error);
                 }
             }
-            if let Some(old_pid) = old_parent {
-                sync_parent_progress(&mut app.tasks, old_pid, Utc::now());
-            }
-            if !changed {
-                app.status = Some((
-                    format!("AI: parent task {} not found", new_parent_prefix),
-                    Instant::now(),
-                    false,
-                ));
-            }
         }
         sync_parent_progress(&mut app.tasks, parent_id, Utc::now());
 
         // Create actual sub-task records when the edit response includes subtasks.
-        if !result.sub_task_specs.is_empty() {
+        if !result.sub_task_specs.is_empty()
+            && nesting_depth(&app.tasks, parent_id) >= MAX_NESTING_DEPTH
+        {
+            push_toast(
+                app,
+                format!("AI: {NESTING_LIMIT_MSG}"),
+                false,
+                Toast::Warning,
+            );
+        } else if !result.sub_task_specs.is_empty() {
             let now = Utc::now();
             let parent_bucket = app
                 .tasks
@@ -4102,7 +6750,7 @@ fn poll_ai(app: &mut App) -> bool {
                     .filter_map(|&idx| new_ids.get(idx).copied())
                     .filter(|&dep_id| dep_id != task_id)
                     .collect();
-                if let Some(task) = app.tasks.iter_mut().find(|t| t.id == task_id) {
+                if let Some(task) = app.task_index.get_mut(&mut app.tasks, task_id) {
                     task.dependencies = dep_ids;
                 }
             }
@@ -4111,21 +6759,25 @@ fn poll_ai(app: &mut App) -> bool {
             if let Some(first_id) = new_ids.first().copied() {
                 sync_parent_progress(&mut app.tasks, first_id, now);
             }
-            app.status = Some((
+            push_toast(
+                app,
                 format!(
                     "AI created {} sub-task{}",
                     count,
                     if count == 1 { "" } else { "s" }
                 ),
-                Instant::now(),
                 false,
-            ));
+                Toast::Success,
+            );
             changed = true;
         }
+        if let Some(warning) = bucket_warning {
+            push_toast(app, warning, false, Toast::Warning);
+        }
     }
 
     if changed {
-        ensure_default_selection(app);
+        follow_selected_task(app);
         persist(app);
     }
 
@@ -4144,28 +6796,47 @@ fn poll_suggestions(app: &mut App) -> bool {
     for event in events {
         match event {
             EmailEvent::NewSuggestion(suggestion) => {
-                if !app
+                // The poller filters every unread email again each minute.
+                let seen = app
                     .suggestions
                     .iter()
                     .any(|s| s.email_id == suggestion.email_id)
-                {
+                    || app.suggestions_dismissed.contains(&suggestion.email_id)
+                    || app
+                        .tasks
+                        .iter()
+                        .any(|t| t.email_id.as_ref() == Some(&suggestion.email_id));
+                if !seen {
                     app.suggestions.push(suggestion);
+                    app.suggestions_new_since_poll += 1;
+                    persist_suggestions(app);
                     has_new = true;
                 }
             }
             EmailEvent::Archived(email_id) => {
                 app.suggestions.retain(|s| s.email_id != email_id);
-                let task_ids_to_remove: Vec<Uuid> = app
-                    .task_email_map
-                    .iter()
-                    .filter(|(_, e_id)| *e_id == &email_id)
-                    .map(|(t_id, _)| *t_id)
-                    .collect();
-                for task_id in &task_ids_to_remove {
-                    app.tasks.retain(|t| t.id != *task_id);
-                    app.task_email_map.remove(task_id);
-                }
-                if !task_ids_to_remove.is_empty() {
+                app.suggestions_dismissed.retain(|id| *id != email_id);
+                persist_suggestions(app);
+                // The task may have grown sub-issues and notes since, so it
+                // only loses the link rather than being deleted.
+                let now = Utc::now();
+                let mut unlinked = Vec::new();
+                for task in app
+                    .tasks
+                    .iter_mut()
+                    .filter(|t| t.email_id.as_deref() == Some(email_id.as_str()))
+                {
+                    task.email_id = None;
+                    task.email_subject = None;
+                    task.updated_at = now;
+                    unlinked.push(task.title.clone());
+                }
+                if let Some(title) = unlinked.first() {
+                    let msg = match unlinked.len() {
+                        1 => format!("Source email archived: {title}"),
+                        n => format!("Source email archived: {title} and {} more", n - 1),
+                    };
+                    push_toast(app, msg, false, Toast::Info);
                     persist(app);
                 }
                 has_new = true;
@@ -4174,12 +6845,21 @@ fn poll_suggestions(app: &mut App) -> bool {
                 checked_at,
                 unread_count,
                 actionable_count,
-                ok,
+                error,
+                failed,
             } => {
                 app.suggestions_last_polled_at = Some(checked_at);
                 app.suggestions_last_poll_unread = unread_count;
                 app.suggestions_last_poll_actionable = actionable_count;
-                app.suggestions_last_poll_ok = ok;
+                // Only once per error: it stays on the status line until fixed.
+                if let Some(err) = error.as_ref().filter(|e| google::needs_reconnect(e)) {
+                    if app.suggestions_last_poll_error.as_ref() != Some(err) {
+                        push_toast(app, err.clone(), false, Toast::Error);
+                    }
+                }
+                app.suggestions_last_poll_error = error;
+                app.suggestions_last_poll_new = std::mem::take(&mut app.suggestions_new_since_poll);
+                app.suggestions_last_poll_failed = failed;
                 has_new = true;
             }
         }
@@ -4210,22 +6890,26 @@ fn poll_google_auth(app: &mut App) -> bool {
         match result {
             Ok(_token) => {
                 app.google_connected = true;
-                app.status = Some((
+                push_toast(
+                    app,
                     "Google account connected!".to_string(),
-                    Instant::now(),
                     false,
-                ));
+                    Toast::Success,
+                );
                 if let Some(ref dir) = app.data_dir {
                     app.calendar_loading = true;
                     app.calendar_rx = Some(spawn_calendar_fetch(dir.clone()));
                     if app.suggestions_rx.is_none() {
-                        app.suggestions_rx =
-                            Some(spawn_email_poller(dir.clone(), app.settings.clone()));
+                        app.suggestions_rx = Some(spawn_email_poller(
+                            dir.clone(),
+                            app.settings.clone(),
+                            tracked_suggestion_emails(app),
+                        ));
                     }
                 }
             }
             Err(e) => {
-                app.status = Some((format!("Google auth failed: {e}"), Instant::now(), true));
+                push_toast(app, format!("Google auth failed: {e}"), true, Toast::Error);
             }
         }
         return true;
@@ -4233,11 +6917,30 @@ fn poll_google_auth(app: &mut App) -> bool {
     false
 }
 
-/// Apply a TaskUpdate to a task, returning true if anything changed.
+fn poll_connection_test(app: &mut App) -> bool {
+    let result = match &app.connection_test_rx {
+        Some(rx) => rx.try_recv().ok(),
+        None => None,
+    };
+    let Some(result) = result else {
+        return false;
+    };
+    app.connection_test_rx = None;
+    end_progress_toast(app);
+    match result {
+        Ok(msg) => push_toast(app, format!("Connection OK: {msg}"), false, Toast::Success),
+        Err(e) => push_toast(app, format!("Connection failed: {e}"), false, Toast::Error),
+    }
+    true
+}
+
+/// Apply an AI update to an existing task, skipping fields the user locked
+/// in settings. Returns true if anything changed.
 fn apply_update(
     task: &mut Task,
     update: &llm::TaskUpdate,
     deps: &[Uuid],
+    settings: &AiSettings,
     now: chrono::DateTime<Utc>,
 ) -> bool {
     let mut task_changed = false;
@@ -4251,14 +6954,20 @@ fn apply_update(
         }
     }
 
-    if let Some(bucket) = &update.bucket {
-        if task.bucket != *bucket {
-            task.bucket = bucket.clone();
+    if let Some(bucket) = update
+        .bucket
+        .as_deref()
+        .filter(|_| !settings.ai_lock_bucket)
+        .and_then(|b| known_bucket(settings, b))
+    {
+        if task.bucket != bucket {
+            task.bucket = bucket;
             task_changed = true;
         }
     }
 
-    if let Some(desc) = &update.description {
+    // A blank description from the model means "no change", never "clear it".
+    if let Some(desc) = update.description.as_ref().filter(|d| !d.trim().is_empty()) {
         if is_edit || task.description.trim().is_empty() {
             task.description = desc.clone();
             task_changed = true;
@@ -4272,16 +6981,16 @@ fn apply_update(
         }
     }
 
-    if let Some(priority) = update.priority {
+    if let Some(priority) = update.priority.filter(|_| !settings.ai_lock_priority) {
         if task.priority != priority {
             task.priority = priority;
             task_changed = true;
         }
     }
 
-    if let Some(due_date) = update.due_date {
+    if let Some(due_date) = update.due_date.filter(|_| !settings.ai_lock_due_date) {
         if task.due_date != due_date {
-            task.due_date = due_date;
+            task.set_due_date(due_date);
             task_changed = true;
         }
     }
@@ -4291,6 +7000,11 @@ fn apply_update(
         task_changed = true;
     }
 
+    if update.assignee.is_some() && task.assignee != update.assignee {
+        task.assignee = update.assignee.clone();
+        task_changed = true;
+    }
+
     if task_changed {
         task.updated_at = now;
     }
@@ -4298,6 +7012,43 @@ fn apply_update(
     task_changed
 }
 
+/// Apply an offline `@id` edit, returning a status line describing it.
+fn apply_quick_edit(
+    tasks: &mut [Task],
+    id: Uuid,
+    quick: &ai::QuickEdit,
+    settings: &AiSettings,
+    now: chrono::DateTime<Utc>,
+) -> Option<String> {
+    let task = tasks.iter_mut().find(|t| t.id == id)?;
+    let mut changes: Vec<String> = Vec::new();
+    if let Some(progress) = quick.progress {
+        task.set_progress(progress, now);
+        changes.push(settings.stage_label(progress).to_string());
+    }
+    if let Some(priority) = quick.priority {
+        task.priority = priority;
+        changes.push(priority.title().to_string());
+    }
+    if let Some(bucket) = &quick.bucket {
+        task.bucket = bucket.clone();
+        changes.push(format!("in {bucket}"));
+    }
+    if let Some(due) = quick.due_date {
+        task.set_due_date(due);
+        changes.push(match due {
+            Some(date) => format!("due {}", settings.format_date(date)),
+            None => "no due date".to_string(),
+        });
+    }
+    task.updated_at = now;
+    let summary = format!("{}: {}", task.title, changes.join(", "));
+    if quick.progress.is_some() {
+        sync_parent_progress(tasks, id, now);
+    }
+    Some(summary)
+}
+
 /// After a child task's progress changes, recompute its parent's progress.
 /// Returns true if the parent was updated.
 fn sync_parent_progress(tasks: &mut [Task], child_id: Uuid, now: chrono::DateTime<Utc>) -> bool {
@@ -4309,22 +7060,7 @@ fn sync_parent_progress(tasks: &mut [Task], child_id: Uuid, now: chrono::DateTim
         Some(pid) => pid,
         None => return false,
     };
-    let child_progresses: Vec<Progress> = tasks
-        .iter()
-        .filter(|t| t.parent_id == Some(parent_id))
-        .map(|t| t.progress)
-        .collect();
-    let new_progress = match compute_parent_progress(&child_progresses) {
-        Some(p) => p,
-        None => return false,
-    };
-    if let Some(parent) = tasks.iter_mut().find(|t| t.id == parent_id) {
-        if parent.progress != new_progress {
-            parent.set_progress(new_progress, now);
-            return true;
-        }
-    }
-    false
+    sync_progress_from_children(tasks, parent_id, now)
 }
 
 fn auto_archive_tasks(tasks: &mut [Task]) -> bool {
@@ -4341,9 +7077,31 @@ fn auto_archive_tasks(tasks: &mut [Task]) -> bool {
     changed
 }
 
-fn build_ai_context(tasks: &[Task]) -> Vec<llm::ContextTask> {
-    let mut refs: Vec<&Task> = tasks.iter().collect();
-    refs.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+/// Stores each task's escalated priority. Only used when `auto_escalate` is on.
+fn auto_escalate_tasks(tasks: &mut [Task], today: chrono::NaiveDate) -> bool {
+    let now = Utc::now();
+    let mut changed = false;
+    for task in tasks.iter_mut() {
+        let levels = escalation_levels(task, today);
+        if levels > task.escalated_levels {
+            task.priority = effective_priority(task, today);
+            task.escalated_levels = levels;
+            task.updated_at = now;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Which tasks the AI gets to see. With `ai_context_visible_only` that's
+/// only the progress stages shown on the board.
+fn ai_context_filter(settings: &AiSettings) -> impl Fn(&Task) -> bool + '_ {
+    move |task| !settings.ai_context_visible_only || settings.is_progress_visible(task.progress)
+}
+
+fn build_ai_context(tasks: &[Task], include: impl Fn(&Task) -> bool) -> Arc<[llm::ContextTask]> {
+    let mut refs: Vec<&Task> = tasks.iter().filter(|t| include(t)).collect();
+    refs.sort_by_key(|t| std::cmp::Reverse(t.updated_at));
 
     refs.into_iter()
         .take(40)
@@ -4355,187 +7113,314 @@ fn build_ai_context(tasks: &[Task]) -> Vec<llm::ContextTask> {
         .collect()
 }
 
+/// Max task lines (parents and sub-tasks) included in the triage prompt.
+const TRIAGE_CONTEXT_LIMIT: usize = 60;
+/// Dependencies listed per triage line before the rest are summarised as "+N".
+const TRIAGE_DEPS_SHOWN: usize = 3;
+
 /// Build rich context for triage: full task details so the AI can match intent.
 /// Shows parent tasks with their sub-tasks indented to expose the full hierarchy.
-fn build_triage_context(tasks: &[Task]) -> String {
+fn build_triage_context(tasks: &[Task], include: impl Fn(&Task) -> bool) -> String {
     // Collect parent (root) tasks sorted by recency.
-    let mut parents: Vec<&Task> = tasks.iter().filter(|t| t.parent_id.is_none()).collect();
-    parents.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    let mut parents: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| t.parent_id.is_none() && include(t))
+        .collect();
+    parents.sort_by_key(|t| std::cmp::Reverse(t.updated_at));
 
     let mut out = String::new();
     let mut count = 0usize;
-    let limit = 60;
-
     for t in &parents {
-        if count >= limit {
+        if count >= TRIAGE_CONTEXT_LIMIT {
             break;
         }
-        let short = t.id.to_string().chars().take(8).collect::<String>();
-        let desc = if t.description.trim().is_empty() {
-            ""
-        } else {
-            t.description.trim()
-        };
-        out.push_str(&format!(
-            "- {} [{}] {} | {} | {} | {}\n",
-            short,
-            t.bucket,
-            t.title,
-            t.progress.title(),
-            t.priority.title(),
-            if desc.is_empty() {
-                "no description"
-            } else {
-                desc
-            }
-        ));
-        count += 1;
-
-        // Show children indented under their parent.
-        let children = children_of(tasks, t.id);
-        for &idx in &children {
-            if count >= limit {
-                break;
-            }
-            let child = &tasks[idx];
-            let child_short = child.id.to_string().chars().take(8).collect::<String>();
-            let child_desc = if child.description.trim().is_empty() {
-                ""
-            } else {
-                child.description.trim()
-            };
-            out.push_str(&format!(
-                "  ↳ {} [{}] {} | {} | {} | {}\n",
-                child_short,
-                child.bucket,
-                child.title,
-                child.progress.title(),
-                child.priority.title(),
-                if child_desc.is_empty() {
-                    "no description"
-                } else {
-                    child_desc
-                }
-            ));
-            count += 1;
-        }
+        push_triage_lines(&mut out, tasks, t, 0, &include, &mut count);
     }
     out
 }
 
-/// Estimate the AI context size in tokens (rough approximation: ~4 chars per token).
-fn estimate_context_tokens(tasks: &[Task], chat_history: &[llm::ChatEntry]) -> usize {
-    let triage_ctx = build_triage_context(tasks);
-    let mut total_chars = triage_ctx.len();
-
-    for entry in chat_history.iter() {
-        total_chars += entry.user_input.len() + entry.ai_summary.len();
-    }
-
-    // Rough estimate: ~4 chars per token for English text.
-    total_chars / 4
-}
-
-fn format_task_snapshot(task: &Task) -> String {
-    let deps = if task.dependencies.is_empty() {
-        "none".to_string()
+/// One triage line for `task`, then its sub-tasks one level deeper. The line
+/// limit also bounds the recursion if parent links ever form a loop.
+fn push_triage_lines(
+    out: &mut String,
+    tasks: &[Task],
+    task: &Task,
+    depth: usize,
+    include: &impl Fn(&Task) -> bool,
+    count: &mut usize,
+) {
+    let short = task.id.to_string().chars().take(8).collect::<String>();
+    let marker = if depth == 0 {
+        "- ".to_string()
     } else {
-        task.dependencies
-            .iter()
-            .map(|id| id.to_string()[..8].to_string())
-            .collect::<Vec<_>>()
-            .join(", ")
+        format!("{}↳ ", "  ".repeat(depth))
     };
-    let due = task
-        .due_date
-        .map(|d| d.format("%Y-%m-%d").to_string())
-        .unwrap_or_else(|| "none".to_string());
-    format!(
-        "Title: {}\nBucket: {}\nDescription: {}\nProgress: {}\nPriority: {}\nDue: {}\nDependencies: {}",
-        task.title,
+    let desc = task.description.trim();
+    out.push_str(&format!(
+        "{}{} [{}] {}{} | {} | {}{} | {}\n",
+        marker,
+        short,
         task.bucket,
-        if task.description.trim().is_empty() { "none" } else { task.description.trim() },
+        task.title,
+        assignee_suffix(task),
         task.progress.title(),
         task.priority.title(),
-        due,
-        deps
-    )
-}
+        deps_suffix(task),
+        if desc.is_empty() {
+            "no description"
+        } else {
+            desc
+        }
+    ));
+    *count += 1;
 
-/// Parse `@<id_prefix> <instruction>` – if the first token is a 4-8 hex prefix matching a task,
-/// return that task's id + the remaining text; otherwise fall back to `fallback_id` + full text.
-fn resolve_at_mention(
-    tasks: &[Task],
-    text: &str,
-    fallback_id: Option<Uuid>,
-) -> (Option<Uuid>, String) {
-    let trimmed = text.trim();
-    let first_space = trimmed.find(' ');
-    if let Some(pos) = first_space {
-        let token = &trimmed[..pos];
-        let rest = trimmed[pos..].trim().to_string();
-        let lower = token.to_ascii_lowercase();
-        if (4..=8).contains(&lower.len()) && lower.chars().all(|c| c.is_ascii_hexdigit()) {
-            if let Some(task) = tasks.iter().find(|t| {
-                let short =
-                    t.id.to_string()
-                        .chars()
-                        .take(lower.len())
-                        .collect::<String>();
-                short.to_ascii_lowercase() == lower
-            }) {
-                return (Some(task.id), rest);
-            }
+    // Show children indented under their parent.
+    for idx in children_of(tasks, task.id) {
+        if *count >= TRIAGE_CONTEXT_LIMIT {
+            break;
+        }
+        let child = &tasks[idx];
+        if include(child) {
+            push_triage_lines(out, tasks, child, depth + 1, include, count);
         }
     }
-    (fallback_id, trimmed.to_string())
 }
 
-/// Annotate an instruction with the target task context so triage AI knows which task to act on.
-fn annotate_mention(tasks: &[Task], target_id: Option<Uuid>, instruction: &str) -> String {
-    if let Some(tid) = target_id {
-        if let Some(task) = tasks.iter().find(|t| t.id == tid) {
-            let short = task.id.to_string().chars().take(8).collect::<String>();
-            return format!(
-                "[target task: {} \"{}\" in {}] {}",
-                short, task.title, task.bucket, instruction
-            );
-        }
+/// " | deps: a1b2c3d4, e5f6a7b8 +2", or nothing without dependencies.
+fn deps_suffix(task: &Task) -> String {
+    if task.dependencies.is_empty() {
+        return String::new();
     }
-    instruction.to_string()
+    let shown: Vec<String> = task
+        .dependencies
+        .iter()
+        .take(TRIAGE_DEPS_SHOWN)
+        .map(|id| id.to_string().chars().take(8).collect())
+        .collect();
+    let more = task.dependencies.len().saturating_sub(TRIAGE_DEPS_SHOWN);
+    let more = if more > 0 {
+        format!(" +{more}")
+    } else {
+        String::new()
+    };
+    format!(" | deps: {}{more}", shown.join(", "))
 }
 
-fn resolve_dependency_prefixes(tasks: &[Task], self_id: Uuid, prefixes: &[String]) -> Vec<Uuid> {
-    let mut out = Vec::new();
-    for prefix in prefixes.iter() {
-        let key = prefix
-            .trim()
-            .chars()
-            .take(8)
-            .collect::<String>()
-            .to_ascii_lowercase();
-        if key.is_empty() {
-            continue;
-        }
-
-        if let Some(id) = tasks.iter().find_map(|t| {
-            let short = t.id.to_string().chars().take(8).collect::<String>();
-            if short.to_ascii_lowercase() == key {
-                Some(t.id)
-            } else {
-                None
-            }
-        }) {
-            if id != self_id && !out.contains(&id) {
-                out.push(id);
-            }
-        }
-    }
-    out
+fn assignee_suffix(task: &Task) -> String {
+    task.assignee
+        .as_deref()
+        .map(|a| format!(" @{a}"))
+        .unwrap_or_default()
 }
 
-fn get_active_task_id(app: &App) -> Option<Uuid> {
-    match app.tab {
+/// Sizes of the parts of a triage prompt that vary, in chars:
+/// (instructions, task list, chat history).
+fn context_breakdown(
+    tasks: &[Task],
+    settings: &AiSettings,
+    chat_history: &[llm::ChatEntry],
+) -> (usize, usize, usize) {
+    let instruction_chars = llm::bounded_system_prompt(&settings.system_prompt)
+        .chars()
+        .count();
+    let triage_chars = build_triage_context(tasks, ai_context_filter(settings)).len();
+    let chat_chars = chat_history
+        .iter()
+        .map(|entry| entry.user_input.len() + entry.ai_summary.len())
+        .sum();
+    (instruction_chars, triage_chars, chat_chars)
+}
+
+/// Estimate the AI context size in tokens (rough approximation: ~4 chars per token).
+fn estimate_context_tokens(
+    tasks: &[Task],
+    settings: &AiSettings,
+    chat_history: &[llm::ChatEntry],
+) -> usize {
+    let (instruction_chars, triage_chars, chat_chars) =
+        context_breakdown(tasks, settings, chat_history);
+    // Rough estimate: ~4 chars per token for English text.
+    (instruction_chars + triage_chars + chat_chars) / 4
+}
+
+/// Cheap stand-in for everything `context_breakdown` reads. Descriptions
+/// aren't hashed; edits to them are caught through `updated_at`.
+fn context_fingerprint(
+    tasks: &[Task],
+    settings: &AiSettings,
+    chat_history: &[llm::ChatEntry],
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tasks.len().hash(&mut hasher);
+    for task in tasks {
+        task.id.hash(&mut hasher);
+        task.updated_at.hash(&mut hasher);
+        task.title.hash(&mut hasher);
+        task.bucket.hash(&mut hasher);
+        task.assignee.hash(&mut hasher);
+        task.progress.hash(&mut hasher);
+        task.priority.hash(&mut hasher);
+        task.parent_id.hash(&mut hasher);
+        task.dependencies.hash(&mut hasher);
+    }
+    settings.ai_context_visible_only.hash(&mut hasher);
+    for progress in Progress::ALL {
+        settings.is_progress_visible(progress).hash(&mut hasher);
+    }
+    settings.system_prompt.hash(&mut hasher);
+    chat_history.len().hash(&mut hasher);
+    for entry in chat_history {
+        (entry.user_input.len(), entry.ai_summary.len()).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// `estimate_context_tokens`, recomputed only when its inputs change, since
+/// the help line asks for it on every frame.
+fn cached_context_tokens(app: &mut App) -> usize {
+    let key = context_fingerprint(&app.tasks, &app.settings, &app.chat_history);
+    match app.context_estimate {
+        Some((cached_key, tokens)) if cached_key == key => tokens,
+        _ => {
+            let tokens = estimate_context_tokens(&app.tasks, &app.settings, &app.chat_history);
+            app.context_estimate = Some((key, tokens));
+            tokens
+        }
+    }
+}
+
+/// Resolve a bulk update to concrete task ids. Explicit id prefixes are
+/// narrowed by the filter; "all" or no ids selects every matching task
+/// (only top-level ones unless `include_subtasks`).
+fn resolve_bulk_targets(
+    tasks: &[Task],
+    targets: &[String],
+    filter: &llm::BulkFilter,
+    include_subtasks: bool,
+) -> Vec<Uuid> {
+    let all = targets.is_empty() || (targets.len() == 1 && targets[0].eq_ignore_ascii_case("all"));
+    if all {
+        return tasks
+            .iter()
+            .filter(|t| include_subtasks || t.parent_id.is_none())
+            .filter(|t| filter.matches(t))
+            .map(|t| t.id)
+            .collect();
+    }
+    let mut index = TaskIndex::default();
+    targets
+        .iter()
+        .filter_map(|prefix| {
+            let id = index.resolve(tasks, prefix).ok()?;
+            index.get(tasks, id)
+        })
+        .filter(|t| filter.matches(t))
+        .map(|t| t.id)
+        .collect()
+}
+
+/// Bulk updates touching at least this many tasks wait for confirmation,
+/// since each target costs a separate AI request.
+const BULK_CONFIRM_THRESHOLD: usize = 5;
+
+fn enqueue_bulk_edits(app: &mut App, task_ids: &[Uuid], instruction: &str) {
+    let Some(ai) = &app.ai else {
+        return;
+    };
+    let context = build_ai_context(&app.tasks, ai_context_filter(&app.settings));
+    for &tid in task_ids {
+        if let Some(task) = app.task_index.get(&app.tasks, tid) {
+            let snapshot = format_task_snapshot(task);
+            ai.enqueue(llm::AiJob {
+                task_id: tid,
+                title: task.title.clone(),
+                suggested_bucket: task.bucket.clone(),
+                context: Arc::clone(&context),
+                buckets: app.settings.buckets.clone(),
+                lock_bucket: app.settings.ai_lock_bucket,
+                lock_priority: app.settings.ai_lock_priority,
+                lock_due_date: app.settings.ai_lock_due_date,
+                edit_instruction: Some(instruction.to_string()),
+                task_snapshot: Some(snapshot),
+                triage_input: None,
+                triage_context: None,
+                chat_history: Vec::new(),
+                user_profile: app.settings.user_profile.clone(),
+                memory_facts: app.settings.memory_facts.clone(),
+                assignee: None,
+            });
+        }
+    }
+    push_toast(
+        app,
+        format!(
+            "AI updating {} task{}…",
+            task_ids.len(),
+            if task_ids.len() == 1 { "" } else { "s" }
+        ),
+        true,
+        Toast::Info,
+    );
+}
+
+/// Parse `@<id_prefix> <instruction>` – if the first token is an id prefix (4 hex digits up to
+/// the full id) matching a task, return that task's id + the remaining text; otherwise fall
+/// back to `fallback_id` + full text. A prefix matching several tasks is an error.
+fn resolve_at_mention(
+    tasks: &[Task],
+    index: &mut TaskIndex,
+    text: &str,
+    fallback_id: Option<Uuid>,
+) -> Result<(Option<Uuid>, String), String> {
+    let trimmed = text.trim();
+    if let Some((token, rest)) = trimmed.split_once(' ') {
+        match index.matching(tasks, token).as_slice() {
+            [] => {}
+            [id] => return Ok((Some(*id), rest.trim().to_string())),
+            many => {
+                return Err(format!(
+                    "ambiguous id @{token} ({} tasks), use more characters",
+                    many.len()
+                ))
+            }
+        }
+    }
+    Ok((fallback_id, trimmed.to_string()))
+}
+
+/// Annotate an instruction with the target task context so triage AI knows which task to act on.
+fn annotate_mention(tasks: &[Task], target_id: Option<Uuid>, instruction: &str) -> String {
+    if let Some(tid) = target_id {
+        if let Some(task) = tasks.iter().find(|t| t.id == tid) {
+            let short = task.id.to_string().chars().take(8).collect::<String>();
+            return format!(
+                "[target task: {} \"{}\" in {}] {}",
+                short, task.title, task.bucket, instruction
+            );
+        }
+    }
+    instruction.to_string()
+}
+
+fn resolve_dependency_prefixes(tasks: &[Task], self_id: Uuid, prefixes: &[String]) -> Vec<Uuid> {
+    let mut out = Vec::new();
+    let mut index = TaskIndex::default();
+    for prefix in prefixes.iter() {
+        let key = prefix.trim().chars().take(8).collect::<String>();
+        if let Ok(id) = index
+            .resolve(tasks, prefix)
+            .or_else(|_| index.resolve(tasks, &key))
+        {
+            if id != self_id && !out.contains(&id) {
+                out.push(id);
+            }
+        }
+    }
+    out
+}
+
+fn get_active_task_id(app: &App) -> Option<Uuid> {
+    match app.tab {
         Tab::Default => app.selected_task_id,
         Tab::Timeline => {
             let indices = sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed);
@@ -4568,7 +7453,14 @@ fn ensure_default_selection(app: &mut App) {
         .get(app.selected_bucket)
         .map(|b| b.name.as_str())
         .unwrap_or("");
-    let bucket_tasks = bucket_task_indices(&app.tasks, bucket_name, &app.settings);
+    let bucket_tasks = bucket_task_indices(
+        &app.tasks,
+        bucket_name,
+        &app.settings,
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
     if bucket_tasks.is_empty() {
         app.selected_task_id = None;
         return;
@@ -4585,7 +7477,42 @@ fn ensure_default_selection(app: &mut App) {
         app.selected_task_id = Some(app.tasks[bucket_tasks[0]].id);
     }
 
-    clamp_bucket_scroll(app, bucket_tasks.len());
+    clamp_bucket_scroll(app, &bucket_tasks);
+}
+
+/// Like `ensure_default_selection`, for changes the user didn't make: if the
+/// selected task moved to another bucket or under a parent, select it (or
+/// the parent) there instead of jumping to the top of the current bucket.
+fn follow_selected_task(app: &mut App) {
+    let Some(task) = app
+        .selected_task_id
+        .and_then(|id| app.task_index.get(&app.tasks, id))
+    else {
+        ensure_default_selection(app);
+        return;
+    };
+    let card = task
+        .parent_id
+        .and_then(|pid| app.task_index.get(&app.tasks, pid))
+        .unwrap_or(task);
+    let visible = app.settings.is_progress_visible(card.progress)
+        && passes_board_filter(
+            card,
+            app.assignee_filter.as_deref(),
+            app.pinned_only,
+            &app.board_query,
+        );
+    if let Some(bucket_idx) = app
+        .settings
+        .buckets
+        .iter()
+        .position(|b| b.name == card.bucket)
+        .filter(|_| visible)
+    {
+        app.selected_task_id = Some(card.id);
+        app.selected_bucket = bucket_idx;
+    }
+    ensure_default_selection(app);
 }
 
 fn move_selection(app: &mut App, delta: i32) {
@@ -4595,7 +7522,14 @@ fn move_selection(app: &mut App, delta: i32) {
         .get(app.selected_bucket)
         .map(|b| b.name.as_str())
         .unwrap_or("");
-    let bucket_tasks = bucket_task_indices(&app.tasks, bucket_name, &app.settings);
+    let bucket_tasks = bucket_task_indices(
+        &app.tasks,
+        bucket_name,
+        &app.settings,
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
     if bucket_tasks.is_empty() {
         app.selected_task_id = None;
         return;
@@ -4617,12 +7551,11 @@ fn move_selection(app: &mut App, delta: i32) {
     let next_idx = bucket_tasks[next as usize];
     app.selected_task_id = Some(app.tasks[next_idx].id);
 
-    clamp_bucket_scroll(app, bucket_tasks.len());
+    clamp_bucket_scroll(app, &bucket_tasks);
 }
 
-fn clamp_bucket_scroll(app: &mut App, total: usize) {
-    let (cols, rows) = terminal::size().unwrap_or((80, 24));
-    let _ = cols;
+fn clamp_bucket_scroll(app: &mut App, bucket_tasks: &[usize]) {
+    let (_, rows) = terminal::size().unwrap_or((80, 24));
 
     // Keep in sync with `render_default_tab`.
     let y_body_top = 2u16;
@@ -4631,41 +7564,40 @@ fn clamp_bucket_scroll(app: &mut App, total: usize) {
     let y_cards_start = y_body_top + 3;
     let cards_area_height = y_input.saturating_sub(y_cards_start) as usize;
 
-    let visible = visible_cards(cards_area_height);
-    let visible = visible.max(1);
-
-    let bucket_name = app
-        .settings
-        .buckets
-        .get(app.selected_bucket)
-        .map(|b| b.name.as_str())
-        .unwrap_or("");
+    let heights: Vec<usize> = bucket_tasks
+        .iter()
+        .map(|&idx| bucket_card_height(&app.tasks, app.tasks[idx].id, &app.settings))
+        .collect();
     let selected_index = app
         .selected_task_id
-        .and_then(|id| {
-            bucket_task_indices(&app.tasks, bucket_name, &app.settings)
-                .iter()
-                .position(|&idx| app.tasks[idx].id == id)
-        })
+        .and_then(|id| bucket_tasks.iter().position(|&idx| app.tasks[idx].id == id))
         .unwrap_or(0);
 
-    if app.selected_bucket >= app.bucket_scrolls.len() {
+    let Some(scroll) = app.bucket_scrolls.get_mut(app.selected_bucket) else {
         return;
-    }
-    let scroll = &mut app.bucket_scrolls[app.selected_bucket];
+    };
 
-    if total <= visible {
+    if heights.iter().sum::<usize>() <= cards_area_height {
         *scroll = 0;
         return;
     }
 
     if selected_index < *scroll {
         *scroll = selected_index;
-    } else if selected_index >= *scroll + visible {
-        *scroll = selected_index.saturating_sub(visible.saturating_sub(1));
+    }
+    while *scroll < selected_index
+        && heights[*scroll..=selected_index].iter().sum::<usize>() > cards_area_height
+    {
+        *scroll += 1;
     }
 
-    let max_scroll = total.saturating_sub(visible);
+    // Don't scroll past the point where the last card sits at the bottom.
+    let mut max_scroll = heights.len() - 1;
+    let mut used = heights[max_scroll];
+    while max_scroll > 0 && used + heights[max_scroll - 1] <= cards_area_height {
+        max_scroll -= 1;
+        used += heights[max_scroll];
+    }
     *scroll = (*scroll).min(max_scroll);
 }
 
@@ -4678,17 +7610,141 @@ fn visible_children_of(tasks: &[Task], parent_id: Uuid, settings: &AiSettings) -
     indices.sort_by(|&a, &b| {
         let ta = &tasks[a];
         let tb = &tasks[b];
-        tb.progress
-            .stage_index()
-            .cmp(&ta.progress.stage_index())
-            .then_with(|| tb.priority.cmp(&ta.priority))
-            .then_with(|| tb.created_at.cmp(&ta.created_at))
+        sort_mode_cmp(settings.sort_mode, ta, tb).unwrap_or_else(|| {
+            let manual = |t: &Task| t.sort_order.unwrap_or(u32::MAX);
+            manual(ta)
+                .cmp(&manual(tb))
+                .then_with(|| tb.progress.stage_index().cmp(&ta.progress.stage_index()))
+                .then_with(|| tb.priority.cmp(&ta.priority))
+                .then_with(|| tb.created_at.cmp(&ta.created_at))
+        })
     });
 
     indices
 }
 
-fn bucket_task_indices(tasks: &[Task], bucket_name: &str, settings: &AiSettings) -> Vec<usize> {
+/// Order for the non-default sort modes; `None` keeps the view's own default.
+/// Due-date order puts undated tasks last.
+fn sort_mode_cmp(mode: SortMode, a: &Task, b: &Task) -> Option<std::cmp::Ordering> {
+    let newest_first = || b.created_at.cmp(&a.created_at);
+    match mode {
+        SortMode::Default => None,
+        SortMode::DueDate => Some(
+            match (a.due_date, b.due_date) {
+                (Some(da), Some(db)) => da.cmp(&db),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+            .then_with(|| b.priority.cmp(&a.priority))
+            .then_with(newest_first),
+        ),
+        SortMode::Priority => Some(b.priority.cmp(&a.priority).then_with(newest_first)),
+        SortMode::Alphabetical => Some(
+            a.title
+                .to_lowercase()
+                .cmp(&b.title.to_lowercase())
+                .then_with(newest_first),
+        ),
+    }
+}
+
+fn toggle_pin(app: &mut App, id: Uuid) {
+    let Some(task) = app.task_index.get_mut(&mut app.tasks, id) else {
+        return;
+    };
+    task.pinned = !task.pinned;
+    let msg = if task.pinned {
+        format!("Pinned: {}", task.title)
+    } else {
+        format!("Unpinned: {}", task.title)
+    };
+    push_toast(app, msg, false, Toast::Success);
+    persist(app);
+    // Unpinning under `/pinned` hides the card.
+    ensure_default_selection(app);
+    ensure_kanban_selection(app);
+}
+
+/// Drawn in front of the title of a task with a color.
+const CARD_SWATCH: &str = "■ ";
+
+/// The terminal color for a task's `color`. Unknown names, say from a hand
+/// edited file, draw no swatch.
+fn task_color(task: &Task) -> Option<Color> {
+    let (r, g, b) = match task.color.as_deref()?.to_ascii_lowercase().as_str() {
+        "red" => (230, 80, 80),
+        "orange" => (240, 150, 60),
+        "yellow" => (230, 200, 60),
+        "green" => (90, 190, 100),
+        "teal" => (60, 180, 170),
+        "blue" => (80, 140, 230),
+        "purple" => (160, 110, 220),
+        "pink" => (230, 120, 180),
+        _ => return None,
+    };
+    Some(Color::Rgb { r, g, b })
+}
+
+/// `card_title` after the color swatch, which on a highlighted card takes
+/// the highlight's color like the rest of the line.
+fn card_title_with_swatch(task: &Task) -> String {
+    if task_color(task).is_some() {
+        format!("{CARD_SWATCH}{}", card_title(task))
+    } else {
+        card_title(task)
+    }
+}
+
+/// Card title with a star in front of pinned tasks.
+fn card_title(task: &Task) -> String {
+    if task.pinned {
+        format!("★ {}", task.title)
+    } else {
+        task.title.clone()
+    }
+}
+
+fn cycle_sort_mode(app: &mut App) {
+    app.settings.sort_mode = app.settings.sort_mode.next();
+    persist_settings(app);
+    push_toast(
+        app,
+        format!("Sort: {}", app.settings.sort_mode.title()),
+        false,
+        Toast::Info,
+    );
+}
+
+/// Whether `task` passes the `/filter @@name` assignee filter (`None` shows
+/// everything), the `/pinned` filter and the `/` quick filter
+/// (case-insensitive title substring).
+fn passes_board_filter(
+    task: &Task,
+    assignee: Option<&str>,
+    pinned_only: bool,
+    query: &str,
+) -> bool {
+    let assigned = match assignee {
+        Some(name) => task
+            .assignee
+            .as_deref()
+            .is_some_and(|a| a.eq_ignore_ascii_case(name)),
+        None => true,
+    };
+    assigned
+        && (task.pinned || !pinned_only)
+        && (query.is_empty() || task.title.to_lowercase().contains(&query.to_lowercase()))
+}
+
+fn bucket_task_indices(
+    tasks: &[Task],
+    bucket_name: &str,
+    settings: &AiSettings,
+    assignee: Option<&str>,
+    pinned_only: bool,
+    query: &str,
+) -> Vec<usize> {
     let mut indices: Vec<usize> = tasks
         .iter()
         .enumerate()
@@ -4696,6 +7752,7 @@ fn bucket_task_indices(tasks: &[Task], bucket_name: &str, settings: &AiSettings)
             if t.bucket == bucket_name
                 && t.parent_id.is_none()
                 && settings.is_progress_visible(t.progress)
+                && passes_board_filter(t, assignee, pinned_only, query)
             {
                 Some(idx)
             } else {
@@ -4707,19 +7764,37 @@ fn bucket_task_indices(tasks: &[Task], bucket_name: &str, settings: &AiSettings)
     indices.sort_by(|&a, &b| {
         let ta = &tasks[a];
         let tb = &tasks[b];
-        tb.progress
-            .stage_index()
-            .cmp(&ta.progress.stage_index())
-            .then_with(|| tb.priority.cmp(&ta.priority))
-            .then_with(|| tb.created_at.cmp(&ta.created_at))
+        sort_mode_cmp(settings.sort_mode, ta, tb).unwrap_or_else(|| {
+            tb.progress
+                .stage_index()
+                .cmp(&ta.progress.stage_index())
+                .then_with(|| tb.priority.cmp(&ta.priority))
+                .then_with(|| tb.created_at.cmp(&ta.created_at))
+        })
     });
 
     indices
 }
 
-fn visible_cards(cards_area_height: usize) -> usize {
-    const CARD_HEIGHT: usize = 8; // 7 lines + 1 spacer
-    cards_area_height / CARD_HEIGHT
+/// Rows a bucket card takes on screen: the card itself, its sub-issue lines
+/// (or one blank line) and the spacer below.
+fn bucket_card_height(tasks: &[Task], id: Uuid, settings: &AiSettings) -> usize {
+    let children = visible_children_of(tasks, id, settings).len();
+    let max_shown = settings.card_max_subissues;
+    let sub_rows = if children == 0 || max_shown == 0 {
+        1
+    } else {
+        children.min(max_shown) + usize::from(children > max_shown)
+    };
+    BUCKET_CARD_LINES + sub_rows + 1
+}
+
+/// Smallest usable terminal: every bucket column needs ~18 cells plus gaps,
+/// so wide bucket layouts raise the bar above the 60x12 baseline.
+fn min_terminal_size(bucket_count: usize) -> (u16, u16) {
+    let n = bucket_count.max(1);
+    let board = 2 * 2 + n * 18 + (n - 1) * 2;
+    (board.max(60) as u16, 12)
 }
 
 fn render(stdout: &mut Stdout, app: &mut App, clear: bool) -> io::Result<()> {
@@ -4729,12 +7804,29 @@ fn render(stdout: &mut Stdout, app: &mut App, clear: bool) -> io::Result<()> {
     }
     queue!(stdout, MoveTo(0, 0))?;
 
-    if cols < 60 || rows < 12 {
+    app.tab_hitboxes.clear();
+    app.card_hitboxes.clear();
+
+    let (min_cols, min_rows) = min_terminal_size(app.settings.buckets.len());
+    app.too_small = cols < min_cols || rows < min_rows;
+    if app.too_small {
         queue!(
             stdout,
+            Hide,
             MoveTo(2, 1),
             SetForegroundColor(Color::DarkGrey),
-            Print("Terminal too small (need ~60x12)."),
+            Print(clamp_text(
+                &format!(
+                    "Terminal too small: {}x{}, need {}x{}.",
+                    cols, rows, min_cols, min_rows
+                ),
+                (cols as usize).saturating_sub(2),
+            )),
+            MoveTo(2, 2),
+            Print(clamp_text(
+                "Resize to continue, Ctrl+C to quit.",
+                (cols as usize).saturating_sub(2),
+            )),
             ResetColor
         )?;
         stdout.flush()?;
@@ -4766,10 +7858,42 @@ fn render(stdout: &mut Stdout, app: &mut App, clear: bool) -> io::Result<()> {
         render_delete_confirm(stdout, app, cols, rows)?;
     }
 
+    if app.confirm_reset.is_some() {
+        render_reset_confirm(stdout, app, cols, rows)?;
+    }
+
     if app.pending_memory.is_some() {
         render_memory_confirm(stdout, app, cols, rows)?;
     }
 
+    if app.pending_bulk.is_some() {
+        render_bulk_confirm(stdout, app, cols, rows)?;
+    }
+
+    if app.confirm_suggestions_all.is_some() {
+        render_suggestions_all_confirm(stdout, app, cols, rows)?;
+    }
+
+    if app.snapshot_list.is_some() {
+        render_snapshots_overlay(stdout, app, cols, rows)?;
+    }
+
+    if app.snapshot_preview.is_some() {
+        render_snapshot_preview(stdout, app, cols, rows)?;
+    }
+
+    if app.help_open {
+        render_help_overlay(stdout, app, cols, rows)?;
+    }
+
+    if app.context_overlay_open {
+        render_context_overlay(stdout, app, cols, rows)?;
+    }
+
+    if app.confirm_quit.is_some() {
+        render_quit_confirm(stdout, app, cols, rows)?;
+    }
+
     if app.status.is_some() {
         render_toast(stdout, app, cols, rows)?;
     }
@@ -4815,7 +7939,7 @@ fn render_tab_label(
     Ok(())
 }
 
-fn render_tabs(stdout: &mut Stdout, app: &App, cols: u16) -> io::Result<()> {
+fn render_tabs(stdout: &mut Stdout, app: &mut App, cols: u16) -> io::Result<()> {
     let width = cols as usize;
     let num_buckets = app.settings.buckets.len().max(1);
     let (x_margin, _) = choose_layout(width, num_buckets);
@@ -4833,6 +7957,8 @@ fn render_tabs(stdout: &mut Stdout, app: &App, cols: u16) -> io::Result<()> {
     for (tab, label) in left_tabs {
         let rendered = format!(" {} ", label);
         render_tab_label(stdout, label, *tab == app.tab, tabs_focused, x)?;
+        app.tab_hitboxes
+            .push((*tab, x, x + rendered.width() as u16));
         x += rendered.width() as u16 + 2;
     }
 
@@ -4847,10 +7973,22 @@ fn render_tabs(stdout: &mut Stdout, app: &App, cols: u16) -> io::Result<()> {
         tabs_focused,
         right_x,
     )?;
+    app.tab_hitboxes.push((
+        Tab::Settings,
+        right_x,
+        right_x + right_rendered.width() as u16,
+    ));
 
     Ok(())
 }
 
+fn print_colored(stdout: &mut Stdout, text: &str, color: Option<Color>) -> io::Result<()> {
+    match color {
+        Some(color) => queue!(stdout, SetForegroundColor(color), Print(text), ResetColor),
+        None => queue!(stdout, Print(text)),
+    }
+}
+
 fn render_input_bar(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -> io::Result<()> {
     let width = cols as usize;
     let num_buckets = app.settings.buckets.len().max(1);
@@ -4910,6 +8048,29 @@ fn render_input_bar(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) ->
             Print(pad_to_width(&clamp_text(placeholder, max_input), max_input)),
             ResetColor
         )?;
+    } else if app.focus == Focus::Input {
+        let start = input_window_start(&app.input, app.input_cursor, max_input);
+        let spans = input_highlights(app);
+        let color_at = |i: usize| {
+            spans
+                .iter()
+                .find(|(range, _)| range.contains(&(start + i)))
+                .map(|(_, color)| *color)
+        };
+        let mut run = String::new();
+        let mut run_color = None;
+        for (i, ch) in shown.chars().enumerate() {
+            let color = color_at(i);
+            if color != run_color && !run.is_empty() {
+                print_colored(stdout, &run, run_color)?;
+                run.clear();
+            }
+            run_color = color;
+            run.push(ch);
+        }
+        print_colored(stdout, &run, run_color)?;
+        let pad = max_input.saturating_sub(shown.width());
+        queue!(stdout, Print(" ".repeat(pad)))?;
     } else {
         queue!(stdout, Print(pad_to_width(&shown, max_input)), ResetColor)?;
     }
@@ -4923,25 +8084,57 @@ fn render_input_bar(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) ->
     )?;
 
     // Help line with context usage bar.
-    let help_text = "i input • esc board • ↑/↓/←/→ nav • p advance • @id edit • /clear";
-    let context_tokens = estimate_context_tokens(&app.tasks, &app.chat_history);
-    let max_tokens: usize = 200_000;
+    let base_help = "i input • esc board • ↑/↓/←/→ nav • p advance • @id edit • ? help";
+    let mut help_parts: Vec<String> = Vec::new();
+    if let Some(query) = &app.goto_query {
+        help_parts.push(format!("go to @{query}▏ enter jump • esc cancel"));
+    } else if app.board_query_editing {
+        help_parts.push(format!("/{}▏ enter keep • esc clear", app.board_query));
+    } else if !app.board_query.is_empty() {
+        help_parts.push(format!("/{} (esc clears)", app.board_query));
+    }
+    if let Some(name) = &app.assignee_filter {
+        help_parts.push(format!("filter @{name} (/filter clears)"));
+    }
+    if app.pinned_only {
+        help_parts.push("pinned (/pinned shows all)".to_string());
+    }
+    help_parts.push(base_help.to_string());
+    let help_text = help_parts.join(" • ");
+    let context_tokens = cached_context_tokens(app);
+    let max_tokens = llm::model_context_window(&llm::resolve_model(&app.settings));
     let ratio = (context_tokens as f64 / max_tokens as f64).clamp(0.0, 1.0);
 
     let bar_width: usize = 8;
     let filled = ((ratio * bar_width as f64).round() as usize).min(bar_width);
     let label = format!("~{}k", context_tokens / 1000);
-    let bar_total = bar_width + 2 + label.len();
+    let mut ai_label = if app.ai.is_some() {
+        let model = llm::resolve_model(&app.settings);
+        format!("{} · {}  ", llm::detect_provider(&model).name(), model)
+    } else {
+        "AI off  ".to_string()
+    };
+    // On narrow terminals the help text matters more than the model name.
+    if ai_label.width() + bar_width + 2 + label.len() + 20 > content_width {
+        ai_label.clear();
+    }
+    let bar_total = bar_width + 2 + label.len() + ai_label.width();
     let help_left_max = content_width.saturating_sub(bar_total + 2);
-    let help_left = clamp_text(help_text, help_left_max);
+    let help_left = clamp_text(&help_text, help_left_max);
     let padding = content_width.saturating_sub(help_left.width() + bar_total);
 
     queue!(
         stdout,
         MoveTo(x, y_help),
-        SetForegroundColor(Color::DarkGrey),
+        SetForegroundColor(if app.board_query_editing || app.goto_query.is_some() {
+            Color::White
+        } else {
+            Color::DarkGrey
+        }),
         Print(&help_left),
+        SetForegroundColor(Color::DarkGrey),
         Print(" ".repeat(padding)),
+        Print(&ai_label),
         Print(&label),
         Print("["),
     )?;
@@ -5165,7 +8358,7 @@ fn render_calendar_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     let y_help = rows.saturating_sub(5);
     let body_bottom = y_help.saturating_sub(1);
 
-    let today = Local::now().date_naive();
+    let today = app.settings.today();
     let year = today.year();
     let month = today.month();
     let first_of_month = chrono::NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(today);
@@ -5191,7 +8384,10 @@ fn render_calendar_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
         ResetColor
     )?;
 
-    let weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    let mut weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    if app.settings.week_start == WeekStart::Sunday {
+        weekdays.rotate_right(1);
+    }
     let grid_top = y_top + 2;
     if body_bottom <= grid_top {
         return Ok(());
@@ -5235,15 +8431,14 @@ fn render_calendar_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     let week_cell_h = (week_rows_available / 6).max(2);
     let grid_bottom = grid_top + (week_cell_h * 6).saturating_sub(1) as u16;
 
-    let month_start = first_of_month
-        - chrono::Duration::days(first_of_month.weekday().num_days_from_monday() as i64);
+    let month_start = app.settings.week_start.week_of(first_of_month);
 
     let mut date_entries: std::collections::HashMap<chrono::NaiveDate, Vec<(i32, String, Color)>> =
         std::collections::HashMap::new();
 
     for evt in &app.calendar_events {
         let parsed = if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(&evt.start_date) {
-            let local_dt = dt.with_timezone(&Local);
+            let local_dt = app.settings.to_local(dt.with_timezone(&Utc));
             let sort = (local_dt.hour() * 60 + local_dt.minute()) as i32;
             let time = if evt.all_day {
                 None
@@ -5259,7 +8454,12 @@ fn render_calendar_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
         if let Some((date, sort, time)) = parsed {
             let end_time = chrono::DateTime::parse_from_rfc3339(&evt.end_date)
                 .ok()
-                .map(|dt| dt.with_timezone(&Local).format("%H:%M").to_string());
+                .map(|dt| {
+                    app.settings
+                        .to_local(dt.with_timezone(&Utc))
+                        .format("%H:%M")
+                        .to_string()
+                });
             let mut line = if let Some(t) = time {
                 if let Some(end) = end_time {
                     format!("{t}-{end} {} [{}]", evt.title, evt.calendar_name)
@@ -5532,7 +8732,7 @@ fn render_checklist_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
 
         let due_str = task
             .due_date
-            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map(|d| app.settings.format_date(d))
             .unwrap_or_else(|| "—".to_string());
         let due_display = clamp_text(&due_str, due_col_w);
         let bucket_display = clamp_text(&task.bucket, bucket_col_w);
@@ -5668,23 +8868,36 @@ fn render_checklist_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
         )?;
         if connected {
             let poll_note = if let Some(checked_at) = app.suggestions_last_polled_at {
-                let checked_local = checked_at.with_timezone(&Local).format("%H:%M:%S");
-                if app.suggestions_last_poll_ok {
+                let checked_local = app.settings.to_local(checked_at).format("%H:%M:%S");
+                if let Some(err) = &app.suggestions_last_poll_error {
                     format!(
-                        "  Last check {} • unread {} • actionable {}",
+                        "  Last check {} • Gmail not accessible: {}",
+                        checked_local, err
+                    )
+                } else {
+                    let mut note = format!(
+                        "  Last check {} • {} new • unread {} • actionable {}",
                         checked_local,
+                        app.suggestions_last_poll_new,
                         app.suggestions_last_poll_unread,
                         app.suggestions_last_poll_actionable
-                    )
-                } else {
-                    format!("  Last check {} • poll failed", checked_local)
+                    );
+                    if let Some((count, err)) = &app.suggestions_last_poll_failed {
+                        note.push_str(&format!(" • {count} not filtered: {err}"));
+                    }
+                    note
                 }
             } else {
                 "  Waiting for first poll...".to_string()
             };
+            let note_color = if app.suggestions_last_poll_error.is_some() {
+                Color::Red
+            } else {
+                Color::DarkGrey
+            };
             queue!(
                 stdout,
-                SetForegroundColor(Color::DarkGrey),
+                SetForegroundColor(note_color),
                 Print(clamp_text(&poll_note, content_width.saturating_sub(18))),
                 ResetColor
             )?;
@@ -5706,14 +8919,13 @@ fn render_checklist_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
                 )?;
             } else if app.suggestions.is_empty() {
                 let empty_msg = if let Some(checked_at) = app.suggestions_last_polled_at {
-                    let checked_local = checked_at.with_timezone(&Local).format("%H:%M:%S");
-                    if app.suggestions_last_poll_ok {
-                        format!(" No suggestions yet. Last checked {}.", checked_local)
-                    } else {
-                        format!(
-                            " No suggestions yet. Last poll failed at {}.",
-                            checked_local
-                        )
+                    let checked_local = app.settings.to_local(checked_at).format("%H:%M:%S");
+                    match &app.suggestions_last_poll_error {
+                        None => format!(" No suggestions yet. Last checked {}.", checked_local),
+                        Some(err) => format!(
+                            " No suggestions yet. Last poll failed at {}: {}",
+                            checked_local, err
+                        ),
                     }
                 } else {
                     " No suggestions yet. Waiting for first poll.".to_string()
@@ -5744,8 +8956,16 @@ fn render_checklist_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
                         && app.checklist_section == ChecklistSection::Suggestions;
                     let priority_bullet = priority_icon(suggestion.priority);
                     let pcolor = priority_color(suggestion.priority);
+                    let mut title = match suggestion.sub_tasks.len() {
+                        0 => suggestion.title.clone(),
+                        n => format!("{} (+{n} sub-issues)", suggestion.title),
+                    };
+                    if !suggestion.sender.is_empty() {
+                        title
+                            .push_str(&format!(" · {}: {}", suggestion.sender, suggestion.subject));
+                    }
                     if is_sel {
-                        let line = format!(" {} {}", priority_bullet, suggestion.title);
+                        let line = format!(" {} {}", priority_bullet, title);
                         queue!(
                             stdout,
                             MoveTo(x, y),
@@ -5764,10 +8984,7 @@ fn render_checklist_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
                             SetForegroundColor(pcolor),
                             Print(format!(" {} ", priority_bullet)),
                             ResetColor,
-                            Print(clamp_text(
-                                &suggestion.title,
-                                content_width.saturating_sub(3)
-                            ))
+                            Print(clamp_text(&title, content_width.saturating_sub(3)))
                         )?;
                     }
                 }
@@ -5778,7 +8995,7 @@ fn render_checklist_tab(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     let help = if app.checklist_section == ChecklistSection::Tasks {
         " tab section • enter toggle • space expand • e edit • d delete • i input"
     } else {
-        " tab section • j/k navigate • enter create task • d dismiss • i input"
+        " tab section • j/k navigate • enter create task • d dismiss • A/X all • i input"
     };
     queue!(
         stdout,
@@ -5909,6 +9126,12 @@ fn handle_checklist_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
                 dismiss_selected_suggestion(app);
             }
         }
+        KeyCode::Char(c @ ('A' | 'X')) => {
+            if app.checklist_section == ChecklistSection::Suggestions && !app.suggestions.is_empty()
+            {
+                app.confirm_suggestions_all = Some(c == 'A');
+            }
+        }
         KeyCode::Char('i') => {
             app.checklist_frozen_order = None;
             app.focus = Focus::Input;
@@ -6006,26 +9229,49 @@ fn render_default_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
     Ok(())
 }
 
+/// "actual / estimate" for cards, e.g. "3h / 5h"; None when neither is set.
+fn effort_label(task: &Task) -> Option<String> {
+    if task.estimate_hours.is_none() && task.actual_hours.is_none() {
+        return None;
+    }
+    let fmt = |h: Option<f32>| h.map(format_hours).unwrap_or_else(|| "—".to_string());
+    Some(format!(
+        "{} / {}",
+        fmt(task.actual_hours),
+        fmt(task.estimate_hours)
+    ))
+}
+
+/// Lines 0-5 of a bucket card: title, description ×2, separator, progress, due.
+const BUCKET_CARD_LINES: usize = 6;
+
 fn render_bucket_column(
     stdout: &mut Stdout,
-    app: &App,
+    app: &mut App,
     bucket_idx: usize,
     x: u16,
     y: u16,
     width: usize,
     max_y: u16,
 ) -> io::Result<()> {
-    const CARD_LINES: usize = 6; // lines 0-5 (title, desc×2, separator, progress, due)
-
     let bucket_name = &app.settings.buckets[bucket_idx].name;
-    let indices = bucket_task_indices(&app.tasks, bucket_name, &app.settings);
+    let indices = bucket_task_indices(
+        &app.tasks,
+        bucket_name,
+        &app.settings,
+        app.assignee_filter.as_deref(),
+        app.pinned_only,
+        &app.board_query,
+    );
     let scroll = app.bucket_scrolls.get(bucket_idx).copied().unwrap_or(0);
 
     let inner_w = width.saturating_sub(2); // 1 char padding each side
+    let now = Utc::now();
+    let today = app.settings.today();
     let mut y_cursor = y;
 
     for (_pos, &idx) in indices.iter().enumerate().skip(scroll) {
-        if y_cursor + CARD_LINES as u16 + 1 > max_y {
+        if y_cursor + BUCKET_CARD_LINES as u16 + 1 > max_y {
             break;
         }
 
@@ -6045,10 +9291,10 @@ fn render_bucket_column(
         let desc_lines = wrap_text(&desc_text, inner_w, 2);
 
         // Build the field table rows.
-        let gauge = progress_gauge(task.progress);
+        let gauge = progress_gauge(&app.tasks, task);
         let due = task
             .due_date
-            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map(|d| app.settings.format_date(d))
             .unwrap_or_else(|| "—".to_string());
         // Sub-issue info.
         let child_indices: Vec<usize> = children_of(&app.tasks, task.id)
@@ -6076,15 +9322,35 @@ fn render_bucket_column(
             )
         };
 
-        // Table row 1: progress │ priority
+        // Escalated priorities show as "⚠ High" in the escalated colour.
+        let priority = effective_priority(task, today);
+        let priority_label = if priority > task.priority {
+            format!("⚠ {}", priority.title())
+        } else {
+            format!("{} {}", priority_icon(priority), priority.title())
+        };
+        let assignee_chip = task
+            .assignee
+            .as_ref()
+            .map(|a| format!(" │ @{a}"))
+            .unwrap_or_default();
+        // Table row 1: progress │ priority │ assignee
         let table_row1 = format!(
-            "{} {} │ {}",
+            "{} {} │ {}{}",
             gauge,
-            task.progress.title(),
-            task.priority.title()
+            progress_label(&app.settings, &app.tasks, task),
+            priority_label,
+            assignee_chip
         );
-        // Table row 2: due │ sub-issues/deps
-        let table_row2 = format!("Due {} │ {}", due, sub_info);
+        // Table row 2: due │ effort │ sub-issues/deps │ blocks
+        let mut table_row2 = match effort_label(task) {
+            Some(effort) => format!("Due {} │ {} │ {}", due, effort, sub_info),
+            None => format!("Due {} │ {}", due, sub_info),
+        };
+        let blocks = dependents_of(&app.tasks, task.id).len();
+        if blocks > 0 {
+            table_row2.push_str(&format!(" │ blocks {blocks}"));
+        }
 
         // Assemble card lines:
         // 0: title (bold)
@@ -6093,7 +9359,7 @@ fn render_bucket_column(
         // 3: separator
         // 4: progress │ priority (colored gauge)
         // 5: due │ deps/sub-count (dim)
-        for line_idx in 0..CARD_LINES {
+        for line_idx in 0..BUCKET_CARD_LINES {
             let y_line = card_top + line_idx as u16;
             queue!(stdout, MoveTo(x, y_line))?;
 
@@ -6108,11 +9374,11 @@ fn render_bucket_column(
             // Colored gauge + priority for progress line (non-selected only).
             if line_idx == 4 && !is_selected {
                 let gc = progress_color(task.progress);
-                let pc = priority_color(task.priority);
-                let pri_icon = priority_icon(task.priority);
+                let pc = priority_color(priority);
                 let gauge_str = format!(" {}", gauge);
-                let progress_part = format!(" {} │ ", task.progress.title());
-                let priority_part = format!("{} {}", pri_icon, task.priority.title());
+                let progress_part =
+                    format!(" {} │ ", progress_label(&app.settings, &app.tasks, task));
+                let priority_part = priority_label.clone();
                 queue!(
                     stdout,
                     SetForegroundColor(gc),
@@ -6122,7 +9388,10 @@ fn render_bucket_column(
                     SetForegroundColor(pc),
                     Print(&priority_part),
                 )?;
-                let used = gauge_str.width() + progress_part.width() + priority_part.width();
+                let mut used = gauge_str.width() + progress_part.width() + priority_part.width();
+                let chip = clamp_text(&assignee_chip, width.saturating_sub(used));
+                queue!(stdout, SetForegroundColor(Color::Cyan), Print(&chip))?;
+                used += chip.width();
                 let pad = width.saturating_sub(used);
                 if pad > 0 {
                     queue!(stdout, Print(" ".repeat(pad)))?;
@@ -6135,17 +9404,25 @@ fn render_bucket_column(
             if line_idx == 0 && !is_selected {
                 let short_id = task.id.to_string().chars().take(8).collect::<String>();
                 let id_str = format!(" {} ", short_id);
-                let title_max = width.saturating_sub(id_str.width());
-                let title_str = clamp_text(&task.title, title_max);
                 queue!(
                     stdout,
                     SetForegroundColor(Color::DarkGrey),
                     Print(&id_str),
                     ResetColor,
-                    SetAttribute(Attribute::Bold),
-                    Print(&title_str),
                 )?;
-                let used = id_str.width() + title_str.width();
+                let mut used = id_str.width();
+                if let Some(swatch) = task_color(task) {
+                    queue!(
+                        stdout,
+                        SetForegroundColor(swatch),
+                        Print(CARD_SWATCH),
+                        ResetColor
+                    )?;
+                    used += CARD_SWATCH.width();
+                }
+                let title_str = clamp_text(&card_title(task), width.saturating_sub(used));
+                queue!(stdout, SetAttribute(Attribute::Bold), Print(&title_str))?;
+                used += title_str.width();
                 let pad = width.saturating_sub(used);
                 if pad > 0 {
                     queue!(stdout, Print(" ".repeat(pad)))?;
@@ -6159,7 +9436,7 @@ fn render_bucket_column(
                     // Title: selected card (bright + bold, includes id).
                     let short_id = task.id.to_string().chars().take(8).collect::<String>();
                     queue!(stdout, SetAttribute(Attribute::Bold))?;
-                    format!(" {} {}", short_id, task.title)
+                    format!(" {} {}", short_id, card_title_with_swatch(task))
                 }
                 1 => {
                     // Desc line 1.
@@ -6177,11 +9454,16 @@ fn render_bucket_column(
                     format!(" {}", l2)
                 }
                 3 => {
-                    // Thin separator.
+                    // Thin separator, ending in the time since the last update.
                     if !is_selected {
                         queue!(stdout, SetForegroundColor(Color::DarkGrey))?;
                     }
-                    format!(" {}", "─".repeat(inner_w))
+                    let age = format!(" {} ─", humanize_age(task.updated_at, now));
+                    if inner_w > age.width() + 4 {
+                        format!(" {}{}", "─".repeat(inner_w - age.width()), age)
+                    } else {
+                        format!(" {}", "─".repeat(inner_w))
+                    }
                 }
                 4 => {
                     // Progress │ Priority (selected: single color).
@@ -6206,12 +9488,14 @@ fn render_bucket_column(
             )?;
         }
 
-        y_cursor += CARD_LINES as u16;
+        y_cursor += BUCKET_CARD_LINES as u16;
+        app.card_hitboxes
+            .push((task.id, x, card_top, width as u16, BUCKET_CARD_LINES as u16));
 
         // Render sub-issues below the card.
         let visible_children = visible_children_of(&app.tasks, task.id, &app.settings);
-        if !visible_children.is_empty() {
-            let max_shown = 3usize;
+        let max_shown = app.settings.card_max_subissues;
+        if !visible_children.is_empty() && max_shown > 0 {
             for &child_idx in visible_children.iter().take(max_shown) {
                 if y_cursor >= max_y {
                     break;
@@ -6262,7 +9546,7 @@ fn render_bucket_column(
                 y_cursor += 1;
             }
         } else {
-            // Blank padding line for cards without children.
+            // Blank padding line for cards without listed children.
             if y_cursor < max_y {
                 queue!(stdout, MoveTo(x, y_cursor), Print(pad_to_width("", width)))?;
                 y_cursor += 1;
@@ -6304,15 +9588,12 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
     let gantt_width = content_width.saturating_sub(label_width + 3); // 3 for " | "
 
     // Determine date range (today + 4 weeks by default, expand if tasks go further)
-    let today = Local::now().date_naive();
+    let today = app.settings.today();
     let mut min_date = today;
     let mut max_date = today + ChronoDuration::days(28);
 
     for task in &app.tasks {
-        let start = task
-            .start_date
-            .map(|dt| dt.date_naive())
-            .unwrap_or_else(|| task.created_at.date_naive());
+        let start = task_start_date(task);
         let end = task.due_date.unwrap_or(start + ChronoDuration::days(7));
         if start < min_date {
             min_date = start;
@@ -6378,11 +9659,18 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
 
     // Draw today marker position
     let today_offset = (today - min_date).num_days().max(0) as usize;
-    let today_col = if total_days > 0 {
-        (today_offset * gantt_width) / total_days
-    } else {
-        0
-    };
+    let today_col = (today_offset * gantt_width)
+        .checked_div(total_days)
+        .unwrap_or(0);
+
+    // Faint gridline at the start of each week.
+    let week_start = app.settings.week_start.weekday();
+    let week_cols: std::collections::HashSet<usize> = (1..total_days)
+        .filter(|&d| (min_date + ChronoDuration::days(d as i64)).weekday() == week_start)
+        .map(|d| d * gantt_width / total_days)
+        .collect();
+
+    let critical: std::collections::HashSet<Uuid> = critical_path(&app.tasks).into_iter().collect();
 
     // Use sorted_timeline_tasks for consistent ordering with key handler
     let indices = sorted_timeline_tasks(&app.tasks, &app.timeline_collapsed);
@@ -6423,6 +9711,9 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
         let task = &app.tasks[indices[sorted_pos]];
         let y = list_top + vis_row as u16;
         let is_selected = sorted_pos == app.timeline_selected;
+        app.card_hitboxes
+            .push((task.id, x, y, content_width as u16, 1));
+        let on_critical_path = critical.contains(&task.id);
 
         // Task label
         const BUCKET_SYMBOLS: &[&str] = &["●", "◆", "■", "▲", "★", "♦"];
@@ -6470,12 +9761,18 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
                 ResetColor
             )?;
         }
+        if on_critical_path {
+            queue!(
+                stdout,
+                MoveTo(gantt_x.saturating_sub(3), y),
+                SetForegroundColor(Color::Red),
+                Print("!"),
+                ResetColor
+            )?;
+        }
 
         // Calculate bar position
-        let start = task
-            .start_date
-            .map(|dt| dt.date_naive())
-            .unwrap_or_else(|| task.created_at.date_naive());
+        let start = task_start_date(task);
         let end = task.due_date.unwrap_or(start + ChronoDuration::days(7));
 
         let start_offset = (start - min_date).num_days().max(0) as usize;
@@ -6485,15 +9782,16 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
         let bar_end = ((end_offset * gantt_width) / total_days.max(1)).max(bar_start + 1);
 
         // Build bar with start/end date labels
-        let start_label = start.format("%m/%d").to_string();
-        let end_label = end.format("%m/%d").to_string();
+        let start_label = app.settings.format_short_date(start);
+        let end_label = app.settings.format_short_date(end);
 
         let bar_len = bar_end - bar_start;
         let both_len = start_label.len() + 1 + end_label.len(); // "MM/DD .... MM/DD"
 
-        // Color based on progress
+        // Color based on progress; unfinished critical-path tasks stand out in red.
         let bar_color = match task.progress {
             Progress::Done => Color::Green,
+            _ if on_critical_path => Color::Red,
             Progress::InProgress => Color::Yellow,
             Progress::Todo => Color::Blue,
             Progress::Backlog => Color::DarkGrey,
@@ -6508,6 +9806,8 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
                 bar_chars.push('█');
             } else if col == today_col {
                 bar_chars.push('│');
+            } else if week_cols.contains(&col) {
+                bar_chars.push('┊');
             } else {
                 bar_chars.push(' ');
             }
@@ -6615,29 +9915,29 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
         )?;
 
         let task = &app.tasks[indices[app.timeline_selected]];
-        let start = task
-            .start_date
-            .map(|dt| dt.date_naive())
-            .unwrap_or_else(|| task.created_at.date_naive());
+        let start = task_start_date(task);
         let end = task.due_date.unwrap_or(start + ChronoDuration::days(7));
-        let gauge = progress_gauge(task.progress);
+        let gauge = progress_gauge(&app.tasks, task);
         let desc = if task.description.trim().is_empty() {
             "—"
         } else {
             task.description.trim()
         };
 
-        let line1 = format!(
+        let mut line1 = format!(
             "{} │ {} {} │ {} │ {} → {}",
             task.title,
             gauge,
-            task.progress.title(),
+            progress_label(&app.settings, &app.tasks, task),
             task.priority.title(),
-            start.format("%Y-%m-%d"),
-            end.format("%Y-%m-%d"),
+            app.settings.format_date(start),
+            app.settings.format_date(end),
         );
+        if critical.contains(&task.id) {
+            line1.push_str(" │ critical path");
+        }
         let line2 = if let Some(pid) = task.parent_id {
-            if let Some(parent) = app.tasks.iter().find(|t| t.id == pid) {
+            if let Some(parent) = app.task_index.get(&app.tasks, pid) {
                 format!("  Parent Task: {}", parent.title)
             } else {
                 format!("  {}", desc)
@@ -6676,7 +9976,9 @@ fn render_timeline_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16)
         SetForegroundColor(Color::Blue),
         Print("█ Todo  "),
         SetForegroundColor(Color::DarkGrey),
-        Print("█ Backlog"),
+        Print("█ Backlog  "),
+        SetForegroundColor(Color::Red),
+        Print("! █ Critical path"),
         ResetColor
     )?;
 
@@ -6690,13 +9992,21 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
     let (_, gap) = choose_layout(width, 4);
     let x = x_margin as u16;
     let y_help = rows.saturating_sub(5);
-    let today = Local::now().date_naive();
+    let today = app.settings.today();
 
     queue!(
         stdout,
         MoveTo(x, 3),
         SetForegroundColor(Color::DarkGrey),
-        Print("Kanban (grouped by progress)."),
+        Print("Kanban (grouped by progress) · "),
+        SetForegroundColor(if app.kanban_bucket.is_some() {
+            Color::Cyan
+        } else {
+            Color::DarkGrey
+        }),
+        Print(app.kanban_bucket.as_deref().unwrap_or("All buckets")),
+        SetForegroundColor(Color::DarkGrey),
+        Print("  [/] bucket"),
         ResetColor
     )?;
 
@@ -6718,13 +10028,21 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
     for (i, stage) in Progress::ALL.iter().take(4).enumerate() {
         let cx = col_x[i] as u16;
         let is_active_col = *stage == app.kanban_stage;
-        let ids = kanban_task_ids(&app.tasks, *stage);
+        let ids = kanban_task_ids(
+            &app.tasks,
+            *stage,
+            app.settings.sort_mode,
+            app.kanban_bucket.as_deref(),
+            app.assignee_filter.as_deref(),
+            app.pinned_only,
+            &app.board_query,
+        );
         let count = ids.len();
         let stage_idx = stage.stage_index();
         let blank_col = pad_to_width("", col_width);
 
         // ── Column header: "Todo (25)" ──
-        let header = format!("{} ({})", stage.title(), count);
+        let header = format!("{} ({})", app.settings.stage_label(*stage), count);
         queue!(stdout, MoveTo(cx, 5))?;
         if is_active_col {
             queue!(
@@ -6762,12 +10080,13 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
         )?;
 
         // ── Scrolling ──
+        // Only follow the selection while it's in this column; after a
+        // background change moves it elsewhere the view stays put.
         let scroll = &mut app.kanban_scroll[stage_idx];
-        if is_active_col {
-            let sel_pos = app
-                .kanban_selected
-                .and_then(|id| ids.iter().position(|x| *x == id))
-                .unwrap_or(0);
+        let sel_pos = app
+            .kanban_selected
+            .and_then(|id| ids.iter().position(|x| *x == id));
+        if let Some(sel_pos) = sel_pos.filter(|_| is_active_col) {
             if max_visible > 0 {
                 if sel_pos >= *scroll + max_visible {
                     *scroll = sel_pos.saturating_sub(max_visible.saturating_sub(1));
@@ -6810,17 +10129,22 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
             let task = app.tasks.iter().find(|t| t.id == *id).unwrap();
             let is_selected = is_active_col && app.kanban_selected == Some(*id);
 
-            let bullet = priority_icon(task.priority);
+            let priority = effective_priority(task, today);
+            let bullet = if priority > task.priority {
+                "⚠"
+            } else {
+                priority_icon(priority)
+            };
 
             // Due date string.
             let due_str: Option<String> = task.due_date.map(|d| {
                 let show_year = d.year() != today.year();
                 let date_fmt = if show_year {
-                    d.format("%b %d %Y").to_string()
+                    app.settings.format_date(d)
                 } else {
-                    d.format("%b %d").to_string()
+                    app.settings.format_short_date(d)
                 };
-                if d < today {
+                if app.settings.is_overdue(d) {
                     format!("⚠ {}", date_fmt)
                 } else if d == today {
                     "due today".to_string()
@@ -6829,7 +10153,7 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
                 }
             });
 
-            let meta_line = if let Some(pid) = task.parent_id {
+            let mut meta_line = if let Some(pid) = task.parent_id {
                 let parent_title = app
                     .tasks
                     .iter()
@@ -6846,11 +10170,27 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
             } else {
                 format!("   {}", task.bucket)
             };
+            if let Some(assignee) = &task.assignee {
+                meta_line.push_str(&format!(" · @{assignee}"));
+            }
+            // Leads the line so it survives clamping in narrow columns.
+            let unmet = unmet_dependencies(&app.tasks, task);
+            if let Some(&first) = unmet.first() {
+                let more = match unmet.len() {
+                    1 => String::new(),
+                    n => format!(" +{}", n - 1),
+                };
+                meta_line = format!(
+                    "   ⧗ needs: {}{more} · {}",
+                    app.tasks[first].title,
+                    meta_line.trim_start()
+                );
+            }
 
             // ── Line 1: priority bullet + title ──
             queue!(stdout, MoveTo(cx, y_cur))?;
             if is_selected {
-                let full = format!(" {} {}", bullet, task.title);
+                let full = format!(" {} {}", bullet, card_title_with_swatch(task));
                 queue!(
                     stdout,
                     SetForegroundColor(Color::Black),
@@ -6862,17 +10202,33 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
                 let prefix = format!(" {} ", bullet);
                 queue!(
                     stdout,
-                    SetForegroundColor(priority_color(task.priority)),
+                    SetForegroundColor(priority_color(priority)),
                     Print(&prefix),
                     ResetColor
                 )?;
-                let title_max = col_width.saturating_sub(prefix.width());
-                let title_text = task.title.clone();
+                let mut title_max = col_width.saturating_sub(prefix.width());
+                if let Some(swatch) = task_color(task) {
+                    queue!(
+                        stdout,
+                        SetForegroundColor(swatch),
+                        Print(clamp_text(CARD_SWATCH, title_max)),
+                        ResetColor
+                    )?;
+                    title_max = title_max.saturating_sub(CARD_SWATCH.width());
+                }
+                let title_text = card_title(task);
+                // Dimmed while it waits on another task, so ready work stands out.
                 queue!(
                     stdout,
-                    Print(pad_to_width(&clamp_text(&title_text, title_max), title_max))
-                )?;
-            }
+                    SetForegroundColor(if unmet.is_empty() {
+                        Color::Reset
+                    } else {
+                        Color::DarkGrey
+                    }),
+                    Print(pad_to_width(&clamp_text(&title_text, title_max), title_max)),
+                    ResetColor
+                )?;
+            }
 
             // ── Line 2: bucket + due date ──
             queue!(stdout, MoveTo(cx, y_cur + 1))?;
@@ -6885,7 +10241,7 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
                     ResetColor
                 )?;
             } else {
-                let is_overdue = task.due_date.is_some_and(|d| d < today);
+                let is_overdue = task.due_date.is_some_and(|d| app.settings.is_overdue(d));
                 let is_due_today = task.due_date == Some(today);
                 let meta_color = if is_overdue {
                     Color::Red
@@ -6902,6 +10258,8 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
                 )?;
             }
 
+            app.card_hitboxes
+                .push((*id, cx, y_cur, col_width as u16, CARD_LINES));
             y_cur += CARD_LINES;
         }
 
@@ -6939,6 +10297,40 @@ fn render_kanban_tab(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -
     Ok(())
 }
 
+/// Whether `buf` would be accepted for `field` on Enter, for fields that
+/// reject bad input; `None` for free text.
+fn settings_buf_valid(field: SettingsField, buf: &str) -> Option<bool> {
+    let buf = buf.trim();
+    match field {
+        SettingsField::Timeout => Some(buf.parse::<u64>().is_ok_and(|secs| secs > 0)),
+        SettingsField::CardSubIssues => Some(buf.parse::<usize>().is_ok()),
+        SettingsField::AiConcurrency => Some(
+            buf.parse::<usize>()
+                .is_ok_and(|n| (1..=storage::MAX_AI_CONCURRENCY).contains(&n)),
+        ),
+        SettingsField::SnapshotRetention => Some(
+            buf.parse::<usize>()
+                .is_ok_and(|n| (1..=storage::MAX_SNAPSHOT_RETENTION).contains(&n)),
+        ),
+        SettingsField::UtcOffset => Some(parse_utc_offset(buf).is_some()),
+        SettingsField::DateFormat => Some(is_valid_date_format(buf)),
+        SettingsField::StageLabels => Some(parse_stage_labels(buf).is_some()),
+        SettingsField::WorkDays => Some(parse_work_days(buf).is_some()),
+        SettingsField::Holidays => Some(parse_holidays(buf).is_some()),
+        SettingsField::WorkdayEnd => Some(parse_workday_end(buf).is_some()),
+        _ => None,
+    }
+}
+
+/// Value color while editing: green once the input parses, red until then.
+fn validity_color(valid: bool) -> Color {
+    if valid {
+        Color::DarkGreen
+    } else {
+        Color::Red
+    }
+}
+
 fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) -> io::Result<()> {
     let width = cols as usize;
     let num_buckets = app.settings.buckets.len().max(1);
@@ -6983,6 +10375,13 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
                     app.settings.user_profile.clone()
                 }
             }
+            SettingsField::SystemPrompt => {
+                if app.settings.system_prompt.trim().is_empty() {
+                    "(not set)".to_string()
+                } else {
+                    one_line(&app.settings.system_prompt)
+                }
+            }
             SettingsField::AiEnabled => {
                 if app.settings.enabled {
                     "On".to_string()
@@ -6990,8 +10389,8 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
                     "Off".to_string()
                 }
             }
-            SettingsField::OpenAiKey => mask_api_key(&app.settings.openai_api_key),
-            SettingsField::AnthropicKey => mask_api_key(&app.settings.anthropic_api_key),
+            SettingsField::OpenAiKey => key_with_source(&app.settings, llm::Provider::OpenAi),
+            SettingsField::AnthropicKey => key_with_source(&app.settings, llm::Provider::Anthropic),
             SettingsField::Model => {
                 if app.settings.model.is_empty() {
                     "(default)".to_string()
@@ -7000,6 +10399,40 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
                 }
             }
             SettingsField::Timeout => format!("{}s", app.settings.timeout_secs),
+            SettingsField::AiConcurrency => match app.settings.ai_concurrency {
+                1 => "1 request at a time".to_string(),
+                n => format!("{n} requests at once"),
+            },
+            SettingsField::AiLockBucket => if app.settings.ai_lock_bucket {
+                "\u{2611} On"
+            } else {
+                "\u{2610} Off"
+            }
+            .to_string(),
+            SettingsField::Keychain => if app.settings.use_keychain {
+                "\u{2611} On"
+            } else {
+                "\u{2610} Off"
+            }
+            .to_string(),
+            SettingsField::AiLockPriority => if app.settings.ai_lock_priority {
+                "\u{2611} On"
+            } else {
+                "\u{2610} Off"
+            }
+            .to_string(),
+            SettingsField::AiLockDueDate => if app.settings.ai_lock_due_date {
+                "\u{2611} On"
+            } else {
+                "\u{2610} Off"
+            }
+            .to_string(),
+            SettingsField::AiVisibleOnly => if app.settings.ai_context_visible_only {
+                "\u{2611} On"
+            } else {
+                "\u{2610} Off"
+            }
+            .to_string(),
             SettingsField::ShowBacklog => if app.settings.show_backlog {
                 "\u{2611} On"
             } else {
@@ -7024,11 +10457,57 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
                 "\u{2610} Off"
             }
             .to_string(),
+            SettingsField::CardSubIssues => match app.settings.card_max_subissues {
+                0 => "0 (count only)".to_string(),
+                n => n.to_string(),
+            },
+            SettingsField::SnapshotRetention => {
+                format!("{} snapshots", app.settings.snapshot_retention)
+            }
+            SettingsField::AutoEscalate => if app.settings.auto_escalate {
+                "\u{2611} On"
+            } else {
+                "\u{2610} Off"
+            }
+            .to_string(),
+            SettingsField::WeekStart => app.settings.week_start.title().to_string(),
+            SettingsField::UtcOffset => format_utc_offset(app.settings.utc_offset_minutes),
+            SettingsField::DateFormat => format!(
+                "{} ({})",
+                app.settings.date_format(),
+                app.settings.format_date(app.settings.today())
+            ),
+            SettingsField::StageLabels => app.settings.stage_labels_text(),
+            SettingsField::WorkDays if app.settings.work_days.is_empty() => "Every day".to_string(),
+            SettingsField::WorkDays => app.settings.work_days_text(),
+            SettingsField::Holidays if app.settings.holidays.is_empty() => "None".to_string(),
+            SettingsField::Holidays => app.settings.holidays_text(),
+            SettingsField::WorkdayEnd => match app.settings.workday_end {
+                Some(_) => app.settings.workday_end_text(),
+                None => "Midnight".to_string(),
+            },
         };
 
         let show_value = if is_current && app.settings_editing {
-            format!("{}\u{258f}", app.settings_buf)
-        } else if is_current && (field.is_toggle() || *field == SettingsField::Model) {
+            if *field == SettingsField::SystemPrompt {
+                // Keep the end of a long prompt in view, where typing happens.
+                format!(
+                    "{}\u{258f}",
+                    clamp_text_start(&one_line(&app.settings_buf), value_w.saturating_sub(1))
+                )
+            } else {
+                format!("{}\u{258f}", app.settings_buf)
+            }
+        } else if is_current
+            && (field.is_toggle()
+                || matches!(
+                    field,
+                    SettingsField::Model
+                        | SettingsField::CardSubIssues
+                        | SettingsField::AiConcurrency
+                        | SettingsField::SnapshotRetention
+                ))
+        {
             format!("\u{25c2} {} \u{25b8}", value)
         } else {
             value
@@ -7044,6 +10523,22 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
         }
 
         let label = format!(" {:<width$}", field.label(), width = label_w);
+        let valid = settings_buf_valid(*field, &app.settings_buf)
+            .filter(|_| is_current && app.settings_editing);
+        if let Some(valid) = valid {
+            let value_width = content_width.saturating_sub(label.width());
+            queue!(
+                stdout,
+                Print(clamp_text(&label, content_width)),
+                SetForegroundColor(validity_color(valid)),
+                Print(pad_to_width(
+                    &clamp_text(&show_value, value_width),
+                    value_width
+                )),
+                ResetColor
+            )?;
+            continue;
+        }
         let row_text = format!("{}{}", label, clamp_text(&show_value, value_w));
         queue!(
             stdout,
@@ -7120,8 +10615,8 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
         "AI active \u{2713}"
     } else if !app.settings.enabled {
         "AI disabled"
-    } else if app.settings.openai_api_key.trim().is_empty()
-        && app.settings.anthropic_api_key.trim().is_empty()
+    } else if llm::api_key(&app.settings, llm::Provider::OpenAi).is_none()
+        && llm::api_key(&app.settings, llm::Provider::Anthropic).is_none()
     {
         "No API key configured"
     } else {
@@ -7131,7 +10626,13 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
         stdout,
         MoveTo(x, status_y),
         SetForegroundColor(Color::DarkGrey),
-        Print(format!(" {}", ai_status)),
+        Print(
+            if app.settings_field == SettingsField::Model && !app.settings_memory_focus {
+                format!(" {ai_status} • t test connection")
+            } else {
+                format!(" {ai_status}")
+            }
+        ),
         ResetColor
     )?;
 
@@ -7151,7 +10652,14 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
             .position(|f| *f == app.settings_field)
             .unwrap_or(0);
         let cy = 5 + field_idx as u16;
-        let cx = x as usize + 1 + label_w + app.settings_buf.width();
+        let buf_w = if app.settings_field == SettingsField::SystemPrompt {
+            one_line(&app.settings_buf)
+                .width()
+                .min(value_w.saturating_sub(1))
+        } else {
+            app.settings_buf.width()
+        };
+        let cx = x as usize + 1 + label_w + buf_w;
         queue!(
             stdout,
             MoveTo((cx as u16).min(cols.saturating_sub(1)), cy),
@@ -7164,23 +10672,27 @@ fn render_settings_tab(stdout: &mut Stdout, app: &App, cols: u16, _rows: u16) ->
     Ok(())
 }
 
+/// The masked key requests will use, and where it comes from.
+fn key_with_source(settings: &AiSettings, provider: llm::Provider) -> String {
+    match llm::api_key(settings, provider) {
+        Some((key, source)) => format!("{} ({})", mask_api_key(&key), source.label()),
+        None => mask_api_key(""),
+    }
+}
+
 fn mask_api_key(key: &str) -> String {
     if key.is_empty() {
         return "(not set)".to_string();
     }
-    if key.len() <= 4 {
-        return "\u{2022}\u{2022}\u{2022}\u{2022}".to_string();
-    }
-    let visible = &key[key.len() - 4..];
-    format!("\u{2022}\u{2022}\u{2022}\u{2022}{}", visible)
+    log::mask_key(key)
 }
 
 fn render_toast(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
-    let Some((status, shown_at, persistent)) = &app.status else {
+    let Some((status, shown_at, persistent, kind)) = &app.status else {
         return Ok(());
     };
 
-    let is_error = status.starts_with("AI error") || status.starts_with("Save failed");
+    let is_error = *kind == Toast::Error;
     let box_width = (cols as usize).clamp(20, 45);
     let inner_w = box_width.saturating_sub(4);
 
@@ -7220,12 +10732,12 @@ fn render_toast(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Res
     }
 
     // Top border.
-    let border_color = if is_error {
-        Color::Red
-    } else {
-        Color::DarkGrey
+    let (border_color, border_label) = match kind {
+        Toast::Success => (Color::Green, "Done"),
+        Toast::Info => (Color::DarkGrey, "Info"),
+        Toast::Warning => (Color::Yellow, "Warning"),
+        Toast::Error => (Color::Red, "Error"),
     };
-    let border_label = if is_error { "Error" } else { "Info" };
     let border_fill = "\u{2500}".repeat(box_width.saturating_sub(border_label.len() + 6));
     queue!(
         stdout,
@@ -7275,28 +10787,662 @@ fn render_toast(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Res
         let ticker = format!("{}s {}", secs_left, bar);
         let ticker_w = ticker.width();
 
-        // Dismiss hint with ticker right-aligned.
-        let hint = "any key";
-        let gap = inner_w.saturating_sub(hint.width() + ticker_w);
-        let dismiss_line = format!("{}{}{}", hint, " ".repeat(gap), ticker);
-        queue!(
-            stdout,
-            MoveTo(inner_x, y0 + box_height - 1),
-            SetForegroundColor(Color::DarkGrey),
-            Print(clamp_text(&dismiss_line, inner_w)),
-            ResetColor
-        )?;
-    }
+        // Dismiss hint with ticker right-aligned.
+        let hint = match app.toast_queue.len() {
+            0 => "any key".to_string(),
+            n => format!("any key · {n} more"),
+        };
+        let gap = inner_w.saturating_sub(hint.width() + ticker_w);
+        let dismiss_line = format!("{}{}{}", hint, " ".repeat(gap), ticker);
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + box_height - 1),
+            SetForegroundColor(Color::DarkGrey),
+            Print(clamp_text(&dismiss_line, inner_w)),
+            ResetColor
+        )?;
+    }
+
+    Ok(())
+}
+
+fn render_memory_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let Some(fact) = &app.pending_memory else {
+        return Ok(());
+    };
+
+    let box_width = (cols as usize).clamp(40, 64);
+    let box_height = 6u16;
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let border_fill: String = "─".repeat(box_width.saturating_sub(16));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::Yellow),
+        Print(clamp_text(
+            &format!("┌─ Remember this? ─{} ", border_fill),
+            box_width,
+        )),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + 2),
+        SetForegroundColor(Color::White),
+        Print(clamp_text(&format!("\"{}\"", fact), inner_w)),
+        ResetColor
+    )?;
+
+    let help = "y save  \u{2022}  n dismiss";
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(help, inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_bulk_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let Some((task_ids, instruction)) = &app.pending_bulk else {
+        return Ok(());
+    };
+    const PREVIEW_ROWS: usize = 5;
+    let titles: Vec<&str> = task_ids
+        .iter()
+        .filter_map(|id| app.tasks.iter().find(|t| t.id == *id))
+        .map(|t| t.title.as_str())
+        .collect();
+    let shown = titles.len().min(PREVIEW_ROWS);
+    let more = titles.len() - shown;
+
+    let box_width = (cols as usize).clamp(40, 64);
+    let box_height = 6 + shown as u16 + u16::from(more > 0);
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let heading = format!("┌─ Update {} tasks? ─", task_ids.len());
+    let border_fill: String = "─".repeat(box_width.saturating_sub(heading.width() + 1));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::Yellow),
+        Print(clamp_text(&format!("{heading}{border_fill} "), box_width)),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + 2),
+        SetForegroundColor(Color::White),
+        Print(clamp_text(&format!("\"{}\"", instruction), inner_w)),
+        ResetColor
+    )?;
+
+    for (i, title) in titles.iter().take(shown).enumerate() {
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + 3 + i as u16),
+            SetForegroundColor(Color::Grey),
+            Print(clamp_text(&format!("\u{2022} {title}"), inner_w)),
+            ResetColor
+        )?;
+    }
+    if more > 0 {
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + 3 + shown as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(clamp_text(&format!("  …and {more} more"), inner_w)),
+            ResetColor
+        )?;
+    }
+
+    let help = "y run  \u{2022}  n cancel";
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(help, inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_suggestions_all_confirm(
+    stdout: &mut Stdout,
+    app: &App,
+    cols: u16,
+    rows: u16,
+) -> io::Result<()> {
+    let Some(accept) = app.confirm_suggestions_all else {
+        return Ok(());
+    };
+    const PREVIEW_ROWS: usize = 5;
+    let shown = app.suggestions.len().min(PREVIEW_ROWS);
+    let more = app.suggestions.len() - shown;
+
+    let box_width = (cols as usize).clamp(40, 64);
+    let box_height = 5 + shown as u16 + u16::from(more > 0);
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let verb = if accept { "Accept" } else { "Dismiss" };
+    let heading = format!("┌─ {verb} {} suggestions? ─", app.suggestions.len());
+    let border_fill: String = "─".repeat(box_width.saturating_sub(heading.width() + 1));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(if accept { Color::Yellow } else { Color::Red }),
+        Print(clamp_text(&format!("{heading}{border_fill} "), box_width)),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    for (i, suggestion) in app.suggestions.iter().take(shown).enumerate() {
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + 2 + i as u16),
+            SetForegroundColor(Color::Grey),
+            Print(clamp_text(
+                &format!("\u{2022} {}", suggestion.title),
+                inner_w
+            )),
+            ResetColor
+        )?;
+    }
+    if more > 0 {
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + 2 + shown as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(clamp_text(&format!("  …and {more} more"), inner_w)),
+            ResetColor
+        )?;
+    }
+
+    let help = if accept {
+        "y create tasks  \u{2022}  t triage with AI  \u{2022}  n cancel"
+    } else {
+        "y dismiss  \u{2022}  n cancel"
+    };
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(help, inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_snapshots_overlay(
+    stdout: &mut Stdout,
+    app: &App,
+    cols: u16,
+    rows: u16,
+) -> io::Result<()> {
+    let Some(entries) = &app.snapshot_list else {
+        return Ok(());
+    };
+    let box_width = (cols as usize).clamp(40, 70);
+    let visible = entries.len().min((rows as usize).saturating_sub(8).max(1));
+    let box_height = visible as u16 + 4;
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+    let scroll = app.snapshot_selected.saturating_sub(visible - 1);
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let heading = format!("┌─ Snapshots ({}) ─", entries.len());
+    let border_fill: String = "─".repeat(box_width.saturating_sub(heading.width() + 1));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(&format!("{heading}{border_fill} "), box_width)),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    let stamp_w = 18usize;
+    for (i, entry) in entries.iter().enumerate().skip(scroll).take(visible) {
+        let selected = i == app.snapshot_selected;
+        let local = app.settings.to_local(entry.timestamp);
+        let stamp = format!(
+            "{} {}",
+            app.settings.format_date(local.date_naive()),
+            local.format("%H:%M")
+        );
+        queue!(stdout, MoveTo(inner_x, y0 + 2 + (i - scroll) as u16))?;
+        if selected {
+            queue!(
+                stdout,
+                SetForegroundColor(Color::Black),
+                SetBackgroundColor(Color::White)
+            )?;
+        } else {
+            queue!(stdout, SetForegroundColor(Color::DarkGrey))?;
+        }
+        queue!(stdout, Print(pad_to_width(&stamp, stamp_w)))?;
+        if !selected {
+            queue!(stdout, SetForegroundColor(Color::White))?;
+        }
+        queue!(
+            stdout,
+            Print(pad_to_width(
+                &clamp_text(
+                    &format!("#{} {}", entry.seq, entry.label),
+                    inner_w.saturating_sub(stamp_w)
+                ),
+                inner_w.saturating_sub(stamp_w)
+            )),
+            ResetColor
+        )?;
+    }
+
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(
+            "Enter restore the state before that change  \u{2022}  Esc close",
+            inner_w
+        )),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_snapshot_preview(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let Some((_, label, diff)) = &app.snapshot_preview else {
+        return Ok(());
+    };
+    // Up to this many titles per kind of change, so all three kinds fit.
+    const PER_KIND: usize = 4;
+    let mut lines: Vec<(String, Color)> = Vec::new();
+    for (titles, mark, color, verb) in [
+        (&diff.removed, '-', Color::Red, "deleted"),
+        (&diff.added, '+', Color::Green, "brought back"),
+        (&diff.modified, '~', Color::Yellow, "reverted"),
+    ] {
+        if titles.is_empty() {
+            continue;
+        }
+        lines.push((format!("{} {verb}", titles.len()), Color::DarkGrey));
+        for title in titles.iter().take(PER_KIND) {
+            lines.push((format!("{mark} {title}"), color));
+        }
+        if titles.len() > PER_KIND {
+            lines.push((
+                format!("  …and {} more", titles.len() - PER_KIND),
+                Color::DarkGrey,
+            ));
+        }
+    }
+    if diff.is_empty() {
+        lines.push((
+            "No task changes; settings are restored too.".to_string(),
+            Color::Grey,
+        ));
+    }
+
+    let box_width = (cols as usize).clamp(40, 64);
+    let box_height = 4 + lines.len() as u16;
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let heading = format!("┌─ Restore to before \"{label}\"? ─");
+    let border_fill: String = "─".repeat(box_width.saturating_sub(heading.width() + 1));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::Yellow),
+        Print(clamp_text(&format!("{heading}{border_fill} "), box_width)),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    for (i, (text, color)) in lines.iter().enumerate() {
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + 2 + i as u16),
+            SetForegroundColor(*color),
+            Print(clamp_text(text, inner_w)),
+            ResetColor
+        )?;
+    }
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text("y restore  \u{2022}  n keep current", inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_help_overlay(stdout: &mut Stdout, app: &mut App, cols: u16, rows: u16) -> io::Result<()> {
+    let mut lines: Vec<(String, &str, bool)> = Vec::new();
+    for (i, (heading, entries)) in help_sections().into_iter().enumerate() {
+        if i > 0 {
+            lines.push((String::new(), "", false));
+        }
+        lines.push((heading.to_string(), "", true));
+        for (keys, desc) in entries {
+            lines.push((keys, desc, false));
+        }
+    }
+
+    let box_width = (cols as usize).clamp(40, 70);
+    let box_height = (lines.len() as u16 + 3).min(rows.saturating_sub(2));
+    let body_height = box_height.saturating_sub(3) as usize;
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    app.help_scroll = app.help_scroll.min(lines.len().saturating_sub(body_height));
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let border_fill: String = "─".repeat(box_width.saturating_sub(11));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(&format!("┌─ Help ─{} ", border_fill), box_width)),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    let key_w = 18usize;
+    for (row, (keys, desc, is_heading)) in lines
+        .iter()
+        .skip(app.help_scroll)
+        .take(body_height)
+        .enumerate()
+    {
+        queue!(stdout, MoveTo(inner_x, y0 + 1 + row as u16))?;
+        if *is_heading {
+            queue!(
+                stdout,
+                SetAttribute(Attribute::Bold),
+                Print(clamp_text(keys, inner_w)),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else if !keys.is_empty() {
+            queue!(
+                stdout,
+                SetForegroundColor(Color::Cyan),
+                Print(pad_to_width(&clamp_text(keys, key_w - 1), key_w)),
+                SetForegroundColor(Color::White),
+                Print(clamp_text(desc, inner_w.saturating_sub(key_w))),
+                ResetColor
+            )?;
+        }
+    }
+
+    let more = if app.help_scroll + body_height < lines.len() {
+        " • ↓ more"
+    } else {
+        ""
+    };
+    let help = format!("↑/↓ scroll • esc close{}", more);
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(&help, inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_context_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let (instruction_chars, triage_chars, chat_chars) =
+        context_breakdown(&app.tasks, &app.settings, &app.chat_history);
+    let tokens = (instruction_chars + triage_chars + chat_chars) / 4;
+    let model = llm::resolve_model(&app.settings);
+    let window = llm::model_context_window(&model);
+    let task_lines = build_triage_context(&app.tasks, ai_context_filter(&app.settings))
+        .lines()
+        .count();
+    let task_total = app.tasks.len();
+
+    let rows_text: Vec<(String, String)> = vec![
+        (
+            "Instructions".to_string(),
+            format!(
+                "{} / {} chars",
+                instruction_chars,
+                storage::SYSTEM_PROMPT_MAX_CHARS
+            ),
+        ),
+        (
+            "Task list".to_string(),
+            format!(
+                "{} chars ({} of {} tasks)",
+                triage_chars, task_lines, task_total
+            ),
+        ),
+        (
+            "Chat history".to_string(),
+            format!("{} chars ({} turns)", chat_chars, app.chat_history.len()),
+        ),
+        ("Estimated".to_string(), format!("~{} tokens", tokens)),
+        (
+            "Model window".to_string(),
+            format!(
+                "{} tokens ({:.1}% used)",
+                window,
+                tokens as f64 * 100.0 / window.max(1) as f64
+            ),
+        ),
+        ("Model".to_string(), model),
+    ];
+    let note = if task_lines >= TRIAGE_CONTEXT_LIMIT && task_total > task_lines {
+        format!(
+            "Only the {} most recently updated tasks are sent.",
+            TRIAGE_CONTEXT_LIMIT
+        )
+    } else {
+        "Estimate assumes ~4 chars per token.".to_string()
+    };
+
+    let box_width = (cols as usize).clamp(40, 60);
+    let box_height = rows_text.len() as u16 + 5;
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    let border_fill: String = "─".repeat(box_width.saturating_sub(14));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(
+            &format!("┌─ AI context ─{} ", border_fill),
+            box_width
+        )),
+        ResetColor
+    )?;
+
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    let label_w = 14usize;
+    for (i, (label, value)) in rows_text.iter().enumerate() {
+        queue!(
+            stdout,
+            MoveTo(inner_x, y0 + 2 + i as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(pad_to_width(label, label_w)),
+            SetForegroundColor(Color::White),
+            Print(clamp_text(value, inner_w.saturating_sub(label_w))),
+            ResetColor
+        )?;
+    }
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + 2 + rows_text.len() as u16),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(&note, inner_w)),
+        MoveTo(inner_x, y0 + box_height - 1),
+        Print(clamp_text("any key close", inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
+    Ok(())
+}
+
+fn render_delete_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let Some(id) = app.confirm_delete_id else {
+        return Ok(());
+    };
+    let title = app
+        .tasks
+        .iter()
+        .find(|t| t.id == id)
+        .map(|t| t.title.as_str())
+        .unwrap_or("Unknown");
+
+    let box_width = (cols as usize).clamp(30, 50);
+    let box_height = 5u16;
+    let x0 = (cols.saturating_sub(box_width as u16)) / 2;
+    let y0 = (rows.saturating_sub(box_height)) / 2;
+
+    // Clear overlay area.
+    for dy in 0..box_height {
+        queue!(
+            stdout,
+            MoveTo(x0, y0 + dy),
+            Print(pad_to_width("", box_width))
+        )?;
+    }
+
+    // Border top.
+    let border_fill: String = "─".repeat(box_width.saturating_sub(14));
+    queue!(
+        stdout,
+        MoveTo(x0, y0),
+        SetForegroundColor(Color::Red),
+        Print(clamp_text(
+            &format!("┌─ Delete? ─{} ", border_fill),
+            box_width,
+        )),
+        ResetColor
+    )?;
+
+    // Task title.
+    let inner_x = x0 + 2;
+    let inner_w = box_width.saturating_sub(4);
+    let msg = format!(
+        "Delete \"{}\"?",
+        clamp_text(title, inner_w.saturating_sub(10))
+    );
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + 2),
+        SetForegroundColor(Color::White),
+        Print(clamp_text(&msg, inner_w)),
+        ResetColor
+    )?;
+
+    // Help line.
+    let help = "enter confirm \u{2022} esc cancel";
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + box_height - 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(help, inner_w)),
+        ResetColor
+    )?;
+
+    queue!(stdout, Hide)?;
 
     Ok(())
 }
 
-fn render_memory_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
-    let Some(fact) = &app.pending_memory else {
+fn render_reset_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let Some(typed) = &app.confirm_reset else {
         return Ok(());
     };
 
-    let box_width = (cols as usize).clamp(40, 64);
+    let box_width = (cols as usize).clamp(30, 50);
     let box_height = 6u16;
     let x0 = (cols.saturating_sub(box_width as u16)) / 2;
     let y0 = (rows.saturating_sub(box_height)) / 2;
@@ -7309,13 +11455,13 @@ fn render_memory_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -
         )?;
     }
 
-    let border_fill: String = "─".repeat(box_width.saturating_sub(16));
+    let border_fill: String = "─".repeat(box_width.saturating_sub(13));
     queue!(
         stdout,
         MoveTo(x0, y0),
-        SetForegroundColor(Color::Yellow),
+        SetForegroundColor(Color::Red),
         Print(clamp_text(
-            &format!("┌─ Remember this? ─{} ", border_fill),
+            &format!("┌─ Reset? ─{} ", border_fill),
             box_width,
         )),
         ResetColor
@@ -7323,15 +11469,36 @@ fn render_memory_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -
 
     let inner_x = x0 + 2;
     let inner_w = box_width.saturating_sub(4);
+    let count = app.tasks.len();
+    let msg = format!(
+        "Delete all {count} task{}?",
+        if count == 1 { "" } else { "s" }
+    );
     queue!(
         stdout,
         MoveTo(inner_x, y0 + 2),
         SetForegroundColor(Color::White),
-        Print(clamp_text(&format!("\"{}\"", fact), inner_w)),
+        Print(clamp_text(&msg, inner_w)),
         ResetColor
     )?;
 
-    let help = "y save  \u{2022}  n dismiss";
+    let prompt = format!("Type {RESET_CONFIRM_WORD} to confirm: ");
+    let (visible, _) = input_visible_window(
+        typed,
+        typed.chars().count(),
+        inner_w.saturating_sub(prompt.width() + 1),
+    );
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + 3),
+        SetForegroundColor(Color::White),
+        Print(clamp_text(&prompt, inner_w)),
+        SetForegroundColor(validity_color(typed == RESET_CONFIRM_WORD)),
+        Print(format!("{visible}▏")),
+        ResetColor
+    )?;
+
+    let help = "enter confirm \u{2022} esc cancel \u{2022} ctrl+z undoes";
     queue!(
         stdout,
         MoveTo(inner_x, y0 + box_height - 1),
@@ -7341,26 +11508,17 @@ fn render_memory_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -
     )?;
 
     queue!(stdout, Hide)?;
+
     Ok(())
 }
 
-fn render_delete_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
-    let Some(id) = app.confirm_delete_id else {
-        return Ok(());
-    };
-    let title = app
-        .tasks
-        .iter()
-        .find(|t| t.id == id)
-        .map(|t| t.title.as_str())
-        .unwrap_or("Unknown");
-
+fn render_quit_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
+    let pending = app.ai.as_ref().map(|ai| ai.pending()).unwrap_or(0);
     let box_width = (cols as usize).clamp(30, 50);
     let box_height = 5u16;
     let x0 = (cols.saturating_sub(box_width as u16)) / 2;
     let y0 = (rows.saturating_sub(box_height)) / 2;
 
-    // Clear overlay area.
     for dy in 0..box_height {
         queue!(
             stdout,
@@ -7369,26 +11527,25 @@ fn render_delete_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -
         )?;
     }
 
-    // Border top.
-    let border_fill: String = "─".repeat(box_width.saturating_sub(14));
+    let border_fill: String = "─".repeat(box_width.saturating_sub(12));
     queue!(
         stdout,
         MoveTo(x0, y0),
-        SetForegroundColor(Color::Red),
+        SetForegroundColor(Color::Yellow),
         Print(clamp_text(
-            &format!("┌─ Delete? ─{} ", border_fill),
-            box_width,
+            &format!("┌─ Quit? ─{} ", border_fill),
+            box_width
         )),
         ResetColor
     )?;
 
-    // Task title.
     let inner_x = x0 + 2;
     let inner_w = box_width.saturating_sub(4);
-    let msg = format!(
-        "Delete \"{}\"?",
-        clamp_text(title, inner_w.saturating_sub(10))
-    );
+    let msg = if pending > 1 {
+        format!("AI is still working ({} jobs) — quit anyway?", pending)
+    } else {
+        "AI is still working — quit anyway?".to_string()
+    };
     queue!(
         stdout,
         MoveTo(inner_x, y0 + 2),
@@ -7397,8 +11554,7 @@ fn render_delete_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -
         ResetColor
     )?;
 
-    // Help line.
-    let help = "enter confirm \u{2022} esc cancel";
+    let help = "y quit \u{2022} n cancel \u{2022} ctrl+c again to force";
     queue!(
         stdout,
         MoveTo(inner_x, y0 + box_height - 1),
@@ -7408,7 +11564,6 @@ fn render_delete_confirm(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -
     )?;
 
     queue!(stdout, Hide)?;
-
     Ok(())
 }
 
@@ -7582,6 +11737,16 @@ fn render_bucket_edit_overlay(
     Ok(())
 }
 
+/// Local date (short, in the configured format) and time of day.
+fn format_stamp(settings: &AiSettings, at: chrono::DateTime<Utc>) -> String {
+    let local = settings.to_local(at);
+    format!(
+        "{} {}",
+        settings.format_short_date(local.date_naive()),
+        local.format("%H:%M")
+    )
+}
+
 fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) -> io::Result<()> {
     let Some(id) = app.edit_task_id else {
         return Ok(());
@@ -7600,7 +11765,9 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     } else {
         task.description.clone()
     };
-    let desc_editing = app.edit_field == EditField::Description && app.editing_text;
+    // While the reparent prompt is open, fields show their saved values.
+    let field_editing = app.editing_text && !app.reparent_prompt;
+    let desc_editing = app.edit_field == EditField::Description && field_editing;
     let max_desc_lines = 6usize;
     let desc_wrapped = if desc_editing {
         None
@@ -7621,8 +11788,20 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     } else {
         2 + child_visible as u16
     };
-    // box_height: 9 (base fields) + desc_lines + sub_section_height
-    let box_height = (9 + desc_lines as u16 + sub_section_height).min(rows.saturating_sub(2));
+    let notes_shown = task.notes.len().min(3);
+    let history_shown = task.progress_history.len().min(3);
+    let dependents = dependents_of(&app.tasks, task.id);
+    let blocks_shown = usize::from(!dependents.is_empty());
+    let email_shown = usize::from(task.email_id.is_some());
+    // box_height: 13 (base fields) + desc_lines + recent notes + sub_section_height + blocks + email + history
+    let box_height = (13
+        + desc_lines as u16
+        + notes_shown as u16
+        + sub_section_height
+        + blocks_shown as u16
+        + email_shown as u16
+        + history_shown as u16)
+        .min(rows.saturating_sub(2));
     let x0 = (cols.saturating_sub(box_width as u16)) / 2;
     let y0 = (rows.saturating_sub(box_height)) / 2;
 
@@ -7653,6 +11832,20 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     let inner_x = x0 + 2;
     let inner_w = box_width.saturating_sub(4);
 
+    let now = Utc::now();
+    let ages = format!(
+        "created {} • updated {}",
+        humanize_age(task.created_at, now),
+        humanize_age(task.updated_at, now)
+    );
+    queue!(
+        stdout,
+        MoveTo(inner_x, y0 + 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(clamp_text(&ages, inner_w)),
+        ResetColor
+    )?;
+
     // Track the current y offset as we render fields.
     let mut y_cursor = y0 + 2;
 
@@ -7703,6 +11896,48 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
             continue;
         }
 
+        if *field == EditField::Notes {
+            let label = format!("{:<width$}", field.label(), width = label_w);
+            let value = if is_current && field_editing {
+                input_visible_window(&app.edit_buf, app.edit_buf_cursor, value_w).0
+            } else if task.notes.is_empty() {
+                "— (n to add)".to_string()
+            } else {
+                format!("{} (n to add)", task.notes.len())
+            };
+            let row_text = format!("{}{}", label, clamp_text(&value, value_w));
+            queue!(stdout, MoveTo(inner_x, y_cursor))?;
+            if is_current {
+                queue!(
+                    stdout,
+                    SetForegroundColor(Color::Black),
+                    SetBackgroundColor(Color::White)
+                )?;
+            } else {
+                queue!(stdout, SetForegroundColor(Color::White))?;
+            }
+            queue!(
+                stdout,
+                Print(pad_to_width(&clamp_text(&row_text, inner_w), inner_w)),
+                ResetColor
+            )?;
+            y_cursor += 1;
+
+            for (at, text) in task.notes.iter().rev().take(notes_shown) {
+                let stamp = format_stamp(&app.settings, *at);
+                let row_text = format!("{}{}  {}", " ".repeat(label_w), stamp, text);
+                queue!(
+                    stdout,
+                    MoveTo(inner_x, y_cursor),
+                    SetForegroundColor(Color::DarkGrey),
+                    Print(pad_to_width(&clamp_text(&row_text, inner_w), inner_w)),
+                    ResetColor
+                )?;
+                y_cursor += 1;
+            }
+            continue;
+        }
+
         if *field == EditField::SubIssues {
             // Separator.
             queue!(
@@ -7817,25 +12052,39 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
             EditField::Progress => {
                 format!(
                     "{} {}",
-                    progress_gauge(task.progress),
-                    task.progress.title()
+                    progress_gauge(&app.tasks, task),
+                    progress_label(&app.settings, &app.tasks, task)
                 )
             }
             EditField::Priority => task.priority.title().to_string(),
             EditField::DueDate => task
                 .due_date
-                .map(|d| d.format("%Y-%m-%d").to_string())
+                .map(|d| app.settings.format_date(d))
+                .unwrap_or_else(|| "—".to_string()),
+            EditField::Assignee => task
+                .assignee
+                .as_ref()
+                .map(|a| format!("@{a}"))
+                .unwrap_or_else(|| "—".to_string()),
+            EditField::Color => task.color.clone().unwrap_or_else(|| "—".to_string()),
+            EditField::Estimate => task
+                .estimate_hours
+                .map(format_hours)
                 .unwrap_or_else(|| "—".to_string()),
-            EditField::Description | EditField::SubIssues => unreachable!(),
+            EditField::Actual => task
+                .actual_hours
+                .map(format_hours)
+                .unwrap_or_else(|| "—".to_string()),
+            EditField::Description | EditField::Notes | EditField::SubIssues => unreachable!(),
         };
 
-        let show_value = if is_current && app.editing_text {
+        let show_value = if is_current && field_editing {
             let (visible, _) = input_visible_window(&app.edit_buf, app.edit_buf_cursor, value_w);
             visible
         } else if is_current
             && matches!(
                 field,
-                EditField::Bucket | EditField::Progress | EditField::Priority
+                EditField::Bucket | EditField::Progress | EditField::Priority | EditField::Color
             )
         {
             format!("◂ {} ▸", value)
@@ -7855,6 +12104,46 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
         }
 
         let label = format!("{:<width$}", field.label(), width = label_w);
+        if let Some(swatch) = (*field == EditField::Color)
+            .then(|| task_color(task))
+            .flatten()
+        {
+            let value_width = inner_w.saturating_sub(label.width() + CARD_SWATCH.width());
+            queue!(
+                stdout,
+                Print(clamp_text(&label, inner_w)),
+                SetForegroundColor(swatch),
+                Print(CARD_SWATCH),
+                SetForegroundColor(if is_current {
+                    Color::Black
+                } else {
+                    Color::White
+                }),
+                Print(pad_to_width(
+                    &clamp_text(&show_value, value_width),
+                    value_width
+                )),
+                ResetColor
+            )?;
+            y_cursor += 1;
+            continue;
+        }
+        if is_current && field_editing && *field == EditField::DueDate {
+            let valid = ai::parse_due_input(&app.edit_buf, &app.settings).is_some();
+            let value_width = inner_w.saturating_sub(label.width());
+            queue!(
+                stdout,
+                Print(clamp_text(&label, inner_w)),
+                SetForegroundColor(validity_color(valid)),
+                Print(pad_to_width(
+                    &clamp_text(&show_value, value_width),
+                    value_width
+                )),
+                ResetColor
+            )?;
+            y_cursor += 1;
+            continue;
+        }
         let row_text = format!("{}{}", label, clamp_text(&show_value, value_w));
         queue!(
             stdout,
@@ -7864,24 +12153,126 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
         y_cursor += 1;
     }
 
+    if !dependents.is_empty() {
+        let titles: Vec<&str> = dependents
+            .iter()
+            .map(|&i| app.tasks[i].title.as_str())
+            .collect();
+        let row_text = format!(
+            "{:<width$}{} — {}",
+            "Blocks",
+            dependents.len(),
+            titles.join(", "),
+            width = label_w
+        );
+        queue!(
+            stdout,
+            MoveTo(inner_x, y_cursor),
+            SetForegroundColor(Color::DarkGrey),
+            Print(pad_to_width(&clamp_text(&row_text, inner_w), inner_w)),
+            ResetColor
+        )?;
+        y_cursor += 1;
+    }
+
+    if task.email_id.is_some() {
+        let subject = task
+            .email_subject
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .unwrap_or("(no subject)");
+        let row_text = format!(
+            "{:<width$}{} — o to open",
+            "From email",
+            subject,
+            width = label_w
+        );
+        queue!(
+            stdout,
+            MoveTo(inner_x, y_cursor),
+            SetForegroundColor(Color::DarkGrey),
+            Print(pad_to_width(&clamp_text(&row_text, inner_w), inner_w)),
+            ResetColor
+        )?;
+        y_cursor += 1;
+    }
+
+    for (i, (progress, at)) in task
+        .progress_history
+        .iter()
+        .rev()
+        .take(history_shown)
+        .enumerate()
+    {
+        let label = if i == 0 { "History" } else { "" };
+        let stamp = format_stamp(&app.settings, *at);
+        let row_text = format!(
+            "{:<width$}{}  → {}",
+            label,
+            stamp,
+            app.settings.stage_label(*progress),
+            width = label_w
+        );
+        queue!(
+            stdout,
+            MoveTo(inner_x, y_cursor),
+            SetForegroundColor(Color::DarkGrey),
+            Print(pad_to_width(&clamp_text(&row_text, inner_w), inner_w)),
+            ResetColor
+        )?;
+        y_cursor += 1;
+    }
+
     // Help line.
     let help_y = y0 + box_height - 1;
-    let help = if app.editing_text {
-        "enter save • esc cancel"
-    } else if app.edit_field == EditField::SubIssues {
-        "↑/↓ select • enter open • a add • d delete • esc close"
+    let prompt = "Move under ";
+    if app.reparent_prompt {
+        let (visible, _) = input_visible_window(
+            &app.edit_buf,
+            app.edit_buf_cursor,
+            inner_w.saturating_sub(prompt.len()),
+        );
+        queue!(
+            stdout,
+            MoveTo(inner_x, help_y),
+            SetForegroundColor(Color::Yellow),
+            Print(prompt),
+            SetForegroundColor(Color::White),
+            Print(pad_to_width(&visible, inner_w.saturating_sub(prompt.len()))),
+            ResetColor
+        )?;
     } else {
-        "↑/↓ field • enter/e edit • ←/→ cycle • d delete • esc close"
-    };
-    queue!(
-        stdout,
-        MoveTo(inner_x, help_y),
-        SetForegroundColor(Color::DarkGrey),
-        Print(clamp_text(help, inner_w)),
-        ResetColor
-    )?;
+        let help = if app.editing_text {
+            "enter save • esc cancel"
+        } else if app.edit_pending_g {
+            "go to: g/t title • d description • s sub-issues"
+        } else if app.edit_field == EditField::SubIssues {
+            "↑/↓ select • shift+↑/↓ reorder • enter open • a add • u promote • d delete • g jump • esc close"
+        } else {
+            "↑/↓ field • enter/e edit • ←/→ cycle • n note • m move • g jump • d delete • esc close"
+        };
+        queue!(
+            stdout,
+            MoveTo(inner_x, help_y),
+            SetForegroundColor(Color::DarkGrey),
+            Print(clamp_text(help, inner_w)),
+            ResetColor
+        )?;
+    }
 
-    if app.editing_text {
+    if app.reparent_prompt {
+        let (_, cursor_vis_x) = input_visible_window(
+            &app.edit_buf,
+            app.edit_buf_cursor,
+            inner_w.saturating_sub(prompt.len()),
+        );
+        let cx = inner_x as usize + prompt.len() + cursor_vis_x;
+        queue!(
+            stdout,
+            MoveTo((cx as u16).min(cols.saturating_sub(1)), help_y),
+            Show
+        )?;
+    } else if app.editing_text {
         let mut cy = y0 + 2;
         for field in EditField::fields_for(is_child_task).iter() {
             if *field == app.edit_field {
@@ -7889,6 +12280,8 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
             }
             if *field == EditField::Description {
                 cy += desc_lines as u16;
+            } else if *field == EditField::Notes {
+                cy += 1 + notes_shown as u16;
             } else if *field == EditField::SubIssues {
                 cy += 2 + child_visible as u16;
             } else {
@@ -7909,8 +12302,28 @@ fn render_edit_overlay(stdout: &mut Stdout, app: &App, cols: u16, rows: u16) ->
     Ok(())
 }
 
-fn progress_gauge(progress: Progress) -> String {
-    let stage = progress.stage_index().min(3);
+/// Four-cell gauge. Tasks with sub-issues fill by the share of them that are
+/// done, in eighths of a cell; the rest fill by stage.
+fn progress_gauge(tasks: &[Task], task: &Task) -> String {
+    let children: Vec<Progress> = children_of(tasks, task.id)
+        .into_iter()
+        .map(|i| tasks[i].progress)
+        .filter(|&p| p != Progress::Archived)
+        .collect();
+    if !children.is_empty() {
+        let done = children.iter().filter(|&&p| p == Progress::Done).count();
+        let eighths = (done * 32 + children.len() / 2) / children.len();
+        let mut out = "█".repeat(eighths / 8);
+        let partial = eighths % 8;
+        if partial > 0 {
+            out.push([' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'][partial]);
+        }
+        while out.chars().count() < 4 {
+            out.push('░');
+        }
+        return out;
+    }
+    let stage = task.progress.stage_index().min(3);
     let mut out = String::new();
     for i in 0..4 {
         if i <= stage {
@@ -7922,6 +12335,20 @@ fn progress_gauge(progress: Progress) -> String {
     out
 }
 
+/// The stage title, marked with `◆ mixed` when the sub-issues are spread
+/// across stages that aren't neighbours.
+fn progress_label(settings: &AiSettings, tasks: &[Task], task: &Task) -> String {
+    let children: Vec<Progress> = children_of(tasks, task.id)
+        .into_iter()
+        .map(|i| tasks[i].progress)
+        .collect();
+    if children_progress_mixed(&children) {
+        format!("{} ◆ mixed", settings.stage_label(task.progress))
+    } else {
+        settings.stage_label(task.progress).to_string()
+    }
+}
+
 fn progress_color(progress: Progress) -> Color {
     match progress {
         Progress::Done => Color::Green,
@@ -8009,6 +12436,32 @@ fn clamp_text(text: &str, max_width: usize) -> String {
     out
 }
 
+/// Like `clamp_text`, but keeps the end of the text.
+fn clamp_text_start(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let max_content = max_width.saturating_sub(1);
+    let mut tail: Vec<char> = Vec::new();
+    let mut w = 0usize;
+    for ch in text.chars().rev() {
+        let ch_w = UnicodeWidthChar::width(ch).unwrap_or(0);
+        if w + ch_w > max_content {
+            break;
+        }
+        tail.push(ch);
+        w += ch_w;
+    }
+    let mut out = String::from("…");
+    out.extend(tail.into_iter().rev());
+    out
+}
+
+/// Multi-line settings shown on a single row, with line breaks marked.
+fn one_line(text: &str) -> String {
+    text.replace('\n', " \u{23ce} ")
+}
+
 fn pad_to_width(text: &str, width: usize) -> String {
     let mut s = text.to_string();
     let current = s.as_str().width();
@@ -8078,6 +12531,12 @@ fn choose_layout(total_width: usize, columns: usize) -> (usize, usize) {
     }
 }
 
+const INGEST_USAGE: &str = "\
+Ingest (image → AI instruction → tasks):
+  aipm ingest --image <path>        PNG, JPEG, GIF or WebP file (also --image=<path>)
+  aipm ingest --clipboard           Image currently on the clipboard (macOS)
+";
+
 fn run_ingest(args: &[String]) -> io::Result<()> {
     let mut image_path: Option<String> = None;
     let mut clipboard = false;
@@ -8091,6 +12550,10 @@ fn run_ingest(args: &[String]) -> io::Result<()> {
             "--clipboard" => {
                 clipboard = true;
             }
+            "--help" | "-h" => {
+                print!("{}", INGEST_USAGE);
+                return Ok(());
+            }
             other if other.starts_with("--image=") => {
                 image_path = Some(other.strip_prefix("--image=").unwrap().to_string());
             }
@@ -8099,9 +12562,8 @@ fn run_ingest(args: &[String]) -> io::Result<()> {
     }
 
     if image_path.is_none() && !clipboard {
-        eprintln!("Usage: aipm ingest --image <path>");
-        eprintln!("       aipm ingest --clipboard");
-        std::process::exit(1);
+        eprint!("{}", INGEST_USAGE);
+        std::process::exit(cli::EXIT_CONFIG);
     }
 
     let (image_data, media_type) = if clipboard {
@@ -8118,8 +12580,14 @@ fn run_ingest(args: &[String]) -> io::Result<()> {
 
     eprintln!("Extracting tasks from image...");
 
-    let instruction =
-        llm::extract_from_image(&settings, &image_data, &media_type).map_err(io::Error::other)?;
+    let instruction = match llm::extract_from_image(&settings, &image_data, &media_type) {
+        Ok(instruction) => instruction,
+        Err(err) if llm::is_timeout(&err) => {
+            eprintln!("Error: {err}");
+            std::process::exit(cli::EXIT_AI_TIMEOUT);
+        }
+        Err(err) => return Err(io::Error::other(err)),
+    };
 
     eprintln!("Extracted: \"{instruction}\"");
     eprintln!();
@@ -8186,6 +12654,13 @@ fn run_cli(instruction: &str) -> io::Result<()> {
         Some(s) => s.load_tasks().unwrap_or_default(),
         None => Vec::new(),
     };
+    let pruned = prune_dangling_dependencies(&mut tasks);
+    if let Some(s) = storage.as_ref().filter(|_| pruned > 0) {
+        eprintln!("{}", pruned_dependencies_msg(pruned));
+        if let Err(err) = s.save_tasks(&tasks) {
+            eprintln!("Save failed: {err}");
+        }
+    }
     let mut settings = match &storage {
         Some(s) => s.load_settings().unwrap_or_default(),
         None => AiSettings::default(),
@@ -8193,47 +12668,86 @@ fn run_cli(instruction: &str) -> io::Result<()> {
 
     let ai = match llm::AiRuntime::from_settings(&settings) {
         Some(ai) => ai,
+        None if instruction.trim().starts_with('@') => {
+            let after_at = instruction.trim().trim_start_matches('@');
+            let (target, edit_text) =
+                match resolve_at_mention(&tasks, &mut TaskIndex::default(), after_at, None) {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        std::process::exit(cli::EXIT_CONFIG);
+                    }
+                };
+            let quick = ai::parse_quick_edit(&edit_text, &settings);
+            let (Some(id), Some(quick)) = (target, quick) else {
+                eprintln!(
+                    "Error: AI not configured. Offline edits take \"@<id> done|high|bucket <name>|due <date>\"."
+                );
+                std::process::exit(cli::EXIT_CONFIG);
+            };
+            let Some(summary) = apply_quick_edit(&mut tasks, id, &quick, &settings, Utc::now())
+            else {
+                eprintln!("No changes.");
+                std::process::exit(cli::EXIT_NO_CHANGES);
+            };
+            // The snapshot reads the files, which still hold the tasks as they
+            // were before the edit.
+            if let Some(s) = &storage {
+                s.snapshot("quick edit");
+            }
+            println!("  ~ {summary}");
+            if let Err(err) = storage.as_ref().map_or(Ok(()), |s| s.save_tasks(&tasks)) {
+                eprintln!("Save failed: {err}");
+                std::process::exit(cli::EXIT_STORAGE);
+            }
+            return Ok(());
+        }
         None => {
             eprintln!(
                 "Error: AI not configured. Set OPENAI_API_KEY or ANTHROPIC_API_KEY, or configure via the Settings tab."
             );
-            std::process::exit(1);
+            std::process::exit(cli::EXIT_CONFIG);
         }
     };
 
     eprintln!("AI processing: \"{}\"", instruction);
     eprintln!();
 
-    let context = build_ai_context(&tasks);
-    let triage_ctx = build_triage_context(&tasks);
+    let context = build_ai_context(&tasks, ai_context_filter(&settings));
+    let triage_ctx = build_triage_context(&tasks, ai_context_filter(&settings));
+    let (instruction, assignee) = extract_assignee(instruction);
     ai.enqueue(llm::AiJob {
         task_id: Uuid::nil(),
         title: String::new(),
         suggested_bucket: default_bucket_name(&settings),
         context,
-        bucket_names: bucket_names(&settings),
-        lock_bucket: false,
-        lock_priority: false,
-        lock_due_date: false,
+        buckets: settings.buckets.clone(),
+        lock_bucket: settings.ai_lock_bucket,
+        lock_priority: settings.ai_lock_priority,
+        lock_due_date: settings.ai_lock_due_date,
         edit_instruction: None,
         task_snapshot: None,
-        triage_input: Some(instruction.to_string()),
+        triage_input: Some(instruction),
         triage_context: Some(triage_ctx),
         chat_history: Vec::new(),
         user_profile: settings.user_profile.clone(),
         memory_facts: settings.memory_facts.clone(),
+        assignee,
     });
 
     let mut pending = 1u32;
+    let mut index = TaskIndex::default();
     let timeout = std::time::Duration::from_secs(90);
     let mut total_changes = 0u32;
     let mut saved = false;
+    let mut timed_out = false;
 
     while pending > 0 {
         let result = match ai.recv_blocking(timeout) {
             Some(r) => r,
             None => {
                 eprintln!("Timeout waiting for AI response.");
+                timed_out = true;
                 break;
             }
         };
@@ -8241,6 +12755,7 @@ fn run_cli(instruction: &str) -> io::Result<()> {
 
         if let Some(err) = &result.error {
             eprintln!("  Error: {}", err);
+            timed_out |= llm::is_timeout(err);
             continue;
         }
 
@@ -8257,8 +12772,14 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                     let bucket = result
                         .update
                         .bucket
-                        .clone()
+                        .as_deref()
+                        .and_then(|b| known_bucket(&settings, b))
                         .unwrap_or_else(|| default_bucket_name(&settings));
+                    if let Some(warning) =
+                        unknown_bucket_warning(&settings, result.update.bucket.as_deref(), &bucket)
+                    {
+                        eprintln!("  Warning: {warning}");
+                    }
                     let mut task = Task::new(bucket.clone(), title, now);
                     if let Some(desc) = &result.update.description {
                         task.description = desc.clone();
@@ -8270,8 +12791,9 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                         task.priority = priority;
                     }
                     if let Some(due_date) = result.update.due_date {
-                        task.due_date = due_date;
+                        task.set_due_date(due_date);
                     }
+                    task.assignee = result.update.assignee.clone();
                     if !result.update.dependencies.is_empty() {
                         task.dependencies = resolve_dependency_prefixes(
                             &tasks,
@@ -8315,7 +12837,7 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                                 .filter_map(|&idx| new_ids.get(idx).copied())
                                 .filter(|&dep_id| dep_id != task_id)
                                 .collect();
-                            if let Some(t) = tasks.iter_mut().find(|t| t.id == task_id) {
+                            if let Some(t) = index.get_mut(&mut tasks, task_id) {
                                 t.dependencies = dep_ids;
                             }
                         }
@@ -8323,253 +12845,216 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                     }
                 }
                 llm::TriageAction::Update(prefix) => {
-                    let target_id = tasks.iter().find_map(|t| {
-                        let short = t.id.to_string().chars().take(8).collect::<String>();
-                        if short.eq_ignore_ascii_case(&prefix) {
-                            Some(t.id)
-                        } else {
-                            None
-                        }
-                    });
-                    if let Some(id) = target_id {
-                        let deps = if !result.update.dependencies.is_empty() {
-                            resolve_dependency_prefixes(&tasks, id, &result.update.dependencies)
-                        } else {
-                            Vec::new()
-                        };
-                        if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
-                            let now = Utc::now();
-                            apply_update(task, &result.update, &deps, now);
-                            println!("  ~ Updated \"{}\"", task.title);
-                            total_changes += 1;
-                        }
-                        if let Some(ref new_parent_prefix) = result.update.parent_id {
-                            let old_parent =
-                                tasks.iter().find(|t| t.id == id).and_then(|t| t.parent_id);
-                            if new_parent_prefix.eq_ignore_ascii_case("none") {
-                                if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
-                                    task.parent_id = None;
-                                    println!("    Promoted to root task");
-                                }
+                    match index.resolve(&tasks, &prefix) {
+                        Ok(id) => {
+                            let deps = if !result.update.dependencies.is_empty() {
+                                resolve_dependency_prefixes(&tasks, id, &result.update.dependencies)
                             } else {
-                                let new_parent_id = tasks.iter().find_map(|t| {
-                                    let short =
-                                        t.id.to_string().chars().take(8).collect::<String>();
-                                    if short.eq_ignore_ascii_case(new_parent_prefix) {
-                                        Some(t.id)
-                                    } else {
-                                        None
-                                    }
-                                });
-                                if let Some(pid) = new_parent_id {
-                                    let parent_title = tasks
-                                        .iter()
-                                        .find(|t| t.id == pid)
-                                        .map(|t| t.title.clone())
-                                        .unwrap_or_default();
-                                    if let Some(task) = tasks.iter_mut().find(|t| t.id == id) {
-                                        task.parent_id = Some(pid);
-                                        println!("    Moved under \"{parent_title}\"");
-                                    }
+                                Vec::new()
+                            };
+                            if let Some(task) = index.get_mut(&mut tasks, id) {
+                                let now = Utc::now();
+                                apply_update(task, &result.update, &deps, &settings, now);
+                                println!("  ~ Updated \"{}\"", task.title);
+                                if let Some(warning) = unknown_bucket_warning(
+                                    &settings,
+                                    result
+                                        .update
+                                        .bucket
+                                        .as_deref()
+                                        .filter(|_| !settings.ai_lock_bucket),
+                                    &task.bucket,
+                                ) {
+                                    eprintln!("  Warning: {warning}");
                                 }
+                                total_changes += 1;
                             }
-                            if let Some(old_pid) = old_parent {
-                                sync_parent_progress(&mut tasks, old_pid, Utc::now());
-                            }
-                            sync_parent_progress(&mut tasks, id, Utc::now());
-                            total_changes += 1;
-                        }
-                        // Create sub-tasks if the update response includes them.
-                        if !result.sub_task_specs.is_empty() {
-                            let now = Utc::now();
-                            let parent_bucket = tasks
-                                .iter()
-                                .find(|t| t.id == id)
-                                .map(|t| t.bucket.clone())
-                                .unwrap_or_else(|| default_bucket_name(&settings));
-                            let count = result.sub_task_specs.len();
-                            let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
-                            for spec in result.sub_task_specs.iter() {
-                                let bucket =
-                                    spec.bucket.clone().unwrap_or_else(|| parent_bucket.clone());
-                                let mut task = Task::new(bucket, spec.title.clone(), now);
-                                task.parent_id = Some(id);
-                                task.description = spec.description.clone();
-                                if let Some(p) = spec.priority {
-                                    task.priority = p;
-                                }
-                                if let Some(prog) = spec.progress {
-                                    task.set_progress(prog, now);
-                                }
-                                if let Some(due) = spec.due_date {
-                                    task.due_date = Some(due);
+                            if let Some(ref new_parent_prefix) = result.update.parent_id {
+                                match apply_parent_change(
+                                    &mut tasks,
+                                    id,
+                                    new_parent_prefix,
+                                    Utc::now(),
+                                ) {
+                                    Ok(Some(parent_title)) => {
+                                        println!("    Moved under \"{parent_title}\"");
+                                        total_changes += 1;
+                                    }
+                                    Ok(None) => {
+                                        println!("    Promoted to root task");
+                                        total_changes += 1;
+                                    }
+                                    Err(err) => eprintln!("  Warning: {err}"),
                                 }
-                                new_ids.push(task.id);
-                                println!("    ↳ Created sub-task \"{}\"", task.title);
-                                tasks.push(task);
+                                sync_parent_progress(&mut tasks, id, Utc::now());
                             }
-                            for (i, spec) in result.sub_task_specs.iter().enumerate() {
-                                if spec.depends_on.is_empty() {
-                                    continue;
-                                }
-                                let task_id = new_ids[i];
-                                let dep_ids: Vec<Uuid> = spec
-                                    .depends_on
+                            // Create sub-tasks if the update response includes them.
+                            if !result.sub_task_specs.is_empty()
+                                && nesting_depth(&tasks, id) >= MAX_NESTING_DEPTH
+                            {
+                                eprintln!("  Warning: {NESTING_LIMIT_MSG}");
+                            } else if !result.sub_task_specs.is_empty() {
+                                let now = Utc::now();
+                                let parent_bucket = tasks
                                     .iter()
-                                    .filter_map(|&idx| new_ids.get(idx).copied())
-                                    .filter(|&dep_id| dep_id != task_id)
-                                    .collect();
-                                if let Some(task) = tasks.iter_mut().find(|t| t.id == task_id) {
-                                    task.dependencies = dep_ids;
+                                    .find(|t| t.id == id)
+                                    .map(|t| t.bucket.clone())
+                                    .unwrap_or_else(|| default_bucket_name(&settings));
+                                let count = result.sub_task_specs.len();
+                                let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
+                                for spec in result.sub_task_specs.iter() {
+                                    let bucket = spec
+                                        .bucket
+                                        .clone()
+                                        .unwrap_or_else(|| parent_bucket.clone());
+                                    let mut task = Task::new(bucket, spec.title.clone(), now);
+                                    task.parent_id = Some(id);
+                                    task.description = spec.description.clone();
+                                    if let Some(p) = spec.priority {
+                                        task.priority = p;
+                                    }
+                                    if let Some(prog) = spec.progress {
+                                        task.set_progress(prog, now);
+                                    }
+                                    if let Some(due) = spec.due_date {
+                                        task.due_date = Some(due);
+                                    }
+                                    new_ids.push(task.id);
+                                    println!("    ↳ Created sub-task \"{}\"", task.title);
+                                    tasks.push(task);
+                                }
+                                for (i, spec) in result.sub_task_specs.iter().enumerate() {
+                                    if spec.depends_on.is_empty() {
+                                        continue;
+                                    }
+                                    let task_id = new_ids[i];
+                                    let dep_ids: Vec<Uuid> = spec
+                                        .depends_on
+                                        .iter()
+                                        .filter_map(|&idx| new_ids.get(idx).copied())
+                                        .filter(|&dep_id| dep_id != task_id)
+                                        .collect();
+                                    if let Some(task) = index.get_mut(&mut tasks, task_id) {
+                                        task.dependencies = dep_ids;
+                                    }
                                 }
+                                total_changes += count as u32;
                             }
-                            total_changes += count as u32;
                         }
-                    } else {
-                        eprintln!("  Warning: task {} not found", prefix);
+                        Err(e) => eprintln!("  Warning: {e}"),
                     }
                 }
-                llm::TriageAction::Delete(prefix) => {
-                    let target = tasks.iter().position(|t| {
-                        let short = t.id.to_string().chars().take(8).collect::<String>();
-                        short.eq_ignore_ascii_case(&prefix)
-                    });
-                    if let Some(pos) = target {
-                        let title = tasks[pos].title.clone();
-                        let id = tasks[pos].id;
-                        let child_ids: Vec<Uuid> = children_of(&tasks, id)
-                            .iter()
-                            .map(|&i| tasks[i].id)
-                            .collect();
-                        tasks.retain(|t| !child_ids.contains(&t.id) && t.id != id);
+                llm::TriageAction::Delete(prefix) => match index.resolve(&tasks, &prefix) {
+                    Ok(id) => {
+                        let title = index
+                            .get(&tasks, id)
+                            .map_or_else(String::new, |t| t.title.clone());
+                        delete_with_children(&mut tasks, id);
                         println!("  - Deleted \"{}\"", title);
                         total_changes += 1;
-                    } else {
-                        eprintln!("  Warning: task {} not found", prefix);
                     }
-                }
+                    Err(e) => eprintln!("  Warning: {e}"),
+                },
                 llm::TriageAction::Decompose { target_id, specs } => {
                     let now = Utc::now();
-                    let parent_uuid = target_id.as_ref().and_then(|prefix| {
-                        tasks.iter().find_map(|t| {
-                            let short = t.id.to_string().chars().take(8).collect::<String>();
-                            if short.eq_ignore_ascii_case(prefix) {
-                                Some(t.id)
-                            } else {
-                                None
-                            }
-                        })
-                    });
+                    let parent_uuid = target_id
+                        .as_ref()
+                        .and_then(|prefix| index.resolve(&tasks, prefix).ok());
                     let parent_title = parent_uuid
-                        .and_then(|id| tasks.iter().find(|t| t.id == id))
+                        .and_then(|id| index.get(&tasks, id))
                         .map(|t| t.title.clone())
                         .unwrap_or_else(|| "(no parent)".to_string());
-                    let default_bucket = specs
-                        .first()
-                        .and_then(|s| s.bucket.clone())
-                        .unwrap_or_else(|| default_bucket_name(&settings));
-                    let count = specs.len();
-                    let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
-                    for spec in specs.iter() {
-                        let bucket = spec
-                            .bucket
-                            .clone()
-                            .unwrap_or_else(|| default_bucket.clone());
-                        let mut task = Task::new(bucket, spec.title.clone(), now);
-                        task.parent_id = parent_uuid;
-                        task.description = spec.description.clone();
-                        if let Some(p) = spec.priority {
-                            task.priority = p;
-                        }
-                        if let Some(prog) = spec.progress {
-                            task.set_progress(prog, now);
-                        }
-                        if let Some(due) = spec.due_date {
-                            task.due_date = Some(due);
-                        }
-                        new_ids.push(task.id);
-                        tasks.push(task);
-                    }
-                    for (i, spec) in specs.iter().enumerate() {
-                        if spec.depends_on.is_empty() {
-                            continue;
-                        }
-                        let task_id = new_ids[i];
-                        let dep_ids: Vec<Uuid> = spec
-                            .depends_on
-                            .iter()
-                            .filter_map(|&idx| new_ids.get(idx).copied())
-                            .filter(|&dep_id| dep_id != task_id)
-                            .collect();
-                        if let Some(task) = tasks.iter_mut().find(|t| t.id == task_id) {
-                            task.dependencies = dep_ids;
+                    if parent_uuid
+                        .is_some_and(|pid| nesting_depth(&tasks, pid) >= MAX_NESTING_DEPTH)
+                    {
+                        eprintln!("  Warning: {NESTING_LIMIT_MSG}");
+                    } else {
+                        let default_bucket = specs
+                            .first()
+                            .and_then(|s| s.bucket.clone())
+                            .unwrap_or_else(|| default_bucket_name(&settings));
+                        let count = specs.len();
+                        let mut new_ids: Vec<Uuid> = Vec::with_capacity(count);
+                        for spec in specs.iter() {
+                            let bucket = spec
+                                .bucket
+                                .clone()
+                                .unwrap_or_else(|| default_bucket.clone());
+                            let mut task = Task::new(bucket, spec.title.clone(), now);
+                            task.parent_id = parent_uuid;
+                            task.description = spec.description.clone();
+                            if let Some(p) = spec.priority {
+                                task.priority = p;
+                            }
+                            if let Some(prog) = spec.progress {
+                                task.set_progress(prog, now);
+                            }
+                            if let Some(due) = spec.due_date {
+                                task.due_date = Some(due);
+                            }
+                            new_ids.push(task.id);
+                            tasks.push(task);
                         }
-                    }
-                    println!(
-                        "  ◆ Decomposed \"{}\" into {} sub-task{}:",
-                        parent_title,
-                        count,
-                        if count == 1 { "" } else { "s" }
-                    );
-                    for (i, spec) in specs.iter().enumerate() {
-                        let deps_str = if spec.depends_on.is_empty() {
-                            String::new()
-                        } else {
-                            let labels: Vec<String> = spec
+                        for (i, spec) in specs.iter().enumerate() {
+                            if spec.depends_on.is_empty() {
+                                continue;
+                            }
+                            let task_id = new_ids[i];
+                            let dep_ids: Vec<Uuid> = spec
                                 .depends_on
                                 .iter()
-                                .map(|&idx| format!("{}", idx + 1))
+                                .filter_map(|&idx| new_ids.get(idx).copied())
+                                .filter(|&dep_id| dep_id != task_id)
                                 .collect();
-                            format!(" (after: {})", labels.join(", "))
-                        };
-                        println!("    {}. {}{}", i + 1, spec.title, deps_str);
+                            if let Some(task) = index.get_mut(&mut tasks, task_id) {
+                                task.dependencies = dep_ids;
+                            }
+                        }
+                        println!(
+                            "  ◆ Decomposed \"{}\" into {} sub-task{}:",
+                            parent_title,
+                            count,
+                            if count == 1 { "" } else { "s" }
+                        );
+                        for (i, spec) in specs.iter().enumerate() {
+                            let deps_str = if spec.depends_on.is_empty() {
+                                String::new()
+                            } else {
+                                let labels: Vec<String> = spec
+                                    .depends_on
+                                    .iter()
+                                    .map(|&idx| format!("{}", idx + 1))
+                                    .collect();
+                                format!(" (after: {})", labels.join(", "))
+                            };
+                            println!("    {}. {}{}", i + 1, spec.title, deps_str);
+                        }
+                        total_changes += count as u32;
                     }
-                    total_changes += count as u32;
                 }
                 llm::TriageAction::BulkUpdate {
                     targets,
+                    filter,
                     instruction,
                 } => {
-                    let task_ids: Vec<Uuid> =
-                        if targets.len() == 1 && targets[0].eq_ignore_ascii_case("all") {
-                            tasks
-                                .iter()
-                                .filter(|t| t.parent_id.is_none())
-                                .map(|t| t.id)
-                                .collect()
-                        } else {
-                            targets
-                                .iter()
-                                .filter_map(|prefix| {
-                                    tasks.iter().find_map(|t| {
-                                        let short =
-                                            t.id.to_string().chars().take(8).collect::<String>();
-                                        if short.eq_ignore_ascii_case(prefix) {
-                                            Some(t.id)
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                })
-                                .collect()
-                        };
+                    let task_ids = resolve_bulk_targets(&tasks, &targets, &filter, false);
 
                     if task_ids.is_empty() {
                         eprintln!("  Warning: no matching tasks found");
                     } else {
-                        let context = build_ai_context(&tasks);
+                        let context = build_ai_context(&tasks, ai_context_filter(&settings));
                         for &tid in &task_ids {
-                            if let Some(task) = tasks.iter().find(|t| t.id == tid) {
+                            if let Some(task) = index.get(&tasks, tid) {
                                 let snapshot = format_task_snapshot(task);
                                 ai.enqueue(llm::AiJob {
                                     task_id: tid,
                                     title: task.title.clone(),
                                     suggested_bucket: task.bucket.clone(),
-                                    context: context.clone(),
-                                    bucket_names: bucket_names(&settings),
-                                    lock_bucket: false,
-                                    lock_priority: false,
-                                    lock_due_date: false,
+                                    context: Arc::clone(&context),
+                                    buckets: settings.buckets.clone(),
+                                    lock_bucket: settings.ai_lock_bucket,
+                                    lock_priority: settings.ai_lock_priority,
+                                    lock_due_date: settings.ai_lock_due_date,
                                     edit_instruction: Some(instruction.clone()),
                                     task_snapshot: Some(snapshot),
                                     triage_input: None,
@@ -8577,6 +13062,7 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                                     chat_history: Vec::new(),
                                     user_profile: settings.user_profile.clone(),
                                     memory_facts: settings.memory_facts.clone(),
+                                    assignee: None,
                                 });
                             }
                         }
@@ -8592,7 +13078,7 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                     // In CLI mode, auto-save without confirmation prompt.
                     settings.memory_facts.push(fact.clone());
                     if let Some(storage) = &storage {
-                        let _ = storage.save_settings(&settings);
+                        let _ = storage.save_settings(&mut settings);
                     }
                     println!("  - Remembered: \"{fact}\"");
                     total_changes += 1;
@@ -8616,12 +13102,27 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                 Vec::new()
             };
 
-            if let Some(task) = tasks.iter_mut().find(|t| t.id == parent_id) {
+            if let Some(task) = index.get_mut(&mut tasks, parent_id) {
                 let now = Utc::now();
-                apply_update(task, &result.update, &deps, now);
+                apply_update(task, &result.update, &deps, &settings, now);
+                if let Some(warning) = unknown_bucket_warning(
+                    &settings,
+                    result
+                        .update
+                        .bucket
+                        .as_deref()
+                        .filter(|_| !settings.ai_lock_bucket),
+                    &task.bucket,
+                ) {
+                    eprintln!("  Warning: {warning}");
+                }
             }
 
-            if !result.sub_task_specs.is_empty() {
+            if !result.sub_task_specs.is_empty()
+                && nesting_depth(&tasks, parent_id) >= MAX_NESTING_DEPTH
+            {
+                eprintln!("  Warning: {NESTING_LIMIT_MSG}");
+            } else if !result.sub_task_specs.is_empty() {
                 let now = Utc::now();
                 let parent_bucket = tasks
                     .iter()
@@ -8660,7 +13161,7 @@ fn run_cli(instruction: &str) -> io::Result<()> {
                         .filter_map(|&idx| new_ids.get(idx).copied())
                         .filter(|&dep_id| dep_id != task_id)
                         .collect();
-                    if let Some(task) = tasks.iter_mut().find(|t| t.id == task_id) {
+                    if let Some(task) = index.get_mut(&mut tasks, task_id) {
                         task.dependencies = dep_ids;
                     }
                 }
@@ -8694,13 +13195,16 @@ fn run_cli(instruction: &str) -> io::Result<()> {
 
     // Persist.
     if total_changes > 0 {
-        if let Some(s) = &storage {
-            s.snapshot("ai triage");
-            if let Err(err) = s.save_tasks(&tasks) {
-                eprintln!("Save failed: {err}");
-            } else {
-                saved = true;
+        match &storage {
+            Some(s) => {
+                s.snapshot("ai triage");
+                if let Err(err) = s.save_tasks(&tasks) {
+                    eprintln!("Save failed: {err}");
+                } else {
+                    saved = true;
+                }
             }
+            None => eprintln!("Save failed: no data directory"),
         }
     }
 
@@ -8716,6 +13220,19 @@ fn run_cli(instruction: &str) -> io::Result<()> {
         );
     }
 
+    // A timeout outranks partial success so scripts know to retry.
+    let code = if total_changes > 0 && !saved {
+        cli::EXIT_STORAGE
+    } else if timed_out {
+        cli::EXIT_AI_TIMEOUT
+    } else if total_changes == 0 {
+        cli::EXIT_NO_CHANGES
+    } else {
+        0
+    };
+    if code != 0 {
+        std::process::exit(code);
+    }
     Ok(())
 }
 
@@ -8723,54 +13240,68 @@ fn print_help() {
     println!("aipm - AI-powered project manager");
     println!();
     println!("Usage:");
-    println!("  aipm                            Open the interactive TUI");
-    println!("  aipm \"<instruction>\"             Run AI instruction headlessly (no TUI)");
-    println!("  aipm task <command>              Task CRUD (see below)");
-    println!("  aipm bucket <command>            Bucket CRUD (see below)");
-    println!("  aipm suggestions <command>       Email suggestions via Apple Mail (see below)");
-    println!("  aipm ingest --image <path>       Extract tasks from an image via AI");
-    println!("  aipm ingest --clipboard          Extract tasks from clipboard image (macOS)");
-    println!("  aipm undo                        Undo the last CLI/AI operation");
-    println!("  aipm history                     List recent undo snapshots");
-    println!("  aipm --help");
-    println!("  aipm --version");
+    println!("  aipm                              Open the interactive TUI");
+    println!("  aipm \"<instruction>\"              Run one AI instruction headlessly, then exit");
+    println!("  aipm task <command>               Task CRUD (see below)");
+    println!("  aipm bucket <command>             Bucket CRUD (see below)");
+    println!("  aipm settings <command>           View or change settings (see below)");
+    println!("  aipm suggestions <command>        Email suggestions via Gmail (see below)");
+    println!("  aipm ingest --image <path>        Extract tasks from an image via AI");
+    println!("  aipm ingest --clipboard           Extract tasks from clipboard image (macOS)");
+    println!("  aipm stats [--cycle-time]         Cycle time, throughput and estimates");
+    println!("  aipm export --format <format>     Export tasks for GitHub Issues or Linear");
+    println!("  aipm status --oneline             One-line task counts for shell prompts");
+    println!("  aipm path                         Show where tasks, settings and snapshots live");
+    println!("  aipm undo                         Undo the last CLI/AI operation");
+    println!("  aipm history                      List recent undo snapshots");
+    println!("  aipm -h, --help                   Show this help");
+    println!("  aipm -V, --version                Print the version");
+    println!("  aipm -v, --verbose ...            Log AI requests, polls and errors to aipm.log");
     println!();
-    println!("Task commands (output JSON):");
-    println!("  aipm task list                   List all tasks");
-    println!("  aipm task show <id>              Show a single task");
-    println!(
-        "  aipm task add --title \"X\" [--bucket \"Y\"] [--priority low|medium|high|critical]"
-    );
-    println!("      [--progress backlog|todo|in-progress|done] [--due YYYY-MM-DD]");
-    println!("      [--description \"...\"] [--parent <id>]");
-    println!("  aipm task edit <id> [--title \"X\"] [--bucket \"Y\"] [--priority ...]");
-    println!("      [--progress ...] [--due YYYY-MM-DD|none] [--description \"...\"]");
-    println!("  aipm task delete <id>            Delete task and its sub-tasks");
+    print!("{}", cli::TASK_USAGE);
+    println!();
+    print!("{}", cli::BUCKET_USAGE);
+    println!();
+    print!("{}", cli::SETTINGS_USAGE);
+    println!();
+    print!("{}", cli::SUGGESTIONS_USAGE);
+    println!();
+    print!("{}", INGEST_USAGE);
+    println!();
+    print!("{}", cli::STATS_USAGE);
     println!();
-    println!("Bucket commands (output JSON):");
-    println!("  aipm bucket list                 List all buckets");
-    println!("  aipm bucket add <name> [--description \"...\"]");
-    println!("  aipm bucket rename <old> <new>");
-    println!("  aipm bucket delete <name>        Moves tasks to first remaining bucket");
+    print!("{}", cli::EXPORT_USAGE);
     println!();
-    println!("Suggestions commands (Apple Mail + AI):");
-    println!("  aipm suggestions list            List unread emails and show AI filtering");
-    println!("  aipm suggestions sync [--limit N] Create tasks from actionable emails");
+    print!("{}", cli::STATUS_USAGE);
     println!();
-    println!("Undo / History:");
-    println!("  aipm undo                        Restore state before last CLI/AI change");
-    println!("  aipm history                     List available undo snapshots (JSON)");
+    print!("{}", cli::PATH_USAGE);
     println!();
-    println!("AI examples:");
+    print!("{}", cli::HISTORY_USAGE);
+    println!();
+    println!("One-shot AI mode:");
+    println!("  Any other non-flag arguments are joined into a single instruction,");
+    println!("  sent to the AI along with your current tasks, and the resulting");
+    println!("  changes are saved (with an undo snapshot). Examples:");
     println!("  aipm \"break down all tickets into sub-issues\"");
     println!("  aipm \"mark the onboarding task as done\"");
-    println!("  aipm \"create a task to set up CI/CD pipeline\"");
+    println!("  aipm create a task to set up CI/CD pipeline");
     println!();
     println!("TUI input (tab 1):");
-    println!("  <text>                  (AI routes into your configured buckets)");
-    println!("  @<id> <instruction>      (AI-edit a specific task by ID prefix)");
-    println!("  /clear                   (clear AI conversation context)");
-    println!("  /exit                    (quit the app)");
+    println!("  <text>                            AI routes into your configured buckets");
+    println!("  @<id> <instruction>               AI-edit a specific task by ID prefix");
+    for (cmd, args, desc) in SLASH_COMMANDS {
+        let usage = if args.is_empty() {
+            format!("/{}", cmd)
+        } else {
+            format!("/{} {}", cmd, args)
+        };
+        println!("  {:<33} {}", usage, desc);
+    }
+    println!("  Press ? in the TUI for all keybindings.");
+    println!();
+    println!("Exit codes (one-shot AI mode and ingest; 1 and 4 for the other commands too):");
+    println!("  0 success   1 usage or configuration error   2 no changes made");
+    println!("  3 AI request timed out   4 tasks or settings could not be saved");
     println!();
     println!("Environment:");
     println!("  OPENAI_API_KEY=...                (for gpt-* models)");
@@ -8784,6 +13315,121 @@ mod tests {
     use super::*;
     use chrono::NaiveDate;
 
+    #[test]
+    fn at_mention_refuses_colliding_prefixes() {
+        let now = Utc::now();
+        let tasks: Vec<Task> = [
+            "0badcafe-0000-4000-8000-000000000001",
+            "0badcafe-1000-4000-8000-000000000002",
+        ]
+        .iter()
+        .map(|id| {
+            let mut t = Task::new("Team".into(), "T".into(), now);
+            t.id = Uuid::parse_str(id).unwrap();
+            t
+        })
+        .collect();
+        let mut index = TaskIndex::default();
+
+        let err = resolve_at_mention(&tasks, &mut index, "0badcafe mark done", None);
+        assert!(err.unwrap_err().contains("ambiguous id"));
+        let (target, rest) =
+            resolve_at_mention(&tasks, &mut index, "0BADCAFE-1 mark done", None).unwrap();
+        assert_eq!(target, Some(tasks[1].id));
+        assert_eq!(rest, "mark done");
+        let (target, rest) =
+            resolve_at_mention(&tasks, &mut index, "fix it", tasks.first().map(|t| t.id)).unwrap();
+        assert_eq!((target, rest.as_str()), (Some(tasks[0].id), "fix it"));
+    }
+
+    #[test]
+    fn ai_parent_change_refuses_ambiguous_ids() {
+        let then = Utc::now() - chrono::Duration::days(1);
+        let mut tasks: Vec<Task> = [
+            "0badcafe-0000-4000-8000-000000000001",
+            "0badcafe-1000-4000-8000-000000000002",
+            "12345678-0000-4000-8000-000000000003",
+        ]
+        .iter()
+        .map(|id| {
+            let mut t = Task::new("Team".into(), "T".into(), then);
+            t.id = Uuid::parse_str(id).unwrap();
+            t
+        })
+        .collect();
+        let child = tasks[2].id;
+        let now = Utc::now();
+
+        let err = apply_parent_change(&mut tasks, child, "0badcafe", now).unwrap_err();
+        assert!(err.contains("ambiguous id"));
+        assert_eq!(tasks[2].parent_id, None);
+
+        apply_parent_change(&mut tasks, child, "0badcafe-1", now).unwrap();
+        assert_eq!(tasks[2].parent_id, Some(tasks[1].id));
+        assert_eq!(tasks[2].updated_at, now);
+    }
+
+    #[test]
+    fn checklist_lines_lose_their_markers() {
+        let items: Vec<&str> = "- [ ] write spec\n\n  * [x] review\n2) ship it\n• tell team\n- \n"
+            .lines()
+            .filter_map(checklist_item)
+            .collect();
+        assert_eq!(items, ["write spec", "review", "ship it", "tell team"]);
+
+        let items: Vec<&str> = "1. 2.5h review\n2.5h review\n10) call\n3)"
+            .lines()
+            .filter_map(checklist_item)
+            .collect();
+        assert_eq!(items, ["2.5h review", "2.5h review", "call"]);
+    }
+
+    #[test]
+    fn parent_gauge_follows_done_share() {
+        let now = Utc::now();
+        let mut parent = Task::new("Team".into(), "Parent".into(), now);
+        parent.progress = Progress::InProgress;
+        let gauge_with = |done: usize| {
+            let mut tasks = vec![parent.clone()];
+            for i in 0..10 {
+                let mut child = Task::new("Team".into(), "c".into(), now);
+                child.parent_id = Some(parent.id);
+                if i < done {
+                    child.progress = Progress::Done;
+                }
+                tasks.push(child);
+            }
+            progress_gauge(&tasks, &parent)
+        };
+        assert_eq!(gauge_with(3), "█▎░░");
+        assert_eq!(gauge_with(9), "███▋");
+        assert_eq!(gauge_with(10), "████");
+        assert_eq!(progress_gauge(&[parent.clone()], &parent), "███░");
+    }
+
+    #[test]
+    fn sub_issues_follow_manual_order() {
+        let now = Utc::now();
+        let parent = Task::new("Team".into(), "Parent".into(), now);
+        let mut tasks = vec![parent.clone()];
+        for (title, order, priority) in [
+            ("second", Some(1), Priority::Low),
+            ("first", Some(0), Priority::Low),
+            ("added later", None, Priority::Critical),
+        ] {
+            let mut child = Task::new("Team".into(), title.into(), now);
+            child.parent_id = Some(parent.id);
+            child.sort_order = order;
+            child.priority = priority;
+            tasks.push(child);
+        }
+        let titles: Vec<&str> = visible_children_of(&tasks, parent.id, &AiSettings::default())
+            .into_iter()
+            .map(|i| tasks[i].title.as_str())
+            .collect();
+        assert_eq!(titles, ["first", "second", "added later"]);
+    }
+
     #[test]
     fn apply_update_clears_due_date_when_requested() {
         let now = Utc::now();
@@ -8795,8 +13441,20 @@ mod tests {
             ..llm::TaskUpdate::default()
         };
 
-        assert!(apply_update(&mut task, &update, &[], now));
+        assert!(apply_update(
+            &mut task,
+            &update,
+            &[],
+            &AiSettings::default(),
+            now
+        ));
         assert_eq!(task.due_date, None);
+
+        task.due_date = Some(NaiveDate::from_ymd_opt(2026, 3, 1).expect("valid date constant"));
+        let mut locked = AiSettings::default();
+        locked.ai_lock_due_date = true;
+        assert!(!apply_update(&mut task, &update, &[], &locked, now));
+        assert!(task.due_date.is_some());
     }
 
     #[test]
@@ -8808,7 +13466,361 @@ mod tests {
 
         let update = llm::TaskUpdate::default();
 
-        assert!(!apply_update(&mut task, &update, &[], now));
+        assert!(!apply_update(
+            &mut task,
+            &update,
+            &[],
+            &AiSettings::default(),
+            now
+        ));
         assert_eq!(task.due_date, Some(due));
     }
+
+    #[test]
+    fn apply_update_ignores_blank_description() {
+        let now = Utc::now();
+        let mut task = Task::new("Inbox".to_string(), "Task".to_string(), now);
+        task.description = "Written by hand".to_string();
+
+        for blank in ["", "  \n"] {
+            let update = llm::TaskUpdate {
+                is_edit: true,
+                description: Some(blank.to_string()),
+                ..llm::TaskUpdate::default()
+            };
+            assert!(!apply_update(
+                &mut task,
+                &update,
+                &[],
+                &AiSettings::default(),
+                now
+            ));
+            assert_eq!(task.description, "Written by hand");
+        }
+    }
+
+    #[test]
+    fn triage_context_can_skip_hidden_tasks() {
+        let now = Utc::now();
+        let parent = Task::new("Team".to_string(), "Launch".to_string(), now);
+        let mut done = Task::new("Team".to_string(), "Write copy".to_string(), now);
+        done.parent_id = Some(parent.id);
+        done.progress = Progress::Done;
+        let tasks = vec![parent, done];
+
+        let mut settings = AiSettings::default();
+        assert!(build_triage_context(&tasks, ai_context_filter(&settings)).contains("Write copy"));
+        settings.ai_context_visible_only = true;
+        let ctx = build_triage_context(&tasks, ai_context_filter(&settings));
+        assert!(ctx.contains("Launch") && !ctx.contains("Write copy"));
+    }
+
+    #[test]
+    fn triage_context_lists_truncated_deps() {
+        let now = Utc::now();
+        let mut task = Task::new("Team".to_string(), "Ship".to_string(), now);
+        task.dependencies = (0..4).map(|_| Uuid::new_v4()).collect();
+        let first = task.dependencies[0].to_string()[..8].to_string();
+        let ctx = build_triage_context(&[task], |_| true);
+        assert!(ctx.contains(&format!("| deps: {first}, ")));
+        assert!(ctx.contains(" +1 | no description"));
+    }
+
+    #[test]
+    fn apply_update_only_moves_to_known_buckets() {
+        let now = Utc::now();
+        let settings = AiSettings::default();
+        let known = default_bucket_name(&settings);
+        let mut task = Task::new("Elsewhere".to_string(), "Task".to_string(), now);
+
+        let mut update = llm::TaskUpdate {
+            bucket: Some("Nowhere".to_string()),
+            ..llm::TaskUpdate::default()
+        };
+        assert!(!apply_update(&mut task, &update, &[], &settings, now));
+        assert_eq!(task.bucket, "Elsewhere");
+        assert_eq!(
+            unknown_bucket_warning(&settings, update.bucket.as_deref(), &task.bucket).as_deref(),
+            Some("AI suggested unknown bucket 'Nowhere', placed in Elsewhere")
+        );
+
+        update.bucket = Some(known.to_uppercase());
+        assert!(apply_update(&mut task, &update, &[], &settings, now));
+        assert_eq!(task.bucket, known);
+        assert_eq!(
+            unknown_bucket_warning(&settings, update.bucket.as_deref(), &task.bucket),
+            None
+        );
+    }
+
+    #[test]
+    fn extract_assignee_strips_token() {
+        assert_eq!(
+            extract_assignee("fix login @@sam  by friday"),
+            ("fix login by friday".to_string(), Some("sam".to_string()))
+        );
+        assert_eq!(
+            extract_assignee("email @bob about @@"),
+            ("email @bob about @@".to_string(), None)
+        );
+        assert_eq!(
+            extract_assignee("  plan:\n- draft  outline\n"),
+            ("  plan:\n- draft  outline\n".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn resolve_bulk_targets_applies_filter() {
+        let now = Utc::now();
+        let mut marketing = Task::new("Marketing".to_string(), "Launch post".to_string(), now);
+        marketing.priority = Priority::Low;
+        let team = Task::new("Team".to_string(), "Standup".to_string(), now);
+        let mut child = Task::new("Marketing".to_string(), "Draft".to_string(), now);
+        child.parent_id = Some(marketing.id);
+        let tasks = vec![marketing.clone(), team.clone(), child.clone()];
+        let filter = llm::BulkFilter {
+            bucket: Some("Marketing".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            resolve_bulk_targets(&tasks, &[], &filter, false),
+            vec![marketing.id]
+        );
+        assert_eq!(
+            resolve_bulk_targets(&tasks, &["all".to_string()], &filter, true),
+            vec![marketing.id, child.id]
+        );
+        let prefix = team.id.to_string()[..8].to_string();
+        assert!(resolve_bulk_targets(&tasks, &[prefix], &filter, true).is_empty());
+    }
+
+    #[test]
+    fn quick_edit_parses_keywords_and_rejects_free_text() {
+        let settings = AiSettings::default();
+        let quick =
+            ai::parse_quick_edit("in progress p:high bucket team due 2026-03-01", &settings)
+                .expect("all words understood");
+        assert_eq!(quick.progress, Some(Progress::InProgress));
+        assert_eq!(quick.priority, Some(Priority::High));
+        assert_eq!(quick.bucket.as_deref(), Some("Team"));
+        assert_eq!(quick.due_date, Some(NaiveDate::from_ymd_opt(2026, 3, 1)));
+        assert!(ai::parse_quick_edit("done and tell the team", &settings).is_none());
+        assert!(ai::parse_quick_edit("bucket Nowhere", &settings).is_none());
+    }
+
+    #[test]
+    fn infer_new_task_reads_priority_and_due_phrases() {
+        let buckets = vec!["Inbox".to_string(), "Team".to_string()];
+        // A Friday.
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).expect("valid date constant");
+        let date = |m, d| NaiveDate::from_ymd_opt(2026, m, d);
+        let settings = AiSettings::default();
+        let infer = |input: &str| {
+            let hints = ai::infer_new_task(input, &buckets, today, &settings).expect("has a title");
+            (hints.title, hints.priority, hints.due_date, hints.bucket)
+        };
+
+        assert_eq!(
+            infer("urgent: fix login"),
+            (
+                "fix login".to_string(),
+                Some(Priority::Critical),
+                None,
+                "Inbox".to_string()
+            )
+        );
+        assert_eq!(infer("reply to Sam asap").1, Some(Priority::High));
+        assert_eq!(infer("tidy the wiki, low priority").1, Some(Priority::Low));
+        assert_eq!(
+            infer("team: ship release by Friday p:high"),
+            (
+                "ship release".to_string(),
+                Some(Priority::High),
+                date(10, 23),
+                "Team".to_string()
+            )
+        );
+        assert_eq!(
+            infer("file taxes due 2027-03-01").2,
+            NaiveDate::from_ymd_opt(2027, 3, 1)
+        );
+        assert_eq!(infer("send invoice due by mon.").2, date(10, 19));
+        assert_eq!(infer("call the bank tomorrow").2, date(10, 17));
+        assert_eq!(infer("renew domain in 2 weeks").2, date(10, 30));
+        assert_eq!(infer("prep demo before next tue").2, date(10, 20));
+
+        // Words that only look like hints stay in the title.
+        assert_eq!(
+            infer("plan Friday standup by the lake"),
+            (
+                "plan Friday standup by the lake".to_string(),
+                None,
+                None,
+                "Inbox".to_string()
+            )
+        );
+        assert_eq!(infer("high score table").1, None);
+        assert!(ai::infer_new_task("urgent by friday", &buckets, today, &settings).is_none());
+    }
+
+    #[test]
+    fn due_phrases_skip_days_off_once_work_days_are_set() {
+        // A Friday, with the following Monday a holiday.
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).expect("valid date constant");
+        let date = |m, d| NaiveDate::from_ymd_opt(2026, m, d);
+        let mut settings = AiSettings::default();
+        settings.work_days = parse_work_days("mon-fri").expect("valid work days");
+        settings.holidays = parse_holidays("2026-10-19").expect("valid holidays");
+        let due = |input: &str| {
+            ai::infer_new_task(input, &[], today, &settings)
+                .expect("has a title")
+                .due_date
+        };
+
+        assert_eq!(due("call the bank tomorrow"), date(10, 20));
+        assert_eq!(due("send invoice in 2 business days"), date(10, 21));
+        assert_eq!(due("renew domain in 3 workdays"), date(10, 22));
+        assert_eq!(due("file taxes due 2026-10-17"), date(10, 17));
+        assert_eq!(parse_work_days("sun-thu").map(|days| days.len()), Some(5));
+        assert!(parse_work_days("funday").is_none());
+    }
+
+    #[test]
+    fn due_today_turns_overdue_at_workday_end() {
+        let mut settings = AiSettings::default();
+        settings.utc_offset_minutes = Some(0);
+        let day = NaiveDate::from_ymd_opt(2026, 10, 16).expect("valid date constant");
+        let at = |h, m| day.and_hms_opt(h, m, 0).expect("valid time").and_utc();
+
+        assert!(!settings.is_overdue_at(day, at(23, 59)));
+        assert!(settings.is_overdue_at(day - chrono::Duration::days(1), at(0, 0)));
+
+        settings.workday_end = parse_workday_end("17:30").expect("valid time");
+        assert!(!settings.is_overdue_at(day, at(17, 29)));
+        assert!(settings.is_overdue_at(day, at(17, 30)));
+        assert!(settings.is_overdue_at(day, at(18, 0)));
+        assert!(!settings.is_overdue_at(day + chrono::Duration::days(1), at(18, 0)));
+
+        // The end is local time.
+        settings.utc_offset_minutes = Some(120);
+        assert!(!settings.is_overdue_at(day, at(15, 29)));
+        assert!(settings.is_overdue_at(day, at(15, 30)));
+    }
+
+    #[test]
+    fn backdate_keeps_the_day_and_rejects_the_future() {
+        let mut settings = AiSettings::default();
+        for offset in [None, Some(14 * 60), Some(-12 * 60)] {
+            settings.utc_offset_minutes = offset;
+            let today = settings.today();
+            let last_week = today - chrono::Duration::days(7);
+            let created = settings
+                .backdate(last_week)
+                .expect("past dates are allowed");
+            assert_eq!(created.date_naive(), last_week);
+            assert_eq!(settings.to_local(created).date_naive(), last_week);
+            assert!(settings.backdate(today).is_some());
+            assert!(settings
+                .backdate(today + chrono::Duration::days(1))
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn merge_keeps_changes_from_both_sides() {
+        let now = Utc::now();
+        let base = vec![
+            Task::new("Team".to_string(), "Edited here".to_string(), now),
+            Task::new("Team".to_string(), "Edited outside".to_string(), now),
+            Task::new("Team".to_string(), "Edited by both".to_string(), now),
+            Task::new("Team".to_string(), "Deleted here".to_string(), now),
+            Task::new(
+                "Team".to_string(),
+                "Deleted here, edited outside".to_string(),
+                now,
+            ),
+        ];
+        let mut local = base.clone();
+        local[0].priority = Priority::High;
+        local[2].priority = Priority::Low;
+        local.retain(|t| !t.title.starts_with("Deleted here"));
+        let mut disk = base.clone();
+        disk[1].priority = Priority::High;
+        disk[2].priority = Priority::Critical;
+        disk[4].progress = Progress::Todo;
+        disk.push(Task::new(
+            "Team".to_string(),
+            "Added outside".to_string(),
+            now,
+        ));
+
+        let (merged, conflicts) = storage::merge_tasks(&base, &local, &disk);
+        let find = |title: &str| merged.iter().find(|t| t.title == title);
+        assert_eq!(conflicts, 2);
+        assert_eq!(find("Edited here").unwrap().priority, Priority::High);
+        assert_eq!(find("Edited outside").unwrap().priority, Priority::High);
+        assert_eq!(find("Edited by both").unwrap().priority, Priority::Low);
+        assert!(find("Deleted here").is_none());
+        assert_eq!(
+            find("Deleted here, edited outside").unwrap().progress,
+            Progress::Todo
+        );
+        assert!(find("Added outside").is_some());
+    }
+
+    #[test]
+    fn stage_labels_rename_only_the_given_stages() {
+        let mut settings = AiSettings::default();
+        settings.stage_labels = parse_stage_labels("Ideas, , Review").unwrap();
+        assert_eq!(settings.stage_label(Progress::Backlog), "Ideas");
+        assert_eq!(settings.stage_label(Progress::Todo), "Todo");
+        assert_eq!(settings.stage_labels_text(), "Ideas, Todo, Review, Done");
+        assert!(parse_stage_labels("a, b, c, d, e").is_none());
+
+        settings.stage_labels = parse_stage_labels("Backlog, Todo, In progress, Done").unwrap();
+        assert!(settings.stage_labels.is_empty());
+    }
+
+    #[test]
+    fn snapshot_diff_lists_what_a_restore_changes() {
+        let now = Utc::now();
+        let kept = Task::new("Team".to_string(), "Kept".to_string(), now);
+        let edited = Task::new("Team".to_string(), "Edited".to_string(), now);
+        let deleted = Task::new("Team".to_string(), "Deleted later".to_string(), now);
+        let snapshot = storage::Snapshot {
+            label: "ai triage".to_string(),
+            timestamp: now,
+            tasks: vec![kept.clone(), edited.clone(), deleted],
+            settings: AiSettings::default(),
+        };
+
+        let mut edited_now = edited;
+        edited_now.progress = Progress::Done;
+        let created = Task::new("Team".to_string(), "Created by AI".to_string(), now);
+        let current = vec![kept, edited_now, created];
+
+        assert_eq!(
+            storage::diff_snapshot(&current, &snapshot),
+            storage::SnapshotDiff {
+                added: vec!["Deleted later".to_string()],
+                removed: vec!["Created by AI".to_string()],
+                modified: vec!["Edited".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn redact_masks_keys_but_not_words() {
+        let msg = "AI HTTP 401: Incorrect API key provided: sk-proj-abcdef123456XYZW, risk-free";
+        assert_eq!(
+            log::redact(msg),
+            "AI HTTP 401: Incorrect API key provided: ••••XYZW, risk-free"
+        );
+        assert_eq!(
+            log::redact("GET https://x.test/v1?key=0123456789abcd failed"),
+            "GET https://x.test/v1?key=••••abcd failed"
+        );
+        assert_eq!(log::redact(&"é".repeat(400)).chars().count(), 301);
+    }
 }
@@ -1,4 +1,6 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -10,7 +12,7 @@ pub struct BucketDef {
 }
 
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Progress {
     Backlog,
     Todo,
@@ -69,7 +71,7 @@ impl Progress {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Priority {
     Low,
     Medium,
@@ -86,6 +88,84 @@ impl Priority {
             Priority::Critical => "Critical",
         }
     }
+
+    /// This priority raised by `levels`, capped at Critical.
+    pub fn raised(self, levels: usize) -> Priority {
+        const ORDER: [Priority; 4] = [
+            Priority::Low,
+            Priority::Medium,
+            Priority::High,
+            Priority::Critical,
+        ];
+        let idx = ORDER.iter().position(|p| *p == self).unwrap_or(0);
+        ORDER[(idx + levels).min(ORDER.len() - 1)]
+    }
+}
+
+/// Card order within a bucket or Kanban column, cycled with `o`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortMode {
+    #[default]
+    Default,
+    DueDate,
+    Priority,
+    Alphabetical,
+}
+
+impl SortMode {
+    pub fn title(self) -> &'static str {
+        match self {
+            SortMode::Default => "Default",
+            SortMode::DueDate => "Due date",
+            SortMode::Priority => "Priority",
+            SortMode::Alphabetical => "Alphabetical",
+        }
+    }
+
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Default => SortMode::DueDate,
+            SortMode::DueDate => SortMode::Priority,
+            SortMode::Priority => SortMode::Alphabetical,
+            SortMode::Alphabetical => SortMode::Default,
+        }
+    }
+}
+
+/// First day of the week for the calendar grid, timeline gridlines and weekly stats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    pub fn title(self) -> &'static str {
+        match self {
+            WeekStart::Monday => "Monday",
+            WeekStart::Sunday => "Sunday",
+        }
+    }
+
+    pub fn toggled(self) -> WeekStart {
+        match self {
+            WeekStart::Monday => WeekStart::Sunday,
+            WeekStart::Sunday => WeekStart::Monday,
+        }
+    }
+
+    pub fn weekday(self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+
+    /// The first day of the week containing `date`.
+    pub fn week_of(self, date: NaiveDate) -> NaiveDate {
+        date.week(self.weekday()).first_day()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,6 +183,56 @@ pub struct Task {
     pub created_at: DateTime<Utc>,
     pub start_date: Option<DateTime<Utc>>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Escalation levels already written into `priority` by auto-escalate.
+    #[serde(default)]
+    pub escalated_levels: usize,
+    #[serde(default)]
+    pub estimate_hours: Option<f32>,
+    #[serde(default)]
+    pub actual_hours: Option<f32>,
+    /// Append-only activity log, oldest first.
+    #[serde(default)]
+    pub notes: Vec<(DateTime<Utc>, String)>,
+    /// Progress transitions, oldest first, capped at `MAX_PROGRESS_HISTORY`.
+    #[serde(default)]
+    pub progress_history: Vec<(Progress, DateTime<Utc>)>,
+    /// Hand-set position among its siblings; unset tasks sort after set ones.
+    #[serde(default)]
+    pub sort_order: Option<u32>,
+    /// When a parent's progress was last set by hand. Auto-sync from its
+    /// children leaves it alone until a child changes stage after this.
+    #[serde(default)]
+    pub progress_locked_at: Option<DateTime<Utc>>,
+    /// Gmail message the task was accepted from as a suggestion. Archiving
+    /// that email clears the link; the task stays.
+    #[serde(default)]
+    pub email_id: Option<String>,
+    #[serde(default)]
+    pub email_subject: Option<String>,
+    /// Shown by the `/pinned` board filter, whatever its bucket.
+    #[serde(default)]
+    pub pinned: bool,
+    /// A name from `TASK_COLORS`, drawn as a swatch on the card. Only for
+    /// grouping by eye; nothing else reads it.
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+pub const MAX_PROGRESS_HISTORY: usize = 50;
+
+/// Colors a task can be marked with, in the order `←/→` cycles them.
+pub const TASK_COLORS: [&str; 8] = [
+    "red", "orange", "yellow", "green", "teal", "blue", "purple", "pink",
+];
+
+/// The palette name matching `input`, ignoring case.
+pub fn parse_task_color(input: &str) -> Option<&'static str> {
+    let input = input.trim();
+    TASK_COLORS
+        .into_iter()
+        .find(|c| c.eq_ignore_ascii_case(input))
 }
 
 impl Task {
@@ -120,6 +250,18 @@ impl Task {
             created_at: now,
             start_date: None,
             updated_at: now,
+            assignee: None,
+            escalated_levels: 0,
+            estimate_hours: None,
+            actual_hours: None,
+            notes: Vec::new(),
+            progress_history: Vec::new(),
+            sort_order: None,
+            progress_locked_at: None,
+            email_id: None,
+            email_subject: None,
+            pinned: false,
+            color: None,
         }
     }
 
@@ -139,6 +281,55 @@ impl Task {
 
         self.progress = next;
         self.updated_at = now;
+        // Reopened later, it escalates again from its current priority.
+        if next == Progress::Done {
+            self.escalated_levels = 0;
+        }
+
+        self.progress_history.push((next, now));
+        if self.progress_history.len() > MAX_PROGRESS_HISTORY {
+            let excess = self.progress_history.len() - MAX_PROGRESS_HISTORY;
+            self.progress_history.drain(..excess);
+        }
+    }
+
+    /// Set the due date. A new date restarts auto-escalate's count, so a
+    /// deadline moved later can escalate again as it comes near.
+    pub fn set_due_date(&mut self, due: Option<NaiveDate>) {
+        if self.due_date != due {
+            self.due_date = due;
+            self.escalated_levels = 0;
+        }
+    }
+
+    pub fn add_note(&mut self, text: String, now: DateTime<Utc>) {
+        self.notes.push((now, text));
+        self.updated_at = now;
+    }
+
+    /// When the task was last moved to Done, if it is still finished.
+    pub fn completed_at(&self) -> Option<DateTime<Utc>> {
+        if !matches!(self.progress, Progress::Done | Progress::Archived) {
+            return None;
+        }
+        self.progress_history
+            .iter()
+            .rev()
+            .find(|(p, _)| *p == Progress::Done)
+            .map(|(_, at)| *at)
+    }
+
+    /// Time from first entering In progress to completion. Falls back to
+    /// `start_date` for tasks started before transitions were recorded.
+    pub fn cycle_time(&self) -> Option<chrono::Duration> {
+        let done = self.completed_at()?;
+        let started = self
+            .progress_history
+            .iter()
+            .find(|(p, _)| *p == Progress::InProgress)
+            .map(|(_, at)| *at)
+            .or(self.start_date)?;
+        (done >= started).then(|| done - started)
     }
 
     pub fn advance_progress(&mut self, now: DateTime<Utc>) {
@@ -152,6 +343,219 @@ impl Task {
     }
 }
 
+/// Parses effort like "1h30m", "90m", "2h" or a plain decimal number of hours.
+pub fn parse_hours(input: &str) -> Option<f32> {
+    let s: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if s.is_empty() {
+        return None;
+    }
+    if let Ok(hours) = s.parse::<f32>() {
+        return (hours >= 0.0 && hours.is_finite()).then_some(hours);
+    }
+
+    let mut total = 0.0f32;
+    let mut num = String::new();
+    let mut seen_unit = false;
+    for c in s.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            num.push(c);
+            continue;
+        }
+        let value: f32 = num.parse().ok()?;
+        num.clear();
+        total += match c {
+            'h' => value,
+            'm' => value / 60.0,
+            _ => return None,
+        };
+        seen_unit = true;
+    }
+    (seen_unit && num.is_empty()).then_some(total)
+}
+
+/// Formats hours compactly: "5h", "45m", "1h30m".
+pub fn format_hours(hours: f32) -> String {
+    let mins = (hours * 60.0).round() as i64;
+    let (h, m) = (mins / 60, mins % 60);
+    match (h, m) {
+        (_, 0) => format!("{h}h"),
+        (0, _) => format!("{m}m"),
+        _ => format!("{h}h{m}m"),
+    }
+}
+
+/// Compact age of `dt` relative to `now`: "just now", "5m ago", "3h ago",
+/// "2d ago", "3w ago", then the date itself ("Mar 4", with the year once it
+/// differs from `now`'s). Future times count as "just now".
+pub fn humanize_age(dt: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - dt).num_seconds().max(0);
+    let (mins, hours, days) = (secs / 60, secs / 3600, secs / 86_400);
+    if mins < 1 {
+        "just now".to_string()
+    } else if hours < 1 {
+        format!("{mins}m ago")
+    } else if days < 1 {
+        format!("{hours}h ago")
+    } else if days < 7 {
+        format!("{days}d ago")
+    } else if days < 28 {
+        format!("{}w ago", days / 7)
+    } else if dt.year() == now.year() {
+        dt.format("%b %-d").to_string()
+    } else {
+        dt.format("%b %-d %Y").to_string()
+    }
+}
+
+/// Unfinished tasks due within this many days are escalated one priority level.
+pub const ESCALATE_WITHIN_DAYS: i64 = 2;
+
+/// How many levels `task` should be escalated on `today`: one when unfinished
+/// and due within `ESCALATE_WITHIN_DAYS` (including today), two when overdue.
+pub fn escalation_levels(task: &Task, today: NaiveDate) -> usize {
+    let Some(due) = task.due_date else {
+        return 0;
+    };
+    if matches!(task.progress, Progress::Done | Progress::Archived) {
+        return 0;
+    }
+    let days_left = (due - today).num_days();
+    if days_left < 0 {
+        2
+    } else if days_left <= ESCALATE_WITHIN_DAYS {
+        1
+    } else {
+        0
+    }
+}
+
+/// Priority to display for `task` on `today`, excluding levels auto-escalate
+/// has already stored. Never lowers the stored priority.
+pub fn effective_priority(task: &Task, today: NaiveDate) -> Priority {
+    let pending = escalation_levels(task, today).saturating_sub(task.escalated_levels);
+    task.priority.raised(pending)
+}
+
+/// Id lookups for a task list that is edited in place. A cached position is
+/// checked against the list before it is returned, and the index rebuilds
+/// itself when it finds one stale, so code that pushes, removes or reorders
+/// tasks doesn't need to keep it in sync.
+#[derive(Debug, Default)]
+pub struct TaskIndex {
+    positions: HashMap<Uuid, usize>,
+    /// The first 8 hex digits of each id (the short id shown in the UI and
+    /// used by the AI) with the task's position. Ordered so a shorter prefix
+    /// is a range scan, and a set so colliding short ids are all kept.
+    short_ids: BTreeSet<(u32, usize)>,
+}
+
+fn short_key(id: Uuid) -> u32 {
+    let b = id.as_bytes();
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+impl TaskIndex {
+    pub fn position(&mut self, tasks: &[Task], id: Uuid) -> Option<usize> {
+        if let Some(&i) = self.positions.get(&id) {
+            if tasks.get(i).is_some_and(|t| t.id == id) {
+                return Some(i);
+            }
+        }
+        let i = tasks.iter().position(|t| t.id == id)?;
+        self.rebuild(tasks);
+        Some(i)
+    }
+
+    pub fn get<'a>(&mut self, tasks: &'a [Task], id: Uuid) -> Option<&'a Task> {
+        self.position(tasks, id).map(|i| &tasks[i])
+    }
+
+    pub fn get_mut<'a>(&mut self, tasks: &'a mut [Task], id: Uuid) -> Option<&'a mut Task> {
+        self.position(tasks, id).map(|i| &mut tasks[i])
+    }
+
+    /// Id of the task named by `prefix`: a full id, or 4-8 leading hex digits
+    /// (any case). A prefix shared by several tasks is an error rather than a
+    /// guess, since acting on the wrong task is worse than asking again.
+    pub fn resolve(&mut self, tasks: &[Task], prefix: &str) -> Result<Uuid, String> {
+        let prefix = prefix.trim();
+        match self.matching(tasks, prefix).as_slice() {
+            [] => Err(format!("task {prefix} not found")),
+            [id] => Ok(*id),
+            many => Err(format!(
+                "ambiguous id {prefix} ({} tasks), use more characters",
+                many.len()
+            )),
+        }
+    }
+
+    /// Every task `prefix` could name. A full id only ever matches itself,
+    /// and anything that isn't an id matches nothing.
+    pub fn matching(&mut self, tasks: &[Task], prefix: &str) -> Vec<Uuid> {
+        if let Ok(id) = Uuid::parse_str(prefix) {
+            return self.position(tasks, id).map(|_| id).into_iter().collect();
+        }
+        if prefix.len() > 8 {
+            let lower = prefix.to_ascii_lowercase();
+            if lower.len() > 36 || !lower.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+                return Vec::new();
+            }
+            let mut ids = self.matching(tasks, &lower[..8]);
+            ids.retain(|id| id.to_string().starts_with(&lower));
+            return ids;
+        }
+        if !(4..=8).contains(&prefix.len()) || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Vec::new();
+        }
+        let Ok(key) = u32::from_str_radix(prefix, 16) else {
+            return Vec::new();
+        };
+        let shift = 32 - 4 * prefix.len() as u32;
+        let lo = key << shift;
+        let hi = lo | ((1 << shift) - 1);
+        if self.positions.len() != tasks.len() {
+            self.rebuild(tasks);
+        }
+        let mut matches = self.short_matches(tasks, lo, hi);
+        let stale = match &matches {
+            None => true,
+            // A task swapped in at the same position isn't in the index yet.
+            Some(m) => m.is_empty() && tasks.iter().any(|t| (lo..=hi).contains(&short_key(t.id))),
+        };
+        if stale {
+            self.rebuild(tasks);
+            matches = self.short_matches(tasks, lo, hi);
+        }
+        matches.unwrap_or_default()
+    }
+
+    /// Ids whose short id falls in `lo..=hi`, or None if an entry is stale.
+    fn short_matches(&self, tasks: &[Task], lo: u32, hi: u32) -> Option<Vec<Uuid>> {
+        self.short_ids
+            .range((lo, 0)..=(hi, usize::MAX))
+            .map(|&(key, i)| {
+                tasks
+                    .get(i)
+                    .filter(|t| short_key(t.id) == key)
+                    .map(|t| t.id)
+            })
+            .collect()
+    }
+
+    fn rebuild(&mut self, tasks: &[Task]) {
+        self.positions.clear();
+        self.short_ids.clear();
+        for (i, task) in tasks.iter().enumerate() {
+            self.positions.insert(task.id, i);
+            self.short_ids.insert((short_key(task.id), i));
+        }
+    }
+}
+
 pub fn children_of(tasks: &[Task], parent_id: Uuid) -> Vec<usize> {
     tasks
         .iter()
@@ -161,6 +565,286 @@ pub fn children_of(tasks: &[Task], parent_id: Uuid) -> Vec<usize> {
         .collect()
 }
 
+/// Tasks whose bucket matches none of `buckets`. Columns match names exactly,
+/// so these never show up on the board.
+pub fn orphaned_bucket_tasks(tasks: &[Task], buckets: &[BucketDef]) -> Vec<usize> {
+    tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| !buckets.iter().any(|b| b.name == t.bucket))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Deepest allowed nesting: top-level tasks have sub-issues, and sub-issues
+/// don't get their own (the board and edit overlay only show one level).
+pub const MAX_NESTING_DEPTH: usize = 1;
+
+/// True when putting `child` under `new_parent` would make `child` its own
+/// ancestor, including `child == new_parent`.
+pub fn would_create_ancestor_cycle(tasks: &[Task], child: Uuid, new_parent: Uuid) -> bool {
+    let mut current = Some(new_parent);
+    // Bounded so a loop already present in the data can't hang the walk.
+    for _ in 0..=tasks.len() {
+        let Some(id) = current else {
+            return false;
+        };
+        if id == child {
+            return true;
+        }
+        current = tasks.iter().find(|t| t.id == id).and_then(|t| t.parent_id);
+    }
+    true
+}
+
+/// Number of ancestors above `id`; 0 for a top-level task.
+pub fn nesting_depth(tasks: &[Task], id: Uuid) -> usize {
+    let mut depth = 0;
+    let mut current = tasks.iter().find(|t| t.id == id).and_then(|t| t.parent_id);
+    while let Some(parent) = current {
+        if depth > tasks.len() {
+            break;
+        }
+        depth += 1;
+        current = tasks
+            .iter()
+            .find(|t| t.id == parent)
+            .and_then(|t| t.parent_id);
+    }
+    depth
+}
+
+/// True when moving `child` and its sub-issues under `new_parent` would nest
+/// tasks deeper than `MAX_NESTING_DEPTH`.
+pub fn would_exceed_nesting(tasks: &[Task], child: Uuid, new_parent: Uuid) -> bool {
+    let mut height = 0;
+    let mut level = vec![child];
+    loop {
+        let next: Vec<Uuid> = tasks
+            .iter()
+            .filter(|t| t.parent_id.is_some_and(|p| level.contains(&p)))
+            .map(|t| t.id)
+            .collect();
+        if next.is_empty() || height > tasks.len() {
+            break;
+        }
+        height += 1;
+        level = next;
+    }
+    nesting_depth(tasks, new_parent) + 1 + height > MAX_NESTING_DEPTH
+}
+
+/// Indices of tasks that list `id` as a dependency, i.e. the tasks it blocks.
+pub fn dependents_of(tasks: &[Task], id: Uuid) -> Vec<usize> {
+    tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.dependencies.contains(&id))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Indices of the task's dependencies that aren't done or archived yet, i.e.
+/// what it is still waiting on. Done tasks wait on nothing.
+pub fn unmet_dependencies(tasks: &[Task], task: &Task) -> Vec<usize> {
+    if matches!(task.progress, Progress::Done | Progress::Archived) {
+        return Vec::new();
+    }
+    task.dependencies
+        .iter()
+        .filter_map(|dep| tasks.iter().position(|t| t.id == *dep))
+        .filter(|&i| !matches!(tasks[i].progress, Progress::Done | Progress::Archived))
+        .collect()
+}
+
+/// The task's fields as plain text, for AI prompts and `aipm task show`.
+pub fn format_task_snapshot(task: &Task) -> String {
+    let deps = if task.dependencies.is_empty() {
+        "none".to_string()
+    } else {
+        task.dependencies
+            .iter()
+            .map(|id| id.to_string()[..8].to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let due = task
+        .due_date
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "none".to_string());
+    format!(
+        "Title: {}\nBucket: {}\nDescription: {}\nProgress: {}\nPriority: {}\nDue: {}\nDependencies: {}",
+        task.title,
+        task.bucket,
+        if task.description.trim().is_empty() { "none" } else { task.description.trim() },
+        task.progress.title(),
+        task.priority.title(),
+        due,
+        deps
+    )
+}
+
+/// Remove `id` together with its sub-tasks, and any dependencies on them.
+/// Returns how many tasks were removed.
+pub fn delete_with_children(tasks: &mut Vec<Task>, id: Uuid) -> usize {
+    let deleted: HashSet<Uuid> = std::iter::once(id)
+        .chain(children_of(tasks, id).into_iter().map(|i| tasks[i].id))
+        .collect();
+    let before = tasks.len();
+    tasks.retain(|t| !deleted.contains(&t.id));
+    for task in tasks.iter_mut() {
+        task.dependencies.retain(|dep| !deleted.contains(dep));
+    }
+    before - tasks.len()
+}
+
+/// Drop dependencies on tasks that no longer exist (deleted by hand or by an
+/// older version) and return how many were removed.
+pub fn prune_dangling_dependencies(tasks: &mut [Task]) -> usize {
+    let ids: HashSet<Uuid> = tasks.iter().map(|t| t.id).collect();
+    let mut pruned = 0;
+    for task in tasks.iter_mut() {
+        let before = task.dependencies.len();
+        task.dependencies.retain(|dep| ids.contains(dep));
+        pruned += before - task.dependencies.len();
+    }
+    pruned
+}
+
+/// Copy `root` and all its descendants with fresh ids, rewiring `parent_id`
+/// and dependencies between the copies; links leaving the subtree are dropped.
+/// Copies restart in Backlog with no history. The root's copy comes first.
+pub fn clone_subtree(tasks: &[Task], root: Uuid, now: DateTime<Utc>) -> Vec<Task> {
+    let mut order = vec![root];
+    let mut i = 0;
+    while i < order.len() {
+        let parent = order[i];
+        for task in tasks {
+            if task.parent_id == Some(parent) && !order.contains(&task.id) {
+                order.push(task.id);
+            }
+        }
+        i += 1;
+    }
+    let new_ids: HashMap<Uuid, Uuid> = order.iter().map(|&id| (id, Uuid::new_v4())).collect();
+    order
+        .iter()
+        .filter_map(|id| tasks.iter().find(|t| t.id == *id))
+        .map(|task| {
+            let mut copy = task.clone();
+            copy.id = new_ids[&task.id];
+            copy.parent_id = task.parent_id.and_then(|p| new_ids.get(&p).copied());
+            copy.dependencies = task
+                .dependencies
+                .iter()
+                .filter_map(|d| new_ids.get(d).copied())
+                .collect();
+            copy.progress = Progress::Backlog;
+            copy.created_at = now;
+            copy.start_date = None;
+            copy.updated_at = now;
+            copy.escalated_levels = 0;
+            copy.actual_hours = None;
+            copy.notes.clear();
+            copy.progress_history.clear();
+            copy.progress_locked_at = None;
+            // Archiving the source email shouldn't delete the copies.
+            copy.email_id = None;
+            copy.email_subject = None;
+            copy.pinned = false;
+            copy
+        })
+        .collect()
+}
+
+/// Longest dependency chain of open tasks, weighted by each task's timeline
+/// span (start to due date, or a week when no due date is set). Done and
+/// archived tasks are left out. Returned in dependency order, first
+/// prerequisite first. Empty when no two open tasks depend on each other or
+/// when the dependency graph contains a cycle.
+pub fn critical_path(tasks: &[Task]) -> Vec<Uuid> {
+    let index: HashMap<Uuid, usize> = tasks.iter().enumerate().map(|(i, t)| (t.id, i)).collect();
+    let is_open = |t: &Task| !matches!(t.progress, Progress::Done | Progress::Archived);
+    let deps: Vec<Vec<usize>> = tasks
+        .iter()
+        .map(|t| {
+            if !is_open(t) {
+                return Vec::new();
+            }
+            t.dependencies
+                .iter()
+                .filter_map(|d| index.get(d).copied())
+                .filter(|&d| tasks[d].id != t.id && is_open(&tasks[d]))
+                .collect()
+        })
+        .collect();
+    if deps.iter().all(|d| d.is_empty()) {
+        return Vec::new();
+    }
+
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); tasks.len()];
+    let mut pending: Vec<usize> = deps.iter().map(|d| d.len()).collect();
+    for (i, d) in deps.iter().enumerate() {
+        for &dep in d {
+            dependents[dep].push(i);
+        }
+    }
+
+    let mut order: Vec<usize> = (0..tasks.len()).filter(|&i| pending[i] == 0).collect();
+    let mut head = 0;
+    while head < order.len() {
+        let i = order[head];
+        head += 1;
+        for &next in &dependents[i] {
+            pending[next] -= 1;
+            if pending[next] == 0 {
+                order.push(next);
+            }
+        }
+    }
+    if order.len() < tasks.len() {
+        return Vec::new();
+    }
+
+    let mut length: Vec<i64> = vec![0; tasks.len()];
+    let mut prev: Vec<Option<usize>> = vec![None; tasks.len()];
+    for &i in &order {
+        let best = deps[i].iter().copied().max_by_key(|&d| length[d]);
+        length[i] = span_days(&tasks[i]) + best.map(|d| length[d]).unwrap_or(0);
+        prev[i] = best;
+    }
+
+    // Only a task with a prerequisite ends a chain, so a long standalone task
+    // can't hide a shorter chain.
+    let Some(mut cur) = (0..tasks.len())
+        .filter(|&i| prev[i].is_some())
+        .max_by_key(|&i| length[i])
+    else {
+        return Vec::new();
+    };
+    let mut path = vec![tasks[cur].id];
+    while let Some(p) = prev[cur] {
+        path.push(tasks[p].id);
+        cur = p;
+    }
+    path.reverse();
+    path
+}
+
+/// Where the task starts on the timeline: its start date, or the day it
+/// was created.
+pub fn task_start_date(task: &Task) -> NaiveDate {
+    task.start_date
+        .map(|dt| dt.date_naive())
+        .unwrap_or_else(|| task.created_at.date_naive())
+}
+
+fn span_days(task: &Task) -> i64 {
+    let start = task_start_date(task);
+    let end = task.due_date.unwrap_or(start + chrono::Duration::days(7));
+    (end - start).num_days().max(1)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Suggestion {
     pub id: Uuid,
@@ -169,6 +853,13 @@ pub struct Suggestion {
     pub description: String,
     pub priority: Priority,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub sender: String,
+    #[serde(default)]
+    pub subject: String,
+    /// Action items that become sub-issues of the task when accepted.
+    #[serde(default)]
+    pub sub_tasks: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -179,10 +870,99 @@ pub enum EmailEvent {
         checked_at: DateTime<Utc>,
         unread_count: usize,
         actionable_count: usize,
-        ok: bool,
+        /// Why Gmail couldn't be read (token refresh, network), if it couldn't.
+        error: Option<String>,
+        /// Emails the AI filter failed on, with the last error. They are
+        /// filtered again on the next poll.
+        failed: Option<(usize, String)>,
     },
 }
 
+/// Data problems found by `/doctor`, as indices into the task list.
+#[derive(Debug, Default, PartialEq)]
+pub struct IntegrityReport {
+    pub unknown_bucket: Vec<usize>,
+    pub dangling_dependencies: Vec<usize>,
+    pub missing_parent: Vec<usize>,
+    pub stale_parent_progress: Vec<usize>,
+}
+
+impl IntegrityReport {
+    pub fn is_empty(&self) -> bool {
+        self.unknown_bucket.is_empty()
+            && self.dangling_dependencies.is_empty()
+            && self.missing_parent.is_empty()
+            && self.stale_parent_progress.is_empty()
+    }
+}
+
+/// Progress a parent should have given its children, or `None` when it
+/// already matches. Auto-archived parents of finished children are fine.
+fn expected_parent_progress(tasks: &[Task], parent: &Task) -> Option<Progress> {
+    let children: Vec<Progress> = tasks
+        .iter()
+        .filter(|t| t.parent_id == Some(parent.id))
+        .map(|t| t.progress)
+        .collect();
+    if progress_lock_holds(tasks, parent) {
+        return None;
+    }
+    let expected = compute_parent_progress(&children)?;
+    let archived_done = expected == Progress::Done && parent.progress == Progress::Archived;
+    (parent.progress != expected && !archived_done).then_some(expected)
+}
+
+pub fn check_integrity(tasks: &[Task], buckets: &[BucketDef]) -> IntegrityReport {
+    let ids: HashSet<Uuid> = tasks.iter().map(|t| t.id).collect();
+    let mut report = IntegrityReport {
+        unknown_bucket: orphaned_bucket_tasks(tasks, buckets),
+        ..IntegrityReport::default()
+    };
+    for (idx, task) in tasks.iter().enumerate() {
+        if task.dependencies.iter().any(|dep| !ids.contains(dep)) {
+            report.dangling_dependencies.push(idx);
+        }
+        if task.parent_id.is_some_and(|pid| !ids.contains(&pid)) {
+            report.missing_parent.push(idx);
+        }
+        if expected_parent_progress(tasks, task).is_some() {
+            report.stale_parent_progress.push(idx);
+        }
+    }
+    report
+}
+
+/// Fixes everything `check_integrity` finds and returns what was wrong.
+/// Tasks in unknown buckets move to `fallback_bucket`; tasks whose parent
+/// is gone become top-level.
+pub fn repair_integrity(
+    tasks: &mut [Task],
+    buckets: &[BucketDef],
+    fallback_bucket: &str,
+    now: DateTime<Utc>,
+) -> IntegrityReport {
+    let report = check_integrity(tasks, buckets);
+    let ids: HashSet<Uuid> = tasks.iter().map(|t| t.id).collect();
+    for &idx in &report.unknown_bucket {
+        tasks[idx].bucket = fallback_bucket.to_string();
+        tasks[idx].updated_at = now;
+    }
+    for &idx in &report.dangling_dependencies {
+        tasks[idx].dependencies.retain(|dep| ids.contains(dep));
+        tasks[idx].updated_at = now;
+    }
+    for &idx in &report.missing_parent {
+        tasks[idx].parent_id = None;
+        tasks[idx].updated_at = now;
+    }
+    for &idx in &report.stale_parent_progress {
+        if let Some(progress) = expected_parent_progress(tasks, &tasks[idx]) {
+            tasks[idx].set_progress(progress, now);
+        }
+    }
+    report
+}
+
 /// Derive a parent's progress from its children.
 ///
 /// - All children Done → Done
@@ -212,3 +992,382 @@ pub fn compute_parent_progress(children_progress: &[Progress]) -> Option<Progres
     }
     Some(Progress::Backlog)
 }
+
+/// True while a hand-set parent progress should survive auto-sync: it was
+/// locked and none of the children has changed stage since.
+fn progress_lock_holds(tasks: &[Task], parent: &Task) -> bool {
+    let Some(locked_at) = parent.progress_locked_at else {
+        return false;
+    };
+    !tasks.iter().any(|t| {
+        t.parent_id == Some(parent.id)
+            && t.progress_history
+                .last()
+                .is_some_and(|&(_, at)| at > locked_at)
+    })
+}
+
+/// Move a parent to the stage its children imply, unless its hand-set progress
+/// is still locked. Returns true when the parent's progress changed.
+pub fn sync_progress_from_children(
+    tasks: &mut [Task],
+    parent_id: Uuid,
+    now: DateTime<Utc>,
+) -> bool {
+    let Some(parent) = tasks.iter().find(|t| t.id == parent_id) else {
+        return false;
+    };
+    if progress_lock_holds(tasks, parent) {
+        return false;
+    }
+    let child_progresses: Vec<Progress> = tasks
+        .iter()
+        .filter(|t| t.parent_id == Some(parent_id))
+        .map(|t| t.progress)
+        .collect();
+    let Some(new_progress) = compute_parent_progress(&child_progresses) else {
+        return false;
+    };
+    let Some(parent) = tasks.iter_mut().find(|t| t.id == parent_id) else {
+        return false;
+    };
+    parent.progress_locked_at = None;
+    if parent.progress == new_progress {
+        return false;
+    }
+    parent.set_progress(new_progress, now);
+    true
+}
+
+/// True when children sit at least two stages apart (say Backlog and Done),
+/// which the single stage from `compute_parent_progress` hides. Archived
+/// counts as Done.
+pub fn children_progress_mixed(children_progress: &[Progress]) -> bool {
+    let stages = children_progress.iter().map(|p| p.stage_index().min(3));
+    match (stages.clone().min(), stages.max()) {
+        (Some(lo), Some(hi)) => hi - lo >= 2,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_open_dependencies_are_unmet() {
+        let now = Utc::now();
+        let mut design = Task::new("Team".into(), "Design".into(), now);
+        let mut review = Task::new("Team".into(), "Review".into(), now);
+        let mut build = Task::new("Team".into(), "Build".into(), now);
+        review.set_progress(Progress::Done, now);
+        build.dependencies = vec![design.id, review.id, Uuid::new_v4()];
+        let tasks = vec![design.clone(), review, build.clone()];
+        assert_eq!(unmet_dependencies(&tasks, &build), vec![0]);
+
+        design.set_progress(Progress::Archived, now);
+        let tasks = vec![design, tasks[1].clone(), build];
+        assert!(unmet_dependencies(&tasks, &tasks[2]).is_empty());
+    }
+
+    #[test]
+    fn hand_set_parent_progress_survives_until_a_child_moves() {
+        let start = Utc::now();
+        let mut parent = Task::new("Team".into(), "Epic".into(), start);
+        let mut child = Task::new("Team".into(), "Step".into(), start);
+        child.parent_id = Some(parent.id);
+        let parent_id = parent.id;
+
+        let locked_at = start + chrono::Duration::seconds(1);
+        parent.set_progress(Progress::Done, locked_at);
+        parent.progress_locked_at = Some(locked_at);
+        let mut tasks = vec![parent, child];
+
+        let later = locked_at + chrono::Duration::seconds(1);
+        assert!(!sync_progress_from_children(&mut tasks, parent_id, later));
+        assert_eq!(tasks[0].progress, Progress::Done);
+        assert!(expected_parent_progress(&tasks, &tasks[0]).is_none());
+
+        tasks[1].set_progress(Progress::InProgress, later);
+        assert!(sync_progress_from_children(&mut tasks, parent_id, later));
+        assert_eq!(tasks[0].progress, Progress::InProgress);
+        assert_eq!(tasks[0].progress_locked_at, None);
+    }
+
+    #[test]
+    fn mixed_children_need_a_stage_gap() {
+        use Progress::*;
+        assert!(children_progress_mixed(&[Done, Backlog]));
+        assert!(children_progress_mixed(&[Archived, InProgress, Todo]));
+        assert!(children_progress_mixed(&[Backlog, InProgress]));
+        assert!(!children_progress_mixed(&[Done, InProgress, InProgress]));
+        assert!(!children_progress_mixed(&[Archived, Done]));
+        assert!(!children_progress_mixed(&[Todo]));
+        assert!(!children_progress_mixed(&[]));
+        assert_eq!(compute_parent_progress(&[Done, Backlog]), Some(InProgress));
+    }
+
+    #[test]
+    fn parse_hours_accepts_units_and_decimals() {
+        assert_eq!(parse_hours("1h30m"), Some(1.5));
+        assert_eq!(parse_hours("1h 30m"), Some(1.5));
+        assert_eq!(parse_hours("90m"), Some(1.5));
+        assert_eq!(parse_hours("2H"), Some(2.0));
+        assert_eq!(parse_hours("1.5"), Some(1.5));
+        assert_eq!(parse_hours("0.5h"), Some(0.5));
+        assert_eq!(parse_hours(""), None);
+        assert_eq!(parse_hours("-1"), None);
+        assert_eq!(parse_hours("1h30"), None);
+        assert_eq!(parse_hours("abc"), None);
+        assert_eq!(format_hours(1.5), "1h30m");
+        assert_eq!(format_hours(0.75), "45m");
+        assert_eq!(format_hours(5.0), "5h");
+    }
+
+    #[test]
+    fn effective_priority_escalates_near_due_date() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let mut task = Task::new("Team".into(), "t".into(), Utc::now());
+        assert_eq!(effective_priority(&task, today), Priority::Medium);
+
+        task.due_date = Some(today + chrono::Duration::days(3));
+        assert_eq!(effective_priority(&task, today), Priority::Medium);
+        task.due_date = Some(today + chrono::Duration::days(2));
+        assert_eq!(effective_priority(&task, today), Priority::High);
+        task.due_date = Some(today - chrono::Duration::days(1));
+        assert_eq!(effective_priority(&task, today), Priority::Critical);
+
+        task.priority = Priority::Critical;
+        assert_eq!(effective_priority(&task, today), Priority::Critical);
+        task.priority = Priority::High;
+        task.escalated_levels = 1;
+        assert_eq!(effective_priority(&task, today), Priority::Critical);
+        task.escalated_levels = 2;
+        assert_eq!(effective_priority(&task, today), Priority::High);
+
+        task.priority = Priority::Low;
+        task.escalated_levels = 0;
+        task.progress = Progress::Done;
+        assert_eq!(effective_priority(&task, today), Priority::Low);
+    }
+
+    #[test]
+    fn escalated_levels_reset_on_new_due_date_and_done() {
+        let now = Utc::now();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        let mut task = Task::new("Team".into(), "t".into(), now);
+        task.set_due_date(Some(today - chrono::Duration::days(1)));
+        task.priority = effective_priority(&task, today);
+        task.escalated_levels = escalation_levels(&task, today);
+        assert_eq!(task.priority, Priority::Critical);
+
+        // Same date: the stored levels still count.
+        task.set_due_date(Some(today - chrono::Duration::days(1)));
+        assert_eq!(task.escalated_levels, 2);
+
+        task.priority = Priority::Medium;
+        task.set_due_date(Some(today + chrono::Duration::days(10)));
+        assert_eq!(task.escalated_levels, 0);
+        // Coming near again escalates again.
+        assert_eq!(
+            effective_priority(&task, today + chrono::Duration::days(9)),
+            Priority::High
+        );
+
+        task.escalated_levels = 1;
+        task.set_progress(Progress::Done, now);
+        assert_eq!(task.escalated_levels, 0);
+    }
+
+    #[test]
+    fn humanize_age_boundaries() {
+        let now = DateTime::parse_from_rfc3339("2026-03-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ago = |secs: i64| humanize_age(now - chrono::Duration::seconds(secs), now);
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(59), "just now");
+        assert_eq!(ago(-30), "just now");
+        assert_eq!(ago(60), "1m ago");
+        assert_eq!(ago(3599), "59m ago");
+        assert_eq!(ago(3600), "1h ago");
+        assert_eq!(ago(86_399), "23h ago");
+        assert_eq!(ago(86_400), "1d ago");
+        assert_eq!(ago(6 * 86_400), "6d ago");
+        assert_eq!(ago(7 * 86_400), "1w ago");
+        assert_eq!(ago(27 * 86_400), "3w ago");
+        assert_eq!(ago(28 * 86_400), "Feb 20");
+        assert_eq!(ago(100 * 86_400), "Dec 10 2025");
+    }
+
+    #[test]
+    fn reparent_guards_catch_cycles_and_depth() {
+        let now = Utc::now();
+        let a = Task::new("Team".into(), "A".into(), now);
+        let mut b = Task::new("Team".into(), "B".into(), now);
+        b.parent_id = Some(a.id);
+        let mut c = Task::new("Team".into(), "C".into(), now);
+        c.parent_id = Some(b.id);
+        let d = Task::new("Team".into(), "D".into(), now);
+        let tasks = vec![a.clone(), b.clone(), c.clone(), d.clone()];
+
+        assert!(would_create_ancestor_cycle(&tasks, a.id, a.id));
+        assert!(would_create_ancestor_cycle(&tasks, a.id, c.id));
+        assert!(would_create_ancestor_cycle(&tasks, b.id, c.id));
+        assert!(!would_create_ancestor_cycle(&tasks, c.id, a.id));
+        assert!(!would_create_ancestor_cycle(&tasks, a.id, d.id));
+
+        assert_eq!(nesting_depth(&tasks, a.id), 0);
+        assert_eq!(nesting_depth(&tasks, c.id), 2);
+        assert!(!would_exceed_nesting(&tasks, d.id, a.id));
+        assert!(would_exceed_nesting(&tasks, d.id, b.id));
+        assert!(would_exceed_nesting(&tasks, a.id, d.id));
+    }
+
+    #[test]
+    fn orphaned_bucket_tasks_match_names_exactly() {
+        let now = Utc::now();
+        let buckets = vec![BucketDef {
+            name: "Team".to_string(),
+            description: None,
+        }];
+        let tasks = vec![
+            Task::new("Team".to_string(), "kept".to_string(), now),
+            Task::new("team".to_string(), "wrong case".to_string(), now),
+            Task::new("Gone".to_string(), "deleted bucket".to_string(), now),
+        ];
+        assert_eq!(orphaned_bucket_tasks(&tasks, &buckets), vec![1, 2]);
+    }
+
+    #[test]
+    fn repair_integrity_fixes_links_and_progress() {
+        let now = Utc::now();
+        let buckets = vec![BucketDef {
+            name: "Team".to_string(),
+            description: None,
+        }];
+        let mut parent = Task::new("Team".to_string(), "parent".to_string(), now);
+        let mut child = Task::new("Team".to_string(), "child".to_string(), now);
+        child.parent_id = Some(parent.id);
+        child.progress = Progress::Done;
+        parent.dependencies = vec![Uuid::new_v4()];
+        let mut stray = Task::new("Gone".to_string(), "stray".to_string(), now);
+        stray.parent_id = Some(Uuid::new_v4());
+        let mut tasks = vec![parent, child, stray];
+
+        let report = repair_integrity(&mut tasks, &buckets, "Team", now);
+        assert_eq!(report.unknown_bucket, vec![2]);
+        assert_eq!(report.dangling_dependencies, vec![0]);
+        assert_eq!(report.missing_parent, vec![2]);
+        assert_eq!(report.stale_parent_progress, vec![0]);
+        assert!(tasks[0].dependencies.is_empty());
+        assert_eq!(tasks[0].progress, Progress::Done);
+        assert_eq!(tasks[2].bucket, "Team");
+        assert_eq!(tasks[2].parent_id, None);
+        assert!(check_integrity(&tasks, &buckets).is_empty());
+    }
+
+    #[test]
+    fn clone_subtree_rewires_links() {
+        let now = Utc::now();
+        let outside = Task::new("Team".into(), "Outside".into(), now);
+        let mut root = Task::new("Team".into(), "Release".into(), now);
+        root.progress = Progress::Done;
+        root.dependencies = vec![outside.id];
+        let mut build = Task::new("Team".into(), "Build".into(), now);
+        build.parent_id = Some(root.id);
+        let mut ship = Task::new("Team".into(), "Ship".into(), now);
+        ship.parent_id = Some(root.id);
+        ship.dependencies = vec![build.id, outside.id];
+        let tasks = vec![outside, ship, root.clone(), build];
+
+        let copies = clone_subtree(&tasks, root.id, now);
+        assert_eq!(copies.len(), 3);
+        let (new_root, new_ship, new_build) = (&copies[0], &copies[1], &copies[2]);
+        assert_eq!(new_root.title, "Release");
+        assert_ne!(new_root.id, root.id);
+        assert_eq!(new_root.parent_id, None);
+        assert!(new_root.dependencies.is_empty());
+        assert_eq!(new_root.progress, Progress::Backlog);
+        assert_eq!(new_ship.parent_id, Some(new_root.id));
+        assert_eq!(new_ship.dependencies, vec![new_build.id]);
+    }
+
+    #[test]
+    fn task_index_survives_removals_and_pushes() {
+        let now = Utc::now();
+        let a = Task::new("Team".into(), "A".into(), now);
+        let b = Task::new("Team".into(), "B".into(), now);
+        let (a_id, b_id) = (a.id, b.id);
+        let mut tasks = vec![a, b];
+        let mut index = TaskIndex::default();
+        assert_eq!(index.position(&tasks, b_id), Some(1));
+
+        tasks.remove(0);
+        let c = Task::new("Team".into(), "C".into(), now);
+        let c_short = c.id.to_string()[..8].to_ascii_uppercase();
+        tasks.push(c);
+        assert_eq!(index.position(&tasks, b_id), Some(0));
+        assert_eq!(index.position(&tasks, a_id), None);
+        assert_eq!(index.resolve(&tasks, &c_short), Ok(tasks[1].id));
+        assert_eq!(index.resolve(&tasks, &c_short[..6]), Ok(tasks[1].id));
+        assert!(index.resolve(&tasks, &c_short[..3]).is_err());
+    }
+
+    #[test]
+    fn task_index_flags_ambiguous_prefixes() {
+        let now = Utc::now();
+        let mut tasks: Vec<Task> = [
+            "abcd1234-0000-4000-8000-000000000001",
+            "abcd1234-0000-4000-8000-000000000002",
+            "abcd9999-0000-4000-8000-000000000003",
+        ]
+        .iter()
+        .map(|id| {
+            let mut t = Task::new("Team".into(), "T".into(), now);
+            t.id = Uuid::parse_str(id).unwrap();
+            t
+        })
+        .collect();
+        let mut index = TaskIndex::default();
+        assert_eq!(index.matching(&tasks, "abcd").len(), 3);
+        assert!(index.resolve(&tasks, "abcd1234").is_err());
+        assert_eq!(index.resolve(&tasks, "ABCD9999"), Ok(tasks[2].id));
+        assert_eq!(
+            index.resolve(&tasks, "abcd1234-0000-4000-8000-000000000002"),
+            Ok(tasks[1].id)
+        );
+
+        tasks.swap_remove(0);
+        assert_eq!(index.resolve(&tasks, "abcd1234"), Ok(tasks[1].id));
+    }
+
+    #[test]
+    fn critical_path_follows_chains_of_open_tasks() {
+        let now = Utc::now();
+        let today = now.date_naive();
+        let mut design = Task::new("Team".into(), "Design".into(), now);
+        design.due_date = Some(today + chrono::Duration::days(2));
+        let mut build = Task::new("Team".into(), "Build".into(), now);
+        build.due_date = Some(today + chrono::Duration::days(3));
+        build.dependencies.push(design.id);
+        // Longer than the whole chain, but depends on nothing.
+        let mut migrate = Task::new("Team".into(), "Migrate".into(), now);
+        migrate.due_date = Some(today + chrono::Duration::days(30));
+        let mut tasks = vec![design, build, migrate];
+
+        assert_eq!(critical_path(&tasks), vec![tasks[0].id, tasks[1].id]);
+
+        tasks[0].set_progress(Progress::Done, now);
+        assert!(critical_path(&tasks).is_empty());
+    }
+
+    #[test]
+    fn task_colors_parse_ignoring_case() {
+        assert_eq!(parse_task_color("teal"), Some("teal"));
+        assert_eq!(parse_task_color(" Purple "), Some("purple"));
+        assert_eq!(parse_task_color("BLUE"), Some("blue"));
+        assert_eq!(parse_task_color("magenta"), None);
+        assert_eq!(parse_task_color(""), None);
+    }
+}
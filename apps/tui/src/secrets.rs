@@ -0,0 +1,114 @@
+//! API keys in the OS keychain. macOS goes through `security` (Keychain),
+//! other systems through `secret-tool` (Secret Service: GNOME Keyring,
+//! KWallet). Both are driven as subprocesses so no keychain library is linked.
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+const SERVICE: &str = "aipm";
+
+fn is_macos() -> bool {
+    cfg!(target_os = "macos")
+}
+
+/// True when the keychain tool for this system can be run.
+pub fn available() -> bool {
+    let tool = if is_macos() {
+        "security"
+    } else {
+        "secret-tool"
+    };
+    Command::new(tool)
+        .arg("--help")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// The secret stored for `account`, if any.
+pub fn load(account: &str) -> Option<String> {
+    let output = if is_macos() {
+        Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"])
+            .stderr(Stdio::null())
+            .output()
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "account", account])
+            .stderr(Stdio::null())
+            .output()
+    }
+    .ok()?;
+    let secret = String::from_utf8(output.stdout).ok()?;
+    let secret = secret.trim_end_matches(['\r', '\n']);
+    (output.status.success() && !secret.is_empty()).then(|| secret.to_string())
+}
+
+/// Store `secret` for `account`, replacing any previous one. The secret goes
+/// over stdin so it never shows up in the process list.
+pub fn store(account: &str, secret: &str) -> io::Result<()> {
+    let (mut cmd, input) = if is_macos() {
+        let mut cmd = Command::new("security");
+        cmd.arg("-i");
+        let line = format!(
+            "add-generic-password -U -s {SERVICE} -a {} -w {}\n",
+            quote(account),
+            quote(secret)
+        );
+        (cmd, line)
+    } else {
+        let mut cmd = Command::new("secret-tool");
+        cmd.args([
+            "store",
+            "--label",
+            &format!("{SERVICE} {account}"),
+            "service",
+            SERVICE,
+            "account",
+            account,
+        ]);
+        (cmd, secret.to_string())
+    };
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    // `security -i` exits 0 even when a command fails, so check stderr too.
+    let err = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() && err.trim().is_empty() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "Keychain write failed: {}",
+            err.trim()
+        )))
+    }
+}
+
+/// Remove the secret for `account`. Missing entries are not an error.
+pub fn delete(account: &str) {
+    let _ = if is_macos() {
+        Command::new("security")
+            .args(["delete-generic-password", "-s", SERVICE, "-a", account])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    } else {
+        Command::new("secret-tool")
+            .args(["clear", "service", SERVICE, "account", account])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    };
+}
+
+/// Double-quote a word for the `security -i` command line.
+fn quote(word: &str) -> String {
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+}
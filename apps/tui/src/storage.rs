@@ -1,14 +1,24 @@
-use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
+use std::fmt::Write as _;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io;
-use std::path::PathBuf;
-
-use chrono::{DateTime, NaiveDate, Utc};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Local, NaiveDate, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::model::{BucketDef, Priority, Progress, Task};
+use crate::log;
+use crate::model::{BucketDef, Priority, Progress, SortMode, Suggestion, Task, WeekStart};
+use crate::secrets;
 
 // ---------------------------------------------------------------------------
 // AiSettings
@@ -24,10 +34,26 @@ pub struct AiSettings {
     /// Legacy single key — migrated into the per-provider fields on load.
     #[serde(default, skip_serializing)]
     api_key: String,
+    /// Keep the API keys in the OS keychain; the settings file then stores
+    /// them empty. A key the keychain refuses stays in the file.
+    #[serde(default)]
+    pub use_keychain: bool,
+    /// Accounts whose key was read from or saved to the keychain, so a key
+    /// that failed to load isn't mistaken for one the user cleared.
+    #[serde(skip)]
+    in_keychain: HashSet<&'static str>,
     pub model: String,
+    /// Model names added by hand (local or enterprise deployments), cycled
+    /// through after the built-in ones.
+    #[serde(default)]
+    pub custom_models: Vec<String>,
     pub timeout_secs: u64,
     #[serde(default = "default_owner_name")]
     pub owner_name: String,
+    /// AI requests allowed in flight at once, e.g. during bulk updates.
+    /// Clamped to 1..=`MAX_AI_CONCURRENCY`.
+    #[serde(default = "default_ai_concurrency")]
+    pub ai_concurrency: usize,
     #[serde(default = "default_true")]
     pub show_backlog: bool,
     #[serde(default = "default_true")]
@@ -36,24 +62,124 @@ pub struct AiSettings {
     pub show_in_progress: bool,
     #[serde(default)]
     pub show_done: bool,
+    /// Sub-issues listed under a bucket card before "+N more"; 0 leaves
+    /// just the count on the card.
+    #[serde(default = "default_card_max_subissues")]
+    pub card_max_subissues: usize,
     #[serde(default = "default_buckets")]
     pub buckets: Vec<BucketDef>,
     #[serde(default)]
     pub email_suggestions_enabled: bool,
+    /// Characters of each email body sent to the suggestion filter, after
+    /// quoted replies and signatures are dropped. 0 sends only the subject
+    /// and sender.
+    #[serde(default = "default_email_body_max_chars")]
+    pub email_body_max_chars: usize,
     #[serde(default)]
     pub user_profile: String,
     #[serde(default)]
     pub memory_facts: Vec<String>,
+    /// Persist escalated priorities (see `model::effective_priority`) instead
+    /// of only showing them.
+    #[serde(default)]
+    pub auto_escalate: bool,
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    /// strftime pattern for dates shown in the UI and accepted when editing
+    /// due dates. Files and JSON output always use ISO.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default)]
+    pub week_start: WeekStart,
+    /// Display names for the stages, e.g. `InProgress: Review`. Only the
+    /// names change; stages without an entry keep their built-in title.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stage_labels: BTreeMap<Progress, String>,
+    /// Fixed UTC offset used for "today" and local times, so due-today and
+    /// overdue checks don't move with the machine's timezone. `None` follows it.
+    #[serde(default)]
+    pub utc_offset_minutes: Option<i32>,
+    /// Working weekdays. When set (or when `holidays` is), `tomorrow` and
+    /// `in N days` land on the next working day. Empty means every day for
+    /// that, and Monday to Friday for `in N workdays`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub work_days: Vec<Weekday>,
+    /// Dates that are never working days.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<NaiveDate>,
+    /// Local time a task due today turns overdue. `None` waits for the date
+    /// to pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workday_end: Option<NaiveTime>,
+    /// Fields AI edits may not change on existing tasks, even when the model
+    /// returns them.
+    #[serde(default)]
+    pub ai_lock_bucket: bool,
+    #[serde(default)]
+    pub ai_lock_priority: bool,
+    #[serde(default)]
+    pub ai_lock_due_date: bool,
+    /// Leave tasks in hidden progress stages (see `is_progress_visible`) out
+    /// of the task lists sent to the AI.
+    #[serde(default)]
+    pub ai_context_visible_only: bool,
+    /// Standing instructions placed ahead of every AI request's own prompt,
+    /// e.g. team conventions. Capped at `SYSTEM_PROMPT_MAX_CHARS`.
+    #[serde(default)]
+    pub system_prompt: String,
+    /// Undo snapshots kept in `history/`; the oldest are removed past this.
+    /// Clamped to 1..=`MAX_SNAPSHOT_RETENTION`.
+    #[serde(default = "default_snapshot_retention")]
+    pub snapshot_retention: usize,
 }
 
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Keeps the instruction preamble from crowding out the task context it is
+/// sent alongside.
+pub const SYSTEM_PROMPT_MAX_CHARS: usize = 2000;
+
+/// Bounds for `timeout_secs`. Below the minimum most replies can't finish;
+/// above the maximum a stuck request holds up the queue too long.
+pub const MIN_TIMEOUT_SECS: u64 = 5;
+pub const MAX_TIMEOUT_SECS: u64 = 600;
+
+/// Upper bound for `ai_concurrency`; providers start rejecting bursts with
+/// HTTP 429 well before this on most plans.
+pub const MAX_AI_CONCURRENCY: usize = 8;
+
+/// Upper bound for `snapshot_retention`; every snapshot holds all tasks, so
+/// a long history adds up on disk.
+pub const MAX_SNAPSHOT_RETENTION: usize = 500;
+
 fn default_owner_name() -> String {
     String::new()
 }
 
+fn default_date_format() -> String {
+    DEFAULT_DATE_FORMAT.to_string()
+}
+
 fn default_true() -> bool {
     true
 }
 
+fn default_card_max_subissues() -> usize {
+    3
+}
+
+fn default_ai_concurrency() -> usize {
+    3
+}
+
+fn default_email_body_max_chars() -> usize {
+    800
+}
+
+fn default_snapshot_retention() -> usize {
+    50
+}
+
 fn default_buckets() -> Vec<BucketDef> {
     vec![
         BucketDef {
@@ -78,17 +204,38 @@ impl Default for AiSettings {
             openai_api_key: String::new(),
             anthropic_api_key: String::new(),
             api_key: String::new(),
+            use_keychain: false,
+            in_keychain: HashSet::new(),
             model: "claude-sonnet-4-5".to_string(),
+            custom_models: Vec::new(),
             timeout_secs: 60,
+            ai_concurrency: default_ai_concurrency(),
             owner_name: String::new(),
             show_backlog: true,
             show_todo: true,
             show_in_progress: true,
             show_done: false,
+            card_max_subissues: default_card_max_subissues(),
             buckets: default_buckets(),
             email_suggestions_enabled: false,
+            email_body_max_chars: default_email_body_max_chars(),
             user_profile: String::new(),
             memory_facts: Vec::new(),
+            auto_escalate: false,
+            sort_mode: SortMode::Default,
+            date_format: default_date_format(),
+            week_start: WeekStart::Monday,
+            stage_labels: BTreeMap::new(),
+            utc_offset_minutes: None,
+            work_days: Vec::new(),
+            holidays: Vec::new(),
+            workday_end: None,
+            ai_lock_bucket: false,
+            ai_lock_priority: false,
+            ai_lock_due_date: false,
+            ai_context_visible_only: false,
+            system_prompt: String::new(),
+            snapshot_retention: default_snapshot_retention(),
         }
     }
 }
@@ -104,6 +251,181 @@ impl AiSettings {
         }
     }
 
+    /// The name shown for a stage: its `stage_labels` entry, or the
+    /// built-in title.
+    pub fn stage_label(&self, progress: Progress) -> &str {
+        self.stage_labels
+            .get(&progress)
+            .map(|label| label.trim())
+            .filter(|label| !label.is_empty())
+            .unwrap_or(progress.title())
+    }
+
+    /// Whether the key for `account` ("openai" or "anthropic") came from the
+    /// keychain rather than the settings file.
+    pub fn key_in_keychain(&self, account: &str) -> bool {
+        self.in_keychain.contains(account)
+    }
+
+    /// The stage whose `stage_label` is `input`, ignoring case.
+    pub fn stage_from_label(&self, input: &str) -> Option<Progress> {
+        let input = input.trim();
+        STAGES
+            .into_iter()
+            .find(|&p| self.stage_label(p).eq_ignore_ascii_case(input))
+    }
+
+    /// The four stage names in order, as edited in settings.
+    pub fn stage_labels_text(&self) -> String {
+        STAGES
+            .iter()
+            .map(|&p| self.stage_label(p))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The configured date format, or ISO when it is not usable.
+    pub fn date_format(&self) -> &str {
+        if is_valid_date_format(&self.date_format) {
+            &self.date_format
+        } else {
+            DEFAULT_DATE_FORMAT
+        }
+    }
+
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        date.format(self.date_format()).to_string()
+    }
+
+    /// The configured format without its year, for compact labels.
+    pub fn format_short_date(&self, date: NaiveDate) -> String {
+        let short = strip_year(self.date_format());
+        if short.contains('%') {
+            date.format(&short).to_string()
+        } else {
+            self.format_date(date)
+        }
+    }
+
+    /// Parses a date in the configured format, falling back to ISO.
+    pub fn parse_date(&self, input: &str) -> Option<NaiveDate> {
+        let input = input.trim();
+        parse_date_with(input, self.date_format())
+            .or_else(|| NaiveDate::parse_from_str(input, DEFAULT_DATE_FORMAT).ok())
+    }
+
+    /// `dt` in the configured timezone.
+    pub fn to_local(&self, dt: DateTime<Utc>) -> DateTime<FixedOffset> {
+        to_offset(dt, self.utc_offset_minutes)
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.to_local(Utc::now()).date_naive()
+    }
+
+    /// A `created_at` for a task backdated to `date`: now for today,
+    /// otherwise the middle of the hours that are `date` both in the
+    /// configured timezone, which stats read, and in UTC, which the Timeline
+    /// reads. The two days always overlap, by 24 hours less the offset.
+    /// `None` for a date in the future.
+    pub fn backdate(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        let now = self.to_local(Utc::now());
+        if date > now.date_naive() {
+            return None;
+        }
+        if date == now.date_naive() {
+            return Some(now.to_utc());
+        }
+        let offset = i64::from(now.offset().local_minus_utc());
+        let start = (-offset).max(0);
+        let end = (86_400 - offset).min(86_400);
+        Some(date.and_time(NaiveTime::MIN).and_utc() + chrono::Duration::seconds((start + end) / 2))
+    }
+
+    /// Past its due date, or due today after `workday_end`.
+    pub fn is_overdue(&self, due: NaiveDate) -> bool {
+        self.is_overdue_at(due, Utc::now())
+    }
+
+    pub fn is_overdue_at(&self, due: NaiveDate, now: DateTime<Utc>) -> bool {
+        let now = self.to_local(now);
+        due < now.date_naive()
+            || (due == now.date_naive() && self.workday_end.is_some_and(|end| now.time() >= end))
+    }
+
+    pub fn is_work_day(&self, date: NaiveDate) -> bool {
+        let weekday = date.weekday();
+        let working = if self.work_days.is_empty() {
+            !matches!(weekday, Weekday::Sat | Weekday::Sun)
+        } else {
+            self.work_days.contains(&weekday)
+        };
+        working && !self.holidays.contains(&date)
+    }
+
+    /// `date`, or the next working day after it when working days or
+    /// holidays are configured.
+    pub fn roll_to_work_day(&self, date: NaiveDate) -> NaiveDate {
+        if self.work_days.is_empty() && self.holidays.is_empty() {
+            return date;
+        }
+        date.iter_days()
+            .take(366)
+            .find(|&d| self.is_work_day(d))
+            .unwrap_or(date)
+    }
+
+    /// The working day `n` working days after `date`.
+    pub fn add_work_days(&self, date: NaiveDate, n: u64) -> Option<NaiveDate> {
+        if n == 0 {
+            return Some(date);
+        }
+        // Bounded in case every day is a holiday.
+        date.checked_add_days(Days::new(1))?
+            .iter_days()
+            .take(n as usize * 7 + 366)
+            .filter(|&d| self.is_work_day(d))
+            .nth(n as usize - 1)
+    }
+
+    /// `work_days` as edited in settings, e.g. "Mon-Fri".
+    pub fn work_days_text(&self) -> String {
+        let mut days = self.work_days.clone();
+        days.sort_by_key(|d| d.num_days_from_monday());
+        days.dedup();
+        let mut runs: Vec<(Weekday, Weekday)> = Vec::new();
+        for day in days {
+            match runs.last_mut() {
+                Some((_, last)) if last.succ() == day => *last = day,
+                _ => runs.push((day, day)),
+            }
+        }
+        runs.iter()
+            .map(|&(first, last)| {
+                if first == last {
+                    format!("{first}")
+                } else {
+                    format!("{first}-{last}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn holidays_text(&self) -> String {
+        self.holidays
+            .iter()
+            .map(|d| d.format(DEFAULT_DATE_FORMAT).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn workday_end_text(&self) -> String {
+        self.workday_end
+            .map(|t| t.format("%H:%M").to_string())
+            .unwrap_or_default()
+    }
+
     /// Migrate the legacy single `api_key` into per-provider fields.
     pub fn migrate_legacy_key(&mut self) {
         if !self.api_key.is_empty() {
@@ -119,6 +441,185 @@ impl AiSettings {
     }
 }
 
+/// The API key fields with the keychain account each is stored under.
+fn keychain_keys(settings: &mut AiSettings) -> [(&'static str, &mut String); 2] {
+    [
+        ("openai", &mut settings.openai_api_key),
+        ("anthropic", &mut settings.anthropic_api_key),
+    ]
+}
+
+/// `dt` at a fixed UTC offset in minutes, or in the system timezone for `None`.
+pub fn to_offset(dt: DateTime<Utc>, utc_offset_minutes: Option<i32>) -> DateTime<FixedOffset> {
+    match utc_offset_minutes.and_then(|m| FixedOffset::east_opt(m * 60)) {
+        Some(offset) => dt.with_timezone(&offset),
+        None => dt.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+/// Parses a UTC offset like "+05:30", "-8" or "UTC+2". Empty, "local" and
+/// "none" mean the system timezone (`Some(None)`); `None` is invalid input.
+pub fn parse_utc_offset(input: &str) -> Option<Option<i32>> {
+    let s = input.trim();
+    if s.is_empty() || s.eq_ignore_ascii_case("local") || s.eq_ignore_ascii_case("none") {
+        return Some(None);
+    }
+    let s = s
+        .strip_prefix("UTC")
+        .or_else(|| s.strip_prefix("utc"))
+        .unwrap_or(s);
+    if s.is_empty() {
+        return Some(Some(0));
+    }
+    let (sign, rest) = match s.as_bytes()[0] {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => (1, s),
+    };
+    let (h, m) = rest.split_once(':').unwrap_or((rest, "0"));
+    let (h, m): (i32, i32) = (h.parse().ok()?, m.parse().ok()?);
+    if !(0..=14).contains(&h) || !(0..60).contains(&m) {
+        return None;
+    }
+    Some(Some(sign * (h * 60 + m)))
+}
+
+pub fn format_utc_offset(utc_offset_minutes: Option<i32>) -> String {
+    match utc_offset_minutes {
+        Some(m) => {
+            let sign = if m < 0 { '-' } else { '+' };
+            format!("UTC{}{:02}:{:02}", sign, m.abs() / 60, m.abs() % 60)
+        }
+        None => "Local".to_string(),
+    }
+}
+
+/// The stages `stage_labels` can rename. Archived is left out: it never has
+/// a column or a card of its own.
+const STAGES: [Progress; 4] = [
+    Progress::Backlog,
+    Progress::Todo,
+    Progress::InProgress,
+    Progress::Done,
+];
+
+/// Parses comma-separated names for Backlog, Todo, In progress and Done.
+/// Missing, empty or built-in names leave that stage unrenamed; more than
+/// four names is an error.
+pub fn parse_stage_labels(input: &str) -> Option<BTreeMap<Progress, String>> {
+    let names: Vec<&str> = input.split(',').map(str::trim).collect();
+    if names.len() > STAGES.len() {
+        return None;
+    }
+    Some(
+        STAGES
+            .iter()
+            .zip(names)
+            .filter(|(p, name)| !name.is_empty() && *name != p.title())
+            .map(|(&p, name)| (p, name.to_string()))
+            .collect(),
+    )
+}
+
+/// Parses working days like "Mon-Fri", "sun-thu" or "mon, wed, fri". Empty
+/// input clears them.
+pub fn parse_work_days(input: &str) -> Option<Vec<Weekday>> {
+    let mut days: Vec<Weekday> = Vec::new();
+    for part in input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (mut day, last) = (
+                    first.trim().parse::<Weekday>().ok()?,
+                    last.trim().parse::<Weekday>().ok()?,
+                );
+                days.push(day);
+                while day != last {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(part.parse().ok()?),
+        }
+    }
+    days.sort_by_key(|d| d.num_days_from_monday());
+    days.dedup();
+    Some(days)
+}
+
+/// Parses comma-separated ISO dates. Empty input clears them.
+pub fn parse_holidays(input: &str) -> Option<Vec<NaiveDate>> {
+    let mut dates = input
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| NaiveDate::parse_from_str(p, DEFAULT_DATE_FORMAT).ok())
+        .collect::<Option<Vec<_>>>()?;
+    dates.sort();
+    dates.dedup();
+    Some(dates)
+}
+
+/// Parses "17:00" or "17". Empty, "none" or "off" clears it (`Some(None)`).
+pub fn parse_workday_end(input: &str) -> Option<Option<NaiveTime>> {
+    let s = input.trim();
+    if s.is_empty() || s.eq_ignore_ascii_case("none") || s.eq_ignore_ascii_case("off") {
+        return Some(None);
+    }
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .ok()
+        .or_else(|| NaiveTime::from_hms_opt(s.parse().ok()?, 0, 0))
+        .map(Some)
+}
+
+/// Whether `fmt` is a strftime pattern that can both render and read back a
+/// full date.
+pub fn is_valid_date_format(fmt: &str) -> bool {
+    if fmt.trim().is_empty() || StrftimeItems::new(fmt).any(|item| matches!(item, Item::Error)) {
+        return false;
+    }
+    let Some(probe) = NaiveDate::from_ymd_opt(Local::now().year(), 12, 31) else {
+        return false;
+    };
+    // Time specifiers make formatting a bare date fail rather than panic here.
+    let mut rendered = String::new();
+    if write!(rendered, "{}", probe.format(fmt)).is_err() {
+        return false;
+    }
+    parse_date_with(&rendered, fmt) == Some(probe)
+}
+
+/// Parses `input` with `fmt`; formats without a year assume the current one.
+fn parse_date_with(input: &str, fmt: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(input, fmt).ok().or_else(|| {
+        let year = Local::now().year();
+        NaiveDate::parse_from_str(&format!("{input} {year}"), &format!("{fmt} %Y")).ok()
+    })
+}
+
+/// Drops the year specifier and the separator joining it to the rest:
+/// "%Y-%m-%d" → "%m-%d", "%d/%m/%Y" → "%d/%m".
+fn strip_year(fmt: &str) -> String {
+    let is_sep = |c: char| matches!(c, '-' | '/' | '.' | ' ' | ',');
+    let mut out = fmt.to_string();
+    for token in ["%Y", "%y"] {
+        let Some(i) = out.find(token) else {
+            continue;
+        };
+        let (mut start, mut end) = (i, i + token.len());
+        if start == 0 {
+            while out[end..].starts_with(is_sep) {
+                end += 1;
+            }
+        } else {
+            while out[..start].ends_with(is_sep) {
+                start -= 1;
+            }
+        }
+        out.replace_range(start..end, "");
+    }
+    out
+}
+
 // ---------------------------------------------------------------------------
 // Legacy JSON format (for migration)
 // ---------------------------------------------------------------------------
@@ -127,15 +628,24 @@ impl AiSettings {
 struct LegacyStore {
     #[allow(dead_code)]
     version: u32,
-    tasks: Vec<Task>,
+    /// Parsed one by one so a bad entry doesn't lose the others.
+    tasks: Vec<serde_json::Value>,
 }
 
 // ---------------------------------------------------------------------------
 // Front-matter serde types
 // ---------------------------------------------------------------------------
 
+/// Version of the task file layout written by this binary. Files without a
+/// `schema` field predate versioning and count as 0. Fields added later only
+/// need a serde default to load older files; bump this when a change needs
+/// more than that, and convert the older shape in `parse_task_file`.
+pub const TASK_SCHEMA: u32 = 1;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct TaskFrontMatter {
+    #[serde(default)]
+    schema: u32,
     id: String,
     title: String,
     bucket: String,
@@ -151,6 +661,50 @@ struct TaskFrontMatter {
     #[serde(skip_serializing_if = "Option::is_none")]
     start_date: Option<String>,
     updated_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    escalated_levels: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimate_hours: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    actual_hours: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<NoteFrontMatter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    progress_history: Vec<TransitionFrontMatter>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sort_order: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    progress_locked_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email_subject: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TransitionFrontMatter {
+    progress: String,
+    at: String,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NoteFrontMatter {
+    at: String,
+    text: String,
 }
 
 // ---------------------------------------------------------------------------
@@ -160,12 +714,62 @@ struct TaskFrontMatter {
 #[derive(Debug, Clone)]
 pub struct Storage {
     dir: PathBuf,
+    /// Task files as this process last loaded or wrote them, by filename.
+    /// `save_tasks` only removes a file that is still in that state, so one
+    /// another process wrote in the meantime is kept.
+    seen: Arc<Mutex<HashMap<String, SeenFile>>>,
+}
+
+/// Enough of a file's metadata to tell whether it changed since it was seen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStat {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStat {
+    fn of(path: &Path) -> Option<FileStat> {
+        let meta = fs::metadata(path).ok()?;
+        Some(FileStat {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
+
+/// A task file as last loaded or written, so a save can tell what changed
+/// without reading it again.
+#[derive(Debug, Clone, Copy)]
+struct SeenFile {
+    stat: FileStat,
+    hash: u64,
+    /// Written with a newer `schema` than `TASK_SCHEMA`.
+    newer: bool,
+}
+
+impl SeenFile {
+    fn new(stat: FileStat, content: &str) -> SeenFile {
+        SeenFile {
+            stat,
+            hash: content_hash(content),
+            newer: file_schema(content) > TASK_SCHEMA,
+        }
+    }
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
 }
 
 impl Storage {
     pub fn new() -> Option<Storage> {
         let dir = data_dir()?;
-        let storage = Storage { dir };
+        let storage = Storage {
+            dir,
+            seen: Arc::default(),
+        };
         // Auto-migrate from legacy JSON if needed.
         if let Err(err) = storage.migrate_from_json() {
             eprintln!("Migration warning: {err}");
@@ -177,29 +781,88 @@ impl Storage {
         Some(storage)
     }
 
+    /// Where each kind of data lives, as printed by `aipm path`. All of it is
+    /// under one directory, so backing up or symlinking that is enough.
+    pub fn paths(&self) -> Vec<(&'static str, PathBuf)> {
+        vec![
+            ("data", self.dir.clone()),
+            ("tasks", self.dir.join("tasks")),
+            ("settings", self.dir.join("settings.yaml")),
+            ("suggestions", self.dir.join("suggestions.json")),
+            ("templates", self.dir.join("templates")),
+            ("snapshots", self.history_dir()),
+            ("google_token", self.dir.join("google_token.json")),
+            ("log", self.dir.join(log::LOG_FILE)),
+        ]
+    }
+
     // -- Tasks ---------------------------------------------------------------
 
     pub fn load_tasks(&self) -> io::Result<Vec<Task>> {
+        self.load_tasks_skipping().map(|(tasks, _, _)| tasks)
+    }
+
+    /// Load every readable task, skipping files that can't be read or parsed
+    /// and returning their names. Skipped files stay on disk untouched. Files
+    /// written with a newer `schema` are loaded as far as this binary
+    /// understands them and returned in the third list; `save_tasks` never
+    /// rewrites them, so fields it doesn't know aren't lost.
+    pub fn load_tasks_skipping(&self) -> io::Result<(Vec<Task>, Vec<String>, Vec<String>)> {
+        self.load_task_files(true)
+    }
+
+    /// `load_tasks_skipping`, remembering the files read as seen only when
+    /// the caller goes on to work with the tasks, not for a snapshot.
+    fn load_task_files(&self, remember: bool) -> io::Result<(Vec<Task>, Vec<String>, Vec<String>)> {
         let tasks_dir = self.dir.join("tasks");
         if !tasks_dir.is_dir() {
-            return Ok(Vec::new());
+            return Ok((Vec::new(), Vec::new(), Vec::new()));
         }
         let mut tasks = Vec::new();
+        let mut skipped = Vec::new();
+        let mut newer = Vec::new();
+        let mut seen = HashMap::new();
         for entry in fs::read_dir(&tasks_dir)? {
             let entry = entry?;
             let path = entry.path();
             if path.extension().and_then(|e| e.to_str()) != Some("md") {
                 continue;
             }
-            let content = fs::read_to_string(&path)?;
-            match parse_task_file(&content) {
-                Ok(task) => tasks.push(task),
+            // Taken before reading, so a file rewritten in between looks
+            // changed rather than seen.
+            let stat = FileStat::of(&path);
+            let parsed = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| {
+                    let file = stat.map(|stat| SeenFile::new(stat, &content));
+                    Ok((parse_task_file(&content)?, file_schema(&content), file))
+                });
+            match parsed {
+                Ok((task, schema, file)) => {
+                    if schema > TASK_SCHEMA {
+                        eprintln!(
+                            "Warning: {} was written by a newer aipm (schema {schema}, this one reads {TASK_SCHEMA}); it won't be saved",
+                            path.display()
+                        );
+                        newer.push(entry.file_name().to_string_lossy().to_string());
+                    }
+                    if let Some(file) = file {
+                        seen.insert(entry.file_name().to_string_lossy().to_string(), file);
+                    }
+                    tasks.push(task);
+                }
                 Err(err) => {
                     eprintln!("Warning: skipping {}: {}", path.display(), err);
+                    skipped.push(entry.file_name().to_string_lossy().to_string());
                 }
             }
         }
-        Ok(tasks)
+        skipped.sort();
+        newer.sort();
+        if remember {
+            *self.seen.lock().unwrap_or_else(|e| e.into_inner()) = seen;
+        }
+        Ok((tasks, skipped, newer))
     }
 
     /// Reload tasks from disk, returning the latest state.
@@ -207,9 +870,161 @@ impl Storage {
         self.load_tasks()
     }
 
+    /// Wait for the lock file that keeps two aipm processes from writing task
+    /// files at the same time. A lock older than `STALE_LOCK` was left by a
+    /// process that died mid-save, since saves take milliseconds.
+    fn lock_tasks(&self) -> io::Result<TasksLock> {
+        const STALE_LOCK: Duration = Duration::from_secs(10);
+        let path = self.dir.join("tasks.lock");
+        let deadline = Instant::now() + Duration::from_secs(3);
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(TasksLock { path });
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|at| at.elapsed().is_ok_and(|age| age > STALE_LOCK));
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                    } else if Instant::now() >= deadline {
+                        let pid = fs::read_to_string(&path).unwrap_or_default();
+                        return Err(io::Error::new(
+                            io::ErrorKind::WouldBlock,
+                            format!("another aipm (pid {}) is saving tasks", pid.trim()),
+                        ));
+                    } else {
+                        thread::sleep(Duration::from_millis(25));
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Changes whenever a task file is added, removed or rewritten, from
+    /// names, sizes and modification times only, so it is cheap to poll.
+    pub fn tasks_stamp(&self) -> u64 {
+        let mut files: Vec<(String, u64, Option<std::time::SystemTime>)> =
+            fs::read_dir(self.dir.join("tasks"))
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let meta = entry.metadata().ok()?;
+                    name.ends_with(".md")
+                        .then(|| (name, meta.len(), meta.modified().ok()))
+                })
+                .collect();
+        files.sort();
+        let mut hasher = DefaultHasher::new();
+        files.hash(&mut hasher);
+        hasher.finish()
+    }
+
     pub fn save_tasks(&self, tasks: &[Task]) -> io::Result<()> {
+        self.save_tasks_inner(tasks, false).inspect_err(|err| {
+            log::error(
+                "storage.save_tasks",
+                serde_json::json!({ "tasks": tasks.len(), "error": err.to_string() }),
+            )
+        })
+    }
+
+    /// `save_tasks`, but also removing task files this process never saw,
+    /// for undo and restore, which put back exactly the snapshot's tasks.
+    fn replace_tasks(&self, tasks: &[Task]) -> io::Result<()> {
+        self.save_tasks_inner(tasks, true).inspect_err(|err| {
+            log::error(
+                "storage.replace_tasks",
+                serde_json::json!({ "tasks": tasks.len(), "error": err.to_string() }),
+            )
+        })
+    }
+
+    /// Save `tasks`, first merging in what another process changed since
+    /// they were loaded as `base` with `tasks_stamp` `stamp`. Checking, merging
+    /// and writing all happen under one lock, so nothing written elsewhere in
+    /// between is overwritten. `tasks` becomes what was saved and `stamp` its
+    /// new stamp. Returns how many tasks conflicted when there was a merge.
+    pub fn save_tasks_merging(
+        &self,
+        base: &[Task],
+        tasks: &mut Vec<Task>,
+        stamp: &mut u64,
+    ) -> io::Result<Option<usize>> {
+        fs::create_dir_all(self.dir.join("tasks"))?;
+        let result = self.lock_tasks().and_then(|_lock| {
+            let mut conflicts = None;
+            if self.tasks_stamp() != *stamp {
+                let disk = self.load_tasks()?;
+                let (merged, count) = merge_tasks(base, tasks, &disk);
+                *tasks = merged;
+                conflicts = Some(count);
+            }
+            self.write_tasks(tasks, false)?;
+            *stamp = self.tasks_stamp();
+            Ok(conflicts)
+        });
+        result.inspect_err(|err| {
+            log::error(
+                "storage.save_tasks",
+                serde_json::json!({ "tasks": tasks.len(), "error": err.to_string() }),
+            )
+        })
+    }
+
+    fn save_tasks_inner(&self, tasks: &[Task], replace: bool) -> io::Result<()> {
+        fs::create_dir_all(self.dir.join("tasks"))?;
+        let _lock = self.lock_tasks()?;
+        self.write_tasks(tasks, replace)
+    }
+
+    /// The writing half of a save, for a caller holding `lock_tasks`.
+    fn write_tasks(&self, tasks: &[Task], replace: bool) -> io::Result<()> {
         let tasks_dir = self.dir.join("tasks");
-        fs::create_dir_all(&tasks_dir)?;
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+
+        // The task files there now, and whether each is as this process last
+        // saw it. Those are known without reading them; only files added or
+        // rewritten elsewhere are read. Checked under the lock, so no other
+        // aipm changes them before this save is done.
+        let mut on_disk: HashMap<String, (SeenFile, bool)> = HashMap::new();
+        for entry in fs::read_dir(&tasks_dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".md") {
+                continue;
+            }
+            let Some(stat) = FileStat::of(&entry.path()) else {
+                continue;
+            };
+            match seen.get(&name).filter(|file| file.stat == stat) {
+                Some(file) => {
+                    on_disk.insert(name, (*file, true));
+                }
+                None => {
+                    if let Ok(content) = fs::read_to_string(entry.path()) {
+                        on_disk.insert(name, (SeenFile::new(stat, &content), false));
+                    }
+                }
+            }
+        }
+
+        // Files from a newer binary may hold fields this one would drop, so
+        // they are neither rewritten nor removed. Keyed by the id prefix in
+        // the filename, so a renamed title doesn't write a second copy.
+        let newer_ids: HashSet<String> = on_disk
+            .iter()
+            .filter(|(_, (file, _))| file.newer)
+            .filter_map(|(name, _)| name.get(..8).map(str::to_string))
+            .collect();
 
         // Collect expected filenames so we can remove stale files.
         let mut expected_files: HashSet<String> = HashSet::new();
@@ -217,34 +1032,54 @@ impl Storage {
         for task in tasks {
             let filename = task_filename(task);
             expected_files.insert(filename.clone());
+            if filename.get(..8).is_some_and(|id| newer_ids.contains(id)) {
+                continue;
+            }
 
             let path = tasks_dir.join(&filename);
             let content = serialize_task_file(task);
+            let hash = content_hash(&content);
 
             // Only write if content changed (avoid unnecessary disk writes).
-            if path.is_file() {
-                if let Ok(existing) = fs::read_to_string(&path) {
-                    if existing == content {
-                        continue;
-                    }
-                }
+            if on_disk
+                .get(&filename)
+                .is_none_or(|(file, _)| file.hash != hash)
+            {
+                let tmp_path = path.with_extension("md.tmp");
+                fs::write(&tmp_path, &content)?;
+                fs::rename(&tmp_path, &path)?;
+            }
+            if let Some(stat) = FileStat::of(&path) {
+                let file = SeenFile {
+                    stat,
+                    hash,
+                    newer: false,
+                };
+                seen.insert(filename, file);
             }
-
-            let tmp_path = path.with_extension("md.tmp");
-            fs::write(&tmp_path, &content)?;
-            fs::rename(&tmp_path, &path)?;
         }
 
         // Remove stale files: deleted tasks or renamed slugs.
-        if let Ok(entries) = fs::read_dir(&tasks_dir) {
-            for entry in entries.flatten() {
-                let name = entry.file_name().to_string_lossy().to_string();
-                if !name.ends_with(".md") {
-                    continue;
-                }
-                if !expected_files.contains(&name) {
-                    let _ = fs::remove_file(entry.path());
-                }
+        for (name, (_, was_seen)) in &on_disk {
+            if expected_files.contains(name)
+                || name.get(..8).is_some_and(|id| newer_ids.contains(id))
+            {
+                continue;
+            }
+            // A file another process added or rewrote since this one last
+            // looked holds changes it doesn't have, so it stays and is picked
+            // up on the next load.
+            if !was_seen && !replace {
+                continue;
+            }
+            // A file that doesn't parse was skipped on load rather than
+            // deleted, so keep it until it is fixed by hand. Seen files were
+            // parsed when loaded.
+            let path = tasks_dir.join(name);
+            let is_task = *was_seen
+                || fs::read_to_string(&path).is_ok_and(|content| parse_task_file(&content).is_ok());
+            if is_task && fs::remove_file(&path).is_ok() {
+                seen.remove(name);
             }
         }
 
@@ -254,6 +1089,22 @@ impl Storage {
     // -- Settings ------------------------------------------------------------
 
     pub fn load_settings(&self) -> io::Result<AiSettings> {
+        let mut settings = self.read_settings_file()?;
+        if settings.use_keychain {
+            let mut loaded = HashSet::new();
+            for (account, key) in keychain_keys(&mut settings) {
+                if let Some(stored) = secrets::load(account).filter(|_| key.is_empty()) {
+                    *key = stored;
+                    loaded.insert(account);
+                }
+            }
+            settings.in_keychain = loaded;
+        }
+        Ok(settings)
+    }
+
+    /// Settings as stored on disk, without keys resolved from the keychain.
+    pub fn read_settings_file(&self) -> io::Result<AiSettings> {
         // Try YAML first, then fall back to legacy JSON.
         let yaml_path = self.dir.join("settings.yaml");
         if yaml_path.is_file() {
@@ -281,7 +1132,47 @@ impl Storage {
         Ok(AiSettings::default())
     }
 
-    pub fn save_settings(&self, settings: &AiSettings) -> io::Result<()> {
+    /// Writes the settings, with the API keys in the keychain when
+    /// `use_keychain` is on. Returns the accounts whose key the keychain
+    /// refused; those keys stay in the file.
+    ///
+    /// A keychain entry is only deleted when its key was loaded from there
+    /// and is now empty (cleared by the user) or back in the file (keychain
+    /// turned off). One that failed to load is left alone.
+    pub fn save_settings(&self, settings: &mut AiSettings) -> io::Result<Vec<&'static str>> {
+        let mut on_disk = settings.clone();
+        if !settings.use_keychain {
+            self.write_settings_file(settings)?;
+            // The keys are back in the file, so drop the keychain copies.
+            for account in settings.in_keychain.drain() {
+                secrets::delete(account);
+            }
+            return Ok(Vec::new());
+        }
+        let mut refused = Vec::new();
+        let mut in_keychain = HashSet::new();
+        for (account, key) in keychain_keys(&mut on_disk) {
+            if key.is_empty() {
+                if settings.in_keychain.contains(account) {
+                    secrets::delete(account);
+                }
+                continue;
+            }
+            if secrets::load(account).as_deref() == Some(key.as_str())
+                || secrets::store(account, key).is_ok()
+            {
+                key.clear();
+                in_keychain.insert(account);
+            } else {
+                refused.push(account);
+            }
+        }
+        self.write_settings_file(&on_disk)?;
+        settings.in_keychain = in_keychain;
+        Ok(refused)
+    }
+
+    fn write_settings_file(&self, settings: &AiSettings) -> io::Result<()> {
         let path = self.dir.join("settings.yaml");
         fs::create_dir_all(&self.dir)?;
         let yaml =
@@ -292,6 +1183,94 @@ impl Storage {
         Ok(())
     }
 
+    // -- Suggestions ---------------------------------------------------------
+
+    /// Pending email suggestions and the email ids dismissed from them. A
+    /// missing or unreadable file means no suggestions yet.
+    pub fn load_suggestions(&self) -> (Vec<Suggestion>, Vec<String>) {
+        #[derive(Deserialize)]
+        struct SuggestionsFile {
+            #[serde(default)]
+            pending: Vec<Suggestion>,
+            #[serde(default)]
+            dismissed: Vec<String>,
+        }
+        fs::read_to_string(self.dir.join("suggestions.json"))
+            .ok()
+            .and_then(|contents| serde_json::from_str::<SuggestionsFile>(&contents).ok())
+            .map(|file| (file.pending, file.dismissed))
+            .unwrap_or_default()
+    }
+
+    pub fn save_suggestions(&self, pending: &[Suggestion], dismissed: &[String]) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "pending": pending,
+            "dismissed": dismissed,
+        }))
+        .map_err(|err| io::Error::other(err.to_string()))?;
+        let path = self.dir.join("suggestions.json");
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &path)
+    }
+
+    // -- Templates -----------------------------------------------------------
+
+    fn template_path(&self, name: &str) -> io::Result<PathBuf> {
+        let slug = slug_from_title(name);
+        if slug.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Template name must contain letters or digits",
+            ));
+        }
+        Ok(self.dir.join("templates").join(format!("{slug}.json")))
+    }
+
+    /// Save a task tree (root first) under `name`, returning the stored name.
+    pub fn save_template(&self, name: &str, tasks: &[Task]) -> io::Result<String> {
+        let path = self.template_path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json =
+            serde_json::to_string_pretty(tasks).map_err(|err| io::Error::other(err.to_string()))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(slug_from_title(name))
+    }
+
+    pub fn load_template(&self, name: &str) -> io::Result<Vec<Task>> {
+        let path = self.template_path(name)?;
+        if !path.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No template named \"{}\"", slug_from_title(name)),
+            ));
+        }
+        let contents = fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    pub fn list_templates(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.dir.join("templates"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let path = e.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                    return None;
+                }
+                path.file_stem()?.to_str().map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
     // -- Migration -----------------------------------------------------------
 
     fn migrate_from_json(&self) -> io::Result<()> {
@@ -318,21 +1297,27 @@ impl Storage {
 
         fs::create_dir_all(&tasks_dir)?;
 
-        for task in &store.tasks {
-            let filename = task_filename(task);
+        let mut migrated = 0;
+        for (i, value) in store.tasks.into_iter().enumerate() {
+            let task: Task = match serde_json::from_value(value) {
+                Ok(task) => task,
+                Err(err) => {
+                    eprintln!("Warning: skipping task {} in tasks.json: {err}", i + 1);
+                    continue;
+                }
+            };
+            let filename = task_filename(&task);
             let path = tasks_dir.join(&filename);
-            let content = serialize_task_file(task);
+            let content = serialize_task_file(&task);
             fs::write(&path, content)?;
+            migrated += 1;
         }
 
-        // Archive the old JSON file.
+        // Archive the old JSON file. Skipped entries can be recovered from it.
         let bak = json_path.with_extension("json.bak");
         fs::rename(&json_path, &bak)?;
 
-        eprintln!(
-            "Migrated {} tasks from tasks.json -> tasks/ directory",
-            store.tasks.len()
-        );
+        eprintln!("Migrated {migrated} tasks from tasks.json -> tasks/ directory");
 
         Ok(())
     }
@@ -457,6 +1442,17 @@ fn progress_to_str(p: Progress) -> &'static str {
     }
 }
 
+fn progress_from_str(s: &str) -> Option<Progress> {
+    match s.to_ascii_lowercase().as_str() {
+        "backlog" => Some(Progress::Backlog),
+        "todo" => Some(Progress::Todo),
+        "inprogress" | "in progress" | "in-progress" => Some(Progress::InProgress),
+        "done" => Some(Progress::Done),
+        "archived" => Some(Progress::Archived),
+        _ => None,
+    }
+}
+
 fn priority_to_str(p: Priority) -> &'static str {
     match p {
         Priority::Low => "Low",
@@ -468,6 +1464,7 @@ fn priority_to_str(p: Priority) -> &'static str {
 
 fn serialize_task_file(task: &Task) -> String {
     let fm = TaskFrontMatter {
+        schema: TASK_SCHEMA,
         id: task.id.to_string(),
         title: task.title.clone(),
         bucket: task.bucket.clone(),
@@ -479,6 +1476,32 @@ fn serialize_task_file(task: &Task) -> String {
         created_at: task.created_at.to_rfc3339(),
         start_date: task.start_date.map(|dt| dt.to_rfc3339()),
         updated_at: task.updated_at.to_rfc3339(),
+        assignee: task.assignee.clone(),
+        escalated_levels: task.escalated_levels,
+        estimate_hours: task.estimate_hours,
+        actual_hours: task.actual_hours,
+        notes: task
+            .notes
+            .iter()
+            .map(|(at, text)| NoteFrontMatter {
+                at: at.to_rfc3339(),
+                text: text.clone(),
+            })
+            .collect(),
+        progress_history: task
+            .progress_history
+            .iter()
+            .map(|(progress, at)| TransitionFrontMatter {
+                progress: progress_to_str(*progress).to_string(),
+                at: at.to_rfc3339(),
+            })
+            .collect(),
+        sort_order: task.sort_order,
+        progress_locked_at: task.progress_locked_at.map(|dt| dt.to_rfc3339()),
+        email_id: task.email_id.clone(),
+        email_subject: task.email_subject.clone(),
+        pinned: task.pinned,
+        color: task.color.clone(),
     };
 
     let yaml = serde_yaml::to_string(&fm).unwrap_or_default();
@@ -499,7 +1522,7 @@ fn serialize_task_file(task: &Task) -> String {
 // Parsing
 // ---------------------------------------------------------------------------
 
-fn parse_task_file(content: &str) -> Result<Task, String> {
+fn split_front_matter(content: &str) -> Result<(&str, &str), String> {
     let trimmed = content.trim_start();
     if !trimmed.starts_with("---") {
         return Err("missing front matter delimiter".to_string());
@@ -515,7 +1538,25 @@ fn parse_task_file(content: &str) -> Result<Task, String> {
         .get(body_start..)
         .unwrap_or("")
         .trim_start_matches(['\r', '\n']);
+    Ok((yaml_str, body))
+}
+
+/// The `schema` a task file was written with, 0 when it has none or can't be
+/// read.
+fn file_schema(content: &str) -> u32 {
+    #[derive(Deserialize)]
+    struct SchemaOnly {
+        #[serde(default)]
+        schema: u32,
+    }
+    split_front_matter(content)
+        .ok()
+        .and_then(|(yaml_str, _)| serde_yaml::from_str::<SchemaOnly>(yaml_str).ok())
+        .map_or(0, |fm| fm.schema)
+}
 
+fn parse_task_file(content: &str) -> Result<Task, String> {
+    let (yaml_str, body) = split_front_matter(content)?;
     let fm: TaskFrontMatter =
         serde_yaml::from_str(yaml_str).map_err(|err| format!("YAML parse error: {err}"))?;
 
@@ -523,14 +1564,8 @@ fn parse_task_file(content: &str) -> Result<Task, String> {
 
     let bucket = fm.bucket.clone();
 
-    let progress = match fm.progress.to_ascii_lowercase().as_str() {
-        "backlog" => Progress::Backlog,
-        "todo" => Progress::Todo,
-        "inprogress" | "in progress" | "in-progress" => Progress::InProgress,
-        "done" => Progress::Done,
-        "archived" => Progress::Archived,
-        other => return Err(format!("unknown progress: {other}")),
-    };
+    let progress = progress_from_str(&fm.progress)
+        .ok_or_else(|| format!("unknown progress: {}", fm.progress.to_ascii_lowercase()))?;
 
     let priority = match fm.priority.to_ascii_lowercase().as_str() {
         "low" => Priority::Low,
@@ -578,6 +1613,26 @@ fn parse_task_file(content: &str) -> Result<Task, String> {
         .map(|dt| dt.with_timezone(&Utc))
         .map_err(|err| format!("invalid updated_at: {err}"))?;
 
+    let notes = fm
+        .notes
+        .into_iter()
+        .filter_map(|note| {
+            DateTime::parse_from_rfc3339(&note.at)
+                .ok()
+                .map(|dt| (dt.with_timezone(&Utc), note.text))
+        })
+        .collect();
+
+    let progress_history = fm
+        .progress_history
+        .into_iter()
+        .filter_map(|entry| {
+            let progress = progress_from_str(&entry.progress)?;
+            let at = DateTime::parse_from_rfc3339(&entry.at).ok()?;
+            Some((progress, at.with_timezone(&Utc)))
+        })
+        .collect();
+
     Ok(Task {
         id,
         bucket,
@@ -591,6 +1646,21 @@ fn parse_task_file(content: &str) -> Result<Task, String> {
         created_at,
         start_date,
         updated_at,
+        assignee: fm.assignee,
+        escalated_levels: fm.escalated_levels,
+        estimate_hours: fm.estimate_hours,
+        actual_hours: fm.actual_hours,
+        notes,
+        progress_history,
+        sort_order: fm.sort_order,
+        progress_locked_at: fm
+            .progress_locked_at
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+        email_id: fm.email_id,
+        email_subject: fm.email_subject,
+        pinned: fm.pinned,
+        color: fm.color,
     })
 }
 
@@ -598,8 +1668,6 @@ fn parse_task_file(content: &str) -> Result<Task, String> {
 // Snapshots (undo history)
 // ---------------------------------------------------------------------------
 
-const MAX_SNAPSHOTS: usize = 50;
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
     pub label: String,
@@ -608,6 +1676,105 @@ pub struct Snapshot {
     pub settings: AiSettings,
 }
 
+/// What restoring a snapshot would do to the current tasks, by title.
+#[derive(Debug, Default, PartialEq)]
+pub struct SnapshotDiff {
+    /// Only in the snapshot: restoring brings them back.
+    pub added: Vec<String>,
+    /// Only in the current tasks: restoring deletes them.
+    pub removed: Vec<String>,
+    /// In both with different content: restoring reverts them.
+    pub modified: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Removes the `tasks.lock` file when the save holding it ends.
+struct TasksLock {
+    path: PathBuf,
+}
+
+impl Drop for TasksLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Three-way merge of tasks changed both here (`local`) and on disk (`disk`)
+/// since both were `base`. Each side's changes are kept; when both changed
+/// the same task, the local version wins, and an edit beats a deletion so
+/// nothing is lost. Returns the merged tasks and how many tasks conflicted.
+pub fn merge_tasks(base: &[Task], local: &[Task], disk: &[Task]) -> (Vec<Task>, usize) {
+    let file =
+        |tasks: &[Task], id: Uuid| tasks.iter().find(|t| t.id == id).map(serialize_task_file);
+    let mut ids: Vec<Uuid> = Vec::new();
+    for task in local.iter().chain(disk).chain(base) {
+        if !ids.contains(&task.id) {
+            ids.push(task.id);
+        }
+    }
+    let mut merged = Vec::new();
+    let mut conflicts = 0;
+    for id in ids {
+        let (b, l, d) = (file(base, id), file(local, id), file(disk, id));
+        let local_changed = l != b;
+        let disk_changed = d != b;
+        let take_disk = match (local_changed, disk_changed) {
+            (false, true) => true,
+            (true, true) if l != d => {
+                conflicts += 1;
+                l.is_none()
+            }
+            _ => false,
+        };
+        let source = if take_disk { disk } else { local };
+        if let Some(task) = source.iter().find(|t| t.id == id) {
+            merged.push(task.clone());
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Whether `a` and `b` hold the same tasks in any order, compared as they
+/// would be written to disk.
+pub fn same_tasks(a: &[Task], b: &[Task]) -> bool {
+    let by_id: HashMap<Uuid, &Task> = b.iter().map(|t| (t.id, t)).collect();
+    a.len() == b.len()
+        && a.iter().all(|t| {
+            by_id
+                .get(&t.id)
+                .is_some_and(|other| serialize_task_file(other) == serialize_task_file(t))
+        })
+}
+
+/// Compare `current` with the tasks in `snapshot`, matching them by id. Two
+/// tasks differ when their task files would differ.
+pub fn diff_snapshot(current: &[Task], snapshot: &Snapshot) -> SnapshotDiff {
+    let mut diff = SnapshotDiff::default();
+    for old in &snapshot.tasks {
+        match current.iter().find(|t| t.id == old.id) {
+            None => diff.added.push(old.title.clone()),
+            Some(now) if serialize_task_file(now) != serialize_task_file(old) => {
+                diff.modified.push(now.title.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    for now in current {
+        if !snapshot.tasks.iter().any(|t| t.id == now.id) {
+            diff.removed.push(now.title.clone());
+        }
+    }
+    diff.added.sort();
+    diff.removed.sort();
+    diff.modified.sort();
+    diff
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     pub seq: u64,
@@ -621,20 +1788,30 @@ impl Storage {
     }
 
     pub fn snapshot(&self, label: &str) {
-        if let Err(err) = self.snapshot_inner(label) {
+        if let Err(err) = self.try_snapshot(label) {
+            log::error(
+                "storage.snapshot",
+                serde_json::json!({ "label": label, "error": err.to_string() }),
+            );
             eprintln!("Snapshot warning: {err}");
         }
     }
 
-    fn snapshot_inner(&self, label: &str) -> io::Result<()> {
+    /// `snapshot` for callers that must not go ahead without one.
+    pub fn try_snapshot(&self, label: &str) -> io::Result<()> {
         let hist = self.history_dir();
         fs::create_dir_all(&hist)?;
 
-        let tasks = self.load_tasks().unwrap_or_default();
-        let settings = self.load_settings().unwrap_or_default();
+        let tasks = self
+            .load_task_files(false)
+            .map(|(tasks, _, _)| tasks)
+            .unwrap_or_default();
+        // The file as-is, so keys kept in the keychain don't land in history.
+        let settings = self.read_settings_file().unwrap_or_default();
         let now = Utc::now();
         let seq = self.next_seq();
 
+        let keep = settings.snapshot_retention.clamp(1, MAX_SNAPSHOT_RETENTION);
         let snap = Snapshot {
             label: label.to_string(),
             timestamp: now,
@@ -649,7 +1826,7 @@ impl Storage {
         fs::write(&tmp, json)?;
         fs::rename(&tmp, &path)?;
 
-        self.trim_history();
+        self.trim_history(keep);
         Ok(())
     }
 
@@ -659,36 +1836,47 @@ impl Storage {
             .last()
             .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No undo history available"))?;
 
-        let content = fs::read_to_string(latest)?;
-        let snap: Snapshot = serde_json::from_str(&content)
-            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
-
-        self.save_tasks(&snap.tasks)?;
-        self.save_settings(&snap.settings)?;
+        let snap = read_snapshot(latest)?;
+        self.replace_tasks(&snap.tasks)?;
+        self.write_settings_file(&snap.settings)?;
 
         let label = snap.label.clone();
         fs::remove_file(latest)?;
         Ok(label)
     }
 
+    /// Go back to the state saved in snapshot `seq`, keeping the history.
+    /// The current state is snapshotted first, so the restore can itself be
+    /// undone. Returns the snapshot's label.
+    pub fn restore_snapshot(&self, seq: u64) -> io::Result<String> {
+        let snap = self.load_snapshot(seq)?;
+        self.snapshot(&format!("restore #{seq}"));
+        self.replace_tasks(&snap.tasks)?;
+        self.write_settings_file(&snap.settings)?;
+        Ok(snap.label)
+    }
+
+    pub fn load_snapshot(&self, seq: u64) -> io::Result<Snapshot> {
+        let path = self
+            .sorted_snapshot_files()
+            .into_iter()
+            .find(|path| snapshot_seq(path) == Some(seq))
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("No snapshot #{seq}"))
+            })?;
+        read_snapshot(&path)
+    }
+
     pub fn list_history(&self) -> Vec<HistoryEntry> {
         let files = self.sorted_snapshot_files();
         let mut entries = Vec::new();
         for path in &files {
-            if let Ok(content) = fs::read_to_string(path) {
-                if let Ok(snap) = serde_json::from_str::<Snapshot>(&content) {
-                    let seq = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .and_then(|n| n.split('-').next())
-                        .and_then(|s| s.parse::<u64>().ok())
-                        .unwrap_or(0);
-                    entries.push(HistoryEntry {
-                        seq,
-                        label: snap.label,
-                        timestamp: snap.timestamp,
-                    });
-                }
+            if let Ok(snap) = read_snapshot(path) {
+                entries.push(HistoryEntry {
+                    seq: snapshot_seq(path).unwrap_or(0),
+                    label: snap.label,
+                    timestamp: snap.timestamp,
+                });
             }
         }
         entries
@@ -710,32 +1898,42 @@ impl Storage {
     fn next_seq(&self) -> u64 {
         self.sorted_snapshot_files()
             .last()
-            .and_then(|p| {
-                p.file_name()?
-                    .to_str()?
-                    .split('-')
-                    .next()?
-                    .parse::<u64>()
-                    .ok()
-            })
+            .and_then(|p| snapshot_seq(p))
             .map(|n| n + 1)
             .unwrap_or(1)
     }
 
-    fn trim_history(&self) {
+    fn trim_history(&self, keep: usize) {
         let files = self.sorted_snapshot_files();
-        if files.len() > MAX_SNAPSHOTS {
-            for path in &files[..files.len() - MAX_SNAPSHOTS] {
+        if files.len() > keep {
+            for path in &files[..files.len() - keep] {
                 let _ = fs::remove_file(path);
             }
         }
     }
 }
 
+fn read_snapshot(path: &Path) -> io::Result<Snapshot> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+}
+
+fn snapshot_seq(path: &Path) -> Option<u64> {
+    path.file_name()?
+        .to_str()?
+        .split('-')
+        .next()?
+        .parse::<u64>()
+        .ok()
+}
+
 // ---------------------------------------------------------------------------
 // Data directory
 // ---------------------------------------------------------------------------
 
+/// `$AIPM_DATA_DIR`, else `~/Library/Application Support/aipm` on macOS and
+/// `$XDG_DATA_HOME/aipm` or `~/.local/share/aipm` elsewhere.
 pub fn data_dir() -> Option<PathBuf> {
     if let Ok(path) = env::var("AIPM_DATA_DIR") {
         if !path.trim().is_empty() {
@@ -743,27 +1941,174 @@ pub fn data_dir() -> Option<PathBuf> {
         }
     }
 
-    if let Ok(path) = env::var("XDG_DATA_HOME") {
-        if !path.trim().is_empty() {
-            return Some(PathBuf::from(path).join("aipm"));
+    let xdg = env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| PathBuf::from(p).join("aipm"));
+    let home = env::var("HOME").ok().map(PathBuf::from);
+
+    if cfg!(target_os = "macos") {
+        // Older versions preferred XDG_DATA_HOME on macOS too; keep using
+        // data already there.
+        if let Some(dir) = xdg.as_ref().filter(|d| d.is_dir()) {
+            return Some(dir.clone());
         }
+        return home.map(|h| h.join("Library").join("Application Support").join("aipm"));
     }
 
-    if let Ok(home) = env::var("HOME") {
-        let app_support = PathBuf::from(&home)
-            .join("Library")
-            .join("Application Support");
-        if app_support.is_dir() {
-            return Some(app_support.join("aipm"));
+    xdg.or_else(|| home.map(|h| h.join(".local").join("share").join("aipm")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage() -> Storage {
+        let dir = env::temp_dir().join(format!("aipm-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        Storage {
+            dir,
+            seen: Arc::default(),
         }
+    }
 
-        return Some(
-            PathBuf::from(home)
-                .join(".local")
-                .join("share")
-                .join("aipm"),
+    #[test]
+    fn saving_keeps_task_files_written_by_another_process() {
+        let tui = temp_storage();
+        let cli = Storage {
+            dir: tui.dir.clone(),
+            seen: Arc::default(),
+        };
+        let now = Utc::now();
+        let mine = Task::new("Team".into(), "Mine".into(), now);
+        tui.save_tasks(std::slice::from_ref(&mine)).unwrap();
+
+        cli.load_tasks().unwrap();
+        let theirs = Task::new("Team".into(), "Theirs".into(), now);
+        cli.save_tasks(&[mine.clone(), theirs.clone()]).unwrap();
+
+        // Saved from a list that predates the other process's task.
+        tui.save_tasks(std::slice::from_ref(&mine)).unwrap();
+        assert_eq!(tui.load_tasks().unwrap().len(), 2);
+
+        // Once loaded here, leaving it out deletes it.
+        tui.save_tasks(std::slice::from_ref(&mine)).unwrap();
+        let left = tui.load_tasks().unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, mine.id);
+
+        fs::remove_dir_all(&tui.dir).unwrap();
+    }
+
+    #[test]
+    fn merging_save_keeps_changes_made_elsewhere() {
+        let tui = temp_storage();
+        let cli = Storage {
+            dir: tui.dir.clone(),
+            seen: Arc::default(),
+        };
+        let now = Utc::now();
+        let base = vec![
+            Task::new("Team".into(), "Plan".into(), now),
+            Task::new("Team".into(), "Build".into(), now),
+        ];
+        tui.save_tasks(&base).unwrap();
+        let mut stamp = tui.tasks_stamp();
+
+        let mut theirs = cli.load_tasks().unwrap();
+        let plan = theirs.iter_mut().find(|t| t.title == "Plan").unwrap();
+        plan.description = "From the command line".into();
+        cli.save_tasks(&theirs).unwrap();
+
+        let mut tasks = base.clone();
+        tasks[1].description = "From the TUI".into();
+        let merged = tui
+            .save_tasks_merging(&base, &mut tasks, &mut stamp)
+            .unwrap();
+        assert_eq!(merged, Some(0));
+        assert_eq!(stamp, tui.tasks_stamp());
+
+        let saved = tui.load_tasks().unwrap();
+        let description = |title: &str| {
+            saved
+                .iter()
+                .find(|t| t.title == title)
+                .map(|t| t.description.clone())
+        };
+        assert_eq!(
+            description("Plan").as_deref(),
+            Some("From the command line")
+        );
+        assert_eq!(description("Build").as_deref(), Some("From the TUI"));
+
+        // Nothing changed elsewhere since, so the next save doesn't merge.
+        assert_eq!(
+            tui.save_tasks_merging(&tasks.clone(), &mut tasks, &mut stamp)
+                .unwrap(),
+            None
         );
+
+        fs::remove_dir_all(&tui.dir).unwrap();
     }
 
-    None
+    #[test]
+    fn saving_rewrites_only_changed_task_files() {
+        let storage = temp_storage();
+        let mut task = Task::new("Team".into(), "Water plants".into(), Utc::now());
+        storage.save_tasks(std::slice::from_ref(&task)).unwrap();
+        let path = storage.dir.join("tasks").join(task_filename(&task));
+        let written = FileStat::of(&path);
+
+        storage.save_tasks(std::slice::from_ref(&task)).unwrap();
+        assert_eq!(FileStat::of(&path), written);
+
+        task.description = "Twice a week".into();
+        storage.save_tasks(std::slice::from_ref(&task)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            serialize_task_file(&task)
+        );
+
+        // Changed outside this process, so the cached state doesn't count.
+        fs::write(&path, "edited by hand").unwrap();
+        storage.save_tasks(std::slice::from_ref(&task)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            serialize_task_file(&task)
+        );
+
+        fs::remove_dir_all(&storage.dir).unwrap();
+    }
+
+    #[test]
+    fn task_color_survives_a_round_trip() {
+        let mut task = Task::new("Team".into(), "Paint the shed".into(), Utc::now());
+        task.color = Some("green".into());
+        let parsed = parse_task_file(&serialize_task_file(&task)).unwrap();
+        assert_eq!(parsed.color.as_deref(), Some("green"));
+
+        task.color = None;
+        let parsed = parse_task_file(&serialize_task_file(&task)).unwrap();
+        assert_eq!(parsed.color, None);
+    }
+
+    #[test]
+    fn stages_are_found_by_their_label() {
+        let mut settings = AiSettings {
+            stage_labels: parse_stage_labels("Ideas, Next, Doing, Shipped").unwrap(),
+            ..AiSettings::default()
+        };
+        assert_eq!(
+            settings.stage_from_label("doing"),
+            Some(Progress::InProgress)
+        );
+        assert_eq!(settings.stage_from_label(" Shipped "), Some(Progress::Done));
+        assert_eq!(settings.stage_from_label("Done"), None);
+
+        settings.stage_labels.clear();
+        assert_eq!(
+            settings.stage_from_label("in progress"),
+            Some(Progress::InProgress)
+        );
+    }
 }